@@ -0,0 +1,290 @@
+//! values of a field of access logs in the common or combined log format of Apache and nginx,
+//! see [`count_field`](fn.count_field.html)
+
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+
+use crate::decode::{read_lines, Transcoder};
+use crate::{CountConfig, CountError, Frequencies};
+
+/// a field of a line of an access log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    /// address of the client
+    Host,
+    /// authenticated user
+    User,
+    /// time of the request, as written
+    Time,
+    Method,
+    /// path of the request, with the query
+    Path,
+    Protocol,
+    Status,
+    /// size of the response
+    Bytes,
+    /// only in the combined log format
+    Referrer,
+    /// only in the combined log format
+    UserAgent,
+}
+
+impl Field {
+    /// name of the field, which is parsed by `from_str`
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Host => "host",
+            Field::User => "user",
+            Field::Time => "time",
+            Field::Method => "method",
+            Field::Path => "path",
+            Field::Protocol => "protocol",
+            Field::Status => "status",
+            Field::Bytes => "bytes",
+            Field::Referrer => "referrer",
+            Field::UserAgent => "user-agent",
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// the name of a field, `referer` and `agent` are also accepted
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let field = match s {
+            "host" => Field::Host,
+            "user" => Field::User,
+            "time" => Field::Time,
+            "method" => Field::Method,
+            "path" => Field::Path,
+            "protocol" => Field::Protocol,
+            "status" => Field::Status,
+            "bytes" => Field::Bytes,
+            "referrer" | "referer" => Field::Referrer,
+            "user-agent" | "agent" => Field::UserAgent,
+            _ => return Err(format!("unknown field {:?}", s)),
+        };
+        Ok(field)
+    }
+}
+
+/// a line of an access log, fields are slices of the line without brackets or quotes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry<'a> {
+    pub host: &'a str,
+    pub user: &'a str,
+    pub time: &'a str,
+    /// the request line, e.g. `GET /index.html HTTP/1.1`
+    pub request: &'a str,
+    pub status: &'a str,
+    pub bytes: &'a str,
+    pub referrer: Option<&'a str>,
+    pub user_agent: Option<&'a str>,
+}
+
+impl<'a> Entry<'a> {
+    /// parse a line of the common or combined log format, `None` if it is in neither
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::accesslog::{Entry, Field};
+    /// let line = r#"127.0.0.1 - - [10/Oct/2024:13:55:36 +0900] "GET /a?b=1 HTTP/1.1" 200 2326 "-" "curl/8.0""#;
+    /// let entry = Entry::parse(line).unwrap();
+    /// assert_eq!(entry.field(Field::Path), Some("/a?b=1"));
+    /// assert_eq!(entry.field(Field::Status), Some("200"));
+    /// assert_eq!(entry.field(Field::UserAgent), Some("curl/8.0"));
+    /// assert_eq!(Entry::parse("not a log"), None);
+    /// ```
+    pub fn parse(line: &'a str) -> Option<Self> {
+        let mut rest = line;
+        let host = word(&mut rest)?;
+        let _ident = word(&mut rest)?;
+        let user = word(&mut rest)?;
+        let time = enclosed(&mut rest, '[', ']')?;
+        let request = quoted(&mut rest)?;
+        let status = word(&mut rest)?;
+        let bytes = word(&mut rest)?;
+        if !status.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let (referrer, user_agent) = if rest.trim().is_empty() {
+            (None, None)
+        } else {
+            (Some(quoted(&mut rest)?), Some(quoted(&mut rest)?))
+        };
+        Some(Entry {
+            host,
+            user,
+            time,
+            request,
+            status,
+            bytes,
+            referrer,
+            user_agent,
+        })
+    }
+
+    /// value of `field`, `None` if the line does not have it
+    pub fn field(&self, field: Field) -> Option<&'a str> {
+        let mut request = self.request.split(' ');
+        match field {
+            Field::Host => Some(self.host),
+            Field::User => Some(self.user),
+            Field::Time => Some(self.time),
+            Field::Method => request.next().filter(|method| !method.is_empty()),
+            Field::Path => request.nth(1),
+            Field::Protocol => request.nth(2),
+            Field::Status => Some(self.status),
+            Field::Bytes => Some(self.bytes),
+            Field::Referrer => self.referrer,
+            Field::UserAgent => self.user_agent,
+        }
+    }
+}
+
+/// a field up to a space
+fn word<'a>(rest: &mut &'a str) -> Option<&'a str> {
+    let text = rest.trim_start_matches(' ');
+    let end = text.find(' ').unwrap_or(text.len());
+    if end == 0 {
+        return None;
+    }
+    *rest = &text[end..];
+    Some(&text[..end])
+}
+
+/// a field between `open` and `close`
+fn enclosed<'a>(rest: &mut &'a str, open: char, close: char) -> Option<&'a str> {
+    let text = rest.trim_start_matches(' ').strip_prefix(open)?;
+    let end = text.find(close)?;
+    *rest = &text[end + close.len_utf8()..];
+    Some(&text[..end])
+}
+
+/// a field between double quotes, in which `\"` is escaped
+fn quoted<'a>(rest: &mut &'a str) -> Option<&'a str> {
+    let text = rest.trim_start_matches(' ').strip_prefix('"')?;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => {
+                *rest = &text[i + 1..];
+                return Some(&text[..i]);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// result of [`count_field`](fn.count_field.html)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldCounts {
+    /// count of each value
+    pub values: Frequencies,
+    /// count of lines in neither of the formats, or without the field
+    pub skipped: usize,
+}
+
+/// count values of `field` of each line of an access log. values are counted whole, normalized
+/// as configured (e.g. by `ignore_case`), and lines in neither format are skipped
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::accesslog::{count_field, Field};
+/// use bicycle_book_wordcount::CountConfig;
+/// let log = r#"1.2.3.4 - - [10/Oct/2024:13:55:36 +0900] "GET / HTTP/1.1" 200 10
+/// 1.2.3.4 - - [10/Oct/2024:13:55:37 +0900] "GET /a HTTP/1.1" 404 0
+/// 5.6.7.8 - - [10/Oct/2024:13:55:38 +0900] "GET / HTTP/1.1" 200 10
+/// broken"#;
+/// let counts = count_field(Cursor::new(log), &CountConfig::default(), Field::Path).unwrap();
+/// assert_eq!(counts.values["/"], 2);
+/// assert_eq!(counts.values["/a"], 1);
+/// assert_eq!(counts.skipped, 1);
+/// ```
+pub fn count_field(
+    input: impl BufRead,
+    config: &CountConfig,
+    field: Field,
+) -> Result<FieldCounts, CountError> {
+    let mut counts = FieldCounts::default();
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        match Entry::parse(line).and_then(|entry| entry.field(field)) {
+            Some(value) => {
+                let value = config.normalize(value);
+                match counts.values.get_mut(value.as_ref()) {
+                    Some(n) => *n += 1,
+                    None => {
+                        counts.values.insert(value.into_owned(), 1);
+                    }
+                }
+            }
+            None if line.is_empty() => {}
+            None => counts.skipped += 1,
+        }
+        Ok::<_, CountError>(())
+    })?;
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn parse_works() {
+        let line = r#"::1 - alice [01/Jan/2024:00:00:00 +0000] "POST /login HTTP/2.0" 302 - "https://example.com/?q=\"x\"" "Mozilla/5.0 (X11)""#;
+        let entry = Entry::parse(line).unwrap();
+        assert_eq!(entry.field(Field::Host), Some("::1"));
+        assert_eq!(entry.field(Field::User), Some("alice"));
+        assert_eq!(entry.field(Field::Time), Some("01/Jan/2024:00:00:00 +0000"));
+        assert_eq!(entry.field(Field::Method), Some("POST"));
+        assert_eq!(entry.field(Field::Protocol), Some("HTTP/2.0"));
+        assert_eq!(entry.field(Field::Bytes), Some("-"));
+        assert_eq!(
+            entry.field(Field::Referrer),
+            Some(r#"https://example.com/?q=\"x\""#)
+        );
+        assert_eq!(entry.field(Field::UserAgent), Some("Mozilla/5.0 (X11)"));
+
+        let common = Entry::parse(r#"h - - [t] "-" 400 0"#).unwrap();
+        assert_eq!(common.field(Field::Method), Some("-"));
+        assert_eq!(common.field(Field::Path), None);
+        assert_eq!(common.field(Field::Referrer), None);
+        assert_eq!(Entry::parse(r#"h - - [t] "GET /" abc 0"#), None);
+        assert_eq!(Entry::parse(r#"h - - [t] "GET /" 200 0 "-""#), None);
+    }
+
+    #[test]
+    fn fields_are_named() {
+        for name in &["host", "path", "status", "referrer", "user-agent"] {
+            assert_eq!(name.parse::<Field>().unwrap().name(), *name);
+        }
+        assert_eq!("agent".parse(), Ok(Field::UserAgent));
+        assert!("size".parse::<Field>().is_err());
+    }
+
+    #[test]
+    fn count_field_normalizes() {
+        let log = "h - - [t] \"GET /A HTTP/1.1\" 200 1\n\nh - - [t] \"GET /a HTTP/1.1\" 200 1\n";
+        let config = CountConfig::default().ignore_case(true);
+        let counts = count_field(Cursor::new(log), &config, Field::Path).unwrap();
+        assert_eq!(counts.values.len(), 1);
+        assert_eq!(counts.values["/a"], 2);
+        assert_eq!(counts.skipped, 0);
+    }
+}
@@ -0,0 +1,318 @@
+//! compact binary serialization of results, for caching counts on disk
+//!
+//! format (version 2):
+//! * magic `b"WCNT"` and version byte
+//! * [metadata](../metadata/struct.Metadata.html): version, mode, hash of the configuration,
+//!   count of inputs and each input, and timestamp
+//! * count of entries, then each entry as key length, key (UTF-8) and count
+//!
+//! strings are of their length and UTF-8, and all integers are unsigned LEB128 varints.
+//! results without metadata are written in version 1, which has none.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+
+use crate::metadata::Metadata;
+use crate::Frequencies;
+
+const MAGIC: &[u8; 4] = b"WCNT";
+
+/// current format version
+pub const VERSION: u8 = 2;
+
+/// format version without metadata
+const VERSION_WITHOUT_METADATA: u8 = 1;
+
+/// write `freqs` in the binary format
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{binary, count, CountOption};
+/// let freqs = count(Cursor::new("aa bb aa"), CountOption::Word);
+/// let mut buf = Vec::new();
+/// binary::write(&freqs, &mut buf).unwrap();
+/// assert_eq!(binary::read(&buf[..]).unwrap(), freqs);
+/// ```
+pub fn write(freqs: &Frequencies, mut out: impl Write) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&[VERSION_WITHOUT_METADATA])?;
+    write_entries(freqs, out)
+}
+
+/// write `freqs` after `metadata`, which is read by
+/// [`Entries::metadata`](struct.Entries.html#method.metadata). results of this can not be read
+/// by versions without metadata
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::binary::{self, Entries};
+/// use bicycle_book_wordcount::metadata::Metadata;
+/// use bicycle_book_wordcount::{count, CountConfig, CountOption};
+/// let freqs = count(Cursor::new("aa bb aa"), CountOption::Word);
+/// let metadata = Metadata::new(&CountConfig::default(), vec!["a.txt".to_string()]);
+/// let mut buf = Vec::new();
+/// binary::write_with_metadata(&freqs, &metadata, &mut buf).unwrap();
+/// assert_eq!(Entries::new(&buf[..]).unwrap().metadata(), Some(&metadata));
+/// assert_eq!(binary::read(&buf[..]).unwrap(), freqs);
+/// ```
+pub fn write_with_metadata(
+    freqs: &Frequencies,
+    metadata: &Metadata,
+    mut out: impl Write,
+) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&[VERSION])?;
+    write_string(&mut out, &metadata.version)?;
+    write_string(&mut out, &metadata.mode)?;
+    write_varint(&mut out, metadata.config)?;
+    write_varint(&mut out, metadata.inputs.len() as u64)?;
+    for input in &metadata.inputs {
+        write_string(&mut out, input)?;
+    }
+    write_varint(&mut out, metadata.timestamp)?;
+    write_entries(freqs, out)
+}
+
+fn write_entries(freqs: &Frequencies, mut out: impl Write) -> io::Result<()> {
+    write_varint(&mut out, freqs.len() as u64)?;
+    for (token, count) in freqs.sorted() {
+        write_string(&mut out, token)?;
+        write_varint(&mut out, count as u64)?;
+    }
+    out.flush()
+}
+
+/// read a result written by [`write`](fn.write.html).
+/// returns an error with kind `InvalidData` if the input is broken or of an unknown version
+pub fn read(input: impl Read) -> io::Result<Frequencies> {
+    let mut freqs = Frequencies::new();
+    for entry in Entries::new(input)? {
+        let (token, count) = entry?;
+        freqs.insert(token, count);
+    }
+    Ok(freqs)
+}
+
+/// sum of results of files in the binary format
+///
+/// entries of each file are read one by one and added to the sum, so only the sum is held in
+/// memory, not the results of the files. errors are prefixed with the path of the file
+///
+/// # Examples
+///
+/// ```
+/// use std::fs::File;
+/// use bicycle_book_wordcount::{binary, count_str, CountOption};
+/// let dir = std::env::temp_dir();
+/// let (a, b) = (dir.join("wordcount_doc_a.bin"), dir.join("wordcount_doc_b.bin"));
+/// binary::write(&count_str("aa bb", CountOption::Word), File::create(&a).unwrap()).unwrap();
+/// binary::write(&count_str("aa", CountOption::Word), File::create(&b).unwrap()).unwrap();
+/// let total = binary::merge_files(&[a, b]).unwrap();
+/// assert_eq!(total["aa"], 2);
+/// assert_eq!(total["bb"], 1);
+/// ```
+pub fn merge_files<P: AsRef<Path>>(paths: &[P]) -> io::Result<Frequencies> {
+    let mut total = Frequencies::new();
+    for path in paths {
+        let path = path.as_ref();
+        let with_path =
+            |e: io::Error| io::Error::new(e.kind(), format!("{}: {}", path.display(), e));
+        let input = BufReader::new(File::open(path).map_err(with_path)?);
+        for entry in Entries::new(input).map_err(with_path)? {
+            total.extend(Some(entry.map_err(with_path)?));
+        }
+    }
+    Ok(total)
+}
+
+/// iterator over entries of a binary result, read one by one
+pub struct Entries<R> {
+    input: R,
+    metadata: Option<Metadata>,
+    remaining: u64,
+}
+
+impl<R: Read> Entries<R> {
+    /// read the header of a binary result
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut header = [0; 5];
+        input.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("not a binary result"));
+        }
+        let metadata = match header[4] {
+            VERSION_WITHOUT_METADATA => None,
+            VERSION => Some(read_metadata(&mut input)?),
+            version => return Err(invalid(&format!("unknown version {}", version))),
+        };
+        let remaining = read_varint(&mut input)?;
+        Ok(Entries {
+            input,
+            metadata,
+            remaining,
+        })
+    }
+
+    /// metadata of the result, `None` if it was written without
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_ref()
+    }
+
+    /// count of entries not read yet
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    fn entry(&mut self) -> io::Result<(String, usize)> {
+        let token = read_string(&mut self.input).map_err(|e| match e.kind() {
+            io::ErrorKind::InvalidData => invalid("key is not UTF-8"),
+            _ => e,
+        })?;
+        let count = read_varint(&mut self.input)?;
+        if count > usize::MAX as u64 {
+            return Err(invalid("count is too large"));
+        }
+        Ok((token, count as usize))
+    }
+}
+
+impl<R: Read> Iterator for Entries<R> {
+    type Item = io::Result<(String, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let entry = self.entry();
+        if entry.is_err() {
+            self.remaining = 0;
+        }
+        Some(entry)
+    }
+}
+
+fn read_metadata(input: &mut impl Read) -> io::Result<Metadata> {
+    let version = read_string(input)?;
+    let mode = read_string(input)?;
+    let config = read_varint(input)?;
+    let mut inputs = Vec::new();
+    for _ in 0..read_varint(input)? {
+        inputs.push(read_string(input)?);
+    }
+    Ok(Metadata {
+        version,
+        mode,
+        config,
+        inputs,
+        timestamp: read_varint(input)?,
+    })
+}
+
+fn write_string(out: &mut impl Write, s: &str) -> io::Result<()> {
+    write_varint(out, s.len() as u64)?;
+    out.write_all(s.as_bytes())
+}
+
+fn read_string(input: &mut impl Read) -> io::Result<String> {
+    let len = read_varint(input)?;
+    let mut bytes = Vec::new();
+    input.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    String::from_utf8(bytes).map_err(|_| invalid("string is not UTF-8"))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_varint(out: &mut impl Write, mut n: u64) -> io::Result<()> {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            return out.write_all(&[byte]);
+        }
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint(input: &mut impl Read) -> io::Result<u64> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        input.read_exact(&mut byte)?;
+        n |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err(invalid("varint is too long"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn varint_works() {
+        for &n in &[0, 1, 127, 128, 300, u64::from(u32::MAX), u64::MAX] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, n).unwrap();
+            assert_eq!(read_varint(&mut &buf[..]).unwrap(), n);
+        }
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 300).unwrap();
+        assert_eq!(buf, vec![0xac, 0x02]);
+    }
+
+    #[test]
+    fn broken_input_fails() {
+        let mut buf = Vec::new();
+        let freqs: Frequencies = vec![("日本".to_string(), 3)]
+            .into_iter()
+            .collect::<std::collections::HashMap<_, _>>()
+            .into();
+        write(&freqs, &mut buf).unwrap();
+
+        let kind = |bytes: &[u8]| read(bytes).unwrap_err().kind();
+        assert_eq!(kind(b"XXXX\x01\x00"), io::ErrorKind::InvalidData);
+        assert_eq!(kind(b"WCNT\x09\x00"), io::ErrorKind::InvalidData);
+        assert_eq!(kind(b"WCNT\x02\x05ab"), io::ErrorKind::UnexpectedEof);
+        assert_eq!(kind(b"WCNT\x02\x01\xff"), io::ErrorKind::InvalidData);
+        assert_eq!(kind(&buf[..buf.len() - 2]), io::ErrorKind::UnexpectedEof);
+        assert_eq!(read(&buf[..]).unwrap()["日本"], 3);
+    }
+
+    #[test]
+    fn entries_are_streamed() {
+        let mut buf = Vec::new();
+        let freqs: Frequencies = vec![("a".to_string(), 2), ("b".to_string(), 1)]
+            .into_iter()
+            .collect::<std::collections::HashMap<_, _>>()
+            .into();
+        write(&freqs, &mut buf).unwrap();
+
+        let mut entries = Entries::new(&buf[..]).unwrap();
+        assert_eq!(entries.remaining(), 2);
+        assert_eq!(entries.next().unwrap().unwrap(), ("a".to_string(), 2));
+        assert_eq!(entries.remaining(), 1);
+    }
+
+    #[test]
+    fn broken_files_fail_merging() {
+        let path = std::env::temp_dir().join("wordcount_binary_broken.bin");
+        std::fs::write(&path, b"WCNT\x01\x02\x01a\x01").unwrap();
+        let error = merge_files(&[&path]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(error.to_string().starts_with(&path.display().to_string()));
+        assert_eq!(merge_files::<&Path>(&[]).unwrap(), Frequencies::new());
+    }
+}
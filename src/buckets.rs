@@ -0,0 +1,171 @@
+//! count of distinct tokens by ranges of frequency, see [`Buckets`](struct.Buckets.html)
+
+use std::fmt;
+use std::hash::Hash;
+
+use crate::Frequencies;
+
+/// upper bounds of the standard buckets: 1, 2-5, 6-10, 11-100 and more than 100
+pub const STANDARD: &[usize] = &[1, 5, 10, 100];
+
+/// a range of counts and how many tokens are counted in it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bucket {
+    /// least count
+    pub min: usize,
+    /// greatest count, `None` for no bound
+    pub max: Option<usize>,
+    /// count of distinct tokens
+    pub tokens: usize,
+}
+
+impl fmt::Display for Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{}", max),
+            Some(max) => write!(f, "{}-{}", self.min, max),
+            None => write!(f, "{}+", self.min),
+        }
+    }
+}
+
+/// how many distinct tokens are counted once, 2 to 5 times and so on
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::buckets::Buckets;
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let freqs = count_str("a a a b b c d", CountOption::Word);
+/// let buckets = Buckets::of(&freqs);
+/// let tokens: Vec<_> = buckets.iter().map(|bucket| bucket.tokens).collect();
+/// assert_eq!(tokens, vec![2, 2, 0, 0, 0]);
+/// assert_eq!(buckets.to_string().lines().nth(2), Some("2-5          2  50.0%"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Buckets {
+    buckets: Vec<Bucket>,
+}
+
+impl Buckets {
+    /// buckets of [`STANDARD`](constant.STANDARD.html) bounds
+    pub fn of<K: Eq + Hash>(freqs: &Frequencies<K>) -> Self {
+        Buckets::with_bounds(freqs, STANDARD)
+    }
+
+    /// buckets up to each of ascending `bounds`, and a bucket above the last bound
+    pub fn with_bounds<K: Eq + Hash>(freqs: &Frequencies<K>, bounds: &[usize]) -> Self {
+        let mut buckets = Vec::with_capacity(bounds.len() + 1);
+        let mut min = 1;
+        for &bound in bounds.iter().filter(|&&bound| bound > 0) {
+            if bound >= min {
+                buckets.push(Bucket {
+                    min,
+                    max: Some(bound),
+                    tokens: 0,
+                });
+                min = bound + 1;
+            }
+        }
+        buckets.push(Bucket {
+            min,
+            max: None,
+            tokens: 0,
+        });
+        for &n in freqs.values().filter(|&&n| n > 0) {
+            let i = buckets
+                .iter()
+                .position(|bucket| bucket.max.map_or(true, |max| n <= max))
+                .unwrap_or(buckets.len() - 1);
+            buckets[i].tokens += 1;
+        }
+        Buckets { buckets }
+    }
+
+    /// buckets in ascending order of counts
+    pub fn iter(&self) -> impl Iterator<Item = &Bucket> {
+        self.buckets.iter()
+    }
+
+    /// count of distinct tokens in all buckets
+    pub fn tokens(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.tokens).sum()
+    }
+}
+
+/// a table of ranges, counts of tokens and shares of tokens with aligned columns
+impl fmt::Display for Buckets {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let total = self.tokens();
+        let names: Vec<_> = self.buckets.iter().map(Bucket::to_string).collect();
+        let width = names
+            .iter()
+            .map(String::len)
+            .chain(Some("count".len()))
+            .max()
+            .unwrap_or(0);
+        writeln!(f, "{:<w$}  tokens  share", "count", w = width)?;
+        for (name, bucket) in names.iter().zip(&self.buckets) {
+            let share = if total == 0 {
+                0.0
+            } else {
+                bucket.tokens as f64 * 100.0 / total as f64
+            };
+            writeln!(
+                f,
+                "{:<w$}  {:>6}  {:>4.1}%",
+                name,
+                bucket.tokens,
+                share,
+                w = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn freqs(counts: &[usize]) -> Frequencies {
+        let map: HashMap<_, _> = counts
+            .iter()
+            .enumerate()
+            .map(|(i, &n)| (i.to_string(), n))
+            .collect();
+        map.into()
+    }
+
+    #[test]
+    fn standard_buckets_work() {
+        let buckets = Buckets::of(&freqs(&[1, 2, 5, 6, 10, 11, 100, 101, 5000]));
+        let tokens: Vec<_> = buckets.iter().map(|bucket| bucket.tokens).collect();
+        assert_eq!(tokens, vec![1, 2, 2, 2, 2]);
+        let names: Vec<_> = buckets.iter().map(Bucket::to_string).collect();
+        assert_eq!(names, vec!["1", "2-5", "6-10", "11-100", "101+"]);
+        assert_eq!(buckets.tokens(), 9);
+        assert_eq!(
+            Buckets::of(&freqs(&[])).to_string(),
+            "count   tokens  share\n\
+             1            0   0.0%\n\
+             2-5          0   0.0%\n\
+             6-10         0   0.0%\n\
+             11-100       0   0.0%\n\
+             101+         0   0.0%\n"
+        );
+    }
+
+    #[test]
+    fn with_bounds_works() {
+        let freqs = freqs(&[1, 2, 3, 0]);
+        // unsorted and zero bounds are skipped
+        let buckets = Buckets::with_bounds(&freqs, &[0, 2, 1]);
+        let names: Vec<_> = buckets.iter().map(Bucket::to_string).collect();
+        assert_eq!(names, vec!["1-2", "3+"]);
+        assert_eq!(buckets.tokens(), 3);
+        let all = Buckets::with_bounds(&freqs, &[]);
+        assert_eq!(all.iter().next().unwrap().tokens, 3);
+    }
+}
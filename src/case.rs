@@ -0,0 +1,264 @@
+//! letter case of tokens
+
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use crate::decode::{read_lines, strip_bom, Transcoder};
+use crate::tally::Tally;
+use crate::{tokens, word_regex, CountConfig, CountError, CountOption, Frequencies};
+
+/// letter case of a word
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Case {
+    /// no uppercase letters, like "rust" (or words without letters, like "42")
+    Lower,
+    /// only the first letter is uppercase, like "Rust"
+    Title,
+    /// two or more letters, all uppercase, like "RUST"
+    Upper,
+    /// other, like "iPhone" or "RuSt"
+    Mixed,
+}
+
+impl Case {
+    /// letter case of `word`
+    pub fn of(word: &str) -> Case {
+        let mut letters = word
+            .chars()
+            .filter(|c| c.is_lowercase() || c.is_uppercase());
+        let first_upper = match letters.next() {
+            Some(c) => c.is_uppercase(),
+            None => return Case::Lower,
+        };
+        let (mut upper, mut lower) = (0, 0);
+        for c in letters {
+            if c.is_uppercase() {
+                upper += 1;
+            } else {
+                lower += 1;
+            }
+        }
+        match (first_upper, upper, lower) {
+            (false, 0, _) => Case::Lower,
+            (true, 0, _) => Case::Title,
+            (true, _, 0) => Case::Upper,
+            _ => Case::Mixed,
+        }
+    }
+}
+
+/// count of occurrences by [`Case`](enum.Case.html)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CaseCounts {
+    /// [`Case::Lower`](enum.Case.html#variant.Lower)
+    pub lower: usize,
+    /// [`Case::Title`](enum.Case.html#variant.Title)
+    pub title: usize,
+    /// [`Case::Upper`](enum.Case.html#variant.Upper)
+    pub upper: usize,
+    /// [`Case::Mixed`](enum.Case.html#variant.Mixed)
+    pub mixed: usize,
+}
+
+impl CaseCounts {
+    /// sum of all cases
+    pub fn total(&self) -> usize {
+        self.lower + self.title + self.upper + self.mixed
+    }
+
+    /// the word is written in more than one case
+    pub fn is_inconsistent(&self) -> bool {
+        [self.lower, self.title, self.upper, self.mixed]
+            .iter()
+            .filter(|&&n| n > 0)
+            .count()
+            > 1
+    }
+
+    fn add(&mut self, case: Case) {
+        match case {
+            Case::Lower => self.lower += 1,
+            Case::Title => self.title += 1,
+            Case::Upper => self.upper += 1,
+            Case::Mixed => self.mixed += 1,
+        }
+    }
+}
+
+/// count of words (regex "\w+") by letter case, keyed by the lowercase word
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::case::count_cases;
+/// let cases = count_cases(Cursor::new("Rust rust RUST rust"));
+/// let rust = cases["rust"];
+/// assert_eq!((rust.lower, rust.title, rust.upper), (2, 1, 1));
+/// assert!(rust.is_inconsistent());
+/// ```
+///
+/// # Panics
+///
+/// input is neither UTF-8 nor UTF-16 with a byte order mark
+pub fn count_cases(input: impl BufRead) -> HashMap<String, CaseCounts> {
+    let re = word_regex();
+    let mut cases: HashMap<String, CaseCounts> = HashMap::new();
+
+    for (i, line) in Transcoder::new(input).lines().enumerate() {
+        let line = line.unwrap();
+        let line = if i == 0 { strip_bom(&line) } else { &line };
+        tokens(&re, line, &CountOption::Word, |word, _| {
+            cases
+                .entry(word.to_lowercase())
+                .or_default()
+                .add(Case::of(word));
+        });
+    }
+    cases
+}
+
+/// same as [`try_count_with`](../fn.try_count_with.html), but also counts of each original
+/// spelling of each key, such as of "HTTP", "Http" and "http" folded to "http" by
+/// [`ignore_case`](../config/struct.CountConfig.html#method.ignore_case). spellings are keyed
+/// by the keys of the result, which are their preferred spellings with
+/// [`original_case`](../config/struct.CountConfig.html#method.original_case)
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::case::count_spellings;
+/// use bicycle_book_wordcount::CountConfig;
+/// let config = CountConfig::default().ignore_case(true);
+/// let input = Cursor::new("HTTP and http, HTTP or Http");
+/// let (freqs, spellings) = count_spellings(input, &config).unwrap();
+/// assert_eq!(freqs["http"], 4);
+/// assert_eq!(spellings["http"]["HTTP"], 2);
+/// assert_eq!(spellings["http"]["Http"], 1);
+/// assert_eq!(spellings["and"].len(), 1);
+/// ```
+pub fn count_spellings(
+    input: impl BufRead,
+    config: &CountConfig,
+) -> Result<(Frequencies, HashMap<String, Frequencies>), CountError> {
+    let mut tally = Tally::with_spellings(config);
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        tally.line(line)
+    })?;
+    Ok(tally.finish_with_spellings())
+}
+
+/// original spellings of case-folded keys
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Spellings {
+    variants: HashMap<String, HashMap<String, usize>>,
+}
+
+impl Spellings {
+    /// record `n` occurrences of `original`, folded to `key`
+    pub(crate) fn add(&mut self, key: &str, original: &str, n: usize) {
+        let variants = match self.variants.get_mut(key) {
+            Some(variants) => variants,
+            None => self.variants.entry(key.to_string()).or_default(),
+        };
+        match variants.get_mut(original) {
+            Some(count) => *count = count.saturating_add(n),
+            None => {
+                variants.insert(original.to_string(), n);
+            }
+        }
+    }
+
+    /// original spellings of `key` and their counts
+    pub fn variants(&self, key: &str) -> Option<&HashMap<String, usize>> {
+        self.variants.get(key)
+    }
+
+    /// spellings of every key as results
+    pub(crate) fn to_frequencies(&self) -> HashMap<String, Frequencies> {
+        self.variants
+            .iter()
+            .map(|(key, variants)| (key.clone(), variants.clone().into()))
+            .collect()
+    }
+
+    /// forget spellings of `key`
+    pub(crate) fn remove(&mut self, key: &str) {
+        self.variants.remove(key);
+    }
+
+    /// most frequent original spelling of `key`, ties are broken by the smallest spelling
+    pub fn preferred(&self, key: &str) -> Option<&str> {
+        self.variants.get(key).and_then(|variants| {
+            variants
+                .iter()
+                .max_by(|(a, x), (b, y)| x.cmp(y).then_with(|| b.cmp(a)))
+                .map(|(spelling, _)| spelling.as_str())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn case_of_works() {
+        assert_eq!(Case::of("rust"), Case::Lower);
+        assert_eq!(Case::of("42"), Case::Lower);
+        assert_eq!(Case::of("Rust"), Case::Title);
+        assert_eq!(Case::of("A"), Case::Title);
+        assert_eq!(Case::of("RUST2018"), Case::Upper);
+        assert_eq!(Case::of("iPhone"), Case::Mixed);
+        assert_eq!(Case::of("HTTPs"), Case::Mixed);
+    }
+
+    #[test]
+    fn count_cases_works() {
+        use std::io::Cursor;
+        let cases = count_cases(Cursor::new("The cat\nthe CAT iPhone"));
+
+        assert_eq!(cases.len(), 3);
+        assert_eq!(
+            cases["the"],
+            CaseCounts {
+                lower: 1,
+                title: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(cases["iphone"].mixed, 1);
+        assert!(!cases["iphone"].is_inconsistent());
+        assert_eq!(cases["cat"].total(), 2);
+    }
+
+    #[test]
+    fn preferred_works() {
+        let mut spellings = Spellings::default();
+        for s in &["nasa", "NASA", "Nasa", "NASA"] {
+            spellings.add("nasa", s, 1);
+        }
+        spellings.add("go", "Go", 1);
+        spellings.add("go", "GO", 1);
+
+        assert_eq!(spellings.preferred("nasa"), Some("NASA"));
+        assert_eq!(spellings.preferred("go"), Some("GO"));
+        assert_eq!(spellings.preferred("rust"), None);
+        assert_eq!(spellings.variants("go").map(|v| v.len()), Some(2));
+    }
+
+    #[test]
+    fn spellings_are_keyed_by_preferred_spellings() {
+        let config = CountConfig::default().ignore_case(true).original_case(true);
+        let input: &[u8] = b"NASA nasa NASA caf\xc3\xa9";
+        let (freqs, spellings) = count_spellings(input, &config).unwrap();
+        assert_eq!(freqs["NASA"], 3);
+        assert_eq!(spellings["NASA"]["nasa"], 1);
+        assert_eq!(spellings["NASA"]["NASA"], 2);
+        let config = config.original_case(false).strip_diacritics(true);
+        let (freqs, spellings) = count_spellings(input, &config).unwrap();
+        assert_eq!(freqs["cafe"], 1);
+        assert_eq!(spellings["cafe"]["café"], 1);
+    }
+}
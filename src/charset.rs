@@ -0,0 +1,300 @@
+//! detection of the charset of input by its content, see [`detect`](fn.detect.html)
+//!
+//! input which is not valid UTF-8 (nor UTF-16 with a byte order mark) is guessed to be
+//! Shift_JIS or Windows-1252 (Latin-1 as written on Windows), whichever decodes it more
+//! plausibly.
+//! available with the feature `detect-encoding`.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+
+use crate::decode::{decode_utf16, DecodePolicy, Encoding};
+use crate::sjis;
+use crate::{try_count_with, CountConfig, CountError, Frequencies};
+
+/// a charset detected by [`detect`](fn.detect.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Charset {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// Shift_JIS, as extended by Windows (Windows-31J)
+    ShiftJis,
+    /// Windows-1252, every byte is a char: ISO-8859-1 but for printable chars such as `€` and
+    /// curly quotes in 0x80–0x9F (the 5 bytes unassigned there are the C1 controls)
+    Windows1252,
+}
+
+impl Charset {
+    /// name of the charset registered to IANA
+    pub fn name(self) -> &'static str {
+        match self {
+            Charset::Utf8 => "UTF-8",
+            Charset::Utf16Le => "UTF-16LE",
+            Charset::Utf16Be => "UTF-16BE",
+            Charset::ShiftJis => "Shift_JIS",
+            Charset::Windows1252 => "windows-1252",
+        }
+    }
+}
+
+impl fmt::Display for Charset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// charset of `bytes`
+///
+/// a UTF-16 byte order mark or valid UTF-8 wins. otherwise it is Shift_JIS if every byte
+/// decodes to a mapped char and two-byte characters are at least as many as half-width katakana
+/// (which are accented letters in Windows-1252), or Windows-1252.
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::charset::{detect, Charset};
+/// assert_eq!(detect("日本語".as_bytes()), Charset::Utf8);
+/// assert_eq!(detect(b"\x93\xfa\x96\x7b\x8c\xea"), Charset::ShiftJis);
+/// assert_eq!(detect(b"caf\xe9 cr\xe8me"), Charset::Windows1252);
+/// ```
+pub fn detect(bytes: &[u8]) -> Charset {
+    sniff(bytes, true)
+}
+
+/// charset of `bytes`, which are the whole input if `whole`, or else a prefix of it, which
+/// may end in the middle of a char
+fn sniff(bytes: &[u8], whole: bool) -> Charset {
+    match Encoding::detect(bytes) {
+        Encoding::Utf16Le => return Charset::Utf16Le,
+        Encoding::Utf16Be => return Charset::Utf16Be,
+        Encoding::Utf8 => {}
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return Charset::Utf8,
+        Err(e) if !whole && e.error_len().is_none() => return Charset::Utf8,
+        Err(_) => {}
+    }
+    let (mut doubles, mut kana) = (0, 0);
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        if sjis::is_lead(byte) {
+            match bytes
+                .get(i + 1)
+                .and_then(|&trail| sjis::double(byte, trail))
+            {
+                // private use chars are rather garbage
+                Some(c) if !('\u{e000}'..='\u{f8ff}').contains(&c) => doubles += 1,
+                None if !whole && i + 1 == bytes.len() => {}
+                _ => return Charset::Windows1252,
+            }
+            i += 2;
+        } else {
+            match sjis::single(byte) {
+                Some(c) if !c.is_ascii() => kana += 1,
+                Some(_) => {}
+                None => return Charset::Windows1252,
+            }
+            i += 1;
+        }
+    }
+    if doubles >= kana {
+        Charset::ShiftJis
+    } else {
+        Charset::Windows1252
+    }
+}
+
+/// chars of Windows-1252 0x80–0x9F, where it differs from ISO-8859-1
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+/// decode `bytes` of a single-byte or Shift_JIS `charset` into `text`. a lead byte at the end
+/// is left undecoded unless `last`, and the number of bytes left is returned
+fn decode_into(bytes: &[u8], charset: Charset, last: bool, text: &mut String) -> usize {
+    if charset == Charset::Windows1252 {
+        text.extend(bytes.iter().map(|&b| match b {
+            0x80..=0x9f => WINDOWS_1252[b as usize - 0x80],
+            b => b as char,
+        }));
+        return 0;
+    }
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        if !last && i + 1 == bytes.len() && sjis::is_lead(byte) {
+            return 1;
+        }
+        let pair = bytes.get(i + 1).filter(|_| sjis::is_lead(byte));
+        match pair.and_then(|&trail| sjis::double(byte, trail)) {
+            Some(c) => {
+                text.push(c);
+                i += 2;
+            }
+            None => {
+                text.push(sjis::single(byte).unwrap_or(char::REPLACEMENT_CHARACTER));
+                i += 1;
+            }
+        }
+    }
+    0
+}
+
+/// `bytes` in `charset` decoded to a string, broken sequences are replaced by U+FFFD.
+/// a byte order mark is removed
+pub fn decode(bytes: &[u8], charset: Charset) -> String {
+    match charset {
+        Charset::Utf8 => {
+            let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+        Charset::Utf16Le | Charset::Utf16Be => {
+            let encoding = Encoding::detect(bytes);
+            decode_utf16(&bytes[2..], encoding, DecodePolicy::Lossy).unwrap_or_default()
+        }
+        Charset::Windows1252 | Charset::ShiftJis => {
+            let mut text = String::with_capacity(bytes.len());
+            decode_into(bytes, charset, true, &mut text);
+            text
+        }
+    }
+}
+
+/// bytes of input read to detect its charset
+const SNIFF_BYTES: usize = 64 * 1024;
+
+/// UTF-8 of input in a single-byte or Shift_JIS charset, decoded as it is read
+struct Decoded<R> {
+    inner: R,
+    charset: Charset,
+    /// a lead byte at the end of the last read
+    undecoded: Vec<u8>,
+    text: String,
+    pos: usize,
+}
+
+impl<R: BufRead> Read for Decoded<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let text = self.fill_buf()?;
+            let n = text.len().min(buf.len());
+            buf[..n].copy_from_slice(&text[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Decoded<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.pos == self.text.len() {
+            self.text.clear();
+            self.pos = 0;
+            let chunk = self.inner.fill_buf()?;
+            let last = chunk.is_empty();
+            if last && self.undecoded.is_empty() {
+                break;
+            }
+            self.undecoded.extend_from_slice(chunk);
+            let n = chunk.len();
+            self.inner.consume(n);
+            let left = decode_into(&self.undecoded, self.charset, last, &mut self.text);
+            self.undecoded.drain(..self.undecoded.len() - left);
+        }
+        Ok(&self.text.as_bytes()[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.text.len());
+    }
+}
+
+/// same as [`try_count_with`](../fn.try_count_with.html), but `input` is decoded from the
+/// charset [`detect`](fn.detect.html)ed from its first 64 KiB, as it is read
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::charset::{count_detected, Charset};
+/// use bicycle_book_wordcount::CountConfig;
+/// let input: &[u8] = b"caf\xe9 caf\xe9 th\xe9";
+/// let (freqs, charset) = count_detected(input, &CountConfig::default()).unwrap();
+/// assert_eq!(charset, Charset::Windows1252);
+/// assert_eq!(freqs["café"], 2);
+/// ```
+pub fn count_detected(
+    mut input: impl Read,
+    config: &CountConfig,
+) -> Result<(Frequencies, Charset), CountError> {
+    let mut prefix = Vec::new();
+    input
+        .by_ref()
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut prefix)?;
+    let charset = sniff(&prefix, prefix.len() < SNIFF_BYTES);
+    let input = BufReader::new(Cursor::new(prefix).chain(input));
+    let freqs = match charset {
+        // counted as usual, so that the policy of invalid input applies
+        Charset::Utf8 | Charset::Utf16Le | Charset::Utf16Be => try_count_with(input, config)?,
+        Charset::ShiftJis | Charset::Windows1252 => {
+            let decoded = Decoded {
+                inner: input,
+                charset,
+                undecoded: Vec::new(),
+                text: String::new(),
+                pos: 0,
+            };
+            try_count_with(decoded, config)?
+        }
+    };
+    Ok((freqs, charset))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detect_works() {
+        assert_eq!(detect(b"plain"), Charset::Utf8);
+        assert_eq!(detect(b"\xff\xfea\x00"), Charset::Utf16Le);
+        // "アイウ" in half-width katakana reads better as Windows-1252
+        assert_eq!(detect(b"\xb1\xb2\xb3"), Charset::Windows1252);
+        // a lead byte at the end
+        assert_eq!(detect(b"\x82\xa0\x82"), Charset::Windows1252);
+        assert_eq!(sniff(b"\x82\xa0\x82", false), Charset::ShiftJis);
+        assert_eq!(sniff("日本".as_bytes().split_at(4).0, false), Charset::Utf8);
+        assert_eq!(detect(b"\x82\xa0\x82\xa2 abc"), Charset::ShiftJis);
+    }
+
+    #[test]
+    fn decode_works() {
+        assert_eq!(
+            decode(b"\x82\xa0\xb1a\x82", Charset::ShiftJis),
+            "あｱa\u{fffd}"
+        );
+        assert_eq!(decode(b"\xe9t\xe9", Charset::Windows1252), "été");
+        assert_eq!(
+            decode(b"\x93quoted\x94 \x80\x81", Charset::Windows1252),
+            "“quoted” €\u{81}"
+        );
+        assert_eq!(decode(b"\xef\xbb\xbfa", Charset::Utf8), "a");
+        assert_eq!(decode(b"\xfe\xff\x00a", Charset::Utf16Be), "a");
+    }
+
+    #[test]
+    fn input_past_the_sniffed_prefix_is_decoded() {
+        // a lead byte of Shift_JIS at the end of the prefix
+        let mut input = b"\x82\xa0".repeat(SNIFF_BYTES / 2 - 1);
+        input.extend_from_slice(b" \x82\xa2\n\x82\xa2");
+        let config = CountConfig::new(crate::CountOption::Line);
+        let (freqs, charset) = count_detected(&input[..], &config).unwrap();
+        assert_eq!(charset, Charset::ShiftJis);
+        assert_eq!(freqs["い"], 1);
+        assert_eq!(freqs.len(), 2);
+    }
+}
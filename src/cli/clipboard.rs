@@ -0,0 +1,71 @@
+//! text of the clipboard, read by a command of the platform
+
+use std::io;
+use std::process::{Command, Stdio};
+
+/// commands printing the clipboard, tried in order
+#[cfg(target_os = "macos")]
+const COMMANDS: &[&[&str]] = &[&["pbpaste"]];
+
+#[cfg(windows)]
+const COMMANDS: &[&[&str]] = &[&["powershell", "-NoProfile", "-Command", "Get-Clipboard -Raw"]];
+
+#[cfg(not(any(target_os = "macos", windows)))]
+const COMMANDS: &[&[&str]] = &[
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-selection", "clipboard", "-o"],
+    &["xsel", "--clipboard", "--output"],
+];
+
+/// text of the clipboard, by the first of the commands found
+pub fn read() -> io::Result<String> {
+    read_with(COMMANDS)
+}
+
+fn read_with(commands: &[&[&str]]) -> io::Result<String> {
+    for command in commands {
+        let output = match Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+        {
+            Ok(output) => output,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        if !output.status.success() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("{} failed: {}", command[0], output.status),
+            ));
+        }
+        return String::from_utf8(output.stdout)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+    }
+    let names: Vec<_> = commands.iter().map(|command| command[0]).collect();
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("no command to read the clipboard: {}", names.join(", ")),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn missing_commands_are_skipped() {
+        let error = read_with(&[&["wordcount-no-such-command"]]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(error.to_string().ends_with("wordcount-no-such-command"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_of_the_first_command_found() {
+        let text = read_with(&[&["wordcount-no-such-command"], &["echo", "copied text"]]).unwrap();
+        assert_eq!(text, "copied text\n");
+        assert!(read_with(&[&["false"]]).is_err());
+    }
+}
@@ -0,0 +1,1936 @@
+//! subcommands composing through saved counts (JSON made by `count` or `merge`)
+//!
+//! * `count [--format json|jsonl|debug|csv|latex|markdown|html|trie|snapshot|parquet] [-o FILE]
+//!   [--config FILE] [--include-binary] [--detect-encoding] [--sample RATE [--seed N]] FILE...`:
+//!   counts of files (or files under directories, but binary ones unless `--include-binary`),
+//!   merged. `--detect-encoding` guesses
+//!   the charset of each file and prints it to stderr (with the feature `detect-encoding`).
+//!   `--sample` counts lines picked at random with probability RATE, and scales counts by
+//!   1 / RATE. `--df FILE` also writes the count of files each token appears in to FILE.
+//!   `--format parquet` (with the feature `parquet`) writes columns of tokens and counts, and of
+//!   files too with `--per-file`. `--format bulk [--index NAME] [--id TEMPLATE]` writes
+//!   Elasticsearch bulk requests, see `Bulk`. `--columns percent,cumulative|none` and
+//!   `--precision N` choose shares in tables and JSON Lines. `--metadata` writes the version,
+//!   the mode, the hash of the configuration, the inputs and the time with the total in JSON or
+//!   CSV, or in the binary format to `-o FILE.bin`. `-n N` writes only the N most frequent
+//!   tokens of the total. see [`settings`](../settings/index.html)
+//! * `top [-n N] [--percent] [--format FORMAT] FILE`: the N most frequent tokens, with counts
+//!   or shares in percent, or in a format of `count` (with the column of shares by
+//!   `--percent`, of tables and JSON Lines). `top --bytes` is of the N tokens taking
+//!   the most bytes (count × length in UTF-8) instead, with the bytes and their shares
+//! * `merge [-o FILE] FILE...`: sum of counts, with metadata if some of saved counts have it.
+//!   counts of another mode or configuration than others are not merged
+//! * `diff OLD NEW`: changes of counts
+//! * `compare [-n N] A B`: similarity (Jaccard index, weighted by counts, and cosine) of two
+//!   counts, tokens only in either and shared ones with both counts by divergence of shares
+//!   (up to N of each)
+//! * `unknown [--config FILE] DICTIONARY FILE...`: counts of tokens of files not in the
+//!   dictionary (a word per line), the most frequent first. the dictionary is counted by the
+//!   same settings, so that e.g. `ignore_case` applies to it
+//! * `stats FILE...`: statistics of lines, and how many top words cover 50%, 80%, ... of words.
+//!   a FILE `-` is stdin
+//! * `progress [--config FILE] [--save] BASELINE FILE...`: words added and removed per file
+//!   since the baseline (counts of files as lines of JSON, as written by `count --per-file`),
+//!   which is saved instead if it does not exist or `--save`
+//!
+//! a FILE with the extension `.json` is read as saved counts, others are counted as text.
+//! `count` reads saved counts (of `.json` and `.bin`) only with `--saved`, not to take e.g.
+//! `package.json` under a directory for counts.
+//! `count` and `merge` go on past files failing to be read, see [`Report`](struct.Report.html).
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use bicycle_book_wordcount::accesslog::{count_field, Field};
+use bicycle_book_wordcount::binary::{self, Entries};
+use bicycle_book_wordcount::buckets::Buckets;
+#[cfg(feature = "detect-encoding")]
+use bicycle_book_wordcount::charset;
+use bicycle_book_wordcount::corpus::Corpus;
+use bicycle_book_wordcount::coverage::Coverage;
+use bicycle_book_wordcount::diff::{Comparison, Diff};
+use bicycle_book_wordcount::duplicate::Duplicates;
+use bicycle_book_wordcount::encode::{Encoder, OutputEncoding};
+#[cfg(feature = "epub")]
+use bicycle_book_wordcount::epub;
+use bicycle_book_wordcount::extension::Extensions;
+use bicycle_book_wordcount::footprint::Footprint;
+use bicycle_book_wordcount::format::{Formats, OutputFormat};
+use bicycle_book_wordcount::metadata::Metadata;
+use bicycle_book_wordcount::output::{
+    read_file_json, read_json_with_metadata, write_csv_with_metadata, write_file_json,
+    write_json_with_metadata, Bulk, Columns, NumberStyle,
+};
+use bicycle_book_wordcount::paragraph::Paragraphs;
+use bicycle_book_wordcount::parallel::{self, Pool, Threads};
+#[cfg(feature = "parquet")]
+use bicycle_book_wordcount::parquet;
+use bicycle_book_wordcount::position::ColumnHistogram;
+use bicycle_book_wordcount::progress::since;
+use bicycle_book_wordcount::quality::check_quality;
+use bicycle_book_wordcount::sample::count_sampled;
+use bicycle_book_wordcount::script::Scripts;
+use bicycle_book_wordcount::similarity;
+use bicycle_book_wordcount::stats::try_count_with_stats;
+use bicycle_book_wordcount::structured::count_key;
+use bicycle_book_wordcount::subtitle::count_subtitles;
+use bicycle_book_wordcount::typo::TypoFolding;
+use bicycle_book_wordcount::uniq::Runs;
+use bicycle_book_wordcount::walk::Symlinks;
+use bicycle_book_wordcount::{
+    try_count_with, walk, CountConfig, CountError, CountOption, Frequencies,
+};
+use regex::Regex;
+
+use super::clipboard;
+use super::interrupt::{self, Interruptible, EXIT_INTERRUPTED};
+use super::settings::Settings;
+
+/// names of subcommands
+pub const NAMES: &[&str] = &[
+    "count",
+    "top",
+    "merge",
+    "diff",
+    "compare",
+    "unknown",
+    "stats",
+    "uniq",
+    "duplicates",
+    "columns",
+    "progress",
+];
+
+/// exit code when some files failed to be read but others were counted
+pub const EXIT_PARTIAL: i32 = 3;
+
+/// exit code when no file was counted, or the command failed
+pub const EXIT_FAILURE: i32 = 1;
+
+/// exit code when all files were counted, but counts of words are out of the bounds of
+/// `--min-words` or `--max-words`
+pub const EXIT_OUT_OF_BOUNDS: i32 = 4;
+
+/// files counted at once per thread of `--jobs`, before their counts are passed on
+const FILES_PER_THREAD: usize = 4;
+
+/// formats of `--format`, of the library and any added here
+fn formats() -> &'static Formats {
+    static FORMATS: OnceLock<Formats> = OnceLock::new();
+    FORMATS.get_or_init(Formats::builtin)
+}
+
+/// output format of counts, by the name of one of `formats`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Format(&'static str);
+
+impl Format {
+    pub const JSON: Format = Format("json");
+    /// rows of `token,count`
+    pub const CSV: Format = Format("csv");
+    /// binary columns of tokens and counts (with the feature `parquet`)
+    pub const PARQUET: Format = Format("parquet");
+    /// Elasticsearch bulk requests, see `Bulk`
+    pub const BULK: Format = Format("bulk");
+
+    /// format named `name` of `--format` or the settings file
+    pub fn parse(name: &str) -> Option<Self> {
+        if name == Format::PARQUET.0 {
+            // known without the feature, to tell that it is needed
+            return Some(Format::PARQUET);
+        }
+        formats()
+            .names()
+            .into_iter()
+            .find(|&n| n == name)
+            .map(Format)
+    }
+
+    /// whether the format has derived columns, of `--columns` and `top --percent`
+    fn has_columns(self) -> bool {
+        formats().get(self.0).is_some_and(|f| f.columns().is_some())
+    }
+
+    /// the format of `formats`
+    fn registered(self) -> io::Result<&'static dyn OutputFormat> {
+        match formats().get(self.0) {
+            Some(format) => Ok(format),
+            None if self == Format::PARQUET => Err(parquet_unsupported()),
+            None => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("unknown format {}", self.0),
+            )),
+        }
+    }
+}
+
+/// derived columns of tables and JSON Lines by `--columns` and `--precision`, or the defaults
+/// of formats
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Derived {
+    pub columns: Option<Columns>,
+    pub precision: Option<usize>,
+}
+
+impl Derived {
+    /// each of `self`, or of `other` if not given
+    pub fn or(self, other: Derived) -> Derived {
+        Derived {
+            columns: self.columns.or(other.columns),
+            precision: self.precision.or(other.precision),
+        }
+    }
+
+    /// `self` with the column of shares in percent, and others given
+    fn with_percent(self) -> Derived {
+        let columns = self.columns.unwrap_or_else(Columns::none);
+        Derived {
+            columns: Some(columns.percent(true)),
+            ..self
+        }
+    }
+
+    /// `columns` with the precision of `self`, if given
+    fn with_precision(self, columns: Columns) -> Columns {
+        match self.precision {
+            Some(digits) => columns.precision(digits),
+            None => columns,
+        }
+    }
+}
+
+/// a parsed subcommand
+// made once per process, not worth boxing the options of `Count`
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Count {
+        format: Option<Format>,
+        output: Option<PathBuf>,
+        settings: Option<PathBuf>,
+        include_binary: bool,
+        /// read `.json` and `.bin` files as saved counts, instead of counting them as text
+        saved: bool,
+        /// threads counting files, instead of the settings
+        jobs: Option<usize>,
+        /// how symbolic links under directories are treated, instead of the settings
+        symlinks: Option<Symlinks>,
+        detect_encoding: bool,
+        /// report signs of broken text of each file
+        check_quality: bool,
+        sample: Option<(f64, u64)>,
+        access_log: Option<Field>,
+        /// key of structured logs (logfmt or JSON lines) to count values of
+        log_key: Option<String>,
+        df_output: Option<PathBuf>,
+        /// count the text of the clipboard too
+        clipboard: bool,
+        /// write counts of each file as soon as it is counted, instead of the total
+        per_file: bool,
+        encoding: Option<OutputEncoding>,
+        /// fold typos of the total into much more frequent tokens
+        fold_typos: bool,
+        /// patterns of tokens not to count, besides those of the settings
+        exclude_patterns: Vec<String>,
+        /// on SIGINT, stop counting files and write counts of the files counted
+        partial_on_interrupt: bool,
+        /// write metadata of the run with the total
+        metadata: bool,
+        /// print totals per file extension to stderr
+        by_extension: bool,
+        /// bounds of words of the total, or of each file with `per_file`
+        bounds: WordBounds,
+        /// index and ids of `--format bulk`
+        bulk: Bulk,
+        derived: Derived,
+        /// the most frequent tokens of the total to write, all if not given
+        n: Option<usize>,
+        files: Vec<PathBuf>,
+    },
+    Top {
+        n: usize,
+        percent: bool,
+        /// bytes taken by each token (count × length in UTF-8) and their shares, instead of
+        /// counts
+        bytes: bool,
+        format: Option<Format>,
+        encoding: Option<OutputEncoding>,
+        derived: Derived,
+        /// grouping of digits of lines of counts, by the locale if `None`
+        numbers: Option<NumberStyle>,
+        file: PathBuf,
+    },
+    Merge {
+        output: Option<PathBuf>,
+        files: Vec<PathBuf>,
+    },
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+    },
+    Compare {
+        n: Option<usize>,
+        a: PathBuf,
+        b: PathBuf,
+    },
+    Unknown {
+        settings: Option<PathBuf>,
+        dictionary: PathBuf,
+        files: Vec<PathBuf>,
+    },
+    Stats {
+        /// grouping of digits, by the locale if `None`
+        numbers: Option<NumberStyle>,
+        /// read hard-wrapped paragraphs as lines
+        unwrap: bool,
+        files: Vec<PathBuf>,
+    },
+    /// counts of runs of equal lines of sorted input, stdin if no file or `-`
+    Uniq {
+        file: Option<PathBuf>,
+    },
+    /// lines appearing in more than one file
+    Duplicates {
+        normalize_whitespace: bool,
+        min_chars: Option<usize>,
+        files: Vec<PathBuf>,
+    },
+    /// histogram of columns where a pattern matches in the lines of files, stdin if no file
+    Columns {
+        pattern: String,
+        files: Vec<PathBuf>,
+    },
+    /// words added and removed per file since the baseline, which is saved if it does not
+    /// exist or `save`
+    Progress {
+        settings: Option<PathBuf>,
+        save: bool,
+        baseline: PathBuf,
+        files: Vec<PathBuf>,
+    },
+}
+
+impl Command {
+    /// parse arguments after the subcommand `name`, `None` if they are wrong. paths may be of
+    /// any bytes the platform allows, but options and their values are UTF-8
+    pub fn parse(name: &str, args: impl IntoIterator<Item = OsString>) -> Option<Self> {
+        let mut format = None;
+        let mut output = None;
+        let mut df_output = None;
+        let mut settings = None;
+        let mut n = None;
+        let mut percent = false;
+        let mut bytes = false;
+        let mut include_binary = false;
+        let mut saved = false;
+        let mut symlinks = None;
+        let mut jobs = None;
+        let mut detect_encoding = false;
+        let mut check_quality = false;
+        let mut rate = None;
+        let mut seed = 0;
+        let mut access_log = None;
+        let mut log_key = None;
+        let mut clipboard = false;
+        let mut per_file = false;
+        let mut fold_typos = false;
+        let mut exclude_patterns = Vec::new();
+        let mut partial_on_interrupt = false;
+        let mut metadata = false;
+        let mut by_extension = false;
+        let mut bounds = WordBounds::default();
+        let mut index = None;
+        let mut id = None;
+        let mut numbers = None;
+        let mut encoding = None;
+        let mut normalize_whitespace = false;
+        let mut min_chars = None;
+        let mut save = false;
+        let mut unwrap = false;
+        let mut derived = Derived::default();
+        let mut files = Vec::new();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let option = match arg.to_str() {
+                Some(arg) if arg.starts_with('-') && arg != "-" => arg,
+                _ => {
+                    files.push(PathBuf::from(arg));
+                    continue;
+                }
+            };
+            match option {
+                "--format" if name == "count" || name == "top" => {
+                    format = Some(Format::parse(&string(args.next())?)?)
+                }
+                "--config" if name == "count" || name == "unknown" || name == "progress" => {
+                    settings = Some(PathBuf::from(args.next()?))
+                }
+                "--include-binary" if name == "count" => include_binary = true,
+                "--saved" if name == "count" => saved = true,
+                "--jobs" | "-j" if name == "count" => {
+                    jobs = Some(string(args.next())?.parse().ok().filter(|&n| n > 0)?)
+                }
+                "--symlinks" if name == "count" => {
+                    symlinks = Some(string(args.next())?.parse().ok()?)
+                }
+                "--detect-encoding" if name == "count" => detect_encoding = true,
+                "--check-quality" if name == "count" => check_quality = true,
+                "--sample" if name == "count" => {
+                    let r: f64 = string(args.next())?.parse().ok()?;
+                    if !(r > 0.0 && r <= 1.0) {
+                        return None;
+                    }
+                    rate = Some(r)
+                }
+                "--seed" if name == "count" => seed = string(args.next())?.parse().ok()?,
+                "--df" if name == "count" => df_output = Some(PathBuf::from(args.next()?)),
+                "--clipboard" if name == "count" => clipboard = true,
+                "--per-file" if name == "count" => per_file = true,
+                "--fold-typos" if name == "count" => fold_typos = true,
+                "--exclude-pattern" if name == "count" => {
+                    exclude_patterns.push(string(args.next())?)
+                }
+                "--partial-on-interrupt" if name == "count" => partial_on_interrupt = true,
+                "--metadata" if name == "count" => metadata = true,
+                "--by-extension" if name == "count" => by_extension = true,
+                "--min-words" if name == "count" => {
+                    bounds.min = Some(string(args.next())?.parse().ok()?)
+                }
+                "--max-words" if name == "count" => {
+                    bounds.max = Some(string(args.next())?.parse().ok()?)
+                }
+                "--index" if name == "count" => index = Some(string(args.next())?),
+                "--id" if name == "count" => id = Some(string(args.next())?),
+                "--columns" if name == "count" || name == "top" => {
+                    derived.columns = Some(string(args.next())?.parse().ok()?)
+                }
+                "--precision" if name == "count" || name == "top" => {
+                    derived.precision = Some(string(args.next())?.parse().ok()?)
+                }
+                "--encoding" if name == "count" || name == "top" => {
+                    encoding = Some(string(args.next())?.parse().ok()?)
+                }
+                "--access-log" if name == "count" => {
+                    access_log = Some(string(args.next())?.parse().ok()?)
+                }
+                "--log-key" if name == "count" => log_key = Some(string(args.next())?),
+                "-o" | "--output" if name == "count" || name == "merge" => {
+                    output = Some(PathBuf::from(args.next()?))
+                }
+                "-n" if name == "top" || name == "compare" || name == "count" => {
+                    n = Some(string(args.next())?.parse().ok()?)
+                }
+                "--percent" if name == "top" => percent = true,
+                "--bytes" if name == "top" => bytes = true,
+                "--numbers" if name == "top" || name == "stats" => {
+                    numbers = match string(args.next())?.as_str() {
+                        "locale" => None,
+                        style => Some(style.parse().ok()?),
+                    }
+                }
+                "--normalize-whitespace" if name == "duplicates" => normalize_whitespace = true,
+                "--min-chars" if name == "duplicates" => {
+                    min_chars = Some(string(args.next())?.parse().ok()?)
+                }
+                "--save" if name == "progress" => save = true,
+                "--unwrap" if name == "stats" => unwrap = true,
+                _ => return None,
+            }
+        }
+
+        let mut bulk = index.as_deref().map_or_else(Bulk::default, Bulk::new);
+        match id.as_deref() {
+            // ids generated by Elasticsearch
+            Some("") => bulk = bulk.id(None),
+            Some(template) => bulk = bulk.id(Some(template)),
+            None => {}
+        }
+
+        let command = match (name, files.len()) {
+            ("count", _)
+                if per_file
+                    && (df_output.is_some()
+                        || format.is_some_and(|f| f != Format::PARQUET && f != Format::BULK)
+                        || fold_typos
+                        || metadata) =>
+            {
+                return None
+            }
+            ("count", _) if access_log.is_some() && log_key.is_some() => return None,
+            ("count", _) if (index.is_some() || id.is_some()) && format != Some(Format::BULK) => {
+                return None
+            }
+            // these write all tokens
+            ("count", _)
+                if n.is_some()
+                    && (per_file
+                        || metadata
+                        || format == Some(Format::BULK)
+                        || output.as_deref().is_some_and(is_binary_result)) =>
+            {
+                return None
+            }
+            ("top", _) if percent && format.is_some_and(|f| !f.has_columns()) => return None,
+            ("count", len) if len > 0 || clipboard => Command::Count {
+                format,
+                output,
+                settings,
+                include_binary,
+                saved,
+                jobs,
+                symlinks,
+                detect_encoding,
+                check_quality,
+                sample: rate.map(|rate| (rate, seed)),
+                access_log,
+                log_key,
+                df_output,
+                clipboard,
+                per_file,
+                encoding,
+                fold_typos,
+                exclude_patterns,
+                partial_on_interrupt,
+                metadata,
+                by_extension,
+                bounds,
+                bulk,
+                derived,
+                n,
+                files,
+            },
+            ("top", _) if bytes && (percent || format.is_some()) => return None,
+            ("top", 1) => Command::Top {
+                n: n.unwrap_or(10),
+                percent,
+                bytes,
+                format,
+                encoding,
+                derived,
+                numbers,
+                file: files.remove(0),
+            },
+            ("merge", n) if n > 1 && output.is_none() && is_binary_result(&files[0]) => {
+                Command::Merge {
+                    output: Some(files.remove(0)),
+                    files,
+                }
+            }
+            ("merge", n) if n > 0 => Command::Merge { output, files },
+            ("diff", 2) => Command::Diff {
+                new: files.remove(1),
+                old: files.remove(0),
+            },
+            ("compare", 2) => Command::Compare {
+                n,
+                b: files.remove(1),
+                a: files.remove(0),
+            },
+            ("unknown", n) if n > 1 => Command::Unknown {
+                settings,
+                dictionary: files.remove(0),
+                files,
+            },
+            ("stats", n) if n > 0 => Command::Stats {
+                numbers,
+                unwrap,
+                files,
+            },
+            ("duplicates", n) if n > 1 => Command::Duplicates {
+                normalize_whitespace,
+                min_chars,
+                files,
+            },
+            ("columns", n) if n > 0 => Command::Columns {
+                pattern: files.remove(0).into_os_string().into_string().ok()?,
+                files,
+            },
+            ("progress", n) if n > 1 => Command::Progress {
+                settings,
+                save,
+                baseline: files.remove(0),
+                files,
+            },
+            ("uniq", n) if n < 2 => Command::Uniq {
+                file: files.pop().filter(|file| file.as_os_str() != "-"),
+            },
+            _ => return None,
+        };
+        Some(command)
+    }
+
+    /// run the command, writing to stdout unless an output file is given.
+    /// nothing is written if no file was counted
+    pub fn run(&self) -> io::Result<Report> {
+        let mut report = Report::default();
+        match self {
+            Command::Count {
+                format,
+                output,
+                settings,
+                include_binary,
+                saved,
+                jobs,
+                symlinks,
+                detect_encoding,
+                check_quality,
+                sample,
+                access_log,
+                log_key,
+                df_output,
+                clipboard,
+                per_file,
+                encoding,
+                fold_typos,
+                exclude_patterns,
+                partial_on_interrupt,
+                metadata,
+                by_extension,
+                bounds,
+                bulk,
+                derived,
+                n,
+                files,
+            } => {
+                let mut settings = Settings::find(settings.as_deref())?;
+                settings.detect_encoding |= detect_encoding;
+                settings.check_quality |= check_quality;
+                settings.saved |= saved;
+                settings.symlinks = symlinks.unwrap_or(settings.symlinks);
+                settings.jobs = jobs.unwrap_or(settings.jobs);
+                settings.sample = *sample;
+                settings.access_log = *access_log;
+                if *partial_on_interrupt {
+                    interrupt::catch();
+                }
+                settings.log_key = log_key.clone();
+                for pattern in exclude_patterns {
+                    let pattern = Regex::new(pattern)
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                    settings.config = settings.config.clone().exclude_pattern(pattern);
+                }
+                let encoding = encoding.unwrap_or(settings.output_encoding);
+                if settings.detect_encoding && !cfg!(feature = "detect-encoding") {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        "detecting encodings needs the feature detect-encoding",
+                    ));
+                }
+                let format = format.unwrap_or(settings.format);
+                if format == Format::PARQUET && !cfg!(feature = "parquet") {
+                    return Err(parquet_unsupported());
+                }
+                if *metadata && format != Format::JSON && format != Format::CSV {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "metadata is written only in the formats json and csv",
+                    ));
+                }
+                let mut corpus = Corpus::new();
+                // counts of files in Parquet are written together at the end
+                let mut by_file = Vec::new();
+                let mut stream = match *per_file && format != Format::PARQUET {
+                    true => Some(create(output.as_deref(), encoding)?),
+                    false => None,
+                };
+                let min_count = settings.min_count;
+                let mut out_of_bounds = Vec::new();
+                let mut emit = |file: &Path, freqs: &Frequencies| {
+                    let name = walk::display(file);
+                    if *per_file {
+                        if let Some(violation) = bounds.check(freqs) {
+                            out_of_bounds.push(format!("{}: {}", name, violation));
+                        }
+                    }
+                    match &mut stream {
+                        Some(out) => {
+                            let freqs = freqs.filter(|_, n| n >= min_count);
+                            match format {
+                                Format::BULK => bulk.write_file(&name, &freqs, &mut *out)?,
+                                _ => write_file_json(&name, &freqs, &mut *out)?,
+                            }
+                            out.flush()
+                        }
+                        None if *per_file => {
+                            by_file.push((name.into_owned(), freqs.filter(|_, n| n >= min_count)));
+                            Ok(())
+                        }
+                        None => {
+                            corpus.add(freqs);
+                            Ok(())
+                        }
+                    }
+                };
+                if *clipboard {
+                    let text = clipboard::read()?;
+                    let freqs = try_count_with(text.as_bytes(), &settings.config);
+                    report.record(Path::new("clipboard"), freqs.map_err(io_error), &mut emit)?;
+                }
+                let mut extensions = Extensions::new();
+                let mut emit_file = |file: &Path, freqs: &Frequencies| {
+                    if *by_extension {
+                        extensions.add(file, freqs);
+                    }
+                    emit(file, freqs)
+                };
+                count_paths(
+                    files,
+                    &settings,
+                    *include_binary,
+                    &mut emit_file,
+                    &mut report,
+                )?;
+                if *by_extension {
+                    eprint!("{}", extensions);
+                }
+                #[cfg(feature = "parquet")]
+                if *per_file && format == Format::PARQUET && report.counted > 0 {
+                    let mut out = create(output.as_deref(), OutputEncoding::Utf8)?;
+                    let files = by_file.iter().map(|(file, freqs)| (file.as_str(), freqs));
+                    parquet::write_files_parquet(files, &mut out)?;
+                }
+                if !per_file && (report.counted > 0 || report.failures.is_empty()) {
+                    if *fold_typos {
+                        let (folded, merges) = TypoFolding::new().apply(&corpus.total);
+                        for merge in merges {
+                            eprintln!(
+                                "folded {} into {} ({})",
+                                merge.from, merge.into, merge.count
+                            );
+                        }
+                        corpus.total = folded;
+                    }
+                    if let Some(violation) = bounds.check(&corpus.total) {
+                        out_of_bounds.push(violation);
+                    }
+                    let total = corpus.total.filter(|_, n| n >= settings.min_count);
+                    let output = output.as_deref();
+                    if *metadata {
+                        let mut inputs = files.clone();
+                        if *clipboard {
+                            inputs.insert(0, PathBuf::from("clipboard"));
+                        }
+                        let metadata = settings.metadata(&inputs);
+                        write_with_metadata(&total, format, output, encoding, &metadata)?;
+                    } else if format == Format::BULK {
+                        let mut out = create(output, encoding)?;
+                        bulk.write(&total, &mut out)?;
+                        out.flush()?;
+                    } else if let Some(path) =
+                        output.filter(|path| format == Format::JSON && is_binary_result(path))
+                    {
+                        total.save(path)?;
+                    } else {
+                        let derived = derived.or(settings.derived);
+                        let n = n.unwrap_or(total.len());
+                        write(&total, format, n, output, encoding, derived)?;
+                    }
+                    if let Some(path) = df_output {
+                        let df = corpus
+                            .document_frequencies
+                            .filter(|token, _| total.contains_key(token));
+                        let derived = Derived::default();
+                        let path = Some(path.as_path());
+                        write(&df, Format::JSON, df.len(), path, encoding, derived)?;
+                    }
+                }
+                report.out_of_bounds = out_of_bounds;
+            }
+            Command::Merge { output, files } => {
+                // binary results are streamed into the total, and fail the whole merge if broken
+                let (binaries, others): (Vec<_>, Vec<_>) =
+                    files.iter().partition(|file| is_binary_result(file));
+                let mut inputs = Vec::new();
+                for file in &binaries {
+                    inputs.push((file.to_path_buf(), binary_metadata(file)?));
+                }
+                let mut total = binary::merge_files(&binaries)?;
+                report.counted += binaries.len();
+                for file in others {
+                    let loaded = load_with_metadata(file, &CountConfig::default());
+                    let loaded = loaded.map(|(freqs, metadata)| {
+                        inputs.push((file.clone(), metadata));
+                        freqs
+                    });
+                    report.record(file, loaded, &mut |_, freqs| {
+                        total.merge(freqs);
+                        Ok(())
+                    })?;
+                }
+                let metadata = merge_metadata(inputs)?;
+                match (output, &metadata) {
+                    _ if report.counted == 0 => {}
+                    (output, Some(metadata)) => {
+                        let (output, encoding) = (output.as_deref(), OutputEncoding::Utf8);
+                        write_with_metadata(&total, Format::JSON, output, encoding, metadata)?
+                    }
+                    (Some(path), None) if is_binary_result(path) => total.save(path)?,
+                    _ => {
+                        let (format, encoding) = (Format::JSON, OutputEncoding::Utf8);
+                        let output = output.as_deref();
+                        write(
+                            &total,
+                            format,
+                            total.len(),
+                            output,
+                            encoding,
+                            Derived::default(),
+                        )?;
+                    }
+                }
+            }
+            Command::Top {
+                n,
+                percent,
+                format: Some(format),
+                encoding,
+                derived,
+                file,
+                ..
+            } => {
+                let derived = if *percent {
+                    derived.with_percent()
+                } else {
+                    *derived
+                };
+                let encoding = encoding.unwrap_or_default();
+                write(&load(file)?, *format, *n, None, encoding, derived)?
+            }
+            Command::Top {
+                n,
+                bytes: true,
+                encoding,
+                numbers,
+                derived,
+                file,
+                ..
+            } => {
+                let digits = derived.precision.unwrap_or(2);
+                let numbers = numbers.unwrap_or_else(locale_numbers);
+                let freqs = load(file)?;
+                let footprint = Footprint::of(&freqs);
+                let mut out = create(None, encoding.unwrap_or_default())?;
+                for token in footprint.top(*n) {
+                    writeln!(
+                        out,
+                        "{}\t{:.*}%\t{}",
+                        numbers.format(token.bytes.min(usize::MAX as u128) as usize),
+                        digits,
+                        token.share * 100.0,
+                        token.token
+                    )?;
+                }
+                out.flush()?;
+            }
+            Command::Top {
+                n,
+                percent,
+                encoding,
+                numbers,
+                derived,
+                file,
+                ..
+            } => {
+                let digits = derived.precision.unwrap_or(2);
+                let numbers = numbers.unwrap_or_else(locale_numbers);
+                let freqs = load(file)?;
+                let shares = if *percent {
+                    freqs.proportions()
+                } else {
+                    HashMap::new()
+                };
+                let mut out = create(None, encoding.unwrap_or_default())?;
+                for (token, count) in freqs.top(*n) {
+                    match shares.get(token) {
+                        Some(share) => writeln!(out, "{:.*}%\t{}", digits, share * 100.0, token)?,
+                        None => writeln!(out, "{}\t{}", numbers.format(count), token)?,
+                    }
+                }
+                out.flush()?;
+            }
+            Command::Diff { old, new } => {
+                let stdout = io::stdout();
+                let mut out = stdout.lock();
+                write!(out, "{}", Diff::between(&load(old)?, &load(new)?))?;
+            }
+            Command::Compare { n, a, b } => {
+                let (a, b) = (load(a)?, load(b)?);
+                let stdout = io::stdout();
+                let mut out = stdout.lock();
+                writeln!(out, "jaccard: {:.4}", similarity::jaccard(&a, &b))?;
+                writeln!(
+                    out,
+                    "weighted jaccard: {:.4}",
+                    similarity::weighted_jaccard(&a, &b)
+                )?;
+                writeln!(out, "cosine: {:.4}", similarity::cosine(&a, &b))?;
+                let comparison = Comparison::of(&a, &b);
+                match n {
+                    Some(n) => write!(out, "{}", comparison.truncated(*n))?,
+                    None => write!(out, "{}", comparison)?,
+                }
+            }
+            Command::Unknown {
+                settings,
+                dictionary,
+                files,
+            } => {
+                let settings = Settings::find(settings.as_deref())?;
+                let dictionary = load_text(dictionary, &settings)?;
+                let mut corpus = Corpus::new();
+                count_paths(
+                    files,
+                    &settings,
+                    false,
+                    &mut adding_to(&mut corpus),
+                    &mut report,
+                )?;
+                let unknown = corpus
+                    .total
+                    .missing_from(&dictionary)
+                    .filter(|_, n| n >= settings.min_count);
+                let stdout = io::stdout();
+                let mut out = stdout.lock();
+                for (token, count) in unknown.sorted() {
+                    writeln!(out, "{}\t{}", count, token)?;
+                }
+            }
+            Command::Stats {
+                numbers,
+                unwrap,
+                files,
+            } => {
+                let numbers = numbers.unwrap_or_else(locale_numbers);
+                let n = |n: usize| numbers.format(n);
+                let scripts = Scripts::new();
+                let stdout = io::stdout();
+                let mut out = stdout.lock();
+                let config = CountConfig::new(CountOption::Word);
+                for file in files {
+                    let reader = open_input(file)?;
+                    let (freqs, stats) = if *unwrap {
+                        try_count_with_stats(Paragraphs::new(reader), &config)
+                    } else {
+                        try_count_with_stats(reader, &config)
+                    }
+                    .map_err(io_error)?;
+                    writeln!(out, "{}", walk::display(file))?;
+                    writeln!(out, "  lines: {}", n(stats.lines()))?;
+                    writeln!(
+                        out,
+                        "  words: {} ({} distinct)",
+                        n(stats.words.sum()),
+                        n(freqs.len())
+                    )?;
+                    writeln!(out, "  chars: {}", n(stats.chars.sum()))?;
+                    writeln!(
+                        out,
+                        "  words per line: mean {:.2}, median {}, max {}",
+                        stats.words.mean(),
+                        stats.words.median(),
+                        n(stats.words.max().unwrap_or(0))
+                    )?;
+                    let coverage = Coverage::of(&freqs);
+                    let tokens: Vec<_> = [50, 80, 90, 95, 99]
+                        .iter()
+                        .map(|&p| format!("{}% {}", p, n(coverage.tokens_for(p as f64 / 100.0))))
+                        .collect();
+                    writeln!(out, "  words to cover: {}", tokens.join(", "))?;
+                    let buckets: Vec<_> = Buckets::of(&freqs)
+                        .iter()
+                        .map(|bucket| format!("{}: {}", bucket, n(bucket.tokens)))
+                        .collect();
+                    writeln!(out, "  distinct words by count: {}", buckets.join(", "))?;
+                    let breakdown = scripts.breakdown(&freqs);
+                    let total = breakdown.total().max(1) as f64;
+                    let shares: Vec<_> = breakdown
+                        .sorted()
+                        .into_iter()
+                        .map(|(script, count)| {
+                            format!("{} {:.1}%", script, count as f64 * 100.0 / total)
+                        })
+                        .collect();
+                    writeln!(out, "  chars of words by script: {}", shares.join(", "))?;
+                }
+            }
+            Command::Uniq { file } => {
+                let input = open_input(file.as_deref().unwrap_or(Path::new("-")))?;
+                let mut out = BufWriter::new(io::stdout().lock());
+                for run in Runs::new(input) {
+                    let (line, count) = run?;
+                    writeln!(out, "{}\t{}", count, line)?;
+                }
+                out.flush()?;
+            }
+            Command::Duplicates {
+                normalize_whitespace,
+                min_chars,
+                files,
+            } => {
+                let mut duplicates = Duplicates::new().normalize_whitespace(*normalize_whitespace);
+                if let Some(n) = min_chars {
+                    duplicates = duplicates.min_chars(*n);
+                }
+                for file in files {
+                    let input = File::open(file).map(BufReader::new);
+                    match input.and_then(|input| duplicates.add(&walk::display(file), input)) {
+                        Ok(()) => report.counted += 1,
+                        Err(e) => report.failures.push((file.clone(), e)),
+                    }
+                }
+                let stdout = io::stdout();
+                let mut out = stdout.lock();
+                for duplicate in duplicates.across_files() {
+                    writeln!(out, "{} files: {}", duplicate.files, duplicate.text)?;
+                    for location in &duplicate.locations {
+                        writeln!(out, "  {}:{}", location.file, location.line)?;
+                    }
+                }
+            }
+            Command::Columns { pattern, files } => {
+                let pattern = Regex::new(pattern)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                let mut histogram = ColumnHistogram::new(pattern);
+                if files.is_empty() {
+                    histogram.read(io::stdin().lock())?;
+                }
+                for file in files {
+                    let input = File::open(file).map(BufReader::new);
+                    match input.and_then(|input| histogram.read(input)) {
+                        Ok(()) => report.counted += 1,
+                        Err(e) => report.failures.push((file.clone(), e)),
+                    }
+                }
+                let mut out = io::stdout().lock();
+                write!(out, "{}", histogram)?;
+                out.flush()?;
+                eprintln!(
+                    "{} of {} lines matched",
+                    histogram.lines_matching(),
+                    histogram.lines()
+                );
+            }
+            Command::Progress {
+                settings,
+                save,
+                baseline,
+                files,
+            } => {
+                let settings = Settings::find(settings.as_deref())?;
+                let mut current = Vec::new();
+                let mut emit = |file: &Path, freqs: &Frequencies| {
+                    current.push((walk::display(file).into_owned(), freqs.clone()));
+                    Ok(())
+                };
+                count_paths(files, &settings, false, &mut emit, &mut report)?;
+                if *save || !baseline.exists() {
+                    // a baseline lacking files would count them as added next time
+                    if !report.failures.is_empty() {
+                        return Ok(report);
+                    }
+                    let mut out = BufWriter::new(File::create(baseline)?);
+                    for (file, freqs) in &current {
+                        write_file_json(file, freqs, &mut out)?;
+                    }
+                    out.flush()?;
+                    eprintln!("saved the baseline to {}", baseline.display());
+                } else {
+                    let old: Vec<_> = read_file_json(BufReader::new(File::open(baseline)?))?
+                        .into_iter()
+                        .filter(|(file, _)| {
+                            report
+                                .failures
+                                .iter()
+                                .all(|(failed, _)| walk::display(failed) != *file)
+                        })
+                        .collect();
+                    let stdout = io::stdout();
+                    let mut out = stdout.lock();
+                    let (mut added, mut removed) = (0, 0);
+                    for file in since(&old, &current) {
+                        writeln!(out, "{}", file)?;
+                        added += file.added;
+                        removed += file.removed;
+                    }
+                    let net = added as i64 - removed as i64;
+                    writeln!(out, "+{} -{} total (net {:+})", added, removed, net)?;
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// a value of an option, which is UTF-8
+fn string(arg: Option<OsString>) -> Option<String> {
+    arg?.into_string().ok()
+}
+
+/// saved counts of a `.json` or `.bin` file, or counts of words of a text file
+pub fn load(path: impl AsRef<Path>) -> io::Result<Frequencies> {
+    load_with(path, &CountConfig::default())
+}
+
+/// same as [`load`](fn.load.html), but text files are counted as configured
+pub fn load_with(path: impl AsRef<Path>, config: &CountConfig) -> io::Result<Frequencies> {
+    load_with_metadata(path, config).map(|(freqs, _)| freqs)
+}
+
+/// same as [`load_with`](fn.load_with.html), with metadata of saved counts if written
+fn load_with_metadata(
+    path: impl AsRef<Path>,
+    config: &CountConfig,
+) -> io::Result<(Frequencies, Option<Metadata>)> {
+    let path = path.as_ref();
+    let file = BufReader::new(walk::open(path)?);
+    if is_json_result(path) {
+        read_json_with_metadata(file)
+    } else if is_binary_result(path) {
+        let mut entries = Entries::new(file)?;
+        let metadata = entries.metadata().cloned();
+        let mut freqs = Frequencies::new();
+        for entry in &mut entries {
+            let (token, count) = entry?;
+            freqs.insert(token, count);
+        }
+        Ok((freqs, metadata))
+    } else {
+        let freqs = try_count_with(file, config).map_err(io_error)?;
+        Ok((freqs, None))
+    }
+}
+
+/// metadata of a result in the binary format, without reading its entries. errors are
+/// prefixed with the path
+fn binary_metadata(path: &Path) -> io::Result<Option<Metadata>> {
+    let with_path =
+        |e: io::Error| io::Error::new(e.kind(), format!("{}: {}", walk::display(path), e));
+    let input = BufReader::new(walk::open(path).map_err(with_path)?);
+    Ok(Entries::new(input).map_err(with_path)?.metadata().cloned())
+}
+
+/// metadata of the sum of inputs, `None` if no saved counts of them have metadata. text
+/// files are of the default settings, and saved counts without metadata are of any
+/// configuration. an error of kind `InvalidData` if metadata of inputs are not compatible
+fn merge_metadata(inputs: Vec<(PathBuf, Option<Metadata>)>) -> io::Result<Option<Metadata>> {
+    if inputs.iter().all(|(_, metadata)| metadata.is_none()) {
+        return Ok(None);
+    }
+    let mut merged: Option<Metadata> = None;
+    let mut unknown = Vec::new();
+    for (file, metadata) in inputs {
+        let metadata = match metadata {
+            Some(metadata) => metadata,
+            None if is_json_result(&file) || is_binary_result(&file) => {
+                unknown.push(file);
+                continue;
+            }
+            None => Settings::default().metadata(std::slice::from_ref(&file)),
+        };
+        let merged = merged.get_or_insert_with(|| Metadata {
+            inputs: Vec::new(),
+            ..metadata.clone()
+        });
+        merged.merge(&metadata).map_err(|e| {
+            let message = format!("{}: {}", walk::display(&file), e);
+            io::Error::new(io::ErrorKind::InvalidData, message)
+        })?;
+    }
+    Ok(merged.map(|mut merged| {
+        let unknown = unknown.iter().map(|file| walk::display(file).into_owned());
+        merged.inputs.extend(unknown);
+        merged
+    }))
+}
+
+/// `path` has the extension `.json` of saved counts
+fn is_json_result(path: impl AsRef<Path>) -> bool {
+    path.as_ref().extension().is_some_and(|e| e == "json")
+}
+
+/// `path` has the extension `.bin` of results in the binary format (`Frequencies::save`)
+fn is_binary_result(path: impl AsRef<Path>) -> bool {
+    path.as_ref().extension().is_some_and(|e| e == "bin")
+}
+
+/// receives counts of each file as soon as it is counted
+type Emit<'a> = dyn FnMut(&Path, &Frequencies) -> io::Result<()> + 'a;
+
+/// emit adding counts to `corpus` as documents
+fn adding_to(corpus: &mut Corpus) -> impl FnMut(&Path, &Frequencies) -> io::Result<()> + '_ {
+    move |_, freqs| {
+        corpus.add(freqs);
+        Ok(())
+    }
+}
+
+/// pass counts of each file by [`load_with`](fn.load_with.html), or of files under each
+/// directory but ignored ones, and binary ones unless `include_binary`, to `emit` in order.
+/// symbolic links are treated as settings say. skipped files are printed to stderr. files are
+/// counted on as many threads as settings say
+fn count_paths(
+    paths: &[PathBuf],
+    settings: &Settings,
+    include_binary: bool,
+    emit: &mut Emit,
+    report: &mut Report,
+) -> io::Result<()> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !walk::is_dir(path) {
+            files.push(path.clone());
+            continue;
+        }
+        let mut traversal = match walk::files_with(path, settings.symlinks) {
+            Ok(traversal) => traversal,
+            Err(e) => {
+                report.record(path, Err(e), emit)?;
+                continue;
+            }
+        };
+        // before sniffing, not to read ignored files
+        traversal.files.retain(|file| {
+            let relative = file.strip_prefix(path).unwrap_or(file);
+            !settings.ignores(&relative.to_string_lossy().replace('\\', "/"))
+        });
+        if !include_binary {
+            traversal = traversal.skip_binary();
+        }
+        for (file, reason) in &traversal.skipped {
+            eprintln!("skipped {}: {}", walk::display(file), reason);
+        }
+        files.extend(traversal.files);
+    }
+    let threads = Threads::new(settings.jobs);
+    // in chunks, to pass on counts of the first files before all files are counted
+    for chunk in files.chunks(threads.threads() * FILES_PER_THREAD) {
+        if interrupt::interrupted() {
+            report.interrupted = true;
+            break;
+        }
+        let loaded =
+            parallel::map_with(chunk, &threads, || (), |_, file| load_text(file, settings));
+        for (file, result) in chunk.iter().zip(loaded) {
+            match result {
+                // the file was being read when interrupted, see `Interruptible`
+                Err(_) if interrupt::interrupted() => report.interrupted = true,
+                result => report.record(file, result, emit)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+/// counts of a text file, or of a sample of its lines, or by its detected charset (printed to
+/// stderr) if settings say so, or saved counts of a file by [`load_with`](fn.load_with.html)
+/// with `--saved`
+fn load_text(path: &Path, settings: &Settings) -> io::Result<Frequencies> {
+    let text = !settings.saved || !(is_json_result(path) || is_binary_result(path));
+    let book = path.extension().is_some_and(|e| e == "epub");
+    if settings.check_quality && text && !book {
+        let quality = check_quality(BufReader::new(Interruptible(walk::open(path)?)))?;
+        if !quality.is_clean() {
+            eprintln!("{}: {}", walk::display(path), quality);
+        }
+    }
+    if let (Some(field), true) = (settings.access_log, text) {
+        let file = BufReader::new(Interruptible(walk::open(path)?));
+        let counts = count_field(file, &settings.config, field).map_err(io_error)?;
+        if counts.skipped > 0 {
+            eprintln!(
+                "{}: {} lines without {} skipped",
+                walk::display(path),
+                counts.skipped,
+                field
+            );
+        }
+        return Ok(counts.values);
+    }
+    if let (Some(key), true) = (&settings.log_key, text) {
+        let file = BufReader::new(Interruptible(walk::open(path)?));
+        let counts = count_key(file, &settings.config, key).map_err(io_error)?;
+        if counts.skipped > 0 {
+            eprintln!(
+                "{}: {} lines without {} skipped",
+                walk::display(path),
+                counts.skipped,
+                key
+            );
+        }
+        return Ok(counts.values);
+    }
+    #[cfg(feature = "epub")]
+    {
+        if book {
+            let book = epub::count_epub(Interruptible(walk::open(path)?), &settings.config)
+                .map_err(io_error)?;
+            return Ok(book.total);
+        }
+    }
+    if let Some("srt") | Some("vtt") = path.extension().and_then(|e| e.to_str()) {
+        let file = BufReader::new(Interruptible(walk::open(path)?));
+        return count_subtitles(file, &settings.config).map_err(io_error);
+    }
+    if let (Some((rate, seed)), true) = (settings.sample, text) {
+        let file = BufReader::new(Interruptible(walk::open(path)?));
+        return count_sampled(file, &settings.config, rate, seed).map_err(io_error);
+    }
+    #[cfg(feature = "detect-encoding")]
+    {
+        if settings.detect_encoding && text {
+            let file = BufReader::new(Interruptible(walk::open(path)?));
+            let (freqs, charset) =
+                charset::count_detected(file, &settings.config).map_err(io_error)?;
+            eprintln!("{}: {}", walk::display(path), charset);
+            return Ok(freqs);
+        }
+    }
+    if !text {
+        return load_with(path, &settings.config);
+    }
+    let file = BufReader::new(Interruptible(walk::open(path)?));
+    try_count_with(file, &settings.config).map_err(io_error)
+}
+
+/// `file` for reading, stdin if `-`
+fn open_input(file: &Path) -> io::Result<Box<dyn BufRead>> {
+    Ok(match file.to_str() {
+        Some("-") => Box::new(io::stdin().lock()),
+        _ => Box::new(BufReader::new(walk::open(file)?)),
+    })
+}
+
+/// I/O errors as they are, others as `Other`
+fn io_error(e: CountError) -> io::Error {
+    match e {
+        CountError::Io(e) => e,
+        e => io::Error::new(io::ErrorKind::Other, e.to_string()),
+    }
+}
+
+/// bounds of counts of words (all tokens) of `count`, by `--min-words` and `--max-words`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WordBounds {
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+}
+
+impl WordBounds {
+    /// how `freqs` is out of the bounds, if it is
+    fn check(&self, freqs: &Frequencies) -> Option<String> {
+        let words = freqs.total();
+        match (self.min, self.max) {
+            (Some(min), _) if words < min => {
+                Some(format!("{} words, fewer than --min-words {}", words, min))
+            }
+            (_, Some(max)) if words > max => {
+                Some(format!("{} words, more than --max-words {}", words, max))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// files counted by a command, and ones failed to be read with the errors
+#[derive(Debug, Default)]
+pub struct Report {
+    pub counted: usize,
+    /// files failed, named by [`walk::display`](../../bicycle_book_wordcount/walk/fn.display.html)
+    /// in messages
+    pub failures: Vec<(PathBuf, io::Error)>,
+    /// how counts are out of [`WordBounds`](struct.WordBounds.html), of the total or of files
+    pub out_of_bounds: Vec<String>,
+    /// counting stopped by SIGINT before all files, see [`interrupt`](../interrupt/index.html)
+    pub interrupted: bool,
+}
+
+impl Report {
+    /// pass counts of `file` to `emit`, or add its error to failures.
+    /// errors of `emit` are returned
+    fn record(
+        &mut self,
+        file: &Path,
+        result: io::Result<Frequencies>,
+        emit: &mut Emit,
+    ) -> io::Result<()> {
+        match result {
+            Ok(freqs) => {
+                self.counted += 1;
+                emit(file, &freqs)
+            }
+            Err(e) => {
+                self.failures.push((file.to_path_buf(), e));
+                Ok(())
+            }
+        }
+    }
+
+    /// 0 if all files were counted, [`EXIT_PARTIAL`](constant.EXIT_PARTIAL.html) if some
+    /// failed, [`EXIT_FAILURE`](constant.EXIT_FAILURE.html) if all failed, and
+    /// [`EXIT_OUT_OF_BOUNDS`](constant.EXIT_OUT_OF_BOUNDS.html) if all were counted but out of
+    /// bounds. [`EXIT_INTERRUPTED`](../interrupt/constant.EXIT_INTERRUPTED.html) if interrupted
+    pub fn exit_code(&self) -> i32 {
+        match (self.counted, self.failures.len()) {
+            _ if self.interrupted => EXIT_INTERRUPTED,
+            (_, 0) if !self.out_of_bounds.is_empty() => EXIT_OUT_OF_BOUNDS,
+            (_, 0) => 0,
+            (0, _) => EXIT_FAILURE,
+            _ => EXIT_PARTIAL,
+        }
+    }
+}
+
+/// summary of failures, one line per file
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let failed = self.failures.len();
+        writeln!(f, "{} of {} files failed:", failed, failed + self.counted)?;
+        for (file, e) in &self.failures {
+            writeln!(f, "  {}: {}", walk::display(file), e)?;
+        }
+        Ok(())
+    }
+}
+
+/// style of numbers of the locale of `LC_ALL`, `LC_NUMERIC` or `LANG`
+fn locale_numbers() -> NumberStyle {
+    ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|locale| !locale.is_empty())
+        .map_or(NumberStyle::Plain, |locale| NumberStyle::of_locale(&locale))
+}
+
+/// a file to write, or stdout, in `encoding`
+fn create(output: Option<&Path>, encoding: OutputEncoding) -> io::Result<Box<dyn Write>> {
+    let out: Box<dyn Write> = match output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout()),
+    };
+    Ok(match encoding {
+        OutputEncoding::Utf8 => out,
+        encoding => Box::new(Encoder::new(out, encoding)),
+    })
+}
+
+fn parquet_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        "writing Parquet needs the feature parquet",
+    )
+}
+
+/// write the `n` most frequent tokens of `freqs`. shares in tables and JSON Lines are of all
+/// tokens
+fn write(
+    freqs: &Frequencies,
+    format: Format,
+    n: usize,
+    output: Option<&Path>,
+    encoding: OutputEncoding,
+    derived: Derived,
+) -> io::Result<()> {
+    // Parquet is binary
+    let encoding = match format {
+        Format::PARQUET => OutputEncoding::Utf8,
+        _ => encoding,
+    };
+    let registered = format.registered()?;
+    // columns of `derived`, or else of the format with the precision of `derived`
+    let columned = registered.columns().and_then(|columns| {
+        let columns = derived.with_precision(derived.columns.unwrap_or(columns));
+        registered.with_columns(columns)
+    });
+    let format = columned.as_deref().unwrap_or(registered);
+    let mut out = create(output, encoding)?;
+    format.write_top(freqs, n, &mut out)?;
+    out.flush()
+}
+
+/// write all of `freqs` with `metadata`, in the binary format to an output `.bin`, or else
+/// in CSV of `Format::CSV` or in JSON
+fn write_with_metadata(
+    freqs: &Frequencies,
+    format: Format,
+    output: Option<&Path>,
+    encoding: OutputEncoding,
+    metadata: &Metadata,
+) -> io::Result<()> {
+    if let Some(path) = output.filter(|path| is_binary_result(path)) {
+        return freqs.save_with_metadata(path, metadata);
+    }
+    let mut out = create(output, encoding)?;
+    match format {
+        Format::CSV => write_csv_with_metadata(freqs, metadata, &mut out)?,
+        _ => write_json_with_metadata(freqs, metadata, &mut out)?,
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    fn parse(args: &[&str]) -> Option<Command> {
+        Command::parse(args[0], args[1..].iter().map(OsString::from))
+    }
+
+    #[test]
+    fn parse_works() {
+        assert_eq!(
+            parse(&["top", "-n", "3", "a.json"]),
+            Some(Command::Top {
+                n: 3,
+                percent: false,
+                bytes: false,
+                format: None,
+                encoding: None,
+                derived: Derived::default(),
+                numbers: None,
+                file: PathBuf::from("a.json")
+            })
+        );
+        assert!(matches!(
+            parse(&["top", "--columns", "cumulative", "--precision", "1", "a"]),
+            Some(Command::Top {
+                derived: Derived {
+                    columns: Some(columns),
+                    precision: Some(1),
+                },
+                ..
+            }) if columns == Columns::none().cumulative(true)
+        ));
+        assert_eq!(parse(&["top", "--columns", "median", "a"]), None);
+        assert_eq!(
+            parse(&["diff", "a", "b"]),
+            Some(Command::Diff {
+                old: PathBuf::from("a"),
+                new: PathBuf::from("b")
+            })
+        );
+        assert_eq!(
+            parse(&["unknown", "dict.txt", "a", "b"]),
+            Some(Command::Unknown {
+                settings: None,
+                dictionary: PathBuf::from("dict.txt"),
+                files: vec![PathBuf::from("a"), PathBuf::from("b")]
+            })
+        );
+        assert_eq!(parse(&["unknown", "dict.txt"]), None);
+        assert_eq!(parse(&["count"]), None);
+        assert_eq!(parse(&["top", "a", "b"]), None);
+        assert!(matches!(
+            parse(&["top", "--bytes", "a"]),
+            Some(Command::Top { bytes: true, .. })
+        ));
+        assert_eq!(parse(&["top", "--bytes", "--percent", "a"]), None);
+        assert_eq!(parse(&["top", "--bytes", "--format", "json", "a"]), None);
+        assert_eq!(parse(&["merge", "-n", "3", "a"]), None);
+        assert_eq!(parse(&["count", "--format", "xml", "a"]), None);
+        assert!(matches!(
+            parse(&["count", "--include-binary", "a"]),
+            Some(Command::Count {
+                include_binary: true,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&["count", "--access-log", "status", "a"]),
+            Some(Command::Count {
+                access_log: Some(Field::Status),
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&["count", "--log-key", "level", "a"]),
+            Some(Command::Count {
+                log_key: Some(ref key),
+                ..
+            }) if key == "level"
+        ));
+        assert_eq!(
+            parse(&[
+                "count",
+                "--access-log",
+                "status",
+                "--log-key",
+                "status",
+                "a"
+            ]),
+            None
+        );
+        assert!(matches!(
+            parse(&["count", "a"]),
+            Some(Command::Count { log_key: None, .. })
+        ));
+        assert_eq!(parse(&["count", "--access-log", "size", "a"]), None);
+        assert!(matches!(
+            parse(&["count", "--symlinks", "skip", "a"]),
+            Some(Command::Count {
+                symlinks: Some(Symlinks::Skip),
+                ..
+            })
+        ));
+        assert_eq!(parse(&["count", "--symlinks", "loop", "a"]), None);
+        assert!(matches!(
+            parse(&["count", "-j", "4", "a"]),
+            Some(Command::Count { jobs: Some(4), .. })
+        ));
+        assert_eq!(parse(&["count", "--jobs", "0", "a"]), None);
+        assert!(matches!(
+            parse(&["count", "--min-words", "10", "--max-words", "250", "a"]),
+            Some(Command::Count {
+                bounds: WordBounds {
+                    min: Some(10),
+                    max: Some(250)
+                },
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&["count", "--by-extension", "a"]),
+            Some(Command::Count {
+                by_extension: true,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&["count", "--clipboard"]),
+            Some(Command::Count {
+                clipboard: true,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&["count", "--per-file", "a", "b"]),
+            Some(Command::Count { per_file: true, .. })
+        ));
+        assert_eq!(parse(&["count", "--per-file", "--df", "df.tsv", "a"]), None);
+        assert_eq!(
+            parse(&["count", "--per-file", "--format", "json", "a"]),
+            None
+        );
+        assert_eq!(
+            parse(&["count", "--per-file", "--format", "bulk", "--id", "", "a"]),
+            parse(&["count", "--per-file", "--format", "bulk", "a"]).map(|mut count| {
+                if let Command::Count { bulk, .. } = &mut count {
+                    *bulk = Bulk::default().id(None);
+                }
+                count
+            })
+        );
+        assert_eq!(parse(&["count", "--index", "logs", "a"]), None);
+        assert!(matches!(
+            parse(&["count", "--per-file", "--format", "parquet", "a"]),
+            Some(Command::Count {
+                per_file: true,
+                format: Some(Format::PARQUET),
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&["count", "--fold-typos", "a"]),
+            Some(Command::Count {
+                fold_typos: true,
+                ..
+            })
+        ));
+        assert_eq!(parse(&["count", "--per-file", "--fold-typos", "a"]), None);
+        match parse(&[
+            "count",
+            "--exclude-pattern",
+            "^[0-9a-f]+$",
+            "--exclude-pattern",
+            "x",
+            "a",
+        ]) {
+            Some(Command::Count {
+                exclude_patterns, ..
+            }) => assert_eq!(exclude_patterns, ["^[0-9a-f]+$", "x"]),
+            command => panic!("{:?}", command),
+        }
+        assert_eq!(parse(&["count", "--per-file", "--metadata", "a"]), None);
+        assert!(matches!(
+            parse(&["count", "--partial-on-interrupt", "a"]),
+            Some(Command::Count {
+                partial_on_interrupt: true,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&["count", "--check-quality", "a"]),
+            Some(Command::Count {
+                check_quality: true,
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&["top", "--encoding", "utf-16le", "a"]),
+            Some(Command::Top {
+                encoding: Some(OutputEncoding::Utf16Le),
+                ..
+            })
+        ));
+        assert_eq!(parse(&["count", "--encoding", "ebcdic", "a"]), None);
+        assert_eq!(parse(&["uniq", "-"]), Some(Command::Uniq { file: None }));
+        assert_eq!(parse(&["uniq", "a", "b"]), None);
+        assert_eq!(
+            parse(&["stats", "--numbers", "indian", "a"]),
+            Some(Command::Stats {
+                numbers: Some(NumberStyle::Indian),
+                unwrap: false,
+                files: vec![PathBuf::from("a")],
+            })
+        );
+        assert_eq!(
+            parse(&["stats", "--unwrap", "a"]),
+            Some(Command::Stats {
+                numbers: None,
+                unwrap: true,
+                files: vec![PathBuf::from("a")],
+            })
+        );
+        assert_eq!(parse(&["stats", "--numbers", "roman", "a"]), None);
+        assert_eq!(
+            parse(&["duplicates", "--min-chars", "10", "a", "b"]),
+            Some(Command::Duplicates {
+                normalize_whitespace: false,
+                min_chars: Some(10),
+                files: vec![PathBuf::from("a"), PathBuf::from("b")],
+            })
+        );
+        assert_eq!(parse(&["duplicates", "a"]), None);
+        assert_eq!(
+            parse(&["columns", "[|]", "a"]),
+            Some(Command::Columns {
+                pattern: "[|]".to_string(),
+                files: vec![PathBuf::from("a")],
+            })
+        );
+        assert_eq!(parse(&["columns"]), None);
+        assert_eq!(
+            parse(&["merge", "out.bin", "a.bin", "b.json"]),
+            Some(Command::Merge {
+                output: Some(PathBuf::from("out.bin")),
+                files: vec![PathBuf::from("a.bin"), PathBuf::from("b.json")],
+            })
+        );
+        assert_eq!(
+            parse(&["merge", "-o", "all.json", "a.bin", "b.bin"]),
+            Some(Command::Merge {
+                output: Some(PathBuf::from("all.json")),
+                files: vec![PathBuf::from("a.bin"), PathBuf::from("b.bin")],
+            })
+        );
+        assert!(matches!(
+            parse(&["top", "--format", "latex", "a"]),
+            Some(Command::Top {
+                format: Some(Format("latex")),
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&["top", "--percent", "--format", "jsonl", "a"]),
+            Some(Command::Top { percent: true, .. })
+        ));
+        assert_eq!(parse(&["top", "--percent", "--format", "csv", "a"]), None);
+        assert!(matches!(
+            parse(&["count", "-n", "5", "--format", "latex", "a"]),
+            Some(Command::Count { n: Some(5), .. })
+        ));
+        assert_eq!(parse(&["count", "-n", "5", "--metadata", "a"]), None);
+        assert_eq!(parse(&["count", "-n", "5", "-o", "a.bin", "a"]), None);
+        assert!(matches!(
+            parse(&["count", "--format", "trie", "a"]),
+            Some(Command::Count {
+                format: Some(Format("trie")),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn saved_counts_compose() -> io::Result<()> {
+        let dir = std::env::temp_dir();
+        let text = dir.join("wordcount_command.txt");
+        let saved = dir.join("wordcount_command.json");
+        let merged = dir.join("wordcount_command_merged.json");
+        let s = |p: &Path| p.to_str().unwrap().to_string();
+        fs::write(&text, "aa bb aa")?;
+
+        let count = parse(&["count", "-o", &s(&saved), &s(&text)]).unwrap();
+        count.run()?;
+        let merge = parse(&["merge", "-o", &s(&merged), &s(&saved), &s(&text)]).unwrap();
+        merge.run()?;
+        let freqs = load(&merged)?;
+        assert_eq!(freqs["aa"], 4);
+        assert_eq!(freqs["bb"], 2);
+
+        // counted as text unless --saved
+        let recount = parse(&["count", "-o", &s(&merged), &s(&saved)]).unwrap();
+        recount.run()?;
+        assert_eq!(load(&merged)?["aa"], 1);
+        let resaved = parse(&["count", "--saved", "-o", &s(&merged), &s(&saved)]).unwrap();
+        resaved.run()?;
+        assert_eq!(load(&merged)?["aa"], 2);
+        // --saved of the settings file, kept without the flag
+        let settings = dir.join("wordcount_command.toml");
+        fs::write(&settings, "saved = true\njobs = 2\n")?;
+        let args = [
+            "count",
+            "--config",
+            &s(&settings),
+            "-o",
+            &s(&merged),
+            &s(&saved),
+        ];
+        parse(&args).unwrap().run()?;
+        assert_eq!(load(&merged)?["aa"], 2);
+
+        for path in &[text, saved, merged, settings] {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn metadata_is_merged() -> io::Result<()> {
+        let dir = std::env::temp_dir();
+        let text = dir.join("wordcount_metadata.txt");
+        let json = dir.join("wordcount_metadata.json");
+        let bin = dir.join("wordcount_metadata.bin");
+        let lines = dir.join("wordcount_metadata_lines.json");
+        let merged = dir.join("wordcount_metadata_merged.json");
+        let s = |p: &Path| p.to_str().unwrap().to_string();
+        fs::write(&text, "aa bb aa")?;
+        let metadata = Settings::default().metadata(&[PathBuf::from("x")]);
+        let metadata = metadata.mode("line");
+        write_json_with_metadata(&Frequencies::new(), &metadata, File::create(&lines)?)?;
+
+        parse(&["count", "--metadata", "-o", &s(&json), &s(&text)])
+            .unwrap()
+            .run()?;
+        parse(&["count", "--metadata", "-o", &s(&bin), &s(&text)])
+            .unwrap()
+            .run()?;
+        let merge = parse(&["merge", "-o", &s(&merged), &s(&json), &s(&bin), &s(&text)]);
+        merge.unwrap().run()?;
+        let (freqs, metadata) = read_json_with_metadata(File::open(&merged)?)?;
+        assert_eq!(freqs["aa"], 6);
+        assert_eq!(metadata.unwrap().inputs, vec![s(&text), s(&text), s(&text)]);
+
+        let merge = parse(&["merge", "-o", &s(&merged), &s(&json), &s(&lines)]);
+        let error = merge.unwrap().run().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with(&s(&lines)));
+
+        for path in &[text, json, bin, lines, merged] {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn failures_are_reported() -> io::Result<()> {
+        let dir = std::env::temp_dir();
+        let text = dir.join("wordcount_command_failures.txt");
+        let merged = dir.join("wordcount_command_failures.json");
+        let missing = dir.join("wordcount_command_missing.txt");
+        let s = |p: &Path| p.to_str().unwrap().to_string();
+        fs::write(&text, "aa bb aa")?;
+
+        let merge = parse(&["merge", "-o", &s(&merged), &s(&text), &s(&missing)]).unwrap();
+        let report = merge.run()?;
+        assert_eq!(report.counted, 1);
+        assert_eq!(report.failures[0].0, missing);
+        assert_eq!(report.exit_code(), EXIT_PARTIAL);
+        assert!(report.to_string().starts_with("1 of 2 files failed:\n"));
+        assert_eq!(load(&merged)?["aa"], 2);
+
+        let merge = parse(&["merge", &s(&missing)]).unwrap();
+        assert_eq!(merge.run()?.exit_code(), EXIT_FAILURE);
+
+        fs::remove_file(text)?;
+        fs::remove_file(merged)?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn paths_need_not_be_utf8() -> io::Result<()> {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        let dir = std::env::temp_dir();
+        let text = dir.join(OsStr::from_bytes(b"wordcount_caf\xe9.txt"));
+        let missing = dir.join(OsStr::from_bytes(b"wordcount_missing_\xff.txt"));
+        let output = dir.join(OsStr::from_bytes(b"wordcount_caf\xe9.json"));
+        if fs::write(&text, "aa bb aa").is_err() {
+            // the file system takes only UTF-8 names
+            return Ok(());
+        }
+        let args = vec![
+            OsString::from("-o"),
+            output.clone().into_os_string(),
+            text.clone().into_os_string(),
+            missing.clone().into_os_string(),
+        ];
+        let report = Command::parse("count", args).unwrap().run()?;
+        assert_eq!(report.counted, 1);
+        assert_eq!(report.failures[0].0, missing);
+        assert!(report
+            .to_string()
+            .contains("wordcount_missing_\u{fffd}.txt: "));
+        assert_eq!(load(&output)?["aa"], 2);
+
+        let args = vec![
+            OsString::from("--format"),
+            OsString::from(OsStr::from_bytes(b"\xff")),
+        ];
+        assert_eq!(Command::parse("count", args), None);
+
+        fs::remove_file(text)?;
+        fs::remove_file(output)?;
+        Ok(())
+    }
+
+    #[test]
+    fn baseline_is_saved() -> io::Result<()> {
+        let dir = std::env::temp_dir();
+        let text = dir.join("wordcount_progress.txt");
+        let baseline = dir.join("wordcount_progress.jsonl");
+        let missing = dir.join("wordcount_progress_missing.txt");
+        let s = |p: &Path| p.to_str().unwrap().to_string();
+        fs::write(&text, "one two two")?;
+        let _ = fs::remove_file(&baseline);
+
+        let progress = parse(&["progress", &s(&baseline), &s(&text), &s(&missing)]).unwrap();
+        progress.run()?;
+        assert!(!baseline.exists());
+        let progress = parse(&["progress", &s(&baseline), &s(&text)]).unwrap();
+        assert_eq!(
+            progress,
+            Command::Progress {
+                settings: None,
+                save: false,
+                baseline: baseline.clone(),
+                files: vec![text.clone()],
+            }
+        );
+        progress.run()?;
+        let saved = read_file_json(BufReader::new(File::open(&baseline)?))?;
+        assert_eq!(saved[0].0, s(&text));
+        assert_eq!(saved[0].1["two"], 2);
+        assert_eq!(parse(&["progress", &s(&baseline)]), None);
+
+        fs::remove_file(text)?;
+        fs::remove_file(baseline)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,608 @@
+//! `wordcount completions bash|zsh|fish` and `wordcount man`, generated from a description of
+//! the subcommands
+
+use std::fmt::Write;
+
+/// an option of a subcommand
+pub struct Opt {
+    pub flag: &'static str,
+    /// possible values, empty for any value, `None` for a flag without value
+    pub values: Option<&'static [&'static str]>,
+    pub help: &'static str,
+}
+
+/// a subcommand
+pub struct Subcommand {
+    pub name: &'static str,
+    pub args: &'static str,
+    pub about: &'static str,
+    pub options: &'static [Opt],
+}
+
+const FORMATS: &[&str] = &[
+    "json", "jsonl", "debug", "csv", "latex", "markdown", "html", "trie", "snapshot", "parquet",
+    "bulk",
+];
+
+const ENCODINGS: &[&str] = &["utf-8", "utf-8-bom", "utf-16le", "shift_jis"];
+
+const NUMBERS: Opt = Opt {
+    flag: "--numbers",
+    values: Some(&[
+        "locale",
+        "plain",
+        "comma",
+        "period",
+        "space",
+        "apostrophe",
+        "indian",
+    ]),
+    help: "grouping of digits of counts (default by the locale)",
+};
+
+const ENCODING: Opt = Opt {
+    flag: "--encoding",
+    values: Some(ENCODINGS),
+    help: "encoding of output instead of UTF-8",
+};
+
+const COLUMNS: Opt = Opt {
+    flag: "--columns",
+    values: Some(&["percent", "cumulative", "percent,cumulative", "none"]),
+    help: "shares in tables and JSON Lines (default percent in tables)",
+};
+
+const PRECISION: Opt = Opt {
+    flag: "--precision",
+    values: Some(&[]),
+    help: "decimals of shares (default 2)",
+};
+
+/// all subcommands
+pub const SUBCOMMANDS: &[Subcommand] = &[
+    Subcommand {
+        name: "count",
+        args: "FILE...",
+        about: "count words of files (or directories) and merge them",
+        options: &[
+            Opt {
+                flag: "--format",
+                values: Some(FORMATS),
+                help: "output format",
+            },
+            Opt {
+                flag: "-o",
+                values: Some(&[]),
+                help: "write to a file instead of stdout",
+            },
+            Opt {
+                flag: "-n",
+                values: Some(&[]),
+                help: "write the N most frequent tokens of the total (default all)",
+            },
+            Opt {
+                flag: "--config",
+                values: Some(&[]),
+                help: "settings file instead of wordcount.toml",
+            },
+            Opt {
+                flag: "--include-binary",
+                values: None,
+                help: "count binary files under directories too",
+            },
+            Opt {
+                flag: "--saved",
+                values: None,
+                help: "read .json and .bin files as saved counts",
+            },
+            Opt {
+                flag: "--detect-encoding",
+                values: None,
+                help: "guess the charset of each file",
+            },
+            Opt {
+                flag: "--sample",
+                values: Some(&[]),
+                help: "count lines at this rate (0 to 1) and scale counts",
+            },
+            Opt {
+                flag: "--seed",
+                values: Some(&[]),
+                help: "seed of --sample",
+            },
+            Opt {
+                flag: "--df",
+                values: Some(&[]),
+                help: "also write counts of files each token appears in",
+            },
+            Opt {
+                flag: "--access-log",
+                values: Some(&[
+                    "host",
+                    "user",
+                    "time",
+                    "method",
+                    "path",
+                    "protocol",
+                    "status",
+                    "bytes",
+                    "referrer",
+                    "user-agent",
+                ]),
+                help: "count values of a field of access logs",
+            },
+            Opt {
+                flag: "--log-key",
+                values: Some(&[]),
+                help: "count values of a key of logfmt or JSON lines",
+            },
+            Opt {
+                flag: "--clipboard",
+                values: None,
+                help: "count the text of the clipboard",
+            },
+            Opt {
+                flag: "--per-file",
+                values: None,
+                help: "write counts of each file as a JSON line as soon as it is counted",
+            },
+            ENCODING,
+            COLUMNS,
+            PRECISION,
+            Opt {
+                flag: "--jobs",
+                values: Some(&[]),
+                help: "count files on N threads",
+            },
+            Opt {
+                flag: "--symlinks",
+                values: Some(&["follow", "skip", "error"]),
+                help: "follow symbolic links, skip them, or fail on ones making a cycle",
+            },
+            Opt {
+                flag: "--check-quality",
+                values: None,
+                help: "report replacement chars, invalid UTF-8 and control chars of each file",
+            },
+            Opt {
+                flag: "--by-extension",
+                values: None,
+                help: "print totals of files per extension to stderr",
+            },
+            Opt {
+                flag: "--partial-on-interrupt",
+                values: None,
+                help: "on Ctrl-C, stop and write counts of the files counted so far",
+            },
+            Opt {
+                flag: "--metadata",
+                values: None,
+                help: "write the version, mode, config hash, inputs and time with counts",
+            },
+            Opt {
+                flag: "--min-words",
+                values: Some(&[]),
+                help: "exit with 4 if there are fewer words (of each file with --per-file)",
+            },
+            Opt {
+                flag: "--max-words",
+                values: Some(&[]),
+                help: "exit with 4 if there are more words (of each file with --per-file)",
+            },
+            Opt {
+                flag: "--fold-typos",
+                values: None,
+                help: "add counts of rare tokens to tokens a char apart and 10 times as frequent",
+            },
+            Opt {
+                flag: "--exclude-pattern",
+                values: Some(&[]),
+                help: "do not count tokens matching this regex, may be repeated",
+            },
+            Opt {
+                flag: "--index",
+                values: Some(&[]),
+                help: "Elasticsearch index of --format bulk",
+            },
+            Opt {
+                flag: "--id",
+                values: Some(&[]),
+                help: "template of ids of --format bulk, of {token} and {file}, or empty",
+            },
+        ],
+    },
+    Subcommand {
+        name: "top",
+        args: "FILE",
+        about: "print the most frequent tokens",
+        options: &[
+            Opt {
+                flag: "-n",
+                values: Some(&[]),
+                help: "count of tokens (default 10)",
+            },
+            Opt {
+                flag: "--percent",
+                values: None,
+                help: "print shares in percent instead of counts, or as a column of --format",
+            },
+            Opt {
+                flag: "--bytes",
+                values: None,
+                help: "print bytes taken by each token (count × length) and their shares",
+            },
+            Opt {
+                flag: "--format",
+                values: Some(FORMATS),
+                help: "output format instead of lines of counts",
+            },
+            ENCODING,
+            COLUMNS,
+            PRECISION,
+            NUMBERS,
+        ],
+    },
+    Subcommand {
+        name: "merge",
+        args: "FILE...",
+        about: "sum counts of files, into the first FILE if it is .bin",
+        options: &[Opt {
+            flag: "-o",
+            values: Some(&[]),
+            help: "write to a file instead of stdout, in the binary format if it is .bin",
+        }],
+    },
+    Subcommand {
+        name: "diff",
+        args: "OLD NEW",
+        about: "print changes of counts",
+        options: &[],
+    },
+    Subcommand {
+        name: "compare",
+        args: "A B",
+        about: "print similarity of two counts and tokens only in either",
+        options: &[Opt {
+            flag: "-n",
+            values: Some(&[]),
+            help: "count of tokens of each kind",
+        }],
+    },
+    Subcommand {
+        name: "unknown",
+        args: "DICTIONARY FILE...",
+        about: "print counts of words not in a dictionary",
+        options: &[Opt {
+            flag: "--config",
+            values: Some(&[]),
+            help: "settings file instead of wordcount.toml",
+        }],
+    },
+    Subcommand {
+        name: "stats",
+        args: "FILE...",
+        about: "print statistics of lines",
+        options: &[
+            NUMBERS,
+            Opt {
+                flag: "--unwrap",
+                values: None,
+                help: "read hard-wrapped paragraphs as lines",
+            },
+        ],
+    },
+    Subcommand {
+        name: "uniq",
+        args: "[FILE]",
+        about: "count runs of equal lines of sorted input, like sort | uniq -c",
+        options: &[],
+    },
+    Subcommand {
+        name: "columns",
+        args: "PATTERN [FILE]...",
+        about: "print a histogram of columns where PATTERN matches in lines",
+        options: &[],
+    },
+    Subcommand {
+        name: "duplicates",
+        args: "FILE...",
+        about: "print lines appearing in more than one file with their locations",
+        options: &[
+            Opt {
+                flag: "--normalize-whitespace",
+                values: None,
+                help: "compare lines with runs of whitespace as one space",
+            },
+            Opt {
+                flag: "--min-chars",
+                values: Some(&[]),
+                help: "ignore lines with fewer chars other than whitespace (default 2)",
+            },
+        ],
+    },
+    Subcommand {
+        name: "progress",
+        args: "BASELINE FILE...",
+        about: "print words added and removed per file since the baseline, or save it",
+        options: &[
+            Opt {
+                flag: "--config",
+                values: Some(&[]),
+                help: "settings file instead of wordcount.toml",
+            },
+            Opt {
+                flag: "--save",
+                values: None,
+                help: "save counts of the files as the baseline",
+            },
+        ],
+    },
+    Subcommand {
+        name: "serve",
+        args: "",
+        about: "serve counting over HTTP",
+        options: &[
+            Opt {
+                flag: "--addr",
+                values: Some(&[]),
+                help: "address to listen on (default 127.0.0.1:8080)",
+            },
+            Opt {
+                flag: "--allow-files",
+                values: Some(&[]),
+                help: "serve POST /file for files under DIR",
+            },
+        ],
+    },
+    Subcommand {
+        name: "tui",
+        args: "FILE",
+        about: "explore counts of a file interactively",
+        options: &[],
+    },
+    Subcommand {
+        name: "repl",
+        args: "",
+        about: "count pasted texts one by one, each ended by a delimiter line or EOF",
+        options: &[Opt {
+            flag: "--delimiter",
+            values: Some(&[]),
+            help: "line ending a text (default .)",
+        }],
+    },
+    Subcommand {
+        name: "completions",
+        args: "SHELL",
+        about: "print a completion script of bash, zsh or fish",
+        options: &[],
+    },
+    Subcommand {
+        name: "man",
+        args: "",
+        about: "print the man page",
+        options: &[],
+    },
+];
+
+/// shells with completion scripts
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+/// completion script of `shell`, `None` if not supported
+pub fn script(shell: &str) -> Option<String> {
+    match shell {
+        "bash" => Some(bash()),
+        "zsh" => Some(zsh()),
+        "fish" => Some(fish()),
+        _ => None,
+    }
+}
+
+fn names() -> Vec<&'static str> {
+    SUBCOMMANDS.iter().map(|s| s.name).collect()
+}
+
+fn bash() -> String {
+    let mut out = String::new();
+    out.push_str("_wordcount() {\n");
+    out.push_str("    local cur prev\n");
+    out.push_str("    cur=\"${COMP_WORDS[COMP_CWORD]}\"\n");
+    out.push_str("    prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n");
+    out.push_str("    if [ \"$COMP_CWORD\" -eq 1 ]; then\n");
+    writeln!(
+        out,
+        "        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+        names().join(" ")
+    )
+    .unwrap();
+    out.push_str("        return\n    fi\n");
+    out.push_str("    case \"${COMP_WORDS[1]}\" in\n");
+    for sub in SUBCOMMANDS {
+        let mut words: Vec<_> = sub.options.iter().map(|o| o.flag).collect();
+        if sub.name == "completions" {
+            words.extend(SHELLS);
+        }
+        writeln!(out, "    {})", sub.name).unwrap();
+        out.push_str("        case \"$prev\" in\n");
+        for opt in sub.options {
+            match opt.values {
+                Some(values) if !values.is_empty() => writeln!(
+                    out,
+                    "            {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;",
+                    opt.flag,
+                    values.join(" ")
+                )
+                .unwrap(),
+                Some(_) => writeln!(
+                    out,
+                    "            {}) COMPREPLY=($(compgen -f -- \"$cur\")); return ;;",
+                    opt.flag
+                )
+                .unwrap(),
+                None => {}
+            }
+        }
+        out.push_str("        esac\n");
+        writeln!(
+            out,
+            "        COMPREPLY=($(compgen -W \"{}\" -f -- \"$cur\")) ;;",
+            words.join(" ")
+        )
+        .unwrap();
+    }
+    out.push_str("    esac\n}\ncomplete -F _wordcount wordcount\n");
+    out
+}
+
+fn zsh() -> String {
+    let mut out = String::from("#compdef wordcount\n\n_wordcount() {\n");
+    out.push_str("    local -a subcommands\n    subcommands=(\n");
+    for sub in SUBCOMMANDS {
+        writeln!(out, "        '{}:{}'", sub.name, sub.about).unwrap();
+    }
+    out.push_str("    )\n    if (( CURRENT == 2 )); then\n");
+    out.push_str("        _describe 'subcommand' subcommands\n        return\n    fi\n");
+    out.push_str("    case $words[2] in\n");
+    for sub in SUBCOMMANDS {
+        writeln!(out, "    {})", sub.name).unwrap();
+        out.push_str("        _arguments \\\n");
+        for opt in sub.options {
+            let action = match opt.values {
+                Some(values) if !values.is_empty() => format!(":value:({})", values.join(" ")),
+                Some(_) => ":file:_files".to_string(),
+                None => String::new(),
+            };
+            writeln!(out, "            '{}[{}]{}' \\", opt.flag, opt.help, action).unwrap();
+        }
+        if sub.name == "completions" {
+            writeln!(out, "            ':shell:({})' ;;", SHELLS.join(" ")).unwrap();
+        } else {
+            out.push_str("            '*:file:_files' ;;\n");
+        }
+    }
+    out.push_str("    esac\n}\n\n_wordcount \"$@\"\n");
+    out
+}
+
+fn fish() -> String {
+    let mut out = String::new();
+    let names = names().join(" ");
+    for sub in SUBCOMMANDS {
+        writeln!(
+            out,
+            "complete -c wordcount -n \"not __fish_seen_subcommand_from {}\" -a {} -d '{}'",
+            names, sub.name, sub.about
+        )
+        .unwrap();
+    }
+    for sub in SUBCOMMANDS {
+        let condition = format!("__fish_seen_subcommand_from {}", sub.name);
+        for opt in sub.options {
+            let flag = match opt.flag.strip_prefix("--") {
+                Some(long) => format!("-l {}", long),
+                None => format!("-s {}", &opt.flag[1..]),
+            };
+            let values = match opt.values {
+                Some(values) if !values.is_empty() => format!(" -x -a '{}'", values.join(" ")),
+                Some(_) => " -r".to_string(),
+                None => String::new(),
+            };
+            writeln!(
+                out,
+                "complete -c wordcount -n \"{}\" {}{} -d '{}'",
+                condition, flag, values, opt.help
+            )
+            .unwrap();
+        }
+    }
+    writeln!(
+        out,
+        "complete -c wordcount -n \"__fish_seen_subcommand_from completions\" -x -a '{}'",
+        SHELLS.join(" ")
+    )
+    .unwrap();
+    out
+}
+
+/// man page in roff
+pub fn man_page() -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        ".TH WORDCOUNT 1 \"\" \"wordcount {}\" \"User Commands\"",
+        env!("CARGO_PKG_VERSION")
+    )
+    .unwrap();
+    out.push_str(".SH NAME\nwordcount \\- count frequencies of chars, words or lines\n");
+    out.push_str(".SH SYNOPSIS\n");
+    for sub in SUBCOMMANDS {
+        writeln!(out, ".B wordcount {}", sub.name).unwrap();
+        for opt in sub.options {
+            match opt.values {
+                Some(_) => writeln!(out, "[\\fB{}\\fR \\fIVALUE\\fR]", escape(opt.flag)).unwrap(),
+                None => writeln!(out, "[\\fB{}\\fR]", escape(opt.flag)).unwrap(),
+            }
+        }
+        if !sub.args.is_empty() {
+            writeln!(out, ".I {}", sub.args).unwrap();
+        }
+        out.push_str(".br\n");
+    }
+    out.push_str(".SH DESCRIPTION\n");
+    out.push_str("A FILE with the extension .json is read as counts saved by \\fBcount\\fR or ");
+    out.push_str("\\fBmerge\\fR, and one with .bin as counts in the binary format, ");
+    out.push_str("other files are counted as text.\n");
+    out.push_str(".SH COMMANDS\n");
+    for sub in SUBCOMMANDS {
+        writeln!(out, ".TP\n.B {}\n{}", sub.name, sub.about).unwrap();
+        for opt in sub.options {
+            let values = match opt.values {
+                Some(values) if !values.is_empty() => format!(" ({})", values.join(", ")),
+                _ => String::new(),
+            };
+            writeln!(
+                out,
+                ".RS\n.TP\n.B {}\n{}{}\n.RE",
+                escape(opt.flag),
+                opt.help,
+                values
+            )
+            .unwrap();
+        }
+    }
+    out.push_str(".SH FILES\n.TP\n.I wordcount.toml\nsettings of \\fBcount\\fR\n");
+    out
+}
+
+/// escape `-` of roff
+fn escape(s: &str) -> String {
+    s.replace('-', "\\-")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scripts_mention_all_subcommands() {
+        for shell in SHELLS {
+            let script = script(shell).unwrap();
+            for sub in SUBCOMMANDS {
+                assert!(script.contains(sub.name), "{} lacks {}", shell, sub.name);
+            }
+            assert!(script.contains("jsonl"), "{}", shell);
+        }
+        assert!(script("tcsh").is_none());
+        assert!(bash().contains(
+            "--format) COMPREPLY=($(compgen -W \"json jsonl debug csv latex markdown html trie snapshot parquet bulk\""
+        ));
+        assert!(fish().contains("-l allow-files -r -d 'serve POST /file for files under DIR'"));
+    }
+
+    #[test]
+    fn man_page_works() {
+        let page = man_page();
+        assert!(page.starts_with(".TH WORDCOUNT 1"));
+        assert!(page
+            .contains(".B wordcount top\n[\\fB\\-n\\fR \\fIVALUE\\fR]\n[\\fB\\-\\-percent\\fR]\n"));
+    }
+}
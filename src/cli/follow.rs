@@ -0,0 +1,168 @@
+//! `--follow`: keep counting a growing file, like `tail -f`
+
+use std::fs::{File, Metadata};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use bicycle_book_wordcount::{CountConfig, Counter};
+
+use super::metrics::Metrics;
+
+/// reads lines appended to a file, reopening it on truncation or rotation
+pub struct Follower {
+    path: PathBuf,
+    reader: Option<BufReader<File>>,
+    identity: Option<Identity>,
+    position: u64,
+    partial: String,
+}
+
+impl Follower {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Follower {
+            path: path.into(),
+            reader: None,
+            identity: None,
+            position: 0,
+            partial: String::new(),
+        }
+    }
+
+    /// feed complete lines appended since the last poll to `counter`, returns count of them.
+    /// a missing file (e.g. during rotation) has no lines
+    pub fn poll(&mut self, counter: &mut Counter) -> io::Result<usize> {
+        let metadata = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let identity = Identity::of(&metadata);
+        let rotated = self.identity.as_ref() != Some(&identity);
+        if rotated || metadata.len() < self.position {
+            self.reopen(&self.path.clone())?;
+            self.identity = Some(identity);
+        }
+
+        let reader = match &mut self.reader {
+            Some(reader) => reader,
+            None => return Ok(0),
+        };
+        let mut lines = 0;
+        loop {
+            let read = reader.read_line(&mut self.partial)?;
+            if read == 0 {
+                break;
+            }
+            self.position += read as u64;
+            if self.partial.ends_with('\n') {
+                let line = self.partial.trim_end_matches(&['\n', '\r'][..]);
+                counter
+                    .line(line)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+                self.partial.clear();
+                lines += 1;
+            }
+        }
+        Ok(lines)
+    }
+
+    fn reopen(&mut self, path: &Path) -> io::Result<()> {
+        self.reader = Some(BufReader::new(File::open(path)?));
+        self.position = 0;
+        self.partial.clear();
+        Ok(())
+    }
+}
+
+/// identity of a file, to detect rotation
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Identity(u64, u64);
+
+impl Identity {
+    #[cfg(unix)]
+    fn of(metadata: &Metadata) -> Self {
+        use std::os::unix::fs::MetadataExt;
+        Identity(metadata.dev(), metadata.ino())
+    }
+
+    #[cfg(not(unix))]
+    fn of(metadata: &Metadata) -> Self {
+        let created = metadata
+            .created()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos() as u64);
+        Identity(created, 0)
+    }
+}
+
+/// follow `path` forever, printing the `top` most frequent tokens every `interval`
+/// when new lines are counted, and updating `metrics`
+pub fn run(
+    path: &Path,
+    config: &CountConfig,
+    top: usize,
+    interval: Duration,
+    metrics: &Metrics,
+) -> io::Result<()> {
+    let mut follower = Follower::new(path);
+    let mut counter = Counter::new(config);
+    loop {
+        let position = follower.position;
+        let lines = follower.poll(&mut counter)?;
+        if lines > 0 {
+            let bytes = follower.position.saturating_sub(position);
+            metrics.add_lines(lines, bytes as usize);
+            metrics.set_result(counter.frequencies());
+            let snapshot = counter.snapshot(top);
+            let stdout = io::stdout();
+            let mut out = stdout.lock();
+            writeln!(
+                out,
+                "--- {} lines, {} tokens ({} unique)",
+                snapshot.lines, snapshot.total, snapshot.unique
+            )?;
+            for (token, count) in snapshot.top {
+                writeln!(out, "{}\t{}", count, token)?;
+            }
+            out.flush()?;
+        }
+        thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+
+    #[test]
+    fn poll_follows_growth_and_truncation() -> io::Result<()> {
+        let path = std::env::temp_dir().join("wordcount_follow_poll.log");
+        fs::write(&path, "aa bb\naa")?;
+        let mut follower = Follower::new(&path);
+        let mut counter = Counter::new(&CountConfig::default());
+
+        assert_eq!(follower.poll(&mut counter)?, 1);
+        assert_eq!(counter.frequencies().get("aa"), Some(&1));
+
+        let mut file = OpenOptions::new().append(true).open(&path)?;
+        file.write_all(b" cc\ndd\n")?;
+        assert_eq!(follower.poll(&mut counter)?, 2);
+        assert_eq!(counter.frequencies()["aa"], 2);
+        assert_eq!(counter.frequencies()["cc"], 1);
+        assert_eq!(follower.poll(&mut counter)?, 0);
+
+        fs::write(&path, "ee\n")?;
+        assert_eq!(follower.poll(&mut counter)?, 1);
+        assert_eq!(counter.frequencies()["ee"], 1);
+        assert_eq!(counter.lines(), 4);
+
+        fs::remove_file(&path)?;
+        assert_eq!(follower.poll(&mut counter)?, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+//! glob patterns of paths, for ignoring files
+//!
+//! `*` matches any chars but `/`, `?` matches a char but `/`, and `**` matches any chars.
+//! a pattern without `/` matches the file name at any depth, like `.gitignore`.
+
+/// a glob pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    pattern: Vec<char>,
+    anywhere: bool,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        Glob {
+            pattern: pattern.trim_start_matches('/').chars().collect(),
+            anywhere: !pattern.contains('/'),
+        }
+    }
+
+    /// `path` (relative, separated by `/`) matches
+    pub fn matches(&self, path: &str) -> bool {
+        let path = path.trim_start_matches("./");
+        let target = if self.anywhere {
+            path.rsplit('/').next().unwrap_or(path)
+        } else {
+            path
+        };
+        let target: Vec<char> = target.chars().collect();
+        matches(&self.pattern, &target)
+    }
+}
+
+fn matches(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', rest @ ..] => {
+            let rest = match rest {
+                ['/', rest @ ..] => rest,
+                _ => rest,
+            };
+            // `**/` also matches no directory
+            (0..=text.len()).any(|i| matches(rest, &text[i..]))
+        }
+        ['*', rest @ ..] => {
+            let end = text.iter().position(|&c| c == '/').unwrap_or(text.len());
+            (0..=end).any(|i| matches(rest, &text[i..]))
+        }
+        ['?', rest @ ..] => match text {
+            [c, tail @ ..] if *c != '/' => matches(rest, tail),
+            _ => false,
+        },
+        [p, rest @ ..] => match text {
+            [c, tail @ ..] if c == p => matches(rest, tail),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn glob_works() {
+        let m = |pattern: &str, path: &str| Glob::new(pattern).matches(path);
+        assert!(m("*.min.js", "web/lib/app.min.js"));
+        assert!(!m("*.min.js", "web/app.js"));
+        assert!(m("target/**", "target/debug/x"));
+        assert!(!m("target/**", "src/target/x"));
+        assert!(m("**/fixtures/*.txt", "fixtures/a.txt"));
+        assert!(m("**/fixtures/*.txt", "tests/fixtures/a.txt"));
+        assert!(!m("src/*.rs", "src/cli/main.rs"));
+        assert!(m("src/?.rs", "./src/a.rs"));
+    }
+}
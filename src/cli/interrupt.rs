@@ -0,0 +1,73 @@
+//! opt-in handling of SIGINT (Ctrl-C), to stop counting and write what has been counted
+//!
+//! after [`catch`](fn.catch.html), the first SIGINT only sets a flag which counting checks
+//! between files, and reading of files by [`Interruptible`](struct.Interruptible.html) too, so
+//! that the file being counted is left out at once. the second one exits at once with
+//! [`EXIT_INTERRUPTED`](constant.EXIT_INTERRUPTED.html). SIGINT is caught only on Unix, and
+//! stops the process as usual elsewhere.
+
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// exit code of a process stopped by SIGINT, by the convention of shells (128 + 2)
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// catch SIGINT from now on
+pub fn catch() {
+    #[cfg(unix)]
+    unix::catch();
+}
+
+/// SIGINT has been caught
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// a reader failing once SIGINT is caught, to stop counting in the middle of a file
+#[derive(Debug)]
+pub struct Interruptible<R>(pub R);
+
+impl<R: Read> Read for Interruptible<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if interrupted() {
+            // not `ErrorKind::Interrupted`, which readers retry
+            return Err(io::Error::new(io::ErrorKind::Other, "interrupted"));
+        }
+        self.0.read(buf)
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::os::raw::c_int;
+    use std::sync::atomic::Ordering;
+
+    use super::{EXIT_INTERRUPTED, INTERRUPTED};
+
+    const SIGINT: c_int = 2;
+    const SIG_IGN: usize = 1;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: usize) -> usize;
+        fn _exit(status: c_int) -> !;
+    }
+
+    /// only async-signal-safe operations: an atomic store and `_exit`
+    extern "C" fn on_sigint(_: c_int) {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            unsafe { _exit(EXIT_INTERRUPTED) }
+        }
+    }
+
+    /// unless SIGINT is ignored, as of background jobs of shells and of `nohup`
+    pub(super) fn catch() {
+        let handler: extern "C" fn(c_int) = on_sigint;
+        unsafe {
+            if signal(SIGINT, handler as usize) == SIG_IGN {
+                signal(SIGINT, SIG_IGN);
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! metrics of the long-running modes (`serve` and `--follow`) in the Prometheus text format,
+//! served at `GET /metrics`
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use bicycle_book_wordcount::Frequencies;
+
+/// counters updated while running, shared between threads
+#[derive(Debug)]
+pub struct Metrics {
+    started: Instant,
+    lines: AtomicU64,
+    bytes: AtomicU64,
+    requests: AtomicU64,
+    unique_keys: AtomicU64,
+    memory_bytes: AtomicU64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            started: Instant::now(),
+            lines: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            requests: AtomicU64::new(0),
+            unique_keys: AtomicU64::new(0),
+            memory_bytes: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Metrics {
+    /// `n` lines of `bytes` bytes are counted
+    pub fn add_lines(&self, n: usize, bytes: usize) {
+        self.lines.fetch_add(n as u64, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// a request is served
+    pub fn add_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// the current (or latest) result is `freqs`
+    pub fn set_result(&self, freqs: &Frequencies) {
+        let memory = freqs.estimated_bytes() as u64;
+        self.unique_keys
+            .store(freqs.len() as u64, Ordering::Relaxed);
+        self.memory_bytes.store(memory, Ordering::Relaxed);
+    }
+
+    /// all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let uptime = self.started.elapsed().as_secs_f64();
+        let lines = self.lines.load(Ordering::Relaxed);
+        let rate = if uptime > 0.0 {
+            lines as f64 / uptime
+        } else {
+            0.0
+        };
+        let metrics: &[(&str, &str, &str, f64)] = &[
+            ("uptime_seconds", "gauge", "seconds since start", uptime),
+            ("lines_total", "counter", "lines counted", lines as f64),
+            (
+                "bytes_total",
+                "counter",
+                "bytes of lines counted",
+                self.bytes.load(Ordering::Relaxed) as f64,
+            ),
+            (
+                "lines_per_second",
+                "gauge",
+                "average lines counted per second",
+                rate,
+            ),
+            (
+                "requests_total",
+                "counter",
+                "HTTP requests served",
+                self.requests.load(Ordering::Relaxed) as f64,
+            ),
+            (
+                "unique_keys",
+                "gauge",
+                "distinct tokens of the latest result",
+                self.unique_keys.load(Ordering::Relaxed) as f64,
+            ),
+            (
+                "memory_bytes",
+                "gauge",
+                "estimated heap bytes of the latest result",
+                self.memory_bytes.load(Ordering::Relaxed) as f64,
+            ),
+        ];
+        let mut text = String::new();
+        for (name, kind, help, value) in metrics {
+            writeln!(text, "# HELP wordcount_{} {}", name, help).unwrap();
+            writeln!(text, "# TYPE wordcount_{} {}", name, kind).unwrap();
+            writeln!(text, "wordcount_{} {}", name, value).unwrap();
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bicycle_book_wordcount::{count_str, CountOption};
+
+    #[test]
+    fn render_works() {
+        let metrics = Metrics::default();
+        metrics.add_lines(3, 20);
+        metrics.add_request();
+        metrics.set_result(&count_str("aa bb aa", CountOption::Word));
+        let text = metrics.render();
+        assert!(text.contains("# TYPE wordcount_lines_total counter\nwordcount_lines_total 3\n"));
+        assert!(text.contains("\nwordcount_requests_total 1\n"));
+        assert!(text.contains("\nwordcount_unique_keys 2\n"));
+        assert!(!text.contains("\nwordcount_memory_bytes 0\n"));
+    }
+}
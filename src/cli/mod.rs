@@ -0,0 +1,14 @@
+//! modes of the command line tool
+
+pub mod clipboard;
+pub mod command;
+pub mod completions;
+pub mod follow;
+pub mod glob;
+pub mod interrupt;
+pub mod metrics;
+pub mod repl;
+pub mod serve;
+pub mod settings;
+pub mod toml;
+pub mod tui;
@@ -0,0 +1,90 @@
+//! `repl`: count pasted text again and again without restarting, for quick checks while
+//! editing
+//!
+//! text is read from stdin until a line of only the delimiter (`.` by default) or EOF, and its
+//! counts are printed at once. EOF without any text quits
+
+use std::io::{self, BufRead, Write};
+
+use bicycle_book_wordcount::stats::count_with_stats;
+use bicycle_book_wordcount::CountOption;
+
+/// line ending a text by default
+pub const DELIMITER: &str = ".";
+
+/// most frequent tokens printed for a text
+const TOP: usize = 5;
+
+/// counts of a text: lines, chars and a summary of words
+pub fn stats(text: &str) -> String {
+    let (freqs, stats) = count_with_stats(text.as_bytes(), CountOption::Word);
+    format!(
+        "lines: {}, chars: {}\n{}",
+        stats.lines(),
+        stats.chars.sum(),
+        freqs.summary(TOP)
+    )
+}
+
+/// read texts from input ended by lines of `delimiter` or EOF, and write counts of each to
+/// output. a prompt is written before each text
+pub fn run_with(
+    mut input: impl BufRead,
+    mut output: impl Write,
+    delimiter: &str,
+) -> io::Result<()> {
+    let mut text = String::new();
+    let mut line = String::new();
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+        text.clear();
+        let ended = loop {
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                break true;
+            }
+            if line.trim_end_matches(&['\r', '\n'][..]) == delimiter {
+                break false;
+            }
+            text.push_str(&line);
+        };
+        if ended && text.is_empty() {
+            writeln!(output)?;
+            return Ok(());
+        }
+        write!(output, "{}", stats(&text))?;
+        // a terminal can be read again after EOF (Ctrl-D), a pipe ends at the next read
+    }
+}
+
+/// count texts of stdin, see [`run_with`](fn.run_with.html)
+pub fn run(delimiter: &str) -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    eprintln!(
+        "paste text and end it with a line of {:?} or EOF, EOF without text quits",
+        delimiter
+    );
+    run_with(stdin.lock(), stdout.lock(), delimiter)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn texts_are_counted_one_by_one() {
+        let input = "the cat\nthe dog\n.\n\n.\nlast one";
+        let mut output = Vec::new();
+        run_with(input.as_bytes(), &mut output, DELIMITER).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let texts: Vec<_> = output.split("> ").skip(1).collect();
+        assert_eq!(texts.len(), 4);
+        assert!(texts[0].starts_with("lines: 2, chars: 14\ntotal: 4, unique: 3\n"));
+        assert!(texts[0].contains("    2  50.0%  the\n"));
+        assert!(texts[1].starts_with("lines: 1, chars: 0\ntotal: 0, unique: 0\n"));
+        assert!(texts[2].starts_with("lines: 1, chars: 8\ntotal: 2, unique: 2\n"));
+        assert_eq!(texts[3], "\n");
+    }
+}
@@ -0,0 +1,466 @@
+//! `wordcount serve`: counting over HTTP
+//!
+//! * `POST /count[?option=char|word|line]`: counts of the request body (UTF-8) as JSON
+//! * `POST /file[?option=...]`: the total and the number of distinct tokens (not the tokens)
+//!   of the file at the path in the request body, relative to the directory of
+//!   `--allow-files DIR` and only under it
+//! * `GET /metrics`: [`metrics`](../metrics/index.html) of the server
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use bicycle_book_wordcount::output::write_json;
+use bicycle_book_wordcount::{try_count_with, CountConfig, CountOption};
+
+use super::metrics::Metrics;
+
+/// largest request body accepted
+const MAX_BODY: usize = 64 * 1024 * 1024;
+
+/// longest request line or header line accepted, in bytes
+const MAX_LINE: usize = 8 * 1024;
+
+/// most header lines of a request
+const MAX_HEADERS: usize = 100;
+
+/// most connections handled at once, more are answered by 503
+const MAX_CONNECTIONS: usize = 16;
+
+/// timeout of each read and write of a connection
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// timeout of each read and write of a connection to `spawn_metrics`, which serves a
+/// connection at a time
+const METRICS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// options of the server
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// serve `POST /file` for files under this directory, which is canonical
+    pub files: Option<PathBuf>,
+    /// updated by each request
+    pub metrics: Arc<Metrics>,
+}
+
+/// a parsed HTTP request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// an HTTP response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    fn json(body: Vec<u8>) -> Self {
+        Response {
+            status: 200,
+            content_type: "application/json",
+            body,
+        }
+    }
+
+    fn metrics(metrics: &Metrics) -> Self {
+        Response {
+            status: 200,
+            content_type: "text/plain; version=0.0.4; charset=utf-8",
+            body: metrics.render().into_bytes(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{}\n", message).into_bytes(),
+        }
+    }
+
+    fn write_to(&self, mut out: impl Write) -> io::Result<()> {
+        write!(
+            out,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            reason(self.status),
+            self.content_type,
+            self.body.len()
+        )?;
+        out.write_all(&self.body)?;
+        out.flush()
+    }
+}
+
+/// serve on `addr` until the process is killed, a thread per connection up to
+/// `MAX_CONNECTIONS` connections
+pub fn run(addr: &str, options: Options) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    eprintln!("listening on http://{}", listener.local_addr()?);
+    let options = Arc::new(options);
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("failed to accept: {}", e);
+                continue;
+            }
+        };
+        let guard = match Active::enter(&active) {
+            Some(guard) => guard,
+            None => {
+                let _ = set_timeouts(&stream, TIMEOUT)
+                    .and_then(|_| Response::error(503, "too many connections").write_to(stream));
+                continue;
+            }
+        };
+        let options = Arc::clone(&options);
+        thread::spawn(move || {
+            let _guard = guard;
+            if let Err(e) = connection(stream, &options) {
+                eprintln!("connection error: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// a connection being handled, counted in the shared counter while it lives
+struct Active(Arc<AtomicUsize>);
+
+impl Active {
+    /// `None` if `MAX_CONNECTIONS` connections are handled
+    fn enter(active: &Arc<AtomicUsize>) -> Option<Self> {
+        active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                if n < MAX_CONNECTIONS {
+                    Some(n + 1)
+                } else {
+                    None
+                }
+            })
+            .ok()
+            .map(|_| Active(Arc::clone(active)))
+    }
+}
+
+impl Drop for Active {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn set_timeouts(stream: &TcpStream, timeout: Duration) -> io::Result<()> {
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))
+}
+
+fn connection(stream: TcpStream, options: &Options) -> io::Result<()> {
+    set_timeouts(&stream, TIMEOUT)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let response = match read_request(&mut reader) {
+        Ok(request) => handle(&request, options),
+        Err(e) => Response::error(400, &e.to_string()),
+    };
+    response.write_to(stream)
+}
+
+/// read a request with a body of `Content-Length`
+pub fn read_request(input: &mut impl BufRead) -> io::Result<Request> {
+    let mut line = String::new();
+    read_head_line(input, &mut line)?;
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method.to_string(), target.to_string()),
+        _ => return Err(invalid("malformed request line")),
+    };
+
+    let mut length = 0;
+    for headers in 0.. {
+        if read_head_line(input, &mut line)? == 0 {
+            return Err(invalid("unexpected end of headers"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if headers == MAX_HEADERS {
+            return Err(invalid("too many headers"));
+        }
+        if let Some(i) = header.find(':') {
+            if header[..i].eq_ignore_ascii_case("content-length") {
+                length = header[i + 1..]
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid("bad Content-Length"))?;
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Err(invalid("request body is too large"));
+    }
+
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    let (path, query) = match target.find('?') {
+        Some(i) => (target[..i].to_string(), Some(target[i + 1..].to_string())),
+        None => (target, None),
+    };
+    Ok(Request {
+        method,
+        path,
+        query,
+        body,
+    })
+}
+
+/// read a line of the request head into `line`, failing if it is longer than `MAX_LINE`
+fn read_head_line(input: &mut impl BufRead, line: &mut String) -> io::Result<usize> {
+    line.clear();
+    let read = input.take(MAX_LINE as u64 + 1).read_line(line)?;
+    if read > MAX_LINE {
+        return Err(invalid("request line or header is too long"));
+    }
+    Ok(read)
+}
+
+/// serve only `GET /metrics` on `addr` in a background thread
+pub fn spawn_metrics(addr: &str, metrics: Arc<Metrics>) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    eprintln!("metrics on http://{}/metrics", listener.local_addr()?);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // an idle client must not hold the only thread
+            let timeouts = set_timeouts(&stream, METRICS_TIMEOUT);
+            let mut reader = match timeouts.and_then(|_| stream.try_clone()) {
+                Ok(stream) => BufReader::new(stream),
+                Err(_) => continue,
+            };
+            let response = match read_request(&mut reader) {
+                Ok(ref request) if request.path == "/metrics" => Response::metrics(&metrics),
+                Ok(_) => Response::error(404, "not found"),
+                Err(e) => Response::error(400, &e.to_string()),
+            };
+            if let Err(e) = response.write_to(stream) {
+                eprintln!("connection error: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// response to a request
+pub fn handle(request: &Request, options: &Options) -> Response {
+    options.metrics.add_request();
+    if request.path == "/metrics" {
+        return match request.method.as_str() {
+            "GET" => Response::metrics(&options.metrics),
+            _ => Response::error(405, "method not allowed"),
+        };
+    }
+    let option = match option(request.query.as_deref()) {
+        Some(option) => option,
+        None => return Response::error(400, "unknown option"),
+    };
+    let config = CountConfig::new(option);
+
+    let result = match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/count") => try_count_with(Cursor::new(&request.body), &config),
+        ("POST", "/file") if options.files.is_some() => {
+            let root = options.files.as_deref().expect("files are served");
+            let path = String::from_utf8_lossy(&request.body);
+            match open_under(root, path.trim()) {
+                Ok(file) => try_count_with(BufReader::new(file), &config),
+                Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                    return Response::error(403, &e.to_string())
+                }
+                Err(e) => return Response::error(404, &e.to_string()),
+            }
+        }
+        (_, "/count") => return Response::error(405, "method not allowed"),
+        (_, "/file") if options.files.is_some() => {
+            return Response::error(405, "method not allowed")
+        }
+        _ => return Response::error(404, "not found"),
+    };
+    match result {
+        Ok(freqs) => {
+            if request.path == "/count" {
+                let lines = request.body.split(|&b| b == b'\n').count();
+                options.metrics.add_lines(lines, request.body.len());
+            }
+            options.metrics.set_result(&freqs);
+            let mut body = Vec::new();
+            if request.path == "/count" {
+                write_json(&freqs, &mut body).unwrap();
+            } else {
+                // the tokens would be the text of the file
+                body = format!(
+                    "{{\"total\":{},\"distinct\":{}}}\n",
+                    freqs.total(),
+                    freqs.len()
+                )
+                .into_bytes();
+            }
+            Response::json(body)
+        }
+        Err(e) => Response::error(400, &e.to_string()),
+    }
+}
+
+/// open `path` relative to `root`, failing with `PermissionDenied` if it is not under `root`
+/// (after resolving `..` and symbolic links)
+fn open_under(root: &Path, path: &str) -> io::Result<File> {
+    let path = root.join(path).canonicalize()?;
+    if !path.starts_with(root) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "the file is not under the directory of --allow-files",
+        ));
+    }
+    File::open(path)
+}
+
+/// `option=...` of a query string, `Word` by default
+fn option(query: Option<&str>) -> Option<CountOption> {
+    let value = query
+        .into_iter()
+        .flat_map(|q| q.split('&'))
+        .find(|pair| pair.starts_with("option="))
+        .map(|pair| &pair["option=".len()..]);
+    match value {
+        None | Some("word") => Some(CountOption::Word),
+        Some("char") => Some(CountOption::Char),
+        Some("line") => Some(CountOption::Line),
+        Some(_) => None,
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn request(raw: &str) -> Request {
+        read_request(&mut Cursor::new(raw.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn read_request_works() {
+        let req = request(
+            "POST /count?option=char HTTP/1.1\r\nHost: x\r\ncontent-length: 3\r\n\r\nabcdef",
+        );
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.path, "/count");
+        assert_eq!(req.query.as_deref(), Some("option=char"));
+        assert_eq!(req.body, b"abc");
+    }
+
+    #[test]
+    fn handle_count() {
+        let options = Options::default();
+        let res = handle(
+            &request("POST /count HTTP/1.1\r\nContent-Length: 8\r\n\r\naa bb aa"),
+            &options,
+        );
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body, b"{\"aa\":2,\"bb\":1}\n");
+
+        let res = handle(
+            &request("POST /count?option=char HTTP/1.1\r\nContent-Length: 2\r\n\r\naa"),
+            &options,
+        );
+        assert_eq!(res.body, b"{\"a\":2}\n");
+
+        let res = handle(&request("GET /metrics HTTP/1.1\r\n\r\n"), &options);
+        let text = String::from_utf8(res.body).unwrap();
+        assert!(text.contains("\nwordcount_requests_total 3\n"));
+        assert!(text.contains("\nwordcount_lines_total 2\n"));
+    }
+
+    #[test]
+    fn handle_errors() {
+        let options = Options::default();
+        let status = |raw: &str| handle(&request(raw), &options).status;
+        assert_eq!(status("GET /count HTTP/1.1\r\n\r\n"), 405);
+        assert_eq!(status("POST /other HTTP/1.1\r\n\r\n"), 404);
+        assert_eq!(status("POST /file HTTP/1.1\r\n\r\n"), 404);
+        assert_eq!(status("POST /count?option=x HTTP/1.1\r\n\r\n"), 400);
+        assert_eq!(status("POST /metrics HTTP/1.1\r\n\r\n"), 405);
+
+        let mut bad = request("POST /count HTTP/1.1\r\n\r\n");
+        bad.body = vec![0xff];
+        assert_eq!(handle(&bad, &options).status, 400);
+    }
+
+    #[test]
+    fn heads_are_limited() {
+        let read = |raw: String| read_request(&mut Cursor::new(raw.into_bytes()));
+        let long = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+        assert!(read(long).is_err());
+        let many = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "a: b\r\n".repeat(MAX_HEADERS + 1)
+        );
+        assert!(read(many).is_err());
+        let enough = format!("GET / HTTP/1.1\r\n{}\r\n", "a: b\r\n".repeat(MAX_HEADERS));
+        assert!(read(enough).is_ok());
+    }
+
+    #[test]
+    fn files_are_under_the_root() {
+        let dir = std::env::temp_dir().join("wordcount_serve_files");
+        let root = dir.join("root");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("in.txt"), "secret words secret").unwrap();
+        std::fs::write(dir.join("out.txt"), "secret").unwrap();
+        let options = Options {
+            files: Some(root.canonicalize().unwrap()),
+            ..Options::default()
+        };
+        let post = |path: &str| {
+            let raw = format!(
+                "POST /file HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+                path.len(),
+                path
+            );
+            handle(&request(&raw), &options)
+        };
+
+        let res = post("in.txt");
+        assert_eq!(res.status, 200);
+        assert_eq!(res.body, b"{\"total\":3,\"distinct\":2}\n");
+        assert_eq!(post("../out.txt").status, 403);
+        assert_eq!(post(dir.join("out.txt").to_str().unwrap()).status, 403);
+        assert_eq!(post("none.txt").status, 404);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,426 @@
+//! settings file of the command line tool (`wordcount.toml`)
+//!
+//! ```toml
+//! mode = "word"             # char, word or line
+//! ignore_case = true
+//! collapse_whitespace = false # count lines differing only in spacing as one
+//! strip_diacritics = false
+//! roman_numerals = false   # count XIV as 14
+//! ordinals = false          # count third and 3RD as 3rd
+//! skip_code_blocks = false
+//! quoted_only = false
+//! invalid_input = "strict"  # strict, lossy, skip or skip_line (not UTF-8 input)
+//! lemmas = "lemmas.tsv"    # count inflected forms as the lemma, lines of form<TAB>lemma
+//! encoding = "utf-8"        # utf-8, or auto to detect (with the feature detect-encoding)
+//! max_token_chars = 64      # keys longer than this are shortened as long_tokens says
+//! long_tokens = "truncate"  # truncate (to the first chars and …), hash or drop
+//! line_key_bytes = 256      # lines longer than this (of line mode) are keyed by a hash
+//! symlinks = "follow"      # follow, skip, or error on a link to a parent directory
+//! jobs = 1                  # threads counting files
+//! check_quality = false     # report signs of broken text of each file
+//! saved = false             # read .json and .bin files as saved counts
+//!
+//! [filters]
+//! stop_words = ["stop.txt"] # files of a word per line
+//! allowed_words = ["dict.txt"] # count only words of these files
+//! exclude_patterns = ["^[0-9a-f]{7,40}$"] # do not count tokens matching these regexes
+//! min_count = 2
+//! ignore = ["target/**", "*.min.js"]
+//!
+//! [output]
+//! format = "json"           # json, jsonl, debug, csv, latex, markdown, html, trie, snapshot, parquet or bulk
+//! encoding = "utf-8"        # utf-8, utf-8-bom, utf-16le or shift_jis (with detect-encoding)
+//! columns = ["percent", "cumulative"] # shares in tables and JSON Lines, [] for none
+//! precision = 1             # decimals of shares, 2 by default
+//! ```
+//!
+//! relative paths of stop word files and the lemma table are relative to the settings file.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bicycle_book_wordcount::accesslog;
+use bicycle_book_wordcount::decode::DecodePolicy;
+use bicycle_book_wordcount::encode::OutputEncoding;
+use bicycle_book_wordcount::metadata::Metadata;
+use bicycle_book_wordcount::normalize::Lemmas;
+use bicycle_book_wordcount::output::Columns;
+use bicycle_book_wordcount::walk::{self, Symlinks};
+use bicycle_book_wordcount::{CountConfig, CountOption, LongTokenPolicy};
+use regex::Regex;
+
+use super::command::{Derived, Format};
+use super::glob::Glob;
+use super::toml::{self, Value};
+
+/// name of the settings file looked up in the current directory
+pub const DEFAULT_PATH: &str = "wordcount.toml";
+
+/// settings of the `count` subcommand
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub config: CountConfig,
+    pub min_count: usize,
+    pub ignore: Vec<Glob>,
+    pub format: Format,
+    pub output_encoding: OutputEncoding,
+    /// derived columns of tables and JSON Lines
+    pub derived: Derived,
+    /// guess the charset of each text file instead of reading UTF-8 (or UTF-16)
+    pub detect_encoding: bool,
+    /// how symbolic links under directories are treated
+    pub symlinks: Symlinks,
+    /// threads counting files
+    pub jobs: usize,
+    /// report signs of broken text of each text file
+    pub check_quality: bool,
+    /// read `.json` and `.bin` files as saved counts
+    pub saved: bool,
+    /// rate and seed to count a sample of lines of text files, not from the settings file
+    pub sample: Option<(f64, u64)>,
+    /// field of access logs to count instead of tokens, not from the settings file
+    pub access_log: Option<accesslog::Field>,
+    /// key of structured logs to count values of instead of tokens, not from the settings file
+    pub log_key: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            config: CountConfig::default(),
+            min_count: 1,
+            ignore: Vec::new(),
+            format: Format::JSON,
+            output_encoding: OutputEncoding::Utf8,
+            derived: Derived::default(),
+            detect_encoding: false,
+            symlinks: Symlinks::default(),
+            jobs: 1,
+            check_quality: false,
+            saved: false,
+            sample: None,
+            access_log: None,
+            log_key: None,
+        }
+    }
+}
+
+impl Settings {
+    /// settings of `path`, or of `wordcount.toml` in the current directory if exists
+    pub fn find(path: Option<&Path>) -> io::Result<Self> {
+        match path {
+            Some(path) => Settings::load(path),
+            None if Path::new(DEFAULT_PATH).is_file() => Settings::load(DEFAULT_PATH),
+            None => Ok(Settings::default()),
+        }
+    }
+
+    /// settings of a file
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let invalid = |e: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        };
+        let document = toml::parse(&text).map_err(invalid)?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+
+        let mut settings = Settings::default();
+        let option = match document.get("mode") {
+            None => CountOption::default(),
+            Some(value) => match (Field { key: "mode", value }).string().map_err(invalid)? {
+                "char" => CountOption::Char,
+                "word" => CountOption::Word,
+                "line" => CountOption::Line,
+                _ => return Err(invalid("unknown mode".to_string())),
+            },
+        };
+        let mut config = CountConfig::new(option);
+        let mut stop_words = Vec::new();
+        let mut allowed_words = None;
+        let mut max_token_chars = None;
+        let mut long_tokens = LongTokenPolicy::default();
+        for (key, value) in &document {
+            let value = Field { key, value };
+            match key.as_str() {
+                "mode" => {}
+                "ignore_case" => config = config.ignore_case(value.boolean().map_err(invalid)?),
+                "collapse_whitespace" => {
+                    config = config.collapse_whitespace(value.boolean().map_err(invalid)?)
+                }
+                "strip_diacritics" => {
+                    config = config.strip_diacritics(value.boolean().map_err(invalid)?)
+                }
+                "roman_numerals" => {
+                    config = config.roman_numerals(value.boolean().map_err(invalid)?)
+                }
+                "ordinals" => config = config.ordinals(value.boolean().map_err(invalid)?),
+                "skip_code_blocks" => {
+                    config = config.skip_code_blocks(value.boolean().map_err(invalid)?)
+                }
+                "quoted_only" => config = config.quoted_only(value.boolean().map_err(invalid)?),
+                "invalid_input" => {
+                    let policy = match value.string().map_err(invalid)? {
+                        "strict" => DecodePolicy::Strict,
+                        "lossy" => DecodePolicy::Lossy,
+                        "skip" => DecodePolicy::SkipInvalid,
+                        "skip_line" => DecodePolicy::SkipLine,
+                        _ => return Err(invalid(format!("unknown policy of {}", key))),
+                    };
+                    config = config.invalid_input(policy)
+                }
+                "lemmas" => {
+                    let file = fs::File::open(base.join(value.string().map_err(invalid)?))?;
+                    config = config.lemmas(Lemmas::from_tsv(io::BufReader::new(file))?)
+                }
+                "encoding" => {
+                    settings.detect_encoding = match value.string().map_err(invalid)? {
+                        "utf-8" => false,
+                        "auto" => true,
+                        _ => return Err(invalid(format!("unknown encoding of {}", key))),
+                    }
+                }
+                "max_token_chars" => max_token_chars = Some(value.count().map_err(invalid)?),
+                "line_key_bytes" => match value.count().map_err(invalid)? {
+                    0 => return Err(invalid(format!("{} must not be 0", key))),
+                    limit => config = config.line_key_bytes(limit),
+                },
+                "long_tokens" => {
+                    long_tokens = match value.string().map_err(invalid)? {
+                        "truncate" => LongTokenPolicy::Truncate,
+                        "hash" => LongTokenPolicy::Hash,
+                        "drop" => LongTokenPolicy::Drop,
+                        _ => return Err(invalid(format!("unknown policy of {}", key))),
+                    }
+                }
+                "symlinks" => {
+                    settings.symlinks = value
+                        .string()
+                        .and_then(|policy| policy.parse())
+                        .map_err(|e| invalid(format!("{} of {}", e, key)))?
+                }
+                "jobs" => match value.count().map_err(invalid)? {
+                    0 => return Err(invalid("jobs must not be 0".to_string())),
+                    jobs => settings.jobs = jobs,
+                },
+                "check_quality" => settings.check_quality = value.boolean().map_err(invalid)?,
+                "saved" => settings.saved = value.boolean().map_err(invalid)?,
+                "filters.stop_words" => {
+                    for file in value.strings().map_err(invalid)? {
+                        stop_words.extend(read_words(&base.join(file))?);
+                    }
+                }
+                "filters.allowed_words" => {
+                    let words = allowed_words.get_or_insert_with(Vec::new);
+                    for file in value.strings().map_err(invalid)? {
+                        words.extend(read_words(&base.join(file))?);
+                    }
+                }
+                "filters.exclude_patterns" => {
+                    for pattern in value.strings().map_err(invalid)? {
+                        let pattern = Regex::new(pattern)
+                            .map_err(|e| invalid(format!("{} of {}", e, key)))?;
+                        config = config.exclude_pattern(pattern);
+                    }
+                }
+                "filters.min_count" => settings.min_count = value.count().map_err(invalid)?,
+                "filters.ignore" => {
+                    let globs = value.strings().map_err(invalid)?;
+                    settings.ignore = globs.into_iter().map(Glob::new).collect();
+                }
+                "output.format" => {
+                    settings.format = match Format::parse(value.string().map_err(invalid)?) {
+                        Some(format) => format,
+                        None => return Err(invalid(format!("unknown format of {}", key))),
+                    }
+                }
+                "output.encoding" => {
+                    settings.output_encoding = value
+                        .string()
+                        .and_then(|name| name.parse())
+                        .map_err(|e| invalid(format!("{} of {}", e, key)))?
+                }
+                "output.columns" => {
+                    let names = value.strings().map_err(invalid)?;
+                    settings.derived.columns = Some(match names.is_empty() {
+                        true => Columns::none(),
+                        false => names.join(",").parse().map_err(invalid)?,
+                    })
+                }
+                "output.precision" => {
+                    settings.derived.precision = Some(value.count().map_err(invalid)?)
+                }
+                _ => return Err(invalid(format!("unknown key {}", key))),
+            }
+        }
+        config = config.stop_words(&stop_words);
+        match max_token_chars {
+            Some(0) => return Err(invalid("max_token_chars must not be 0".to_string())),
+            Some(limit) => config = config.max_token_chars(limit, long_tokens),
+            None => {}
+        }
+        if let Some(words) = allowed_words {
+            config = config.allowed_words(&words);
+        }
+        settings.config = config;
+        Ok(settings)
+    }
+
+    /// `path` (relative to the counted directory) is ignored
+    pub fn ignores(&self, path: &str) -> bool {
+        self.ignore.iter().any(|glob| glob.matches(path))
+    }
+
+    /// metadata of counts of `inputs` by these settings. settings of outputs and of reading
+    /// files are not of the configuration, and default ones are not mixed into its hash, so
+    /// that the default settings are of the default `CountConfig`
+    pub fn metadata(&self, inputs: &[PathBuf]) -> Metadata {
+        let inputs = inputs.iter().map(|input| walk::display(input).into_owned());
+        let mut metadata = Metadata::new(&self.config, inputs.collect());
+        if self.min_count > 1 {
+            metadata = metadata.setting("min_count", &self.min_count.to_string());
+        }
+        if let Some((rate, seed)) = self.sample {
+            metadata = metadata.setting("sample", &format!("{} {}", rate, seed));
+        }
+        match (self.access_log, &self.log_key) {
+            (Some(field), _) => metadata.mode(&format!("access-log {}", field)),
+            (None, Some(key)) => metadata.mode(&format!("log-key {}", key)),
+            (None, None) => metadata,
+        }
+    }
+}
+
+/// a value with its key for errors
+struct Field<'a> {
+    key: &'a str,
+    value: &'a Value,
+}
+
+impl<'a> Field<'a> {
+    fn error(&self, expected: &str) -> String {
+        format!("{} must be {}", self.key, expected)
+    }
+
+    fn string(&self) -> Result<&'a str, String> {
+        match self.value {
+            Value::String(s) => Ok(s),
+            _ => Err(self.error("a string")),
+        }
+    }
+
+    fn boolean(&self) -> Result<bool, String> {
+        match self.value {
+            Value::Boolean(b) => Ok(*b),
+            _ => Err(self.error("a boolean")),
+        }
+    }
+
+    fn count(&self) -> Result<usize, String> {
+        match self.value {
+            Value::Integer(n) if *n >= 0 => Ok(*n as usize),
+            _ => Err(self.error("a non-negative integer")),
+        }
+    }
+
+    fn strings(&self) -> Result<Vec<&'a str>, String> {
+        match self.value {
+            Value::Array(values) => values
+                .iter()
+                .map(|value| match value {
+                    Value::String(s) => Ok(s.as_str()),
+                    _ => Err(self.error("an array of strings")),
+                })
+                .collect(),
+            _ => Err(self.error("an array of strings")),
+        }
+    }
+}
+
+/// words of a file, a word per line. empty lines and lines starting with `#` are ignored
+fn read_words(path: &PathBuf) -> io::Result<Vec<String>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn load_works() -> io::Result<()> {
+        let dir = std::env::temp_dir().join("wordcount_settings_load");
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("stop.txt"), "# stop words\nthe\n\na\n")?;
+        let path = dir.join("wordcount.toml");
+        fs::write(
+            &path,
+            "ignore_case = true\nsymlinks = \"skip\"\nmax_token_chars = 3\njobs = 4\ncheck_quality = true\n\
+             saved = true\n[filters]\nstop_words = [\"stop.txt\"]\nmin_count = 2\n\
+             ignore = [\"*.log\"]\n[output]\nformat = \"jsonl\"\nencoding = \"UTF-16LE\"\n\
+             columns = []\nprecision = 3\n",
+        )?;
+
+        let settings = Settings::load(&path)?;
+        assert_eq!(settings.min_count, 2);
+        assert_eq!(settings.symlinks, Symlinks::Skip);
+        assert_eq!(settings.jobs, 4);
+        assert!(settings.check_quality && settings.saved);
+        assert_eq!(settings.format, Format::parse("jsonl").unwrap());
+        assert_eq!(settings.output_encoding, OutputEncoding::Utf16Le);
+        assert_eq!(settings.derived.columns, Some(Columns::none()));
+        assert_eq!(settings.derived.precision, Some(3));
+        assert!(settings.ignores("logs/a.log"));
+        assert!(!settings.ignores("a.txt"));
+        let freqs =
+            bicycle_book_wordcount::count_with(Cursor::new("The cat a CAT cats"), &settings.config);
+        assert_eq!(freqs.len(), 2);
+        assert_eq!(freqs["cat"], 2);
+        assert_eq!(freqs["cat…"], 1);
+
+        fs::write(dir.join("dict.txt"), "cat\ndog\n")?;
+        fs::write(&path, "[filters]\nallowed_words = [\"dict.txt\"]\n")?;
+        let settings = Settings::load(&path)?;
+        let freqs =
+            bicycle_book_wordcount::count_with(Cursor::new("cat xyz dog"), &settings.config);
+        assert_eq!(freqs.len(), 2);
+
+        fs::write(&path, "[filters]\nexclude_patterns = [\"^x\", \"\\\\d\"]\n")?;
+        let settings = Settings::load(&path)?;
+        let freqs =
+            bicycle_book_wordcount::count_with(Cursor::new("xyz cat a1 dog"), &settings.config);
+        assert_eq!(freqs.len(), 2);
+        fs::write(&path, "[filters]\nexclude_patterns = [\"(\"]\n")?;
+        let err = Settings::load(&path).unwrap_err();
+        assert!(
+            err.to_string().contains("filters.exclude_patterns"),
+            "{}",
+            err
+        );
+
+        fs::write(&path, "mode = \"words\"\n")?;
+        let err = Settings::load(&path).unwrap_err();
+        assert!(err.to_string().ends_with("unknown mode"), "{}", err);
+        fs::write(&path, "jobs = 0\n")?;
+        let err = Settings::load(&path).unwrap_err();
+        assert!(err.to_string().ends_with("jobs must not be 0"), "{}", err);
+        fs::write(&path, "[filters]\nmin_count = true\n")?;
+        let err = Settings::load(&path).unwrap_err();
+        assert!(
+            err.to_string().contains("filters.min_count must be"),
+            "{}",
+            err
+        );
+
+        fs::remove_dir_all(&dir)
+    }
+}
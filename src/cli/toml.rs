@@ -0,0 +1,275 @@
+//! a subset of TOML enough for settings files
+//!
+//! supported are `key = value` pairs, `[table]` headers, `#` comments, and values of
+//! basic or literal strings, integers, booleans and (multi-line) arrays of them.
+//! keys in a table are flattened to `table.key`.
+
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// a value of a key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+/// `table.key` => value
+pub type Document = BTreeMap<String, Value>;
+
+/// parse a document, an error has the line number (start from 1)
+pub fn parse(text: &str) -> Result<Document, String> {
+    let mut document = Document::new();
+    let mut table = String::new();
+    let mut lines = text.lines().enumerate();
+
+    while let Some((i, line)) = lines.next() {
+        let error = |e: String| format!("line {}: {}", i + 1, e);
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') {
+            if !line.ends_with(']') || line.starts_with("[[") {
+                return Err(error("bad table header".to_string()));
+            }
+            table = line[1..line.len() - 1].trim().to_string();
+            check_key(&table).map_err(error)?;
+            continue;
+        }
+
+        let eq = line
+            .find('=')
+            .ok_or_else(|| error("expected key = value".to_string()))?;
+        let key = line[..eq].trim();
+        check_key(key).map_err(error)?;
+        // an array may continue over lines until its brackets are balanced
+        let mut source = line[eq + 1..].trim().to_string();
+        while source.starts_with('[') && !balanced(&source) {
+            match lines.next() {
+                Some((_, next)) => {
+                    source.push('\n');
+                    source.push_str(strip_comment(next));
+                }
+                None => return Err(error("unterminated array".to_string())),
+            }
+        }
+        let mut chars = source.chars().peekable();
+        let value = parse_value(&mut chars).map_err(error)?;
+        skip_whitespace(&mut chars);
+        if chars.peek().is_some() {
+            return Err(error("unexpected text after the value".to_string()));
+        }
+
+        let full = if table.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", table, key)
+        };
+        if document.insert(full.clone(), value).is_some() {
+            return Err(error(format!("duplicate key {}", full)));
+        }
+    }
+    Ok(document)
+}
+
+fn check_key(key: &str) -> Result<(), String> {
+    let bare = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+    if key.split('.').all(bare) {
+        Ok(())
+    } else {
+        Err(format!("bad key {:?}", key))
+    }
+}
+
+/// `line` without a comment, `#` in strings are kept
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+/// brackets outside strings are balanced
+fn balanced(source: &str) -> bool {
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut escaped = false;
+    for c in source.chars() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '[') => depth += 1,
+            (None, ']') => depth -= 1,
+            _ => {}
+        }
+        escaped = false;
+    }
+    depth <= 0
+}
+
+type Input<'a> = Peekable<Chars<'a>>;
+
+fn skip_whitespace(chars: &mut Input) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+fn parse_value(chars: &mut Input) -> Result<Value, String> {
+    skip_whitespace(chars);
+    match chars.peek() {
+        Some('"') => {
+            chars.next();
+            parse_basic_string(chars).map(Value::String)
+        }
+        Some('\'') => {
+            chars.next();
+            parse_literal_string(chars).map(Value::String)
+        }
+        Some('[') => {
+            chars.next();
+            let mut values = Vec::new();
+            loop {
+                skip_whitespace(chars);
+                if chars.peek() == Some(&']') {
+                    chars.next();
+                    return Ok(Value::Array(values));
+                }
+                values.push(parse_value(chars)?);
+                skip_whitespace(chars);
+                match chars.next() {
+                    Some(',') => {}
+                    Some(']') => return Ok(Value::Array(values)),
+                    _ => return Err("expected ',' or ']' in an array".to_string()),
+                }
+            }
+        }
+        Some(_) => {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == ',' || c == ']' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            match word.as_str() {
+                "true" => Ok(Value::Boolean(true)),
+                "false" => Ok(Value::Boolean(false)),
+                _ => word
+                    .replace('_', "")
+                    .parse()
+                    .map(Value::Integer)
+                    .map_err(|_| format!("bad value {:?}", word)),
+            }
+        }
+        None => Err("expected a value".to_string()),
+    }
+}
+
+fn parse_literal_string(chars: &mut Input) -> Result<String, String> {
+    let mut s = String::new();
+    loop {
+        match chars.next() {
+            Some('\'') => return Ok(s),
+            Some('\n') | None => return Err("unterminated string".to_string()),
+            Some(c) => s.push(c),
+        }
+    }
+}
+
+fn parse_basic_string(chars: &mut Input) -> Result<String, String> {
+    let mut s = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(s),
+            Some('\\') => match chars.next() {
+                Some('"') => s.push('"'),
+                Some('\\') => s.push('\\'),
+                Some('n') => s.push('\n'),
+                Some('t') => s.push('\t'),
+                Some('r') => s.push('\r'),
+                Some(c @ 'u') | Some(c @ 'U') => {
+                    let len = if c == 'u' { 4 } else { 8 };
+                    let hex: String = chars.take(len).collect();
+                    let c = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(std::char::from_u32)
+                        .ok_or_else(|| format!("bad escape \\{}{}", c, hex))?;
+                    s.push(c);
+                }
+                _ => return Err("bad escape".to_string()),
+            },
+            Some('\n') | None => return Err("unterminated string".to_string()),
+            Some(c) => s.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    #[test]
+    fn parse_works() {
+        let document = parse(
+            "# settings\n\
+             mode = \"word\" # trailing\n\
+             ignore_case = true\n\
+             \n\
+             [filters]\n\
+             min_count = 1_000\n\
+             stop_words = [\n  'a#b.txt', # first\n  \"c\\u00e9\",\n]\n",
+        )
+        .unwrap();
+        assert_eq!(document["mode"], string("word"));
+        assert_eq!(document["ignore_case"], Value::Boolean(true));
+        assert_eq!(document["filters.min_count"], Value::Integer(1000));
+        assert_eq!(
+            document["filters.stop_words"],
+            Value::Array(vec![string("a#b.txt"), string("cé")])
+        );
+        assert_eq!(document.len(), 4);
+    }
+
+    #[test]
+    fn parse_errors_have_line_numbers() {
+        let error = |text: &str| parse(text).unwrap_err();
+        assert_eq!(error("a = 1\nb = x"), "line 2: bad value \"x\"");
+        assert!(error("a = 1\na = 2").starts_with("line 2: duplicate key"));
+        assert!(error("a b = 1").starts_with("line 1: bad key"));
+        assert!(error("a = [1,\n2").starts_with("line 1: unterminated array"));
+        assert!(error("a = \"x").starts_with("line 1: unterminated string"));
+        assert!(error("a = 'x").starts_with("line 1: unterminated string"));
+        assert!(error("a = 1 2").starts_with("line 1: unexpected text"));
+    }
+}
@@ -0,0 +1,213 @@
+//! `tui FILE`: explore counts of a file interactively, a page of tokens at a time
+//!
+//! commands read from stdin, one per line:
+//!
+//! * `/TEXT`: show only tokens containing TEXT, `/` alone shows all
+//! * `sort count|token`: order of tokens
+//! * `n`, `p`: next and previous page
+//! * `show TOKEN`: sample lines of a token
+//! * `q`: quit
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use bicycle_book_wordcount::sample::count_with_samples;
+use bicycle_book_wordcount::{CountConfig, CountError, Frequencies};
+
+/// tokens on a page
+const PAGE: usize = 20;
+
+/// sample lines kept per token
+const SAMPLES: usize = 5;
+
+/// order of tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    /// most frequent first
+    Count,
+    /// alphabetical
+    Token,
+}
+
+/// state of the explorer
+#[derive(Debug)]
+pub struct Explorer {
+    freqs: Frequencies,
+    samples: HashMap<String, Vec<String>>,
+    filter: String,
+    sort: Sort,
+    page: usize,
+    /// tokens matching the filter, in order
+    rows: Vec<(String, usize)>,
+}
+
+impl Explorer {
+    pub fn new(freqs: Frequencies, samples: HashMap<String, Vec<String>>) -> Self {
+        let mut explorer = Explorer {
+            freqs,
+            samples,
+            filter: String::new(),
+            sort: Sort::Count,
+            page: 0,
+            rows: Vec::new(),
+        };
+        explorer.refresh();
+        explorer
+    }
+
+    fn refresh(&mut self) {
+        let matched = self.freqs.containing(&self.filter);
+        let rows = match self.sort {
+            Sort::Count => matched.sorted(),
+            Sort::Token => matched.sorted_by_key(),
+        };
+        self.rows = rows
+            .into_iter()
+            .map(|(token, n)| (token.clone(), n))
+            .collect();
+        self.page = 0;
+    }
+
+    /// count of pages, at least 1
+    fn pages(&self) -> usize {
+        ((self.rows.len() + PAGE - 1) / PAGE).max(1)
+    }
+
+    /// the current page of tokens
+    pub fn view(&self) -> String {
+        let mut out = String::new();
+        let start = self.page * PAGE;
+        for (token, n) in self.rows.iter().skip(start).take(PAGE) {
+            writeln!(out, "{:>8}  {}", n, token).unwrap();
+        }
+        write!(
+            out,
+            "page {}/{}, {} of {} tokens",
+            self.page + 1,
+            self.pages(),
+            self.rows.len(),
+            self.freqs.len()
+        )
+        .unwrap();
+        if !self.filter.is_empty() {
+            write!(out, " containing {:?}", self.filter).unwrap();
+        }
+        out.push('\n');
+        out
+    }
+
+    /// run a command, returns what to print, `None` to quit
+    pub fn command(&mut self, line: &str) -> Option<String> {
+        let line = line.trim();
+        let (name, arg) = match line.find(' ') {
+            Some(i) => (&line[..i], line[i + 1..].trim()),
+            None => (line, ""),
+        };
+        let out = match name {
+            "q" | "quit" => return None,
+            _ if line.starts_with('/') => {
+                self.filter = line[1..].to_string();
+                self.refresh();
+                self.view()
+            }
+            "sort" => {
+                self.sort = match arg {
+                    "count" => Sort::Count,
+                    "token" => Sort::Token,
+                    _ => return Some("usage: sort count|token\n".to_string()),
+                };
+                self.refresh();
+                self.view()
+            }
+            "n" | "next" => {
+                self.page = (self.page + 1).min(self.pages() - 1);
+                self.view()
+            }
+            "p" | "prev" => {
+                self.page = self.page.saturating_sub(1);
+                self.view()
+            }
+            "show" => match self.samples.get(arg) {
+                Some(lines) => {
+                    let mut out =
+                        format!("{}: {}\n", arg, self.freqs.get(arg).copied().unwrap_or(0));
+                    for line in lines {
+                        writeln!(out, "  {}", line).unwrap();
+                    }
+                    out
+                }
+                None => format!("no token {:?}\n", arg),
+            },
+            "" => self.view(),
+            _ => "commands: /TEXT, sort count|token, n, p, show TOKEN, q\n".to_string(),
+        };
+        Some(out)
+    }
+}
+
+/// explore counts of `path`, reading commands from stdin
+pub fn run(path: &Path, config: &CountConfig) -> io::Result<()> {
+    let reader = BufReader::new(File::open(path)?);
+    let (freqs, samples) = count_with_samples(reader, config, SAMPLES).map_err(|e| match e {
+        CountError::Io(e) => e,
+        e => io::Error::new(io::ErrorKind::Other, e.to_string()),
+    })?;
+    let mut explorer = Explorer::new(freqs, samples);
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    write!(out, "{}> ", explorer.view())?;
+    out.flush()?;
+    for line in io::stdin().lock().lines() {
+        match explorer.command(&line?) {
+            Some(text) => write!(out, "{}> ", text)?,
+            None => break,
+        }
+        out.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    fn explorer(text: &str) -> Explorer {
+        let input = Cursor::new(text.to_string());
+        let (freqs, samples) = count_with_samples(input, &CountConfig::default(), 2).unwrap();
+        Explorer::new(freqs, samples)
+    }
+
+    #[test]
+    fn filter_and_sort_work() {
+        let mut explorer = explorer("bb aa bb\nab cc");
+        assert!(explorer.view().starts_with("       2  bb\n       1  aa\n"));
+        let view = explorer.command("/a").unwrap();
+        assert_eq!(
+            view,
+            "       1  aa\n       1  ab\npage 1/1, 2 of 4 tokens containing \"a\"\n"
+        );
+        let view = explorer.command("sort token").unwrap();
+        assert!(view.starts_with("       1  aa\n"));
+        explorer.command("/");
+        assert!(explorer.view().contains("4 of 4 tokens\n"));
+        assert!(explorer.command("q").is_none());
+    }
+
+    #[test]
+    fn pages_and_samples_work() {
+        let text: Vec<_> = (0..30).map(|i| format!("w{:02}", i)).collect();
+        let mut explorer = explorer(&text.join(" "));
+        assert!(explorer.command("n").unwrap().contains("page 2/2"));
+        assert!(explorer.command("n").unwrap().contains("page 2/2"));
+        assert!(explorer.command("p").unwrap().contains("page 1/2"));
+        assert!(explorer
+            .command("show w03")
+            .unwrap()
+            .starts_with("w03: 1\n  w00 w01"));
+        assert_eq!(explorer.command("show zz").unwrap(), "no token \"zz\"\n");
+    }
+}
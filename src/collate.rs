@@ -0,0 +1,187 @@
+//! locale-sensitive ordering of tokens, see [`Collator`](struct.Collator.html)
+//!
+//! a small subset of the Unicode Collation Algorithm for Latin scripts, with tailorings of
+//! some languages. other scripts are ordered by code point
+
+use std::cmp::Ordering;
+
+use crate::normalize::{base, is_combining};
+
+/// primary weight of a letter, spaced so tailored letters fit between letters
+const fn weight(c: char) -> u32 {
+    c as u32 * 4
+}
+
+const A: u32 = weight('a');
+const D: u32 = weight('d');
+const E: u32 = weight('e');
+const L: u32 = weight('l');
+const N: u32 = weight('n');
+const O: u32 = weight('o');
+const S: u32 = weight('s');
+const U: u32 = weight('u');
+const Z: u32 = weight('z');
+
+/// lowercase letters => primary weights
+type Tailoring = &'static [(char, &'static [u32])];
+
+/// letters of the root collation without canonical decomposition
+const ROOT: Tailoring = &[
+    ('æ', &[A, E]),
+    ('đ', &[D + 1]),
+    ('ł', &[L + 1]),
+    ('œ', &[O, E]),
+    ('ø', &[O + 1]),
+    ('ß', &[S, S]),
+];
+
+/// Swedish and Finnish: å, ä, ö after z
+const SWEDISH: Tailoring = &[
+    ('å', &[Z + 1]),
+    ('ä', &[Z + 2]),
+    ('æ', &[Z + 2]),
+    ('ö', &[Z + 3]),
+    ('ø', &[Z + 3]),
+];
+
+/// Danish and Norwegian: æ, ø, å after z
+const DANISH: Tailoring = &[
+    ('æ', &[Z + 1]),
+    ('ä', &[Z + 1]),
+    ('ø', &[Z + 2]),
+    ('ö', &[Z + 2]),
+    ('å', &[Z + 3]),
+];
+
+/// German phone book order: umlauts as the letter followed by e
+const GERMAN_PHONEBOOK: Tailoring = &[('ä', &[A, E]), ('ö', &[O, E]), ('ü', &[U, E])];
+
+/// Spanish: ñ after n
+const SPANISH: Tailoring = &[('ñ', &[N + 1])];
+
+/// compares strings by the rules of a locale, the root collation by default
+///
+/// letters are compared ignoring accents and case first, then by accents, then by case
+/// (lowercase first), and finally by code point.
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::collate::Collator;
+/// let mut words = vec!["zebra", "Äpfel", "Apfel", "ørn"];
+/// words.sort_by(|a, b| Collator::new("de").compare(a, b));
+/// assert_eq!(words, vec!["Apfel", "Äpfel", "ørn", "zebra"]);
+/// words.sort_by(|a, b| Collator::new("sv-SE").compare(a, b));
+/// assert_eq!(words, vec!["Apfel", "zebra", "Äpfel", "ørn"]);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Collator {
+    /// letters sorted differently from the root collation
+    tailoring: Tailoring,
+}
+
+impl Collator {
+    /// collator of a BCP 47 language tag like "sv" or "de-DE-u-co-phonebk".
+    /// tailored languages are sv, fi, da, nb, nn, no, es and German phone book order (de with
+    /// "-u-co-phonebk"); others use the root collation
+    pub fn new(tag: &str) -> Self {
+        let tag = tag.to_ascii_lowercase().replace('_', "-");
+        let language = tag.split('-').next().unwrap_or("");
+        let tailoring = match language {
+            "sv" | "fi" => SWEDISH,
+            "da" | "nb" | "nn" | "no" => DANISH,
+            "de" if tag.contains("-co-phonebk") => GERMAN_PHONEBOOK,
+            "es" => SPANISH,
+            _ => &[],
+        };
+        Collator { tailoring }
+    }
+
+    /// sort key of `s`, which orders as [`compare`](#method.compare)
+    pub fn key(&self, s: &str) -> CollationKey {
+        let mut key = CollationKey {
+            primary: Vec::new(),
+            secondary: Vec::new(),
+            tertiary: Vec::new(),
+            text: s.to_string(),
+        };
+        for c in s.chars() {
+            if is_combining(c) {
+                if let Some(accent) = key.secondary.last_mut() {
+                    *accent = c as u32;
+                }
+                continue;
+            }
+            let lower = c.to_lowercase().next().unwrap_or(c);
+            let tailored = self.tailoring.iter().chain(ROOT).find(|(t, _)| *t == lower);
+            let (weights, accent) = match tailored {
+                Some((_, weights)) => (weights.to_vec(), lower as u32),
+                None => match base(lower) {
+                    Some(b) => (vec![weight(b)], lower as u32),
+                    None => (vec![weight(lower)], 0),
+                },
+            };
+            for w in weights {
+                key.primary.push(w);
+                key.secondary.push(accent);
+            }
+            key.tertiary.push(c.is_uppercase() as u8);
+        }
+        key
+    }
+
+    /// order of `a` and `b` in the locale
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.key(a).cmp(&self.key(b))
+    }
+}
+
+/// sort key by a [`Collator`](struct.Collator.html), for sorting many strings
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CollationKey {
+    primary: Vec<u32>,
+    secondary: Vec<u32>,
+    tertiary: Vec<u8>,
+    text: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sorted(tag: &str, words: &[&str]) -> Vec<String> {
+        let collator = Collator::new(tag);
+        let mut words: Vec<_> = words.iter().map(|w| w.to_string()).collect();
+        words.sort_by_cached_key(|w| collator.key(w));
+        words
+    }
+
+    #[test]
+    fn levels_are_ordered() {
+        assert_eq!(
+            sorted("en", &["cote", "Côte", "côte", "Cote", "coter"]),
+            vec!["cote", "Cote", "côte", "Côte", "coter"]
+        );
+        // decomposed accents compare as accents
+        let collator = Collator::default();
+        assert_eq!(collator.compare("cafe\u{301}", "cafez"), Ordering::Less);
+        assert_eq!(collator.compare("Straße", "strasse"), Ordering::Greater);
+        assert_eq!(collator.compare("Straße", "strasser"), Ordering::Less);
+    }
+
+    #[test]
+    fn tailorings_work() {
+        let words = ["ål", "öl", "ära", "zoo", "oxe"];
+        assert_eq!(sorted("sv", &words), vec!["oxe", "zoo", "ål", "ära", "öl"]);
+        assert_eq!(
+            sorted("da_DK", &words),
+            vec!["oxe", "zoo", "ära", "öl", "ål"]
+        );
+        assert_eq!(sorted("de", &words), vec!["ål", "ära", "öl", "oxe", "zoo"]);
+        assert_eq!(
+            sorted("de-u-co-phonebk", &["Müller", "Mueller", "Mulder"]),
+            vec!["Mueller", "Müller", "Mulder"]
+        );
+        assert_eq!(sorted("es", &["ñu", "nz", "oa"]), vec!["nz", "ñu", "oa"]);
+    }
+}
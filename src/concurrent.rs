@@ -0,0 +1,155 @@
+//! counter shared by threads, see [`ConcurrentCounter`](struct.ConcurrentCounter.html)
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::Frequencies;
+
+/// default count of shards
+const SHARDS: usize = 16;
+
+/// counts of tokens which many threads can add to at once (e.g. in `Arc`)
+///
+/// tokens are spread over shards, each locked separately, so threads adding different tokens
+/// rarely wait for each other
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use bicycle_book_wordcount::concurrent::ConcurrentCounter;
+/// let counter = Arc::new(ConcurrentCounter::new());
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let counter = Arc::clone(&counter);
+///         thread::spawn(move || counter.add("aa"))
+///     })
+///     .collect();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+/// assert_eq!(counter.get("aa"), 4);
+/// assert_eq!(counter.snapshot()["aa"], 4);
+/// ```
+#[derive(Debug)]
+pub struct ConcurrentCounter {
+    shards: Vec<Mutex<HashMap<String, usize>>>,
+    hasher: RandomState,
+}
+
+impl Default for ConcurrentCounter {
+    fn default() -> Self {
+        ConcurrentCounter::with_shards(SHARDS)
+    }
+}
+
+impl ConcurrentCounter {
+    /// empty counter
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// empty counter with `n` shards, more shards for more threads
+    ///
+    /// # Panics
+    ///
+    /// `n` is 0
+    pub fn with_shards(n: usize) -> Self {
+        assert!(n > 0, "shards must not be 0");
+        ConcurrentCounter {
+            shards: (0..n).map(|_| Mutex::default()).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// add an occurrence of `token`
+    pub fn add(&self, token: &str) {
+        self.add_n(token, 1);
+    }
+
+    /// add `n` occurrences of `token` (saturating at `usize::MAX`)
+    pub fn add_n(&self, token: &str, n: usize) {
+        let mut shard = self.shard(token);
+        match shard.get_mut(token) {
+            Some(count) => *count = count.saturating_add(n),
+            None => {
+                shard.insert(token.to_string(), n);
+            }
+        }
+    }
+
+    /// add all counts of `freqs`
+    pub fn merge(&self, freqs: &Frequencies) {
+        for (token, &n) in freqs.iter() {
+            self.add_n(token, n);
+        }
+    }
+
+    /// count of `token` so far
+    pub fn get(&self, token: &str) -> usize {
+        self.shard(token).get(token).cloned().unwrap_or(0)
+    }
+
+    /// copy of all counts so far. shards are copied one by one, so adds during the snapshot
+    /// may be partially included
+    pub fn snapshot(&self) -> Frequencies {
+        let mut freqs = Frequencies::new();
+        for shard in &self.shards {
+            let shard = lock(shard);
+            freqs.extend(shard.iter().map(|(token, &n)| (token.clone(), n)));
+        }
+        freqs
+    }
+
+    fn shard(&self, token: &str) -> MutexGuard<'_, HashMap<String, usize>> {
+        let mut hasher = self.hasher.build_hasher();
+        token.hash(&mut hasher);
+        let hash = hasher.finish();
+        lock(&self.shards[hash as usize % self.shards.len()])
+    }
+}
+
+/// lock even if another thread panicked while holding it, counts are still consistent
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn concurrent_adds_are_counted() {
+        let counter = ConcurrentCounter::with_shards(3);
+        thread::scope(|scope| {
+            for t in 0..8 {
+                let counter = &counter;
+                scope.spawn(move || {
+                    for i in 0..1000 {
+                        counter.add(&(i % 10).to_string());
+                        counter.add(if t % 2 == 0 { "even" } else { "odd" });
+                    }
+                });
+            }
+        });
+        let freqs = counter.snapshot();
+        assert_eq!(freqs.len(), 12);
+        assert_eq!(freqs["7"], 800);
+        assert_eq!(freqs["even"], 4000);
+        assert_eq!(counter.get("odd"), 4000);
+        assert_eq!(counter.get("none"), 0);
+    }
+
+    #[test]
+    fn merge_works() {
+        let counter = ConcurrentCounter::new();
+        counter.add("a");
+        counter.merge(&crate::count_str("a b a", crate::CountOption::Word));
+        assert_eq!(counter.get("a"), 3);
+        assert_eq!(counter.get("b"), 1);
+    }
+}
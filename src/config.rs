@@ -0,0 +1,1217 @@
+//! configuration for [`count_with`](../fn.count_with.html)
+
+use regex::{Regex, RegexBuilder};
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::decode::{DecodePolicy, LongLinePolicy, Reading};
+use crate::error::CountError;
+use crate::frequencies::fnv1a;
+use crate::normalize::{self, Lemmas};
+use crate::segment::Dictionary;
+use crate::CountOption;
+
+/// version of the fields hashed by `CountConfig::fingerprint`, to be bumped when they change
+const FINGERPRINT_VERSION: u32 = 1;
+
+/// configuration for [`count_with`](../fn.count_with.html), built by chained methods
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::{CountConfig, CountOption};
+/// let config = CountConfig::new(CountOption::Word).ignore_case(true);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CountConfig {
+    pub(crate) option: CountOption,
+    /// regex of words by a [`WordCharPolicy`](struct.WordCharPolicy.html)
+    pub(crate) word_chars: Option<Regex>,
+    pub(crate) ignore_case: bool,
+    pub(crate) collapse_whitespace: bool,
+    pub(crate) original_case: bool,
+    pub(crate) strip_diacritics: bool,
+    pub(crate) roman_numerals: bool,
+    pub(crate) ordinals: bool,
+    pub(crate) overflow: OverflowPolicy,
+    pub(crate) quoted_only: bool,
+    pub(crate) skip_code_blocks: bool,
+    pub(crate) pattern: Option<(Regex, usize)>,
+    pub(crate) phrases: Vec<String>,
+    pub(crate) max_keys: Option<usize>,
+    pub(crate) dictionary: Option<Arc<Dictionary>>,
+    pub(crate) stop_words: Vec<String>,
+    pub(crate) allowed_words: Option<Vec<String>>,
+    pub(crate) exclude_patterns: Vec<Regex>,
+    pub(crate) lemmas: Option<Arc<Lemmas>>,
+    pub(crate) transform: Option<Transform>,
+    pub(crate) filter: Option<Filter>,
+    pub(crate) keep_bom: bool,
+    pub(crate) invalid_input: DecodePolicy,
+    pub(crate) max_line: Option<(usize, LongLinePolicy)>,
+    pub(crate) max_token: Option<(usize, LongTokenPolicy)>,
+    pub(crate) line_key_bytes: Option<usize>,
+    pub(crate) time_limit: Option<Duration>,
+    pub(crate) limits: Limits,
+}
+
+/// function mapping (or dropping) each key, see [`CountConfig::transform`](struct.CountConfig.html#method.transform)
+#[derive(Clone)]
+pub(crate) struct Transform(Arc<TransformFn>);
+
+type TransformFn = dyn Fn(&str) -> Option<Cow<'_, str>> + Send + Sync;
+
+impl Transform {
+    pub(crate) fn apply<'a>(&self, key: &'a str) -> Option<Cow<'a, str>> {
+        (self.0)(key)
+    }
+}
+
+impl fmt::Debug for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Transform")
+    }
+}
+
+/// predicate of keys to count, see [`CountConfig::filter`](struct.CountConfig.html#method.filter)
+#[derive(Clone)]
+pub(crate) struct Filter(Arc<dyn Fn(&str) -> bool + Send + Sync>);
+
+impl Filter {
+    pub(crate) fn accepts(&self, key: &str) -> bool {
+        (self.0)(key)
+    }
+}
+
+impl fmt::Debug for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Filter")
+    }
+}
+
+/// what to do when a count exceeds `usize::MAX`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// stay at `usize::MAX`
+    Saturate,
+    /// fail with [`CountError::Overflow`](../error/enum.CountError.html#variant.Overflow)
+    Error,
+    /// wrap around to 0
+    Wrap,
+}
+
+/// option default value
+impl Default for OverflowPolicy {
+    fn default() -> Self {
+        OverflowPolicy::Saturate
+    }
+}
+
+impl OverflowPolicy {
+    /// `count + n` by the policy, `None` if it overflows with `Error`
+    pub fn add(self, count: usize, n: usize) -> Option<usize> {
+        match self {
+            OverflowPolicy::Saturate => Some(count.saturating_add(n)),
+            OverflowPolicy::Error => count.checked_add(n),
+            OverflowPolicy::Wrap => Some(count.wrapping_add(n)),
+        }
+    }
+}
+
+/// what to do with a key longer than the limit of
+/// [`CountConfig::max_token_chars`](struct.CountConfig.html#method.max_token_chars), such as a
+/// blob of base64
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum LongTokenPolicy {
+    /// keep the first chars up to the limit, followed by `…`. long keys of the same start are
+    /// counted together
+    #[default]
+    Truncate,
+    /// replace by `#` and 16 hex digits of a hash of the key, so that the same long keys are
+    /// still counted together, and different ones (almost always) apart
+    Hash,
+    /// do not count it
+    Drop,
+}
+
+impl LongTokenPolicy {
+    /// `key` by the policy if it is longer than `limit` chars, `None` to drop it
+    pub fn apply<'a>(self, key: Cow<'a, str>, limit: usize) -> Option<Cow<'a, str>> {
+        // no char is shorter than a byte
+        let end = match key.char_indices().nth(limit) {
+            Some((end, _)) if key.len() > limit => end,
+            _ => return Some(key),
+        };
+        match self {
+            LongTokenPolicy::Truncate => Some(Cow::Owned(format!("{}…", &key[..end]))),
+            LongTokenPolicy::Hash => Some(Cow::Owned(format!("#{:016x}", fnv1a(key.as_bytes())))),
+            LongTokenPolicy::Drop => None,
+        }
+    }
+}
+
+/// `key` of a line, or if it is longer than `limit` bytes, its first chars up to `limit` bytes
+/// followed by `…#` and 32 hex digits of a hash of the whole key, see
+/// [`CountConfig::line_key_bytes`](struct.CountConfig.html#method.line_key_bytes)
+pub(crate) fn line_key(key: Cow<str>, limit: usize) -> Cow<str> {
+    if key.len() <= limit {
+        return key;
+    }
+    let mut end = limit;
+    while !key.is_char_boundary(end) {
+        end -= 1;
+    }
+    Cow::Owned(format!(
+        "{}…#{:032x}",
+        &key[..end],
+        fnv1a_128(key.as_bytes())
+    ))
+}
+
+/// 128-bit FNV-1a hash of `bytes`, the same in any run and on any platform, so that keys of
+/// lines saved by different runs are merged
+fn fnv1a_128(bytes: &[u8]) -> u128 {
+    const OFFSET: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+    const PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+    bytes.iter().fold(OFFSET, |hash, &b| {
+        (hash ^ u128::from(b)).wrapping_mul(PRIME)
+    })
+}
+
+/// which chars are word chars of [`CountOption::Word`](../enum.CountOption.html#variant.Word),
+/// without writing the regex of words. letters are always word chars, and the default is the
+/// same as `\w` of the `regex` crate (letters, marks, decimal digits and connector
+/// punctuation), see [`CountConfig::word_chars`](struct.CountConfig.html#method.word_chars)
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::WordCharPolicy;
+/// let policy = WordCharPolicy::new().underscore(false).extra("-");
+/// assert!(policy.regex().is_match("well-known"));
+/// assert_eq!(policy.regex().find("snake_case").unwrap().as_str(), "snake");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordCharPolicy {
+    digits: bool,
+    marks: bool,
+    underscore: bool,
+    connectors: bool,
+    extra: Vec<char>,
+}
+
+impl Default for WordCharPolicy {
+    fn default() -> Self {
+        WordCharPolicy {
+            digits: true,
+            marks: true,
+            underscore: true,
+            connectors: true,
+            extra: Vec::new(),
+        }
+    }
+}
+
+impl WordCharPolicy {
+    /// the same word chars as `\w`
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// decimal digits (`Nd`) such as `0` and `٣` are word chars
+    pub fn digits(mut self, yes: bool) -> Self {
+        self.digits = yes;
+        self
+    }
+
+    /// combining marks (`M`) such as U+0301 and Devanagari vowel signs are word chars.
+    /// without them, words of decomposed text and of many scripts of India are split
+    pub fn marks(mut self, yes: bool) -> Self {
+        self.marks = yes;
+        self
+    }
+
+    /// `_` is a word char
+    pub fn underscore(mut self, yes: bool) -> Self {
+        self.underscore = yes;
+        self
+    }
+
+    /// connector punctuation (`Pc`) other than `_`, such as `‿` and `＿`, are word chars
+    pub fn connectors(mut self, yes: bool) -> Self {
+        self.connectors = yes;
+        self
+    }
+
+    /// these chars are word chars too, such as `-` and `'` for "well-known" and "don't"
+    pub fn extra(mut self, chars: &str) -> Self {
+        self.extra.extend(chars.chars());
+        self
+    }
+
+    /// regex of a word, a run of word chars
+    pub fn regex(&self) -> Regex {
+        let mut class = String::from(r"[\p{Alphabetic}\p{Join_Control}");
+        if self.digits {
+            class.push_str(r"\p{Nd}");
+        }
+        if self.marks {
+            class.push_str(r"\p{M}");
+        }
+        match (self.underscore, self.connectors) {
+            (true, true) => class.push_str(r"\p{Pc}"),
+            (true, false) => class.push('_'),
+            (false, true) => class.push_str(r"[\p{Pc}&&[^_]]"),
+            (false, false) => {}
+        }
+        for c in &self.extra {
+            class.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+        }
+        class.push_str("]+");
+        Regex::new(&class).unwrap()
+    }
+}
+
+/// limits of patterns from untrusted users, e.g. of requests to a server: sizes of the
+/// compiled pattern, and the time of matching it in a line, see
+/// [`CountConfig::user_pattern`](struct.CountConfig.html#method.user_pattern)
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use bicycle_book_wordcount::config::PatternLimits;
+/// let limits = PatternLimits::new().size(1 << 16).time(Duration::from_millis(10));
+/// assert!(limits.compile(r"[A-Z]+-\d+").is_ok());
+/// let error = limits.compile(r"\w{1000}").unwrap_err();
+/// assert!(error.to_string().contains("size limit"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatternLimits {
+    size: usize,
+    dfa_size: usize,
+    nesting: u32,
+    time: Option<Duration>,
+}
+
+/// 1 MiB of the compiled pattern and of its DFA, nesting of 64, and 100 ms per line
+impl Default for PatternLimits {
+    fn default() -> Self {
+        PatternLimits {
+            size: 1 << 20,
+            dfa_size: 1 << 20,
+            nesting: 64,
+            time: Some(Duration::from_millis(100)),
+        }
+    }
+}
+
+impl PatternLimits {
+    /// the default limits
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// bytes of the compiled pattern, larger ones fail to compile
+    pub fn size(mut self, bytes: usize) -> Self {
+        self.size = bytes;
+        self
+    }
+
+    /// bytes of the cache of the lazy DFA of each thread matching the pattern. matching goes
+    /// on slower if it is full
+    pub fn dfa_size(mut self, bytes: usize) -> Self {
+        self.dfa_size = bytes;
+        self
+    }
+
+    /// depth of nested groups and repetitions, deeper ones fail to compile
+    pub fn nesting(mut self, depth: u32) -> Self {
+        self.nesting = depth;
+        self
+    }
+
+    /// time of matching a line, `None` for no limit, see
+    /// [`CountConfig::time_limit`](struct.CountConfig.html#method.time_limit)
+    pub fn time(mut self, limit: impl Into<Option<Duration>>) -> Self {
+        self.time = limit.into();
+        self
+    }
+
+    /// compile `pattern` within the limits of sizes and nesting
+    pub fn compile(&self, pattern: &str) -> Result<Regex, regex::Error> {
+        RegexBuilder::new(pattern)
+            .size_limit(self.size)
+            .dfa_size_limit(self.dfa_size)
+            .nest_limit(self.nesting)
+            .build()
+    }
+}
+
+/// limits of resources of counting an input, for services counting inputs of untrusted users:
+/// distinct keys, estimated memory of counts, bytes of a line and bytes of the input, see
+/// [`CountConfig::limits`](struct.CountConfig.html#method.limits). no limit by default
+///
+/// counting fails with [`CountError::Limit`](../error/enum.CountError.html#variant.Limit) as
+/// soon as a limit is exceeded, reading a byte more than the limits of bytes at most. the token
+/// exceeding a limit of keys or memory and the rest of its line are not counted
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::config::Limits;
+/// use bicycle_book_wordcount::error::{CountError, Limit};
+/// use bicycle_book_wordcount::{try_count_with, CountConfig};
+/// let config = CountConfig::default().limits(Limits::new().keys(3).line(80).bytes(1 << 20));
+/// assert!(try_count_with(Cursor::new("a b c\na b"), &config).is_ok());
+/// match try_count_with(Cursor::new("a b\nc d"), &config) {
+///     Err(CountError::Limit { limit, line }) => assert_eq!((limit, line), (Limit::Keys(3), 2)),
+///     result => panic!("{:?}", result),
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub(crate) keys: Option<usize>,
+    pub(crate) memory: Option<usize>,
+    pub(crate) line: Option<usize>,
+    pub(crate) bytes: Option<u64>,
+}
+
+impl Limits {
+    /// no limits
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// distinct keys counted. unlike [`CountConfig::max_keys`](struct.CountConfig.html#method.max_keys),
+    /// which evicts keys, counting fails
+    pub fn keys(mut self, n: usize) -> Self {
+        self.keys = Some(n);
+        self
+    }
+
+    /// bytes of counts estimated as [`Frequencies::estimated_bytes`](../frequencies/struct.Frequencies.html#method.estimated_bytes)
+    pub fn memory(mut self, bytes: usize) -> Self {
+        self.memory = Some(bytes);
+        self
+    }
+
+    /// bytes of a line without its terminator. longer lines are not read into memory
+    pub fn line(mut self, bytes: usize) -> Self {
+        self.line = Some(bytes);
+        self
+    }
+
+    /// bytes of an input, after decoding to UTF-8
+    pub fn bytes(mut self, bytes: u64) -> Self {
+        self.bytes = Some(bytes);
+        self
+    }
+}
+
+impl CountConfig {
+    /// configuration which counts by `option`, otherwise same as [`count`](../fn.count.html)
+    pub fn new(option: CountOption) -> Self {
+        CountConfig {
+            option,
+            ..Default::default()
+        }
+    }
+
+    /// count case-insensitively, keys are lowercase
+    pub fn ignore_case(mut self, yes: bool) -> Self {
+        self.ignore_case = yes;
+        self
+    }
+
+    /// remove leading and trailing whitespace of tokens and make other runs of whitespace one
+    /// space, so that lines (of [`CountOption::Line`](../enum.CountOption.html#variant.Line))
+    /// differing only in spacing are counted as one, see
+    /// [`collapse_whitespace`](../normalize/fn.collapse_whitespace.html)
+    pub fn collapse_whitespace(mut self, yes: bool) -> Self {
+        self.collapse_whitespace = yes;
+        self
+    }
+
+    /// with `ignore_case`, report each key by its most frequent original spelling
+    /// (e.g. "NASA" rather than "nasa") instead of lowercase
+    pub fn original_case(mut self, yes: bool) -> Self {
+        self.original_case = yes;
+        self
+    }
+
+    /// remove diacritics before counting (café => cafe),
+    /// see [`strip_diacritics`](../normalize/fn.strip_diacritics.html)
+    pub fn strip_diacritics(mut self, yes: bool) -> Self {
+        self.strip_diacritics = yes;
+        self
+    }
+
+    /// count roman numerals as arabic numerals (XIV => 14), e.g. of chapters and articles,
+    /// see [`roman_numerals`](../normalize/fn.roman_numerals.html)
+    pub fn roman_numerals(mut self, yes: bool) -> Self {
+        self.roman_numerals = yes;
+        self
+    }
+
+    /// count ordinals as digits and a suffix (third, Third and 3RD => 3rd),
+    /// see [`ordinals`](../normalize/fn.ordinals.html)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count_with, CountConfig};
+    /// let config = CountConfig::default().roman_numerals(true).ordinals(true);
+    /// let freqs = count_with(Cursor::new("Article XIV, the 3rd and Third of 14"), &config);
+    /// assert_eq!(freqs["14"], 2);
+    /// assert_eq!(freqs["3rd"], 2);
+    /// ```
+    pub fn ordinals(mut self, yes: bool) -> Self {
+        self.ordinals = yes;
+        self
+    }
+
+    /// what to do when a count overflows, default is
+    /// [`OverflowPolicy::Saturate`](enum.OverflowPolicy.html#variant.Saturate)
+    pub fn overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+
+    /// count only text inside single or double quotes (in a line),
+    /// e.g. for user-visible strings in source files. a backslash escapes the next char
+    pub fn quoted_only(mut self, yes: bool) -> Self {
+        self.quoted_only = yes;
+        self
+    }
+
+    /// skip fenced and indented code blocks of Markdown (or reStructuredText literal blocks),
+    /// so only prose is counted
+    pub fn skip_code_blocks(mut self, yes: bool) -> Self {
+        self.skip_code_blocks = yes;
+        self
+    }
+
+    /// count matches of `pattern` instead of the count target, keyed by its capture group `group`
+    /// (0 is the whole match). matches without the group are not counted
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use regex::Regex;
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count_with, CountConfig};
+    /// let config = CountConfig::default().pattern(Regex::new(r"ERROR: (\w+)").unwrap(), 1);
+    /// let freqs = count_with(Cursor::new("ERROR: disk\nINFO: ok\nERROR: disk"), &config);
+    /// assert_eq!(freqs["disk"], 2);
+    /// assert_eq!(freqs.len(), 1);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// `pattern` has no group `group`
+    pub fn pattern(mut self, pattern: Regex, group: usize) -> Self {
+        assert!(
+            group < pattern.captures_len(),
+            "pattern has no group {}",
+            group
+        );
+        self.pattern = Some((pattern, group));
+        self
+    }
+
+    /// same as [`pattern`](#method.pattern) of `pattern` compiled within `limits`, and with its
+    /// [`time_limit`](#method.time_limit). an error instead of a panic if there is no group
+    /// `group`, so that patterns and groups of untrusted users can be passed as they are:
+    /// [`CountError::Pattern`](../error/enum.CountError.html#variant.Pattern) if `pattern` does
+    /// not compile, and [`CountError::NoGroup`](../error/enum.CountError.html#variant.NoGroup)
+    /// if it has no group `group`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::config::PatternLimits;
+    /// use bicycle_book_wordcount::{try_count_with, CountConfig, CountError};
+    /// let limits = PatternLimits::new();
+    /// let config = CountConfig::default().user_pattern(r"user=(\w+)", 1, &limits).unwrap();
+    /// let freqs = try_count_with(Cursor::new("user=ann\nuser=bob user=ann"), &config).unwrap();
+    /// assert_eq!(freqs["ann"], 2);
+    /// let no_group = CountConfig::default().user_pattern(r"user=(\w+)", 2, &limits);
+    /// assert!(matches!(no_group, Err(CountError::NoGroup { group: 2, groups: 2 })));
+    /// let invalid = CountConfig::default().user_pattern(r"user=(", 1, &limits);
+    /// assert!(matches!(invalid, Err(CountError::Pattern(_))));
+    /// ```
+    pub fn user_pattern(
+        self,
+        pattern: &str,
+        group: usize,
+        limits: &PatternLimits,
+    ) -> Result<Self, CountError> {
+        let pattern = limits.compile(pattern).map_err(CountError::Pattern)?;
+        if group >= pattern.captures_len() {
+            return Err(CountError::NoGroup {
+                group,
+                groups: pattern.captures_len(),
+            });
+        }
+        let config = self.pattern(pattern, group);
+        Ok(match limits.time {
+            Some(limit) => config.time_limit(limit),
+            None => config,
+        })
+    }
+
+    /// fail with [`CountError::TimeLimit`](../error/enum.CountError.html#variant.TimeLimit)
+    /// if matching the pattern (of [`pattern`](#method.pattern) or of
+    /// [`CountOption::Custom`](../enum.CountOption.html#variant.Custom)) in a line takes
+    /// longer than `limit`. the time is checked between matches, so a search for the next
+    /// match is not stopped, which takes time linear in the length of the line; limit lines
+    /// by [`max_line_bytes`](#method.max_line_bytes) too for input of untrusted users
+    pub fn time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// fail counting an input exceeding `limits`, see [`Limits`](struct.Limits.html). the
+    /// limits of bytes are of input read by lines (e.g. by [`try_count_with`](../fn.try_count_with.html)),
+    /// and the limits of keys and memory are of counts of the input
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// count these multi-word phrases as single tokens in [`CountOption::Word`](../enum.CountOption.html#variant.Word),
+    /// the longest phrase wins. phrase keys are normalized words joined by a space
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count_with, CountConfig};
+    /// let config = CountConfig::default().phrases(&["new york", "new york city"]);
+    /// let freqs = count_with(Cursor::new("new york city is in new york"), &config);
+    /// assert_eq!(freqs["new york city"], 1);
+    /// assert_eq!(freqs["new york"], 1);
+    /// assert_eq!(freqs.get("york"), None);
+    /// ```
+    pub fn phrases<S: AsRef<str>>(mut self, phrases: &[S]) -> Self {
+        self.phrases = phrases.iter().map(|p| p.as_ref().to_string()).collect();
+        self
+    }
+
+    /// track at most `n` keys, for untrusted inputs. when a new key exceeds the limit,
+    /// the least frequent quarter of keys (ties broken by the key) is evicted
+    ///
+    /// this is an approximation: counts of evicted keys are lost, and a key seen again starts
+    /// from 1, so counts may be less than actual (never more). keys far more frequent than
+    /// the evicted ones are counted exactly
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count_with, CountConfig};
+    /// let config = CountConfig::default().max_keys(2);
+    /// let freqs = count_with(Cursor::new("a a a b c d a"), &config);
+    /// assert_eq!(freqs.len(), 2);
+    /// assert_eq!(freqs["a"], 4);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// `n` is 0
+    pub fn max_keys(mut self, n: usize) -> Self {
+        assert!(n > 0, "max_keys must not be 0");
+        self.max_keys = Some(n);
+        self
+    }
+
+    /// do not count these tokens, compared after normalization (e.g. `ignore_case`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count_with, CountConfig};
+    /// let config = CountConfig::default().ignore_case(true).stop_words(&["the", "A"]);
+    /// let freqs = count_with(Cursor::new("The cat and a dog"), &config);
+    /// assert_eq!(freqs.len(), 3);
+    /// assert_eq!(freqs.get("the"), None);
+    /// ```
+    pub fn stop_words<S: AsRef<str>>(mut self, words: &[S]) -> Self {
+        self.stop_words = words.iter().map(|w| w.as_ref().to_string()).collect();
+        self
+    }
+
+    /// count only these tokens (e.g. words of a dictionary), compared after normalization.
+    /// stop words are not counted even if allowed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count_with, CountConfig};
+    /// let config = CountConfig::default().ignore_case(true).allowed_words(&["cat", "Dog"]);
+    /// let freqs = count_with(Cursor::new("The cat and a dog, xyzzy"), &config);
+    /// assert_eq!(freqs.len(), 2);
+    /// assert_eq!(freqs["dog"], 1);
+    /// ```
+    pub fn allowed_words<S: AsRef<str>>(mut self, words: &[S]) -> Self {
+        self.allowed_words = Some(words.iter().map(|w| w.as_ref().to_string()).collect());
+        self
+    }
+
+    /// do not count tokens matching `pattern`, tested on keys after normalization like stop
+    /// words. a match anywhere in a key excludes it, so anchor the pattern with `^` and `$` to
+    /// exclude keys of a shape (e.g. hex hashes or session ids). may be called more than once
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use regex::Regex;
+    /// use bicycle_book_wordcount::{count_with, CountConfig};
+    /// let config = CountConfig::default()
+    ///     .exclude_pattern(Regex::new(r"^[0-9a-f]{7,40}$").unwrap())
+    ///     .exclude_pattern(Regex::new(r"^sess_").unwrap());
+    /// let freqs = count_with(Cursor::new("fixed 3f2a9c1 in sess_8xk2 deadbeef00"), &config);
+    /// assert_eq!(freqs.len(), 2);
+    /// assert_eq!(freqs["fixed"], 1);
+    /// ```
+    pub fn exclude_pattern(mut self, pattern: Regex) -> Self {
+        self.exclude_patterns.push(pattern);
+        self
+    }
+
+    /// count forms in `lemmas` as their lemma. forms are looked up after the other
+    /// normalization, so write them in lowercase with `ignore_case`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::normalize::Lemmas;
+    /// use bicycle_book_wordcount::{count_with, CountConfig};
+    /// let lemmas = Lemmas::new(&[("ran", "run"), ("runs", "run")]);
+    /// let config = CountConfig::default().ignore_case(true).lemmas(lemmas);
+    /// let freqs = count_with(Cursor::new("Run, ran, runs"), &config);
+    /// assert_eq!(freqs["run"], 3);
+    /// ```
+    pub fn lemmas(mut self, lemmas: Lemmas) -> Self {
+        self.lemmas = Some(Arc::new(lemmas));
+        self
+    }
+
+    /// apply `f` to the key of each token after the other normalization: count it as the
+    /// returned key, or drop it if `None`. stop words and allowed words are compared with the
+    /// returned key
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count_with, CountConfig};
+    /// // drop numbers, and count words without a trailing "s"
+    /// let config = CountConfig::default().transform(|key| match key {
+    ///     _ if key.chars().all(|c| c.is_ascii_digit()) => None,
+    ///     _ => Some(Cow::Borrowed(key.strip_suffix('s').unwrap_or(key))),
+    /// });
+    /// let freqs = count_with(Cursor::new("cats 42 cat"), &config);
+    /// assert_eq!(freqs.len(), 1);
+    /// assert_eq!(freqs["cat"], 2);
+    /// ```
+    pub fn transform(
+        mut self,
+        f: impl Fn(&str) -> Option<Cow<'_, str>> + Send + Sync + 'static,
+    ) -> Self {
+        self.transform = Some(Transform(Arc::new(f)));
+        self
+    }
+
+    /// count only keys for which `predicate` is true. it is called with the key (after
+    /// [`transform`](#method.transform)) before the key is copied into the result
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count_with, CountConfig};
+    /// let config = CountConfig::default().filter(|key| key.chars().count() > 2);
+    /// let freqs = count_with(Cursor::new("a cat is on the mat"), &config);
+    /// assert_eq!(freqs.len(), 3);
+    /// assert_eq!(freqs.get("on"), None);
+    /// ```
+    pub fn filter(mut self, predicate: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        self.filter = Some(Filter(Arc::new(predicate)));
+        self
+    }
+
+    /// in [`CountOption::Word`](../enum.CountOption.html#variant.Word), split words of scripts
+    /// written without spaces (Thai, Lao, Khmer, Myanmar) by `dictionary`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::segment::Dictionary;
+    /// use bicycle_book_wordcount::{count_with, CountConfig};
+    /// let config = CountConfig::default().dictionary(Dictionary::new(&["ไป", "ไม่", "ไหน"]));
+    /// let freqs = count_with(Cursor::new("ไม่ไปไหน ไป"), &config);
+    /// assert_eq!(freqs["ไป"], 2);
+    /// assert_eq!(freqs["ไม่"], 1);
+    /// ```
+    pub fn dictionary(mut self, dictionary: Dictionary) -> Self {
+        self.dictionary = Some(Arc::new(dictionary));
+        self
+    }
+
+    /// split words of [`CountOption::Word`](../enum.CountOption.html#variant.Word) by the
+    /// word chars of `policy` instead of `\w`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count_with, CountConfig, WordCharPolicy};
+    /// let config = CountConfig::default().word_chars(WordCharPolicy::new().digits(false));
+    /// let freqs = count_with(Cursor::new("h2o 42 abc"), &config);
+    /// assert_eq!(freqs["h"], 1);
+    /// assert_eq!(freqs["o"], 1);
+    /// assert_eq!(freqs.get("42"), None);
+    /// ```
+    pub fn word_chars(mut self, policy: WordCharPolicy) -> Self {
+        self.word_chars = Some(policy.regex());
+        self
+    }
+
+    /// count a byte order mark (U+FEFF) at the start of input as a part of the first line,
+    /// instead of removing it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count_with, CountConfig, CountOption};
+    /// let input = "\u{feff}aa\naa";
+    /// let freqs = count_with(Cursor::new(input), &CountConfig::new(CountOption::Line));
+    /// assert_eq!(freqs["aa"], 2);
+    /// let config = CountConfig::new(CountOption::Line).keep_bom(true);
+    /// let freqs = count_with(Cursor::new(input), &config);
+    /// assert_eq!(freqs["\u{feff}aa"], 1);
+    /// ```
+    pub fn keep_bom(mut self, yes: bool) -> Self {
+        self.keep_bom = yes;
+        self
+    }
+
+    /// what to do with input which is not UTF-8, default is to fail with
+    /// [`DecodePolicy::Strict`](../decode/enum.DecodePolicy.html#variant.Strict)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::decode::DecodePolicy;
+    /// use bicycle_book_wordcount::{CountConfig, Counter};
+    /// let config = CountConfig::default().invalid_input(DecodePolicy::SkipLine);
+    /// let mut counter = Counter::new(&config);
+    /// counter.read(Cursor::new(b"ok\nbroken \xff\nok")).unwrap();
+    /// assert_eq!(counter.skipped(), 1);
+    /// assert_eq!(counter.finish()["ok"], 2);
+    /// ```
+    pub fn invalid_input(mut self, policy: DecodePolicy) -> Self {
+        self.invalid_input = policy;
+        self
+    }
+
+    /// what to do with a line longer than `limit` bytes (without the line terminator), to
+    /// protect from huge inputs of a single line. without a limit, a whole line is read into
+    /// memory however long it is
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::decode::LongLinePolicy;
+    /// use bicycle_book_wordcount::{try_count_with, CountConfig};
+    /// let input = "ok\naaaa bbbb\nok";
+    /// let config = CountConfig::default().max_line_bytes(6, LongLinePolicy::Truncate);
+    /// let freqs = try_count_with(Cursor::new(input), &config).unwrap();
+    /// assert_eq!(freqs["aaaa"], 1);
+    /// assert_eq!(freqs["b"], 1);
+    ///
+    /// let config = CountConfig::default().max_line_bytes(6, LongLinePolicy::Error);
+    /// let err = try_count_with(Cursor::new(input), &config).unwrap_err();
+    /// assert_eq!(err.to_string(), "failed to read input: line 2 is longer than 6 bytes");
+    /// ```
+    pub fn max_line_bytes(mut self, limit: usize, policy: LongLinePolicy) -> Self {
+        self.max_line = Some((limit, policy));
+        self
+    }
+
+    /// what to do with a key longer than `limit` chars (after normalization and
+    /// [`transform`](#method.transform)), to keep results readable and bounded. filters and
+    /// stop words see the key made by `policy`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count_with, CountConfig, LongTokenPolicy};
+    /// let input = "data iVBORw0KGgoAAAANSUhEUgAA iVBORw0KGgoAAAANSUhEUgBB";
+    /// let config = CountConfig::default().max_token_chars(8, LongTokenPolicy::Truncate);
+    /// let freqs = count_with(Cursor::new(input), &config);
+    /// assert_eq!(freqs["iVBORw0K…"], 2);
+    /// assert_eq!(freqs["data"], 1);
+    ///
+    /// let config = CountConfig::default().max_token_chars(8, LongTokenPolicy::Drop);
+    /// assert_eq!(count_with(Cursor::new(input), &config).len(), 1);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// `limit` is 0
+    pub fn max_token_chars(mut self, limit: usize, policy: LongTokenPolicy) -> Self {
+        assert!(limit > 0, "max_token_chars must not be 0");
+        self.max_token = Some((limit, policy));
+        self
+    }
+
+    /// key a line (of [`CountOption::Line`](../enum.CountOption.html#variant.Line)) longer than
+    /// `limit` bytes by its start up to `limit` bytes, `…#` and a hash of the whole line, so that
+    /// huge unique lines take at most 36 bytes more than `limit` as keys, while the same lines
+    /// are still counted together. other options are not affected.
+    ///
+    /// the hash is 128-bit FNV-1a, so distinct lines practically never share a key by chance,
+    /// and the same line has the same key in any run, so that saved counts are merged. it is not
+    /// a cryptographic hash: lines of untrusted users may be made to share a key.
+    /// [`count_with_hashed_lines`](../hashed/fn.count_with_hashed_lines.html) records where the
+    /// lines behind the keys are
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count_with, CountConfig, CountOption};
+    /// let long = "x".repeat(1000);
+    /// let input = format!("ok\n{}\n{}\n", long, long);
+    /// let config = CountConfig::new(CountOption::Line).line_key_bytes(8);
+    /// let freqs = count_with(Cursor::new(input), &config);
+    /// assert_eq!(freqs["ok"], 1);
+    /// let (key, count) = freqs.iter().find(|(_, &n)| n == 2).unwrap();
+    /// assert!(key.starts_with("xxxxxxxx…#"));
+    /// assert_eq!((key.len(), *count), (44, 2));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// `limit` is 0
+    pub fn line_key_bytes(mut self, limit: usize) -> Self {
+        assert!(limit > 0, "line_key_bytes must not be 0");
+        self.line_key_bytes = Some(limit);
+        self
+    }
+
+    /// count target
+    pub fn option(&self) -> &CountOption {
+        &self.option
+    }
+
+    /// hash of what is counted by the configuration, the same on any platform and in any run,
+    /// to tell whether counts can be merged. functions of [`transform`](#method.transform)
+    /// and [`filter`](#method.filter) are not told apart, and how input is read
+    /// ([`invalid_input`](#method.invalid_input), [`keep_bom`](#method.keep_bom),
+    /// [`max_line_bytes`](#method.max_line_bytes)) and limits are not hashed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::{CountConfig, CountOption};
+    /// let words = CountConfig::new(CountOption::Word);
+    /// assert_eq!(words.fingerprint(), CountConfig::new(CountOption::Word).fingerprint());
+    /// assert_ne!(words.fingerprint(), words.clone().ignore_case(true).fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        // every field is named, so that a new one is either hashed or left out here on purpose
+        let CountConfig {
+            option,
+            word_chars,
+            ignore_case,
+            collapse_whitespace,
+            original_case,
+            strip_diacritics,
+            roman_numerals,
+            ordinals,
+            overflow,
+            quoted_only,
+            skip_code_blocks,
+            pattern,
+            phrases,
+            max_keys,
+            dictionary,
+            stop_words,
+            allowed_words,
+            exclude_patterns,
+            lemmas,
+            transform,
+            filter,
+            keep_bom: _,
+            invalid_input: _,
+            max_line: _,
+            max_token,
+            line_key_bytes,
+            time_limit: _,
+            limits: _,
+        } = self;
+        let mut text = format!("v{}", FINGERPRINT_VERSION);
+        let mut field = |name: &str, value: &dyn fmt::Debug| {
+            text.push_str(&format!(" {}={:?}", name, value));
+        };
+        field("option", option);
+        field("word_chars", &word_chars.as_ref().map(Regex::as_str));
+        field("ignore_case", ignore_case);
+        field("collapse_whitespace", collapse_whitespace);
+        field("original_case", original_case);
+        field("strip_diacritics", strip_diacritics);
+        field("roman_numerals", roman_numerals);
+        field("ordinals", ordinals);
+        field("overflow", overflow);
+        field("quoted_only", quoted_only);
+        field("skip_code_blocks", skip_code_blocks);
+        field(
+            "pattern",
+            &pattern.as_ref().map(|(re, group)| (re.as_str(), group)),
+        );
+        field("phrases", phrases);
+        field("max_keys", max_keys);
+        // tables are hashed apart, since their debug output is in the order of hash maps
+        field("dictionary", &dictionary.as_ref().map(|d| d.fingerprint()));
+        field("stop_words", stop_words);
+        field("allowed_words", allowed_words);
+        let excluded: Vec<_> = exclude_patterns.iter().map(Regex::as_str).collect();
+        field("exclude_patterns", &excluded);
+        field("lemmas", &lemmas.as_ref().map(|l| l.fingerprint()));
+        field("transform", &transform.is_some());
+        field("filter", &filter.is_some());
+        field("max_token", max_token);
+        field("line_key_bytes", line_key_bytes);
+        fnv1a(text.as_bytes())
+    }
+
+    /// how lines of input are read
+    pub(crate) fn reading(&self) -> Reading {
+        Reading {
+            decode: self.invalid_input,
+            max_line: self.max_line,
+            limits: self.limits,
+        }
+    }
+
+    /// key for a token
+    pub(crate) fn normalize<'a>(&self, token: &'a str) -> Cow<'a, str> {
+        let mut key = Cow::Borrowed(token);
+        if self.collapse_whitespace {
+            key = normalize::collapse_whitespace(token);
+        }
+        // before lowercasing, as only numerals in uppercase are replaced
+        if self.roman_numerals {
+            key = map(key, normalize::roman_numerals);
+        }
+        if self.ordinals {
+            key = map(key, normalize::ordinals);
+        }
+        if self.ignore_case && key.chars().any(char::is_uppercase) {
+            key = Cow::Owned(key.to_lowercase());
+        }
+        if self.strip_diacritics {
+            key = map(key, normalize::strip_diacritics);
+        }
+        if let Some(lemma) = self.lemmas.as_ref().and_then(|lemmas| lemmas.lemma(&key)) {
+            key = Cow::Owned(lemma.to_string());
+        }
+        key
+    }
+}
+
+/// apply `f` to `key`, keeping it borrowed if possible
+fn map<'a>(key: Cow<'a, str>, f: impl Fn(&str) -> Cow<'_, str>) -> Cow<'a, str> {
+    match key {
+        Cow::Borrowed(key) => f(key),
+        Cow::Owned(key) => Cow::Owned(f(&key).into_owned()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn overflow_policy_works() {
+        let max = usize::MAX;
+        assert_eq!(OverflowPolicy::Saturate.add(max, 1), Some(max));
+        assert_eq!(OverflowPolicy::Error.add(max, 1), None);
+        assert_eq!(OverflowPolicy::Wrap.add(max, 2), Some(1));
+        for &policy in &[
+            OverflowPolicy::Saturate,
+            OverflowPolicy::Error,
+            OverflowPolicy::Wrap,
+        ] {
+            assert_eq!(policy.add(1, 2), Some(3));
+        }
+    }
+
+    #[test]
+    fn long_tokens_are_bounded() {
+        let apply = |policy: LongTokenPolicy, key: &str, limit| {
+            policy
+                .apply(Cow::Borrowed(key), limit)
+                .map(|key| key.into_owned())
+        };
+        let truncate = LongTokenPolicy::Truncate;
+        assert_eq!(
+            apply(truncate, "日本語です", 5).as_deref(),
+            Some("日本語です")
+        );
+        assert_eq!(apply(truncate, "日本語です", 3).as_deref(), Some("日本語…"));
+        assert_eq!(apply(truncate, "abcd", 3).as_deref(), Some("abc…"));
+        let hashed = apply(LongTokenPolicy::Hash, "abcd", 3).unwrap();
+        assert_eq!(hashed.len(), 17);
+        assert!(hashed.starts_with('#'));
+        assert_eq!(apply(LongTokenPolicy::Hash, "abcd", 3).unwrap(), hashed);
+        assert_ne!(apply(LongTokenPolicy::Hash, "abce", 3).unwrap(), hashed);
+        assert_eq!(apply(LongTokenPolicy::Drop, "abcd", 3), None);
+        assert_eq!(
+            apply(LongTokenPolicy::Drop, "abc", 3).as_deref(),
+            Some("abc")
+        );
+    }
+
+    #[test]
+    fn word_chars_are_configurable() {
+        let default = WordCharPolicy::new().regex();
+        let words = "e\u{301}t\u{e9} a_b a\u{203f}b 42 x&&y-z[]";
+        let matches = |re: &Regex| re.find_iter(words).map(|m| m.as_str()).collect::<Vec<_>>();
+        assert_eq!(matches(&default), matches(&Regex::new(r"\w+").unwrap()));
+        let policy = WordCharPolicy::new()
+            .marks(false)
+            .underscore(false)
+            .digits(false)
+            .extra("&-[]");
+        assert_eq!(
+            matches(&policy.regex()),
+            vec!["e", "t\u{e9}", "a", "b", "a\u{203f}b", "x&&y-z[]"]
+        );
+        let policy = WordCharPolicy::new().connectors(false);
+        assert_eq!(matches(&policy.regex())[1..3], ["a_b", "a"]);
+    }
+
+    #[test]
+    fn time_limit_stops_matching() {
+        use crate::{try_count_with, CountError};
+        let input = format!("b\nb\n{}\n", "a ".repeat(100_000));
+        let user_pattern =
+            |limits: &PatternLimits| CountConfig::default().user_pattern("a", 0, limits).unwrap();
+        let config = user_pattern(&PatternLimits::new().time(Duration::from_nanos(0)));
+        match try_count_with(input.as_bytes(), &config) {
+            Err(CountError::TimeLimit { line, limit }) => {
+                assert_eq!((line, limit), (3, Duration::from_nanos(0)))
+            }
+            other => panic!("{:?}", other),
+        }
+        let pattern = PatternLimits::new().compile("a").unwrap();
+        let config = CountConfig::default().pattern(pattern.clone(), 0);
+        let config = config.time_limit(Duration::from_secs(60));
+        let freqs = try_count_with(input.as_bytes(), &config).unwrap();
+        assert_eq!(freqs["a"], 100_000);
+        let config = CountConfig::new(CountOption::Custom(Arc::new(pattern)));
+        let config = config.time_limit(Duration::from_nanos(0));
+        let timed_out = try_count_with(input.as_bytes(), &config);
+        assert!(matches!(
+            timed_out,
+            Err(CountError::TimeLimit { line: 3, .. })
+        ));
+        let config = user_pattern(&PatternLimits::new().time(None));
+        assert_eq!(config.time_limit, None);
+        let nested = PatternLimits::new().nesting(2).compile("((((a))))");
+        assert!(nested.is_err());
+    }
+
+    #[test]
+    fn limits_fail_counting() {
+        use crate::error::Limit;
+        use crate::{try_count_with, CountError, Counter};
+        let count = |limits: Limits, input: &str| {
+            let config = CountConfig::default().limits(limits);
+            match try_count_with(input.as_bytes(), &config) {
+                Err(CountError::Limit { limit, line }) => Some((limit, line)),
+                Ok(_) => None,
+                Err(e) => panic!("{}", e),
+            }
+        };
+        let line = Limits::new().line(3);
+        assert_eq!(count(line, "abc\r\nabcd\n"), Some((Limit::Line(3), 2)));
+        assert_eq!(count(line, "abc\n"), None);
+        let long = format!("a\n{}", "b".repeat(1 << 20));
+        assert_eq!(count(line, &long), Some((Limit::Line(3), 2)));
+        assert_eq!(
+            count(Limits::new().bytes(4), "ab\ncd"),
+            Some((Limit::Bytes(4), 2))
+        );
+        assert_eq!(count(Limits::new().bytes(5), "ab\ncd"), None);
+        assert_eq!(
+            count(Limits::new().memory(1), "a"),
+            Some((Limit::Memory(1), 1))
+        );
+        assert_eq!(count(Limits::new().memory(1 << 20), "a b c"), None);
+
+        let config = CountConfig::default().limits(Limits::new().keys(1).line(10));
+        let mut counter = Counter::new(&config);
+        assert!(counter.line("a a").is_ok());
+        match counter.line("b") {
+            Err(CountError::Limit { limit, line }) => {
+                assert_eq!((limit, line), (Limit::Keys(1), 2))
+            }
+            other => panic!("{:?}", other),
+        }
+        match counter.line(&"c".repeat(11)) {
+            Err(e) => assert_eq!(e.to_string(), "a line longer than 10 bytes at line 3"),
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(config.fingerprint(), CountConfig::default().fingerprint());
+    }
+
+    #[test]
+    fn hashed_lines_are_merged_across_runs() {
+        let long = "x".repeat(1000);
+        let config = CountConfig::new(CountOption::Line).line_key_bytes(8);
+        let mut first = crate::count_with(format!("{}\n", long).as_bytes(), &config);
+        let second = crate::count_with(format!("ok\n{}\n", long).as_bytes(), &config);
+        // the key of another run, which must stay the same
+        let key = "xxxxxxxx…#00e2531c2570995111249bb059f17d2d";
+        assert_eq!(first[key], 1);
+        first.merge(&second);
+        assert_eq!(first[key], 2);
+        assert_eq!(first.len(), 2);
+    }
+
+    #[test]
+    fn fingerprint_is_of_what_is_counted() {
+        let words = CountConfig::default();
+        let read = CountConfig::default()
+            .keep_bom(true)
+            .invalid_input(DecodePolicy::Lossy)
+            .max_line_bytes(10, LongLinePolicy::Truncate)
+            .time_limit(Duration::from_secs(1));
+        assert_eq!(read.fingerprint(), words.fingerprint());
+        let excluded = words.clone().exclude_pattern(Regex::new("^a").unwrap());
+        assert_ne!(excluded.fingerprint(), words.fingerprint());
+        let keyed = words.clone().line_key_bytes(10);
+        assert_ne!(keyed.fingerprint(), words.fingerprint());
+    }
+}
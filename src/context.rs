@@ -0,0 +1,143 @@
+//! context around each match, see [`count_with_context`](fn.count_with_context.html)
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use crate::decode::{read_numbered_lines, Transcoder};
+use crate::tally::Tally;
+use crate::{word_regex, CountConfig, CountError, Frequencies};
+
+/// size of context on each side of a match, within the line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    /// n chars (Unicode)
+    Chars(usize),
+    /// n words (regex "\w+"), and the text between them
+    Words(usize),
+}
+
+impl Context {
+    /// start offset of the context in the text before a match
+    fn start(self, re: &Regex, before: &str) -> usize {
+        match self {
+            Context::Chars(0) | Context::Words(0) => before.len(),
+            Context::Chars(n) => before.char_indices().rev().nth(n - 1).map_or(0, |(i, _)| i),
+            Context::Words(n) => {
+                let words: Vec<_> = re.find_iter(before).collect();
+                words.iter().rev().nth(n - 1).map_or(0, |m| m.start())
+            }
+        }
+    }
+
+    /// end offset of the context in the text after a match
+    fn end(self, re: &Regex, after: &str) -> usize {
+        match self {
+            Context::Chars(0) | Context::Words(0) => 0,
+            Context::Chars(n) => after.char_indices().nth(n).map_or(after.len(), |(i, _)| i),
+            Context::Words(n) => re
+                .find_iter(after)
+                .nth(n - 1)
+                .map_or(after.len(), |m| m.end()),
+        }
+    }
+}
+
+/// a match with its context
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// line number, start from 1
+    pub line: usize,
+    /// context before the match
+    pub before: String,
+    /// the matched text as it appeared
+    pub text: String,
+    /// context after the match
+    pub after: String,
+}
+
+/// same as [`try_count_with`](../fn.try_count_with.html), but also record each match with its
+/// context, like grep. best used with a [`pattern`](../config/struct.CountConfig.html#method.pattern)
+/// or [`phrases`](../config/struct.CountConfig.html#method.phrases), as every token is recorded
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use regex::Regex;
+/// use bicycle_book_wordcount::context::{count_with_context, Context};
+/// use bicycle_book_wordcount::CountConfig;
+/// let config = CountConfig::default().pattern(Regex::new(r"(?i)\b(error)\b").unwrap(), 1);
+/// let input = Cursor::new("ok\nread error on sda1 again");
+/// let (freqs, matches) = count_with_context(input, &config, Context::Words(2)).unwrap();
+/// assert_eq!(freqs["error"], 1);
+/// let m = &matches["error"][0];
+/// assert_eq!((m.line, m.before.as_str(), m.after.as_str()), (2, "read ", " on sda1"));
+/// ```
+pub fn count_with_context(
+    input: impl BufRead,
+    config: &CountConfig,
+    context: Context,
+) -> Result<(Frequencies, HashMap<String, Vec<Match>>), CountError> {
+    let re = word_regex();
+    let mut tally = Tally::new(config);
+    let mut matches: HashMap<String, Vec<Match>> = HashMap::new();
+
+    read_numbered_lines(Transcoder::new(input), config.reading(), |number, line| {
+        tally.line_with_tokens(line, |key, token| {
+            let start = token.as_ptr() as usize - line.as_ptr() as usize;
+            let end = start + token.len();
+            let from = context.start(&re, &line[..start]);
+            let to = end + context.end(&re, &line[end..]);
+            matches.entry(key.to_string()).or_default().push(Match {
+                line: number,
+                before: line[from..start].to_string(),
+                text: token.to_string(),
+                after: line[end..to].to_string(),
+            });
+        })
+    })?;
+    Ok(tally.finish_with(matches))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CountOption;
+    use std::io::Cursor;
+
+    fn contexts(input: &str, context: Context) -> Vec<(String, String)> {
+        let config = CountConfig::new(CountOption::Word).phrases(&["new york"]);
+        let (_, matches) = count_with_context(Cursor::new(input), &config, context).unwrap();
+        matches["new york"]
+            .iter()
+            .map(|m| (m.before.clone(), m.after.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn chars_context() {
+        let pair = |b: &str, a: &str| (b.to_string(), a.to_string());
+        assert_eq!(
+            contexts("あいう new york えお\nnew york", Context::Chars(2)),
+            vec![pair("う ", " え"), pair("", "")]
+        );
+        assert_eq!(
+            contexts("ab new york cd", Context::Chars(0)),
+            vec![pair("", "")]
+        );
+    }
+
+    #[test]
+    fn words_context() {
+        let pair = |b: &str, a: &str| (b.to_string(), a.to_string());
+        assert_eq!(
+            contexts("in new york, and new york city!", Context::Words(1)),
+            vec![pair("in ", ", and"), pair("and ", " city")]
+        );
+        assert_eq!(
+            contexts("a b new york", Context::Words(5)),
+            vec![pair("a b ", "")]
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! words on the same lines as a target word, see [`count_alongside`](fn.count_alongside.html)
+
+use std::collections::HashSet;
+use std::io::BufRead;
+
+use crate::decode::{read_lines, Transcoder};
+use crate::tally::Scanner;
+use crate::{CountConfig, CountError, Frequencies};
+
+/// result of [`count_alongside`](fn.count_alongside.html)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Alongside {
+    /// count of lines with the target
+    pub lines: usize,
+    /// count of lines with the target per other word
+    pub words: Frequencies,
+}
+
+/// which words appear on the same lines as `target` and on how many of them, in a pass.
+/// `target` is normalized as configured (e.g. by `ignore_case`), and words on a line are
+/// counted once however many times they appear
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::cooccurrence::count_alongside;
+/// use bicycle_book_wordcount::CountConfig;
+/// let log = "ERROR disk full\nINFO ok\nERROR disk disk slow\nERROR net down";
+/// let config = CountConfig::default();
+/// let alongside = count_alongside(Cursor::new(log), &config, "ERROR").unwrap();
+/// assert_eq!(alongside.lines, 3);
+/// assert_eq!(alongside.words["disk"], 2);
+/// assert_eq!(alongside.words.get("ok"), None);
+/// assert_eq!(alongside.words.get("ERROR"), None);
+/// ```
+pub fn count_alongside(
+    input: impl BufRead,
+    config: &CountConfig,
+    target: &str,
+) -> Result<Alongside, CountError> {
+    let target = config.normalize(target).into_owned();
+    let mut scanner = Scanner::new(config);
+    let mut alongside = Alongside::default();
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        let mut keys = HashSet::new();
+        scanner.line(line, |key, _| {
+            if !keys.contains(key) {
+                keys.insert(key.to_string());
+            }
+        });
+        if keys.remove(&target) {
+            alongside.lines += 1;
+            alongside.words.extend(keys.into_iter().map(|key| (key, 1)));
+        }
+        Ok::<_, CountError>(())
+    })?;
+    Ok(alongside)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn count_alongside_works() {
+        let config = CountConfig::default().ignore_case(true);
+        let text = "Error a\nerror a error b\nc\n";
+        let alongside = count_alongside(Cursor::new(text), &config, "ERROR").unwrap();
+        assert_eq!(alongside.lines, 2);
+        assert_eq!(alongside.words["a"], 2);
+        assert_eq!(alongside.words["b"], 1);
+        assert_eq!(alongside.words.len(), 2);
+        let none = count_alongside(Cursor::new(text), &config, "zz").unwrap();
+        assert_eq!(none, Alongside::default());
+    }
+}
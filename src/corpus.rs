@@ -0,0 +1,79 @@
+//! counts of many documents with document frequencies, see [`Corpus`](struct.Corpus.html)
+
+use std::io::BufRead;
+
+use crate::similarity::Idf;
+use crate::{try_count_with, CountConfig, CountError, Frequencies};
+
+/// sum of counts of documents, and the count of documents each token appears in
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::corpus::Corpus;
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let mut corpus = Corpus::new();
+/// corpus.add(&count_str("aa aa bb", CountOption::Word));
+/// corpus.add(&count_str("aa cc", CountOption::Word));
+/// assert_eq!(corpus.documents, 2);
+/// assert_eq!(corpus.total["aa"], 3);
+/// assert_eq!(corpus.document_frequencies["aa"], 2);
+/// assert_eq!(corpus.document_frequencies["bb"], 1);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Corpus {
+    /// sum of counts
+    pub total: Frequencies,
+    /// count of documents containing each token
+    pub document_frequencies: Frequencies,
+    /// count of documents added
+    pub documents: usize,
+}
+
+impl Corpus {
+    pub fn new() -> Self {
+        Corpus::default()
+    }
+
+    /// add counts of a document
+    pub fn add(&mut self, document: &Frequencies) {
+        self.total.merge(document);
+        let tokens = document.keys().map(|token| (token.clone(), 1));
+        self.document_frequencies.extend(tokens);
+        self.documents += 1;
+    }
+
+    /// inverse document frequencies of the documents
+    pub fn idf(&self) -> Idf<String> {
+        Idf::with_frequencies(self.documents, &self.document_frequencies)
+    }
+}
+
+/// counts of each input as a document, by [`try_count_with`](../fn.try_count_with.html)
+pub fn count_documents<R: BufRead>(
+    inputs: impl IntoIterator<Item = R>,
+    config: &CountConfig,
+) -> Result<Corpus, CountError> {
+    let mut corpus = Corpus::new();
+    for input in inputs {
+        corpus.add(&try_count_with(input, config)?);
+    }
+    Ok(corpus)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn count_documents_works() {
+        let inputs = vec![Cursor::new("aa bb"), Cursor::new("aa"), Cursor::new("")];
+        let corpus = count_documents(inputs, &CountConfig::default()).unwrap();
+        assert_eq!(corpus.documents, 3);
+        assert_eq!(corpus.total.total(), 3);
+        assert_eq!(corpus.document_frequencies["aa"], 2);
+        let idf = corpus.idf();
+        assert!(idf.idf(&"bb".to_string()) > idf.idf(&"aa".to_string()));
+    }
+}
@@ -0,0 +1,133 @@
+//! incremental counting, see [`Counter`](struct.Counter.html)
+
+use std::cmp::Ordering;
+use std::io::BufRead;
+
+use crate::decode::{read_lines, Reading, Transcoder};
+use crate::tally::Tally;
+use crate::{CountConfig, CountError, Frequencies};
+
+/// counts lines fed one by one, for inputs which grow over time (e.g. log files)
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{Counter, CountConfig};
+/// let mut counter = Counter::new(&CountConfig::default());
+/// counter.line("aa bb").unwrap();
+/// assert_eq!(counter.frequencies()["aa"], 1);
+///
+/// counter.read(Cursor::new("aa\ncc")).unwrap();
+/// let freqs = counter.finish();
+/// assert_eq!(freqs["aa"], 2);
+/// assert_eq!(freqs.len(), 3);
+/// ```
+pub struct Counter {
+    tally: Tally,
+    lines: usize,
+    skipped: usize,
+    reading: Reading,
+}
+
+impl Counter {
+    /// counter which counts as configured
+    pub fn new(config: &CountConfig) -> Self {
+        Counter {
+            tally: Tally::new(config),
+            lines: 0,
+            skipped: 0,
+            reading: config.reading(),
+        }
+    }
+
+    /// count a line (without the line terminator)
+    pub fn line(&mut self, line: &str) -> Result<(), CountError> {
+        self.lines += 1;
+        self.tally.line(line)
+    }
+
+    /// count all lines read from input, decoded as configured by
+    /// [`CountConfig::invalid_input`](../config/struct.CountConfig.html#method.invalid_input)
+    pub fn read(&mut self, input: impl BufRead) -> Result<(), CountError> {
+        let reading = self.reading;
+        self.skipped += read_lines(Transcoder::new(input), reading, |line| self.line(line))?;
+        Ok(())
+    }
+
+    /// count of lines fed so far
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    /// count of lines skipped by [`read`](#method.read) because they are broken UTF-8 (or
+    /// UTF-16), with
+    /// [`DecodePolicy::SkipLine`](../decode/enum.DecodePolicy.html#variant.SkipLine)
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// counts so far. with `original_case`, keys are still case-folded until
+    /// [`finish`](#method.finish)
+    pub fn frequencies(&self) -> &Frequencies {
+        self.tally.frequencies()
+    }
+
+    /// totals and the `n` most frequent tokens so far, owned so that counting can go on while
+    /// it is shown (e.g. as progress of a huge stream). cheaper than sorting all tokens with
+    /// [`Frequencies::top`](../frequencies/struct.Frequencies.html#method.top), in the same
+    /// order
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::{Counter, CountConfig};
+    /// let mut counter = Counter::new(&CountConfig::default());
+    /// counter.line("b a b c b a").unwrap();
+    /// let snapshot = counter.snapshot(2);
+    /// counter.line("c c c").unwrap();
+    /// assert_eq!((snapshot.lines, snapshot.total, snapshot.unique), (1, 6, 3));
+    /// assert_eq!(snapshot.top, vec![("b".to_string(), 3), ("a".to_string(), 2)]);
+    /// assert_eq!(counter.snapshot(1).top, vec![("c".to_string(), 4)]);
+    /// ```
+    pub fn snapshot(&self, n: usize) -> Snapshot {
+        let freqs = self.tally.frequencies();
+        let order = |a: &(&String, usize), b: &(&String, usize)| -> Ordering {
+            b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0))
+        };
+        let mut top: Vec<_> = freqs.iter().map(|(token, &count)| (token, count)).collect();
+        if n < top.len() {
+            top.select_nth_unstable_by(n, order);
+            top.truncate(n);
+        }
+        top.sort_unstable_by(order);
+        Snapshot {
+            lines: self.lines,
+            total: freqs.total(),
+            unique: freqs.len(),
+            top: top
+                .into_iter()
+                .map(|(token, count)| (token.clone(), count))
+                .collect(),
+        }
+    }
+
+    /// the final result
+    pub fn finish(self) -> Frequencies {
+        self.tally.finish()
+    }
+}
+
+/// totals of a [`Counter`](struct.Counter.html) at a time, see
+/// [`Counter::snapshot`](struct.Counter.html#method.snapshot)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    /// lines fed
+    pub lines: usize,
+    /// count of all tokens
+    pub total: usize,
+    /// count of distinct tokens
+    pub unique: usize,
+    /// the most frequent tokens with their counts, in descending order of counts
+    pub top: Vec<(String, usize)>,
+}
@@ -0,0 +1,109 @@
+//! cumulative coverage of occurrences by top-ranked tokens, see [`Coverage`](struct.Coverage.html)
+
+use std::hash::Hash;
+
+use crate::Frequencies;
+
+/// how many of the most frequent tokens cover how much of all occurrences
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::coverage::Coverage;
+/// use bicycle_book_wordcount::{count, CountOption};
+/// let freqs = count(Cursor::new("a a a a a b b b c d"), CountOption::Word);
+/// let coverage = Coverage::of(&freqs);
+/// assert_eq!(coverage.tokens_for(0.5), 1);
+/// assert_eq!(coverage.tokens_for(0.8), 2);
+/// assert_eq!(coverage.tokens_for(1.0), 4);
+/// assert_eq!(coverage.covered_by(3), 0.9);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    /// occurrences covered by the top 1, 2, ... tokens
+    cumulative: Vec<u128>,
+}
+
+impl Coverage {
+    /// coverage of counts
+    pub fn of<K: Eq + Hash>(freqs: &Frequencies<K>) -> Self {
+        let mut counts: Vec<usize> = freqs.values().cloned().collect();
+        counts.sort_unstable_by(|a, b| b.cmp(a));
+        let mut total = 0;
+        let cumulative = counts
+            .into_iter()
+            .map(|n| {
+                total += n as u128;
+                total
+            })
+            .collect();
+        Coverage { cumulative }
+    }
+
+    /// count of distinct tokens
+    pub fn len(&self) -> usize {
+        self.cumulative.len()
+    }
+
+    /// there are no tokens
+    pub fn is_empty(&self) -> bool {
+        self.cumulative.is_empty()
+    }
+
+    /// total occurrences
+    pub fn total(&self) -> u128 {
+        self.cumulative.last().cloned().unwrap_or(0)
+    }
+
+    /// fewest top tokens which cover at least `fraction` (0.0 to 1.0) of all occurrences
+    pub fn tokens_for(&self, fraction: f64) -> usize {
+        let target = fraction.clamp(0.0, 1.0) * self.total() as f64;
+        match self.cumulative.iter().position(|&n| n as f64 >= target) {
+            Some(i) if target > 0.0 => i + 1,
+            _ => 0,
+        }
+    }
+
+    /// fraction of all occurrences covered by the top `n` tokens, 0 if there are no tokens
+    pub fn covered_by(&self, n: usize) -> f64 {
+        if n == 0 || self.is_empty() {
+            return 0.0;
+        }
+        let covered = self.cumulative[n.min(self.len()) - 1];
+        covered as f64 / self.total() as f64
+    }
+
+    /// (top n, covered fraction) for each n of 1, 2, ..., the coverage curve
+    pub fn curve(&self) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let total = self.total() as f64;
+        self.cumulative
+            .iter()
+            .enumerate()
+            .map(move |(i, &n)| (i + 1, n as f64 / total))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{count_str, CountOption};
+
+    #[test]
+    fn coverage_of_empty() {
+        let coverage = Coverage::of(&Frequencies::<String>::new());
+        assert_eq!(coverage.tokens_for(0.9), 0);
+        assert_eq!(coverage.covered_by(10), 0.0);
+        assert_eq!(coverage.curve().count(), 0);
+    }
+
+    #[test]
+    fn curve_works() {
+        let coverage = Coverage::of(&count_str("x y x z x y", CountOption::Word));
+        let curve: Vec<_> = coverage.curve().collect();
+        assert_eq!(curve, vec![(1, 0.5), (2, 5.0 / 6.0), (3, 1.0)]);
+        assert_eq!(coverage.covered_by(10), 1.0);
+        assert_eq!(coverage.tokens_for(0.0), 0);
+        assert_eq!(coverage.tokens_for(0.51), 2);
+    }
+}
@@ -0,0 +1,700 @@
+//! decoding bytes to text, see [`count_bytes`](../fn.count_bytes.html) and
+//! [`Transcoder`](struct.Transcoder.html)
+
+use std::borrow::Cow;
+use std::io::{self, BufRead, Read};
+use std::str;
+
+use crate::config::Limits;
+use crate::error::{Exceeded, Limit};
+
+/// what to do with bytes which are not UTF-8
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecodePolicy {
+    /// fail with an error
+    Strict,
+    /// replace each invalid sequence by U+FFFD
+    Lossy,
+    /// remove invalid sequences
+    SkipInvalid,
+    /// skip whole lines containing invalid sequences,
+    /// counted by [`Counter::skipped`](../struct.Counter.html#method.skipped)
+    SkipLine,
+}
+
+/// option default value
+impl Default for DecodePolicy {
+    fn default() -> Self {
+        DecodePolicy::Strict
+    }
+}
+
+/// byte order mark, which some editors put at the start of UTF-8 files
+pub(crate) const BOM: char = '\u{feff}';
+
+/// `line` without a leading byte order mark
+pub(crate) fn strip_bom(line: &str) -> &str {
+    line.strip_prefix(BOM).unwrap_or(line)
+}
+
+/// decode `bytes` as UTF-8 by `policy`, borrowing if they are valid
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::decode::{decode, DecodePolicy};
+/// let bytes = b"a\xffb";
+/// assert!(decode(bytes, DecodePolicy::Strict).is_err());
+/// assert_eq!(decode(bytes, DecodePolicy::Lossy).unwrap(), "a\u{fffd}b");
+/// assert_eq!(decode(bytes, DecodePolicy::SkipInvalid).unwrap(), "ab");
+/// assert_eq!(decode(b"a\nb\xff\nc", DecodePolicy::SkipLine).unwrap(), "a\nc");
+/// ```
+pub fn decode(bytes: &[u8], policy: DecodePolicy) -> Result<Cow<'_, str>, str::Utf8Error> {
+    match policy {
+        DecodePolicy::Strict => str::from_utf8(bytes).map(Cow::Borrowed),
+        DecodePolicy::Lossy => Ok(String::from_utf8_lossy(bytes)),
+        DecodePolicy::SkipInvalid => Ok(skip_invalid(bytes)),
+        DecodePolicy::SkipLine => Ok(skip_invalid_lines(bytes)),
+    }
+}
+
+/// call `f` with each line (without the line terminator) of input decoded and limited as
+/// `reading`, and return the count of lines skipped by [`DecodePolicy::SkipLine`](enum.DecodePolicy.html#variant.SkipLine)
+pub(crate) fn read_lines<E: From<io::Error>>(
+    input: impl BufRead,
+    reading: impl Into<Reading>,
+    f: impl FnMut(&str) -> Result<(), E>,
+) -> Result<usize, E> {
+    read_lines_with(&mut Vec::new(), input, reading, f)
+}
+
+/// same as `read_lines`, but lines are read into `buf`, to reuse it for many inputs
+pub(crate) fn read_lines_with<E: From<io::Error>>(
+    buf: &mut Vec<u8>,
+    input: impl BufRead,
+    reading: impl Into<Reading>,
+    mut f: impl FnMut(&str) -> Result<(), E>,
+) -> Result<usize, E> {
+    read_numbered_lines_with(buf, input, reading, |_, line| f(line))
+}
+
+/// same as `read_lines`, but `f` is called with the number of each line (from 1) too,
+/// counting lines skipped
+pub(crate) fn read_numbered_lines<E: From<io::Error>>(
+    input: impl BufRead,
+    reading: impl Into<Reading>,
+    f: impl FnMut(usize, &str) -> Result<(), E>,
+) -> Result<usize, E> {
+    read_numbered_lines_with(&mut Vec::new(), input, reading, f)
+}
+
+fn read_numbered_lines_with<E: From<io::Error>>(
+    buf: &mut Vec<u8>,
+    input: impl BufRead,
+    reading: impl Into<Reading>,
+    mut f: impl FnMut(usize, &str) -> Result<(), E>,
+) -> Result<usize, E> {
+    let Reading {
+        decode: policy,
+        max_line,
+        limits,
+    } = reading.into();
+    // a byte more than the limit, to tell that it is exceeded without reading further
+    let mut input = input.take(limits.bytes.map_or(u64::MAX, |n| n.saturating_add(1)));
+    // long enough to tell a line longer than either limit
+    let cap = match (max_line, limits.line) {
+        (Some((a, _)), Some(b)) => Some(a.max(b)),
+        (max_line, line) => max_line.map(|(a, _)| a).or(line),
+    };
+    // a line longer than the cap is an error, not to be read to its end
+    let stop = limits.line.is_some() || matches!(max_line, Some((_, LongLinePolicy::Error)));
+    let line_policy = match policy {
+        DecodePolicy::SkipLine => DecodePolicy::Strict,
+        policy => policy,
+    };
+    let mut skipped = 0;
+    let mut number = 0;
+    loop {
+        buf.clear();
+        number += 1;
+        let (read, dropped) = match cap {
+            // room for the line terminator
+            Some(cap) => read_line_capped(&mut input, buf, cap.saturating_add(2), stop)?,
+            None => (input.read_until(b'\n', buf)?, false),
+        };
+        if read == 0 {
+            return Ok(skipped);
+        }
+        if let (Some(n), 0) = (limits.bytes, input.limit()) {
+            return Err(io::Error::from(Exceeded {
+                limit: Limit::Bytes(n),
+                line: number,
+            })
+            .into());
+        }
+        let mut line = &buf[..];
+        if !dropped {
+            if let Some(rest) = line.strip_suffix(b"\n") {
+                line = rest.strip_suffix(b"\r").unwrap_or(rest);
+            }
+        }
+        match limits.line {
+            Some(n) if line.len() > n => {
+                return Err(io::Error::from(Exceeded {
+                    limit: Limit::Line(n),
+                    line: number,
+                })
+                .into())
+            }
+            _ => {}
+        }
+        if let Some((limit, long)) = max_line {
+            if line.len() > limit {
+                if long == LongLinePolicy::Error {
+                    let message = format!("line {} is longer than {} bytes", number, limit);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, message).into());
+                }
+                // not to split a char of UTF-8
+                let mut end = limit;
+                while end > 0 && limit - end < 3 && line[end] & 0xc0 == 0x80 {
+                    end -= 1;
+                }
+                line = &line[..end];
+            }
+        }
+        match (decode(line, line_policy), policy) {
+            (Ok(line), _) => f(number, &line)?,
+            (Err(_), DecodePolicy::SkipLine) => skipped += 1,
+            (Err(e), _) => return Err(io::Error::new(io::ErrorKind::InvalidData, e).into()),
+        }
+    }
+}
+
+/// what to do with a line longer than the limit of
+/// [`CountConfig::max_line_bytes`](../config/struct.CountConfig.html#method.max_line_bytes)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum LongLinePolicy {
+    /// fail with an error of kind `InvalidData`
+    #[default]
+    Error,
+    /// count the first bytes of the line up to the limit (at a char boundary), and skip the rest
+    Truncate,
+}
+
+/// how lines are read by `read_lines`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Reading {
+    pub(crate) decode: DecodePolicy,
+    /// the limit of bytes of a line (without the line terminator)
+    pub(crate) max_line: Option<(usize, LongLinePolicy)>,
+    /// limits of bytes of a line and of the input, failing with `Exceeded`
+    pub(crate) limits: Limits,
+}
+
+impl From<DecodePolicy> for Reading {
+    fn from(decode: DecodePolicy) -> Self {
+        Reading {
+            decode,
+            max_line: None,
+            limits: Limits::default(),
+        }
+    }
+}
+
+/// read a line into `buf` as `read_until`, but keep at most `cap` bytes of it. returns the
+/// count of bytes read, and whether bytes are dropped. with `stop`, the rest of a line longer
+/// than `cap` is left unread
+fn read_line_capped(
+    input: &mut impl BufRead,
+    buf: &mut Vec<u8>,
+    cap: usize,
+    stop: bool,
+) -> io::Result<(usize, bool)> {
+    let (mut read, mut dropped) = (0, false);
+    loop {
+        let available = match input.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let (len, done) = match available.iter().position(|&b| b == b'\n') {
+            Some(i) => (i + 1, true),
+            None => (available.len(), available.is_empty()),
+        };
+        let kept = len.min(cap.saturating_sub(buf.len()));
+        buf.extend_from_slice(&available[..kept]);
+        if stop && kept < len {
+            input.consume(kept);
+            return Ok((read + kept, true));
+        }
+        dropped |= kept < len;
+        input.consume(len);
+        read += len;
+        if done {
+            return Ok((read, dropped));
+        }
+    }
+}
+
+/// encoding of input, detected by its byte order mark
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// UTF-8, with or without a byte order mark
+    Utf8,
+    /// UTF-16 little endian, starting with `FF FE`
+    Utf16Le,
+    /// UTF-16 big endian, starting with `FE FF`
+    Utf16Be,
+}
+
+impl Encoding {
+    /// encoding of input starting with `bytes`, UTF-8 without a UTF-16 byte order mark
+    pub fn detect(bytes: &[u8]) -> Self {
+        match bytes {
+            [0xff, 0xfe, ..] => Encoding::Utf16Le,
+            [0xfe, 0xff, ..] => Encoding::Utf16Be,
+            _ => Encoding::Utf8,
+        }
+    }
+
+    fn unit(self, bytes: [u8; 2]) -> u16 {
+        match self {
+            Encoding::Utf16Be => u16::from_be_bytes(bytes),
+            _ => u16::from_le_bytes(bytes),
+        }
+    }
+}
+
+/// decode UTF-16 `bytes` (after the byte order mark) by `policy`, `None` if they are broken
+/// with [`DecodePolicy::Strict`](enum.DecodePolicy.html#variant.Strict)
+pub(crate) fn decode_utf16(
+    bytes: &[u8],
+    encoding: Encoding,
+    policy: DecodePolicy,
+) -> Option<String> {
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| encoding.unit([pair[0], pair[1]]));
+    let mut text = String::new();
+    // start of the current line in `text`, and whether it is broken (for `SkipLine`)
+    let mut line = 0;
+    let mut broken = false;
+    for c in char::decode_utf16(units) {
+        match (c, policy) {
+            (Ok('\n'), DecodePolicy::SkipLine) if broken => {
+                text.truncate(line);
+                broken = false;
+            }
+            (Ok(c), _) => {
+                text.push(c);
+                if c == '\n' {
+                    line = text.len();
+                }
+            }
+            (Err(_), DecodePolicy::Strict) => return None,
+            (Err(_), DecodePolicy::Lossy) => text.push(char::REPLACEMENT_CHARACTER),
+            (Err(_), DecodePolicy::SkipInvalid) => {}
+            (Err(_), DecodePolicy::SkipLine) => broken = true,
+        }
+    }
+    match (bytes.len() % 2 == 1, policy) {
+        (true, DecodePolicy::Strict) => None,
+        (true, DecodePolicy::Lossy) => {
+            text.push(char::REPLACEMENT_CHARACTER);
+            Some(text)
+        }
+        (odd, DecodePolicy::SkipLine) if odd || broken => {
+            text.truncate(line);
+            Some(text)
+        }
+        _ => Some(text),
+    }
+}
+
+/// reader of UTF-8 from input in UTF-8 or UTF-16 (detected by [`Encoding::detect`](enum.Encoding.html#method.detect)),
+/// which transcodes UTF-16 on the fly and passes UTF-8 through
+///
+/// counting functions reading from [`BufRead`](https://doc.rust-lang.org/std/io/trait.BufRead.html)
+/// wrap their input by this, so UTF-16 files are counted as well.
+/// broken UTF-16 (a lone surrogate, or an odd byte at the end) is transcoded to a byte `FF`,
+/// which is not UTF-8. so it is an error of kind `InvalidData` where UTF-8 is read strictly
+/// (such as by `lines`), and counting decodes such lines by
+/// [`CountConfig::invalid_input`](../config/struct.CountConfig.html#method.invalid_input) as
+/// lines of broken UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{BufRead, Cursor, Read};
+/// use bicycle_book_wordcount::decode::{Encoding, Transcoder};
+/// let bytes = [0xff, 0xfe, b'h', 0, b'i', 0, b'\n', 0, 0x42, 0x30];
+/// let mut reader = Transcoder::new(Cursor::new(bytes));
+/// let lines: Vec<String> = reader.by_ref().lines().map(Result::unwrap).collect();
+/// assert_eq!(lines, vec!["hi", "あ"]);
+/// assert_eq!(reader.encoding(), Some(Encoding::Utf16Le));
+/// ```
+pub struct Transcoder<R> {
+    input: R,
+    /// `None` until input is read
+    encoding: Option<Encoding>,
+    /// transcoded UTF-8 (or the first bytes of UTF-8, held to detect the encoding) and the
+    /// position read so far
+    buf: Vec<u8>,
+    pos: usize,
+    /// the first byte of a code unit split between reads
+    odd: Option<u8>,
+    /// a high surrogate waiting for the low one
+    high: Option<u16>,
+}
+
+impl<R: BufRead> Transcoder<R> {
+    pub fn new(input: R) -> Self {
+        Transcoder {
+            input,
+            encoding: None,
+            buf: Vec::new(),
+            pos: 0,
+            odd: None,
+            high: None,
+        }
+    }
+
+    /// encoding of input, `None` until something is read
+    pub fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    fn detect(&mut self) -> io::Result<Encoding> {
+        if let Some(encoding) = self.encoding {
+            return Ok(encoding);
+        }
+        // a byte order mark may be split between reads of a pipe, so the first bytes are held
+        // in `buf` until there are 2 of them
+        let mut head = Vec::new();
+        let encoding = loop {
+            let available = self.input.fill_buf()?;
+            if head.is_empty() && available.len() >= 2 {
+                let encoding = Encoding::detect(available);
+                if encoding != Encoding::Utf8 {
+                    self.input.consume(2);
+                }
+                break encoding;
+            }
+            match available.first() {
+                Some(&byte) if head.len() < 2 => {
+                    head.push(byte);
+                    self.input.consume(1);
+                }
+                _ => break Encoding::detect(&head),
+            }
+        };
+        if encoding == Encoding::Utf8 {
+            self.buf = head;
+            self.pos = 0;
+        }
+        self.encoding = Some(encoding);
+        Ok(encoding)
+    }
+
+    /// transcode the next chunk of UTF-16 into `buf`, leaving it empty at the end of input
+    fn transcode(&mut self, encoding: Encoding) -> io::Result<()> {
+        self.buf.clear();
+        self.pos = 0;
+        while self.buf.is_empty() {
+            let chunk = self.input.fill_buf()?;
+            if chunk.is_empty() {
+                if self.odd.take().is_some() {
+                    self.buf.push(BROKEN);
+                }
+                if self.high.take().is_some() {
+                    self.buf.push(BROKEN);
+                }
+                return Ok(());
+            }
+            let len = chunk.len();
+            let mut bytes = chunk.iter().cloned();
+            while let Some(byte) = bytes.next() {
+                let pair = match self.odd.take() {
+                    Some(first) => [first, byte],
+                    None => match bytes.next() {
+                        Some(second) => [byte, second],
+                        None => {
+                            self.odd = Some(byte);
+                            break;
+                        }
+                    },
+                };
+                let unit = encoding.unit(pair);
+                let c = match (self.high.take(), unit) {
+                    (Some(high), 0xdc00..=0xdfff) => {
+                        let c = 0x10000 + ((high as u32 - 0xd800) << 10) + (unit as u32 - 0xdc00);
+                        char::from_u32(c)
+                    }
+                    (high, _) => {
+                        if high.is_some() {
+                            // not followed by a low surrogate
+                            self.buf.push(BROKEN);
+                        }
+                        if let 0xd800..=0xdbff = unit {
+                            self.high = Some(unit);
+                            continue;
+                        }
+                        char::from_u32(unit as u32)
+                    }
+                };
+                match c {
+                    Some(c) => {
+                        let mut utf8 = [0; 4];
+                        self.buf
+                            .extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+                    }
+                    // a lone low surrogate
+                    None => self.buf.push(BROKEN),
+                }
+            }
+            self.input.consume(len);
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for Transcoder<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let n = self.fill_buf()?.read(out)?;
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Transcoder<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self.detect()? {
+            Encoding::Utf8 if self.pos < self.buf.len() => Ok(&self.buf[self.pos..]),
+            Encoding::Utf8 => self.input.fill_buf(),
+            encoding => {
+                if self.pos == self.buf.len() {
+                    self.transcode(encoding)?;
+                }
+                Ok(&self.buf[self.pos..])
+            }
+        }
+    }
+
+    fn consume(&mut self, n: usize) {
+        match self.encoding {
+            Some(Encoding::Utf16Le) | Some(Encoding::Utf16Be) => self.pos += n,
+            // the bytes held while detecting the encoding
+            _ if self.pos < self.buf.len() => self.pos += n,
+            _ => self.input.consume(n),
+        }
+    }
+}
+
+/// a byte which is not UTF-8, in place of broken UTF-16
+const BROKEN: u8 = 0xff;
+
+fn skip_invalid_lines(bytes: &[u8]) -> Cow<'_, str> {
+    if let Ok(text) = str::from_utf8(bytes) {
+        return Cow::Borrowed(text);
+    }
+    bytes
+        .split_inclusive(|&b| b == b'\n')
+        .filter_map(|line| str::from_utf8(line).ok())
+        .collect::<String>()
+        .into()
+}
+
+fn skip_invalid(mut bytes: &[u8]) -> Cow<'_, str> {
+    let mut text = String::new();
+    loop {
+        match str::from_utf8(bytes) {
+            Ok(valid) if text.is_empty() => return Cow::Borrowed(valid),
+            Ok(valid) => {
+                text.push_str(valid);
+                return Cow::Owned(text);
+            }
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                // valid_up_to guarantees this is UTF-8
+                text.push_str(str::from_utf8(valid).unwrap());
+                bytes = &rest[e.error_len().unwrap_or(rest.len())..];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_lines_works() {
+        let input: &[u8] = b"aa\r\nb\xffb\ncc";
+        let read = |policy| {
+            let mut lines = Vec::new();
+            read_lines(input, policy, |line| -> io::Result<()> {
+                lines.push(line.to_string());
+                Ok(())
+            })
+            .map(|skipped| (lines, skipped))
+        };
+        assert_eq!(
+            read(DecodePolicy::SkipLine).unwrap(),
+            (vec!["aa".into(), "cc".into()], 1)
+        );
+        assert_eq!(read(DecodePolicy::Lossy).unwrap().0[1], "b\u{fffd}b");
+        assert_eq!(read(DecodePolicy::SkipInvalid).unwrap().0[1], "bb");
+        let error = read(DecodePolicy::Strict).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn long_lines_are_limited() {
+        let input = "abc\r\nあいう\nabcd\r\n".as_bytes();
+        let read = |policy| {
+            let reading = Reading {
+                decode: DecodePolicy::Strict,
+                max_line: Some((4, policy)),
+                ..Reading::default()
+            };
+            let mut lines = Vec::new();
+            let input = io::BufReader::with_capacity(2, input);
+            read_lines(input, reading, |line| -> io::Result<()> {
+                lines.push(line.to_string());
+                Ok(())
+            })
+            .map(|_| lines)
+        };
+        assert_eq!(
+            read(LongLinePolicy::Truncate).unwrap(),
+            vec!["abc", "あ", "abcd"]
+        );
+        let error = read(LongLinePolicy::Error).unwrap_err();
+        assert_eq!(error.to_string(), "line 2 is longer than 4 bytes");
+        // a line without end fails without reading it to the end
+        let endless = io::BufReader::new(io::repeat(b'a'));
+        let reading = Reading {
+            max_line: Some((4, LongLinePolicy::Error)),
+            ..Reading::default()
+        };
+        assert!(read_lines(endless, reading, |_| -> io::Result<()> { Ok(()) }).is_err());
+    }
+
+    #[test]
+    fn skip_invalid_works() {
+        assert!(matches!(skip_invalid(b"abc"), Cow::Borrowed("abc")));
+        assert_eq!(skip_invalid(b"\xffa\xe3\x81b\xe3\x81\x82\xf0"), "abあ");
+        assert_eq!(skip_invalid(b""), "");
+    }
+
+    fn utf16(text: &str, encoding: Encoding) -> Vec<u8> {
+        let mut bytes = match encoding {
+            Encoding::Utf16Be => vec![0xfe, 0xff],
+            _ => vec![0xff, 0xfe],
+        };
+        for unit in text.encode_utf16() {
+            match encoding {
+                Encoding::Utf16Be => bytes.extend_from_slice(&unit.to_be_bytes()),
+                _ => bytes.extend_from_slice(&unit.to_le_bytes()),
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn transcoder_works() {
+        let text = "aa 🦀\nあい\n";
+        for &encoding in &[Encoding::Utf16Le, Encoding::Utf16Be] {
+            let bytes = utf16(text, encoding);
+            // split code units and surrogate pairs between reads
+            let reader = io::BufReader::with_capacity(3, &bytes[..]);
+            let mut decoded = String::new();
+            Transcoder::new(reader)
+                .read_to_string(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, text);
+            assert_eq!(
+                decode_utf16(&bytes[2..], encoding, DecodePolicy::Strict).unwrap(),
+                text
+            );
+        }
+
+        let mut plain = String::new();
+        Transcoder::new(text.as_bytes())
+            .read_to_string(&mut plain)
+            .unwrap();
+        assert_eq!(plain, text);
+    }
+
+    #[test]
+    fn byte_order_marks_are_detected_across_reads() {
+        let text = "aa\nb";
+        for &encoding in &[Encoding::Utf16Le, Encoding::Utf16Be] {
+            let bytes = utf16(text, encoding);
+            let mut transcoder = Transcoder::new(io::BufReader::with_capacity(1, &bytes[..]));
+            let mut decoded = String::new();
+            transcoder.read_to_string(&mut decoded).unwrap();
+            assert_eq!(
+                (decoded.as_str(), transcoder.encoding()),
+                (text, Some(encoding))
+            );
+        }
+        for text in &["", "a", "ab", "aa\nb"] {
+            let mut transcoder = Transcoder::new(io::BufReader::with_capacity(1, text.as_bytes()));
+            let mut decoded = String::new();
+            transcoder.read_to_string(&mut decoded).unwrap();
+            assert_eq!(
+                (decoded.as_str(), transcoder.encoding()),
+                (*text, Some(Encoding::Utf8))
+            );
+        }
+    }
+
+    #[test]
+    fn broken_utf16() {
+        let lone_surrogate = [0xff, 0xfe, 0x3d, 0xd8, b'a', 0];
+        let mut text = String::new();
+        let result = Transcoder::new(&lone_surrogate[..]).read_to_string(&mut text);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let odd = [0xff, 0xfe, b'a', 0, b'b'];
+        assert!(Transcoder::new(&odd[..]).read_to_string(&mut text).is_err());
+
+        // broken lines are decoded by the policy, like broken UTF-8
+        let mut bytes = utf16("ok\n", Encoding::Utf16Le);
+        bytes.extend_from_slice(&[0x3d, 0xd8, b'a', 0, b'\n', 0, 0x00, 0xdc, b'\n', 0]);
+        bytes.extend_from_slice(&[b'b', 0, 0x3d, 0xd8]);
+        let read = |policy| {
+            let mut lines = Vec::new();
+            read_lines(
+                Transcoder::new(&bytes[..]),
+                policy,
+                |line| -> io::Result<()> {
+                    lines.push(line.to_string());
+                    Ok(())
+                },
+            )
+            .map(|skipped| (lines, skipped))
+        };
+        let lossy = read(DecodePolicy::Lossy).unwrap().0;
+        assert_eq!(lossy, vec!["ok", "\u{fffd}a", "\u{fffd}", "b\u{fffd}"]);
+        assert_eq!(
+            read(DecodePolicy::SkipInvalid).unwrap().0[1..],
+            ["a", "", "b"]
+        );
+        assert_eq!(
+            read(DecodePolicy::SkipLine).unwrap(),
+            (vec!["ok".into()], 3)
+        );
+        let error = read(DecodePolicy::Strict).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let lossy = decode_utf16(&lone_surrogate[2..], Encoding::Utf16Le, DecodePolicy::Lossy);
+        assert_eq!(lossy.unwrap(), "\u{fffd}a");
+        let skip = decode_utf16(&odd[2..], Encoding::Utf16Le, DecodePolicy::SkipInvalid);
+        assert_eq!(skip.unwrap(), "a");
+        assert_eq!(
+            decode_utf16(&odd[2..], Encoding::Utf16Le, DecodePolicy::Strict),
+            None
+        );
+    }
+}
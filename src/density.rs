@@ -0,0 +1,143 @@
+//! where in the input a word is concentrated, see [`keyword_density`](fn.keyword_density.html)
+
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+use crate::decode::{read_lines, Transcoder};
+use crate::tally::Scanner;
+use crate::{CountConfig, CountError};
+
+/// window of [`keyword_density`](fn.keyword_density.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Window {
+    /// sliding window of N tokens, moved by a token
+    Tokens(usize),
+    /// consecutive blocks of K lines
+    Lines(usize),
+}
+
+/// series of densities (occurrences of `target` / tokens) over windows of the input
+///
+/// with `Window::Tokens(n)`, the i-th value is the density in tokens `i..i + n`, so there are
+/// `tokens - n + 1` values (one value over all tokens, if there are fewer tokens than `n`).
+/// with `Window::Lines(k)`, the i-th value is the density in lines `i * k..(i + 1) * k`,
+/// and it is 0 if the lines have no token.
+/// `target` is normalized as tokens are, and input is read as by
+/// [`try_count_with`](../fn.try_count_with.html).
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{density::{keyword_density, Window}, CountConfig};
+/// let config = CountConfig::default();
+/// let series = keyword_density(Cursor::new("a x a b b b"), &config, "a", Window::Tokens(3));
+/// assert_eq!(series.unwrap(), vec![2.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0, 0.0]);
+///
+/// let series = keyword_density(Cursor::new("a a\nb a\n\nb"), &config, "a", Window::Lines(2));
+/// assert_eq!(series.unwrap(), vec![0.75, 0.0]);
+/// ```
+///
+/// # Panics
+///
+/// the window size is 0
+pub fn keyword_density(
+    input: impl BufRead,
+    config: &CountConfig,
+    target: &str,
+    window: Window,
+) -> Result<Vec<f64>, CountError> {
+    let input = Transcoder::new(input);
+    let target = config.normalize(target);
+    let mut scanner = Scanner::new(config);
+    let mut series = Vec::new();
+
+    match window {
+        Window::Tokens(n) => {
+            assert!(n > 0, "window size must be positive");
+            let mut window = VecDeque::with_capacity(n);
+            let mut hits = 0;
+            read_lines(input, config.reading(), |line| -> Result<(), CountError> {
+                scanner.line(line, |key, _| {
+                    let hit = key == target;
+                    if window.len() == n && window.pop_front() == Some(true) {
+                        hits -= 1;
+                    }
+                    window.push_back(hit);
+                    if hit {
+                        hits += 1;
+                    }
+                    if window.len() == n {
+                        series.push(hits as f64 / n as f64);
+                    }
+                });
+                scanner.check_time()
+            })?;
+            if series.is_empty() && !window.is_empty() {
+                series.push(hits as f64 / window.len() as f64);
+            }
+        }
+        Window::Lines(k) => {
+            assert!(k > 0, "window size must be positive");
+            let (mut hits, mut tokens) = (0, 0);
+            let mut lines = 0;
+            read_lines(input, config.reading(), |line| -> Result<(), CountError> {
+                scanner.line(line, |key, _| {
+                    tokens += 1;
+                    if key == target {
+                        hits += 1;
+                    }
+                });
+                lines += 1;
+                if lines == k {
+                    series.push(ratio(hits, tokens));
+                    hits = 0;
+                    tokens = 0;
+                    lines = 0;
+                }
+                scanner.check_time()
+            })?;
+            if lines > 0 {
+                series.push(ratio(hits, tokens));
+            }
+        }
+    }
+    Ok(series)
+}
+
+fn ratio(hits: usize, tokens: usize) -> f64 {
+    if tokens == 0 {
+        0.0
+    } else {
+        hits as f64 / tokens as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CountOption;
+    use std::io::Cursor;
+
+    #[test]
+    fn short_input_makes_one_window() {
+        let config = CountConfig::new(CountOption::Word).ignore_case(true);
+        let series = keyword_density(
+            Cursor::new("Error ok"),
+            &config,
+            "ERROR",
+            Window::Tokens(10),
+        );
+        assert_eq!(series.unwrap(), vec![0.5]);
+        let empty = keyword_density(Cursor::new(""), &config, "x", Window::Tokens(2));
+        assert!(empty.unwrap().is_empty());
+    }
+
+    #[test]
+    fn lines_window_works() {
+        let config = CountConfig::new(CountOption::Word);
+        let input = Cursor::new("x\ny\nx x\nz\nx");
+        let series = keyword_density(input, &config, "x", Window::Lines(2)).unwrap();
+        assert_eq!(series, vec![0.5, 2.0 / 3.0, 1.0]);
+    }
+}
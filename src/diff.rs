@@ -0,0 +1,284 @@
+//! comparison of two results, see [`Diff`](struct.Diff.html) and
+//! [`Comparison`](struct.Comparison.html)
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::Frequencies;
+
+/// a difference of a token between two results
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Change {
+    /// only in the new result
+    Added { token: String, count: usize },
+    /// only in the old result
+    Removed { token: String, count: usize },
+    /// in both results with different counts
+    Changed {
+        token: String,
+        from: usize,
+        to: usize,
+    },
+}
+
+impl Change {
+    /// the token changed
+    pub fn token(&self) -> &str {
+        match self {
+            Change::Added { token, .. }
+            | Change::Removed { token, .. }
+            | Change::Changed { token, .. } => token,
+        }
+    }
+}
+
+/// formatted like unified diff: `+word 12`, `-word 3`, `~word 5→9`
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added { token, count } => write!(f, "+{} {}", token, count),
+            Change::Removed { token, count } => write!(f, "-{} {}", token, count),
+            Change::Changed { token, from, to } => write!(f, "~{} {}→{}", token, from, to),
+        }
+    }
+}
+
+/// changes from an old result to a new result, sorted by token
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count, diff::Diff, CountOption};
+/// let old = count(Cursor::new("aa bb bb cc"), CountOption::Word);
+/// let new = count(Cursor::new("bb dd aa"), CountOption::Word);
+/// let diff = Diff::between(&old, &new);
+/// assert_eq!(diff.to_string(), "~bb 2→1\n-cc 1\n+dd 1\n");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Diff {
+    changes: Vec<Change>,
+}
+
+impl Diff {
+    /// changes from `old` to `new`, tokens with the same count are not included
+    pub fn between(old: &Frequencies, new: &Frequencies) -> Diff {
+        let mut changes = Vec::new();
+        for (token, &from) in old.iter() {
+            match new.get(token) {
+                None => changes.push(Change::Removed {
+                    token: token.clone(),
+                    count: from,
+                }),
+                Some(&to) if to != from => changes.push(Change::Changed {
+                    token: token.clone(),
+                    from,
+                    to,
+                }),
+                Some(_) => {}
+            }
+        }
+        for (token, &count) in new.iter() {
+            if !old.contains_key(token) {
+                changes.push(Change::Added {
+                    token: token.clone(),
+                    count,
+                });
+            }
+        }
+        changes.sort_by(|a, b| a.token().cmp(b.token()));
+        Diff { changes }
+    }
+
+    /// all changes
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// no token is changed
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// occurrences added: counts of added tokens and increases of changed ones
+    pub fn added(&self) -> usize {
+        self.changes
+            .iter()
+            .map(|change| match *change {
+                Change::Added { count, .. } => count,
+                Change::Changed { from, to, .. } => to.saturating_sub(from),
+                Change::Removed { .. } => 0,
+            })
+            .fold(0, usize::saturating_add)
+    }
+
+    /// occurrences removed: counts of removed tokens and decreases of changed ones
+    pub fn removed(&self) -> usize {
+        self.changes
+            .iter()
+            .map(|change| match *change {
+                Change::Removed { count, .. } => count,
+                Change::Changed { from, to, .. } => from.saturating_sub(to),
+                Change::Added { .. } => 0,
+            })
+            .fold(0, usize::saturating_add)
+    }
+}
+
+/// a change per line
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// a token in both results of a [`Comparison`](struct.Comparison.html)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shared {
+    pub token: String,
+    pub a: usize,
+    pub b: usize,
+    /// difference of the shares of the token in either result (0.0 to 1.0)
+    pub divergence: f64,
+}
+
+/// tokens exclusive to each of two results, and shared ones with both counts
+///
+/// exclusive tokens are sorted by count (descending), shared ones by divergence (descending),
+/// ties by token.
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::diff::Comparison;
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let a = count_str("aa aa aa bb cc", CountOption::Word);
+/// let b = count_str("aa bb bb dd", CountOption::Word);
+/// let comparison = Comparison::of(&a, &b);
+/// assert_eq!(comparison.only_a, vec![("cc".to_string(), 1)]);
+/// assert_eq!(comparison.only_b, vec![("dd".to_string(), 1)]);
+/// let shared: Vec<_> = comparison.shared.iter().map(|s| (s.token.as_str(), s.a, s.b)).collect();
+/// // aa: 0.6 → 0.25, bb: 0.2 → 0.5
+/// assert_eq!(shared, vec![("aa", 3, 1), ("bb", 1, 2)]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Comparison {
+    pub only_a: Vec<(String, usize)>,
+    pub only_b: Vec<(String, usize)>,
+    pub shared: Vec<Shared>,
+}
+
+impl Comparison {
+    pub fn of(a: &Frequencies, b: &Frequencies) -> Comparison {
+        let (total_a, total_b) = (a.total() as f64, b.total() as f64);
+        let mut comparison = Comparison::default();
+        for (token, &n) in a.iter() {
+            match b.get(token) {
+                Some(&m) => comparison.shared.push(Shared {
+                    token: token.clone(),
+                    a: n,
+                    b: m,
+                    divergence: (n as f64 / total_a - m as f64 / total_b).abs(),
+                }),
+                None => comparison.only_a.push((token.clone(), n)),
+            }
+        }
+        comparison.only_b = b.missing_from(a).into_iter().collect();
+        for only in &mut [&mut comparison.only_a, &mut comparison.only_b] {
+            only.sort_by(|(s, n), (t, m)| m.cmp(n).then_with(|| s.cmp(t)));
+        }
+        comparison.shared.sort_by(|s, t| {
+            t.divergence
+                .partial_cmp(&s.divergence)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| s.token.cmp(&t.token))
+        });
+        comparison
+    }
+
+    /// same comparison but with up to `n` tokens of each kind
+    pub fn truncated(mut self, n: usize) -> Comparison {
+        self.only_a.truncate(n);
+        self.only_b.truncate(n);
+        self.shared.truncate(n);
+        self
+    }
+}
+
+/// sections of exclusive tokens (`count token`) and shared tokens (`a b token`)
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, only) in &[("only in a:", &self.only_a), ("only in b:", &self.only_b)] {
+            writeln!(f, "{}", name)?;
+            for (token, n) in only.iter() {
+                writeln!(f, "  {}\t{}", n, token)?;
+            }
+        }
+        writeln!(f, "shared:")?;
+        for shared in &self.shared {
+            writeln!(f, "  {}\t{}\t{}", shared.a, shared.b, shared.token)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn freqs(pairs: &[(&str, usize)]) -> Frequencies {
+        pairs
+            .iter()
+            .map(|&(t, n)| (t.to_string(), n))
+            .collect::<HashMap<_, _>>()
+            .into()
+    }
+
+    #[test]
+    fn between_works() {
+        let old = freqs(&[("a", 1), ("b", 2), ("c", 3)]);
+        let new = freqs(&[("b", 5), ("c", 3), ("d", 1)]);
+        let diff = Diff::between(&old, &new);
+
+        assert_eq!(
+            diff.changes(),
+            &[
+                Change::Removed {
+                    token: "a".to_string(),
+                    count: 1
+                },
+                Change::Changed {
+                    token: "b".to_string(),
+                    from: 2,
+                    to: 5
+                },
+                Change::Added {
+                    token: "d".to_string(),
+                    count: 1
+                },
+            ]
+        );
+        assert!(Diff::between(&old, &old).is_empty());
+        assert_eq!((diff.added(), diff.removed()), (4, 1));
+        assert_eq!(Diff::between(&new, &old).removed(), 4);
+    }
+
+    #[test]
+    fn comparison_works() {
+        let a = freqs(&[("a", 1), ("b", 1), ("c", 2)]);
+        let b = freqs(&[("b", 1), ("c", 1), ("d", 2), ("e", 2)]);
+        let comparison = Comparison::of(&a, &b);
+        assert_eq!(
+            comparison.only_b,
+            vec![("d".to_string(), 2), ("e".to_string(), 2)]
+        );
+        assert_eq!(
+            comparison.truncated(1).to_string(),
+            "only in a:\n  1\ta\nonly in b:\n  2\td\nshared:\n  2\t1\tc\n"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! detection of lines appearing in more than one file, such as copy-pasted boilerplate,
+//! see [`Duplicates`](struct.Duplicates.html)
+
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+use crate::decode::{read_lines, strip_bom, DecodePolicy, Transcoder};
+use crate::intern::{Id, Interner};
+
+/// a place of a line
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// name of the file given to [`add`](struct.Duplicates.html#method.add)
+    pub file: String,
+    /// line number, starting at 1
+    pub line: usize,
+}
+
+/// a line found in more than one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+    /// the first occurrence of the line, as written
+    pub text: String,
+    /// every occurrence, in the order added
+    pub locations: Vec<Location>,
+    /// count of distinct files in `locations`
+    pub files: usize,
+}
+
+/// occurrences of a line
+#[derive(Debug, Clone)]
+struct Occurrences {
+    text: String,
+    /// interned file names and line numbers
+    locations: Vec<(Id, usize)>,
+    files: usize,
+}
+
+/// fingerprints of lines of files added one by one
+///
+/// a line is remembered by its text (with whitespace normalized if configured), the text of
+/// its first occurrence and its locations, of file names stored once. lines with fewer than
+/// [`min_chars`](#method.min_chars) chars other than whitespace are ignored
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::duplicate::Duplicates;
+/// let mut duplicates = Duplicates::new().normalize_whitespace(true);
+/// duplicates.add("a.rs", Cursor::new("// Copyright 2024 Example\nfn a() {}\n")).unwrap();
+/// duplicates.add("b.rs", Cursor::new("fn b() {}\n//  Copyright 2024 Example\n")).unwrap();
+/// let found = duplicates.across_files();
+/// assert_eq!(found.len(), 1);
+/// assert_eq!(found[0].text, "// Copyright 2024 Example");
+/// assert_eq!(found[0].locations[1].file, "b.rs");
+/// assert_eq!(found[0].locations[1].line, 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Duplicates {
+    normalize_whitespace: bool,
+    min_chars: usize,
+    lines: HashMap<String, Occurrences>,
+    /// names of files added
+    names: Interner,
+    /// count of files added
+    added: usize,
+}
+
+impl Default for Duplicates {
+    fn default() -> Self {
+        Duplicates::new()
+    }
+}
+
+impl Duplicates {
+    /// no lines yet, whitespace is not normalized and lines of 1 char are ignored
+    pub fn new() -> Self {
+        Duplicates {
+            normalize_whitespace: false,
+            min_chars: 2,
+            lines: HashMap::new(),
+            names: Interner::new(),
+            added: 0,
+        }
+    }
+
+    /// compare lines with leading and trailing whitespace removed and other runs of whitespace
+    /// as one space
+    pub fn normalize_whitespace(mut self, yes: bool) -> Self {
+        self.normalize_whitespace = yes;
+        self
+    }
+
+    /// ignore lines with fewer chars other than whitespace, such as `}`
+    pub fn min_chars(mut self, n: usize) -> Self {
+        self.min_chars = n;
+        self
+    }
+
+    /// the text compared for a line
+    fn key<'a>(&self, line: &'a str) -> std::borrow::Cow<'a, str> {
+        if self.normalize_whitespace {
+            crate::normalize::collapse_whitespace(line)
+        } else {
+            line.into()
+        }
+    }
+
+    /// add lines of a file named `file`, decoded from UTF-16 if it starts with its BOM. broken
+    /// UTF-8 is replaced by U+FFFD
+    pub fn add(&mut self, file: &str, input: impl BufRead) -> io::Result<()> {
+        self.added += 1;
+        let file = self.names.intern(file);
+        let mut number = 0;
+        read_lines(Transcoder::new(input), DecodePolicy::Lossy, |line| {
+            number += 1;
+            let line = if number == 1 { strip_bom(line) } else { line };
+            let visible = line.chars().filter(|c| !c.is_whitespace()).count();
+            if visible == 0 || visible < self.min_chars {
+                return Ok(());
+            }
+            let key = self.key(line).into_owned();
+            let occurrences = self.lines.entry(key).or_insert_with(|| Occurrences {
+                text: line.to_string(),
+                locations: Vec::new(),
+                files: 0,
+            });
+            if occurrences
+                .locations
+                .last()
+                .map_or(true, |&(f, _)| f != file)
+            {
+                occurrences.files += 1;
+            }
+            occurrences.locations.push((file, number));
+            Ok::<_, io::Error>(())
+        })?;
+        Ok(())
+    }
+
+    /// lines found in more than one file, in more files first, then in order of text
+    pub fn across_files(&self) -> Vec<Duplicate> {
+        let mut found: Vec<_> = self
+            .lines
+            .values()
+            .filter(|o| o.files > 1)
+            .map(|o| Duplicate {
+                text: o.text.clone(),
+                locations: o
+                    .locations
+                    .iter()
+                    .map(|&(file, line)| Location {
+                        file: self.names.resolve(file).to_string(),
+                        line,
+                    })
+                    .collect(),
+                files: o.files,
+            })
+            .collect();
+        found.sort_by(|a, b| b.files.cmp(&a.files).then_with(|| a.text.cmp(&b.text)));
+        found
+    }
+
+    /// count of files added
+    pub fn files(&self) -> usize {
+        self.added
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn lines_of_one_file_are_not_duplicates() {
+        let mut duplicates = Duplicates::new();
+        duplicates
+            .add("a", Cursor::new("same line\nsame line\n}\n"))
+            .unwrap();
+        duplicates.add("b", Cursor::new("}\nother\n")).unwrap();
+        assert_eq!(duplicates.across_files(), vec![]);
+        duplicates.add("c", Cursor::new("x\nsame line\n")).unwrap();
+        let found = duplicates.across_files();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].files, 2);
+        let lines: Vec<_> = found[0].locations.iter().map(|l| l.line).collect();
+        assert_eq!(lines, vec![1, 2, 2]);
+        assert_eq!(duplicates.files(), 3);
+    }
+
+    #[test]
+    fn whitespace_is_normalized_if_configured() {
+        let add = |duplicates: &mut Duplicates| {
+            duplicates
+                .add("a", Cursor::new("\u{feff}let x = 1;\n"))
+                .unwrap();
+            duplicates
+                .add("b", Cursor::new("  let  x = 1;\r\n"))
+                .unwrap();
+        };
+        let mut exact = Duplicates::new();
+        add(&mut exact);
+        assert!(exact.across_files().is_empty());
+        let mut normalized = Duplicates::new().normalize_whitespace(true).min_chars(1);
+        add(&mut normalized);
+        assert_eq!(normalized.across_files()[0].text, "let x = 1;");
+    }
+
+    #[test]
+    fn utf16_is_decoded() {
+        let mut utf16 = vec![0xff, 0xfe];
+        utf16.extend("same line\n".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        let mut duplicates = Duplicates::new();
+        duplicates.add("a", &utf16[..]).unwrap();
+        duplicates.add("b", Cursor::new("same line\n")).unwrap();
+        let found = duplicates.across_files();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].locations[0].file, "a");
+    }
+}
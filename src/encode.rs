@@ -0,0 +1,233 @@
+//! writing of text in an encoding other than UTF-8, see [`Encoder`](struct.Encoder.html)
+
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// encoding of output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OutputEncoding {
+    #[default]
+    Utf8,
+    /// UTF-8 starting with a byte order mark, which Excel needs to read UTF-8 CSV
+    Utf8Bom,
+    /// UTF-16 little endian starting with a byte order mark, as Windows writes
+    Utf16Le,
+    /// Shift_JIS as extended by Windows (Windows-31J), available with the feature
+    /// `detect-encoding`
+    #[cfg(feature = "detect-encoding")]
+    ShiftJis,
+}
+
+impl OutputEncoding {
+    /// name of the encoding, which is parsed by `from_str`
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputEncoding::Utf8 => "utf-8",
+            OutputEncoding::Utf8Bom => "utf-8-bom",
+            OutputEncoding::Utf16Le => "utf-16le",
+            #[cfg(feature = "detect-encoding")]
+            OutputEncoding::ShiftJis => "shift_jis",
+        }
+    }
+}
+
+impl fmt::Display for OutputEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// the name of an encoding (case-insensitive), `sjis` and `cp932` are also accepted
+impl FromStr for OutputEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let encoding = match s.to_lowercase().as_str() {
+            "utf-8" | "utf8" => OutputEncoding::Utf8,
+            "utf-8-bom" => OutputEncoding::Utf8Bom,
+            "utf-16le" => OutputEncoding::Utf16Le,
+            #[cfg(feature = "detect-encoding")]
+            "shift_jis" | "sjis" | "cp932" => OutputEncoding::ShiftJis,
+            #[cfg(not(feature = "detect-encoding"))]
+            "shift_jis" | "sjis" | "cp932" => {
+                return Err("Shift_JIS needs the feature detect-encoding".to_string())
+            }
+            _ => return Err(format!("unknown encoding {:?}", s)),
+        };
+        Ok(encoding)
+    }
+}
+
+/// writer of UTF-8 written to it in another encoding
+///
+/// a byte order mark of the encoding is written before the first text. chars which the
+/// encoding can not represent are written as `?`, and broken UTF-8 as U+FFFD. calls to
+/// `write` may split chars; bytes of a char left incomplete at the end are dropped
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use bicycle_book_wordcount::encode::{Encoder, OutputEncoding};
+/// let mut encoder = Encoder::new(Vec::new(), OutputEncoding::Utf16Le);
+/// encoder.write_all("a€".as_bytes()).unwrap();
+/// assert_eq!(encoder.into_inner().unwrap(), b"\xff\xfea\x00\xac\x20");
+/// ```
+pub struct Encoder<W: Write> {
+    out: W,
+    encoding: OutputEncoding,
+    /// bytes of a char split by `write`
+    pending: Vec<u8>,
+    started: bool,
+    #[cfg(feature = "detect-encoding")]
+    sjis: Option<crate::sjis::Encoder>,
+}
+
+impl<W: Write> Encoder<W> {
+    pub fn new(out: W, encoding: OutputEncoding) -> Self {
+        Encoder {
+            out,
+            encoding,
+            pending: Vec::new(),
+            started: false,
+            #[cfg(feature = "detect-encoding")]
+            sjis: match encoding {
+                OutputEncoding::ShiftJis => Some(crate::sjis::Encoder::new()),
+                _ => None,
+            },
+        }
+    }
+
+    /// the underlying writer, after flushing it
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush()?;
+        Ok(self.out)
+    }
+
+    /// bytes of `text` in the encoding
+    fn encode(&self, text: &str, bytes: &mut Vec<u8>) {
+        match self.encoding {
+            OutputEncoding::Utf8 | OutputEncoding::Utf8Bom => {
+                bytes.extend_from_slice(text.as_bytes())
+            }
+            OutputEncoding::Utf16Le => {
+                for unit in text.encode_utf16() {
+                    bytes.extend_from_slice(&unit.to_le_bytes());
+                }
+            }
+            #[cfg(feature = "detect-encoding")]
+            OutputEncoding::ShiftJis => {
+                let sjis = self.sjis.as_ref().unwrap();
+                for c in text.chars() {
+                    if !sjis.encode(c, bytes) {
+                        bytes.push(b'?');
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut bytes = Vec::with_capacity(buf.len() * 2);
+        if !self.started && !buf.is_empty() {
+            self.started = true;
+            match self.encoding {
+                OutputEncoding::Utf8Bom => bytes.extend_from_slice(b"\xef\xbb\xbf"),
+                OutputEncoding::Utf16Le => bytes.extend_from_slice(b"\xff\xfe"),
+                _ => {}
+            }
+        }
+        self.pending.extend_from_slice(buf);
+        let mut rest = &self.pending[..];
+        let mut text = String::new();
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    rest = &[];
+                    break;
+                }
+                Err(e) => {
+                    let (valid, after) = rest.split_at(e.valid_up_to());
+                    text.push_str(std::str::from_utf8(valid).unwrap());
+                    match e.error_len() {
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            rest = &after[len..];
+                        }
+                        None => {
+                            rest = after;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        self.pending = rest.to_vec();
+        self.encode(&text, &mut bytes);
+        self.out.write_all(&bytes)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn encoded(encoding: OutputEncoding, chunks: &[&[u8]]) -> Vec<u8> {
+        let mut encoder = Encoder::new(Vec::new(), encoding);
+        for chunk in chunks {
+            encoder.write_all(chunk).unwrap();
+        }
+        encoder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn split_chars_are_joined() {
+        let bytes = "日本".as_bytes();
+        let chunks = [&bytes[..2], &bytes[2..4], &bytes[4..]];
+        assert_eq!(
+            encoded(OutputEncoding::Utf16Le, &chunks),
+            b"\xff\xfe\xe5\x65\x2c\x67"
+        );
+        assert_eq!(
+            encoded(OutputEncoding::Utf8Bom, &chunks),
+            b"\xef\xbb\xbf\xe6\x97\xa5\xe6\x9c\xac"
+        );
+        assert_eq!(encoded(OutputEncoding::Utf16Le, &[]), b"");
+        assert_eq!(
+            encoded(OutputEncoding::Utf16Le, &[b"a\xffb"]),
+            b"\xff\xfea\x00\xfd\xffb\x00"
+        );
+    }
+
+    #[cfg(feature = "detect-encoding")]
+    #[test]
+    fn shift_jis_works() {
+        assert_eq!(
+            encoded(
+                OutputEncoding::ShiftJis,
+                &["{\"日本\":1, \"é\":2}".as_bytes()]
+            ),
+            b"{\"\x93\xfa\x96\x7b\":1, \"?\":2}"
+        );
+    }
+
+    #[test]
+    fn names_are_parsed() {
+        assert_eq!("UTF-16LE".parse(), Ok(OutputEncoding::Utf16Le));
+        assert_eq!("utf-8-bom".parse(), Ok(OutputEncoding::Utf8Bom));
+        assert!("latin-1".parse::<OutputEncoding>().is_err());
+        assert_eq!(
+            OutputEncoding::Utf8Bom.name().parse(),
+            Ok(OutputEncoding::Utf8Bom)
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! counting of each chapter of EPUB books, see [`count_epub`](fn.count_epub.html)
+//!
+//! available with the feature `epub`.
+
+use regex::Regex;
+use std::io::{self, Cursor, Read};
+use std::sync::OnceLock;
+
+use crate::zip::Archive;
+use crate::{try_count_with, CountConfig, CountError, Frequencies};
+
+/// a chapter of a book, a document of the spine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    /// the first heading of the document, or its title, or `path` if it has neither
+    pub title: String,
+    /// path of the document in the archive
+    pub path: String,
+    pub freqs: Frequencies,
+}
+
+/// counts of each chapter and of the whole book
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Book {
+    /// chapters in reading order
+    pub chapters: Vec<Chapter>,
+    /// sum of all chapters
+    pub total: Frequencies,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("epub: {}", message))
+}
+
+/// most bytes of the documents read from a book, in total
+const MAX_TEXT: usize = 256 * 1024 * 1024;
+
+/// value of `name` of the attributes of a tag
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    static ATTRIBUTE: OnceLock<Regex> = OnceLock::new();
+    let re = ATTRIBUTE
+        .get_or_init(|| Regex::new(r#"(?:^|\s)([^\s=]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+    let captures = re.captures_iter(attributes).find(|c| &c[1] == name)?;
+    captures
+        .get(2)
+        .or_else(|| captures.get(3))
+        .map(|m| m.as_str())
+}
+
+/// `href` relative to the directory of `base`, with `%XX` decoded and `#fragment` removed
+fn resolve(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or("");
+    let mut bytes = Vec::with_capacity(href.len());
+    let mut rest = href.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        let hex = tail.get(..2).and_then(|h| std::str::from_utf8(h).ok());
+        match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+            Some(decoded) if b == b'%' => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(b);
+                rest = tail;
+            }
+        }
+    }
+    let href = String::from_utf8_lossy(&bytes);
+    let mut parts: Vec<&str> = base.split('/').collect();
+    parts.pop();
+    for part in href.split('/') {
+        match part {
+            "." | "" => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// visible text of an XHTML document: elements of `head`, `script` and `style` are removed,
+/// other tags are removed and break lines at blocks, and entities are decoded
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::epub::visible_text;
+/// let xhtml = "<html><head><title>T</title></head>\
+///              <body><h1>One</h1><p>Tom &amp; Jerry<br/>ran</p></body></html>";
+/// assert_eq!(visible_text(xhtml), "One\nTom & Jerry\nran\n");
+/// ```
+pub fn visible_text(xhtml: &str) -> String {
+    let mut text = String::with_capacity(xhtml.len() / 2);
+    let mut rest = xhtml;
+    let mut hidden: Option<String> = None;
+    while let Some(i) = rest.find('<') {
+        if hidden.is_none() {
+            push_decoded(&mut text, &rest[..i]);
+        }
+        rest = &rest[i..];
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map(|end| end + 3)
+        } else {
+            rest.find('>').map(|end| end + 1)
+        };
+        let end = end.unwrap_or(rest.len());
+        let tag = rest.get(1..end.saturating_sub(1)).unwrap_or("");
+        rest = &rest[end..];
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == ':')
+            .collect::<String>()
+            .to_lowercase();
+        match &hidden {
+            Some(h) if closing && *h == name => hidden = None,
+            Some(_) => {}
+            None if !closing
+                && !tag.ends_with('/')
+                && ["head", "script", "style"].contains(&name.as_str()) =>
+            {
+                hidden = Some(name)
+            }
+            None if is_block(&name) && !text.is_empty() && !text.ends_with('\n') => text.push('\n'),
+            None => {}
+        }
+    }
+    if hidden.is_none() {
+        push_decoded(&mut text, rest);
+    }
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+/// elements which start or end a line of text
+fn is_block(name: &str) -> bool {
+    matches!(
+        name,
+        "p" | "div"
+            | "br"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "li"
+            | "tr"
+            | "blockquote"
+            | "section"
+            | "pre"
+            | "dt"
+            | "dd"
+            | "hr"
+            | "body"
+    )
+}
+
+/// `text` with whitespace collapsed and entities decoded
+fn push_decoded(out: &mut String, text: &str) {
+    let mut rest = text;
+    let mut space = out.is_empty() || out.ends_with('\n');
+    while !rest.is_empty() {
+        let c = rest.chars().next().unwrap();
+        let (decoded, len) = match c {
+            '&' => match rest
+                .find(';')
+                .and_then(|end| entity(&rest[1..end]).map(|c| (c, end + 1)))
+            {
+                Some((c, len)) => (c, len),
+                None => ('&', 1),
+            },
+            c => (c, c.len_utf8()),
+        };
+        rest = &rest[len..];
+        if c != '&' && decoded.is_whitespace() {
+            if !space {
+                out.push(' ');
+                space = true;
+            }
+        } else {
+            out.push(decoded);
+            space = false;
+        }
+    }
+}
+
+/// char of a character reference or of a predefined entity
+fn entity(name: &str) -> Option<char> {
+    if let Some(code) = name.strip_prefix('#') {
+        let value = match code.strip_prefix('x').or_else(|| code.strip_prefix('X')) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => code.parse().ok()?,
+        };
+        return std::char::from_u32(value);
+    }
+    let c = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// text of the first element `name` of `xhtml`
+fn first_text(xhtml: &str, name: &str) -> Option<String> {
+    let re = Regex::new(&format!(r"(?is)<{0}(?:\s[^>]*)?>(.*?)</{0}\s*>", name)).unwrap();
+    let inner = re.captures(xhtml)?.get(1)?.as_str();
+    let text = visible_text(inner).trim().replace('\n', " ");
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// count each document of the spine of an EPUB book, in reading order
+///
+/// the archive is read into memory. documents are found by `META-INF/container.xml` and the
+/// package document it points to, and their [`visible_text`](fn.visible_text.html) is counted.
+/// a book of more than 256 MiB of documents (uncompressed) is an error
+pub fn count_epub(mut input: impl Read, config: &CountConfig) -> Result<Book, CountError> {
+    let mut bytes = Vec::new();
+    input.read_to_end(&mut bytes)?;
+    let archive = Archive::new(bytes)?;
+    let mut left = MAX_TEXT;
+    let mut text = |path: &str| -> io::Result<String> {
+        let bytes = archive.read(path, left)?;
+        left -= bytes.len();
+        String::from_utf8(bytes).map_err(|_| invalid(format!("{} is not UTF-8", path)))
+    };
+
+    let container = text("META-INF/container.xml")?;
+    let rootfile = Regex::new(r"<(?:\w+:)?rootfile\b([^>]*)>").unwrap();
+    let package_path = rootfile
+        .captures(&container)
+        .and_then(|c| attribute(c.get(1)?.as_str(), "full-path"))
+        .ok_or_else(|| invalid("no rootfile in container.xml".to_string()))?
+        .to_string();
+    let package = text(&package_path)?;
+
+    let tags = Regex::new(r"<(?:\w+:)?(item|itemref)\b([^>]*)>").unwrap();
+    let mut items = Vec::new();
+    let mut spine = Vec::new();
+    for c in tags.captures_iter(&package) {
+        let attributes = &c[2];
+        if &c[1] == "item" {
+            if let (Some(id), Some(href)) =
+                (attribute(attributes, "id"), attribute(attributes, "href"))
+            {
+                items.push((id.to_string(), resolve(&package_path, href)));
+            }
+        } else if let Some(idref) = attribute(attributes, "idref") {
+            spine.push(idref.to_string());
+        }
+    }
+
+    let mut book = Book::default();
+    for idref in spine {
+        let path = match items.iter().find(|(id, _)| *id == idref) {
+            Some((_, path)) => path.clone(),
+            None => return Err(invalid(format!("no item {} in the manifest", idref)).into()),
+        };
+        let xhtml = text(&path)?;
+        let title = first_text(&xhtml, "h[1-6]")
+            .or_else(|| first_text(&xhtml, "title"))
+            .unwrap_or_else(|| path.clone());
+        let freqs = try_count_with(Cursor::new(visible_text(&xhtml)), config)?;
+        book.total.merge(&freqs);
+        book.chapters.push(Chapter { title, path, freqs });
+    }
+    Ok(book)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::zip::test::archive;
+
+    const CONTAINER: &str = r#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#;
+
+    const PACKAGE: &str = r#"<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <manifest>
+    <item id="c2" href="text/chapter%202.xhtml" media-type="application/xhtml+xml"/>
+    <item href="text/one.xhtml" id='c1' media-type="application/xhtml+xml"/>
+    <item id="css" href="style.css" media-type="text/css"/>
+  </manifest>
+  <spine><itemref idref="c1"/><itemref idref="c2"/></spine>
+</package>"#;
+
+    #[test]
+    fn chapters_are_counted_in_order() {
+        let one = "<html><head><title>Book</title><style>p { x: y }</style></head>\
+                   <body><h1>Chapter <i>One</i></h1><p>the cat</p><p>the dog</p></body></html>";
+        let two = "<html><head><title>Second</title></head><body><p>the end</p></body></html>";
+        let bytes = archive(&[
+            ("mimetype", "application/epub+zip"),
+            ("META-INF/container.xml", CONTAINER),
+            ("OEBPS/content.opf", PACKAGE),
+            ("OEBPS/text/one.xhtml", one),
+            ("OEBPS/text/chapter 2.xhtml", two),
+        ]);
+        let book = count_epub(&bytes[..], &CountConfig::default()).unwrap();
+        let titles: Vec<_> = book.chapters.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, vec!["Chapter One", "Second"]);
+        assert_eq!(book.chapters[1].path, "OEBPS/text/chapter 2.xhtml");
+        assert_eq!(book.chapters[0].freqs["the"], 2);
+        assert_eq!(book.chapters[0].freqs.get("Book"), None);
+        assert_eq!(book.total["the"], 3);
+        assert_eq!(book.total.get("x"), None);
+    }
+
+    #[test]
+    fn broken_books_are_errors() {
+        let bytes = archive(&[("META-INF/container.xml", CONTAINER)]);
+        assert!(count_epub(&bytes[..], &CountConfig::default()).is_err());
+        assert!(count_epub(&b"PK"[..], &CountConfig::default()).is_err());
+    }
+
+    #[test]
+    fn visible_text_works() {
+        assert_eq!(
+            visible_text("<p>a<!-- <p>hidden</p> -->b &#65;&#x42; &bogus;</p>\n<script>x</script>"),
+            "ab AB &bogus;\n"
+        );
+        assert_eq!(
+            resolve("OEBPS/content.opf", "../images/a%20b.png#top"),
+            "images/a b.png"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! errors of counting
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+/// error of [`try_count_with`](../fn.try_count_with.html), and of configuring it by
+/// [`CountConfig::user_pattern`](../config/struct.CountConfig.html#method.user_pattern)
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CountError {
+    /// failed to read input, an input which is not UTF-8 has kind `InvalidData`
+    Io(io::Error),
+    /// count of the token exceeds `usize::MAX` with [`OverflowPolicy::Error`](../config/enum.OverflowPolicy.html#variant.Error)
+    Overflow(String),
+    /// matching the pattern in the line (counted from 1) of input took longer than the limit of
+    /// [`CountConfig::time_limit`](../config/struct.CountConfig.html#method.time_limit)
+    TimeLimit { line: usize, limit: Duration },
+    /// counting exceeded `limit` of [`CountConfig::limits`](../config/struct.CountConfig.html#method.limits)
+    /// in the line (counted from 1) of input
+    Limit { limit: Limit, line: usize },
+    /// the pattern of `user_pattern` failed to compile
+    Pattern(regex::Error),
+    /// the pattern of `user_pattern` has no capture group `group`, but only `groups` groups
+    /// (including the whole match)
+    NoGroup { group: usize, groups: usize },
+}
+
+/// a limit of [`Limits`](../config/struct.Limits.html) with its value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// distinct keys
+    Keys(usize),
+    /// estimated bytes of counts
+    Memory(usize),
+    /// bytes of a line
+    Line(usize),
+    /// bytes of input
+    Bytes(u64),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Limit::Keys(n) => write!(f, "more than {} distinct keys", n),
+            Limit::Memory(n) => write!(f, "counts of more than an estimated {} bytes", n),
+            Limit::Line(n) => write!(f, "a line longer than {} bytes", n),
+            Limit::Bytes(n) => write!(f, "input longer than {} bytes", n),
+        }
+    }
+}
+
+/// a limit exceeded while reading, carried by an `io::Error` of kind `InvalidData` out of
+/// reading lines, and unwrapped into `CountError::Limit`
+#[derive(Debug)]
+pub(crate) struct Exceeded {
+    pub(crate) limit: Limit,
+    pub(crate) line: usize,
+}
+
+impl fmt::Display for Exceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}", self.limit, self.line)
+    }
+}
+
+impl Error for Exceeded {}
+
+impl From<Exceeded> for io::Error {
+    fn from(e: Exceeded) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+impl fmt::Display for CountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CountError::Io(e) => write!(f, "failed to read input: {}", e),
+            CountError::Overflow(token) => write!(f, "count of {:?} overflowed", token),
+            CountError::TimeLimit { line, limit } => write!(
+                f,
+                "matching the pattern in line {} took longer than {:?}",
+                line, limit
+            ),
+            CountError::Limit { limit, line } => write!(f, "{} at line {}", limit, line),
+            CountError::Pattern(e) => write!(f, "invalid pattern: {}", e),
+            CountError::NoGroup { group, groups } => {
+                write!(f, "pattern has no group {}, only {} groups", group, groups)
+            }
+        }
+    }
+}
+
+impl Error for CountError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CountError::Io(e) => Some(e),
+            CountError::Pattern(e) => Some(e),
+            CountError::Overflow(_)
+            | CountError::TimeLimit { .. }
+            | CountError::Limit { .. }
+            | CountError::NoGroup { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for CountError {
+    fn from(e: io::Error) -> Self {
+        if !e.get_ref().is_some_and(|inner| inner.is::<Exceeded>()) {
+            return CountError::Io(e);
+        }
+        match e.into_inner().map(|inner| inner.downcast::<Exceeded>()) {
+            Some(Ok(exceeded)) => CountError::Limit {
+                limit: exceeded.limit,
+                line: exceeded.line,
+            },
+            _ => unreachable!("checked to be Exceeded"),
+        }
+    }
+}
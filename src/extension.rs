@@ -0,0 +1,175 @@
+//! totals of counted files per file extension, to see where the words of a repository live,
+//! see [`Extensions`](struct.Extensions.html)
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+use crate::{try_count_with, walk, CountConfig, CountError, Frequencies};
+
+/// totals of the files of an extension
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtensionTotals {
+    /// count of files
+    pub files: usize,
+    /// counts of all the files
+    pub freqs: Frequencies,
+}
+
+/// extension (lowercase, without the dot, empty for files without one) => totals of files
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::extension::Extensions;
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let mut extensions = Extensions::new();
+/// extensions.add("src/main.rs", &count_str("fn main", CountOption::Word));
+/// extensions.add("src/lib.RS", &count_str("pub fn", CountOption::Word));
+/// extensions.add("README.md", &count_str("usage", CountOption::Word));
+/// extensions.add("LICENSE", &count_str("MIT or Apache", CountOption::Word));
+/// let rs = extensions.get("rs").unwrap();
+/// assert_eq!((rs.files, rs.freqs.total(), rs.freqs["fn"]), (2, 4, 2));
+/// assert_eq!(extensions.get("").unwrap().files, 1);
+/// assert_eq!(
+///     extensions.to_string(),
+///     ".rs     2 files  4 tokens  3 unique
+/// (none)  1 file   3 tokens  3 unique
+/// .md     1 file   1 token   1 unique
+/// "
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Extensions {
+    map: HashMap<String, ExtensionTotals>,
+}
+
+impl Extensions {
+    /// no files
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// add counts of the file of `path` to the totals of its extension
+    pub fn add(&mut self, path: impl AsRef<Path>, freqs: &Frequencies) {
+        let extension = path
+            .as_ref()
+            .extension()
+            .map_or_else(String::new, |e| e.to_string_lossy().to_lowercase());
+        let totals = self.map.entry(extension).or_default();
+        totals.files += 1;
+        totals.freqs.merge(freqs);
+    }
+
+    /// totals of `extension` (lowercase, without the dot)
+    pub fn get(&self, extension: &str) -> Option<&ExtensionTotals> {
+        self.map.get(extension)
+    }
+
+    /// count of distinct extensions
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// extensions and their totals, the most tokens first (ties by extension)
+    pub fn sorted(&self) -> Vec<(&str, &ExtensionTotals)> {
+        let mut sorted: Vec<_> = self
+            .map
+            .iter()
+            .map(|(extension, totals)| (extension.as_str(), totals))
+            .collect();
+        sorted.sort_by_key(|&(extension, totals)| (Reverse(totals.freqs.total()), extension));
+        sorted
+    }
+}
+
+/// a line per extension in the order of [`sorted`](#method.sorted), with counts of files,
+/// tokens and unique tokens, aligned
+impl fmt::Display for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plural = |n: usize, what: &str| match n {
+            1 => format!("1 {}", what),
+            n => format!("{} {}s", n, what),
+        };
+        let rows: Vec<_> = self
+            .sorted()
+            .into_iter()
+            .map(|(extension, totals)| {
+                [
+                    match extension {
+                        "" => "(none)".to_string(),
+                        extension => format!(".{}", extension),
+                    },
+                    plural(totals.files, "file"),
+                    plural(totals.freqs.total(), "token"),
+                    format!("{} unique", totals.freqs.len()),
+                ]
+            })
+            .collect();
+        let width = |i: usize| rows.iter().map(|row| row[i].chars().count()).max();
+        let widths = [width(0), width(1), width(2)];
+        for row in &rows {
+            for (cell, width) in row.iter().zip(&widths) {
+                write!(f, "{:<w$}  ", cell, w = width.unwrap_or(0))?;
+            }
+            writeln!(f, "{}", row[3])?;
+        }
+        Ok(())
+    }
+}
+
+/// counts of the text files under `root` as configured, in total and per extension, in a
+/// traversal by [`walk::text_files`](../walk/fn.text_files.html)
+pub fn count_by_extension(
+    root: impl AsRef<Path>,
+    config: &CountConfig,
+) -> Result<(Frequencies, Extensions), CountError> {
+    let mut total = Frequencies::new();
+    let mut extensions = Extensions::new();
+    for file in walk::text_files(root)?.files {
+        let input = BufReader::new(walk::open(&file)?);
+        let freqs = try_count_with(input, config).map_err(|e| match e {
+            CountError::Io(e) => CountError::Io(io::Error::new(
+                e.kind(),
+                format!("{}: {}", walk::display(&file), e),
+            )),
+            e => e,
+        })?;
+        extensions.add(&file, &freqs);
+        total.merge(&freqs);
+    }
+    Ok((total, extensions))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn count_by_extension_works() -> Result<(), CountError> {
+        let root = std::env::temp_dir().join("wordcount_extension_count_by_extension");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src"))?;
+        fs::write(root.join("src").join("a.rs"), "fn a fn")?;
+        fs::write(root.join("b.txt"), "notes")?;
+        fs::write(root.join("c.bin"), b"\x00\x01")?;
+
+        let counted = count_by_extension(&root, &CountConfig::default());
+        fs::remove_dir_all(&root)?;
+        let (total, extensions) = counted?;
+        assert_eq!(total.total(), 4);
+        assert_eq!(extensions.len(), 2);
+        assert_eq!(extensions.get("rs").unwrap().freqs["fn"], 2);
+        assert_eq!(extensions.get("txt").unwrap().files, 1);
+        assert_eq!(extensions.get("bin"), None);
+        assert_eq!(Extensions::new().to_string(), "");
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+//! bytes taken by each token across the input (count × encoded length), to find which terms
+//! dominate storage of e.g. log archives, see [`Footprint`](struct.Footprint.html)
+
+use std::fmt;
+
+use crate::Frequencies;
+
+/// bytes of a token in all of its occurrences
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenBytes<'a> {
+    pub token: &'a str,
+    pub count: usize,
+    /// count × encoded length of the token
+    pub bytes: u128,
+    /// fraction (0.0 to 1.0) of the bytes of all tokens
+    pub share: f64,
+}
+
+/// bytes of tokens, the most bytes first (ties by token)
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::footprint::Footprint;
+/// use bicycle_book_wordcount::{count, CountOption};
+/// let freqs = count(Cursor::new("GET GET GET /index.html 200 200"), CountOption::Word);
+/// let footprint = Footprint::of(&freqs);
+/// assert_eq!(footprint.total(), 24);
+/// let top = &footprint.tokens()[0];
+/// assert_eq!((top.token, top.count, top.bytes, top.share), ("GET", 3, 9, 0.375));
+/// assert_eq!(footprint.get("200").unwrap().bytes, 6);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Footprint<'a> {
+    tokens: Vec<TokenBytes<'a>>,
+    total: u128,
+}
+
+impl<'a> Footprint<'a> {
+    /// bytes of tokens in UTF-8
+    pub fn of(freqs: &'a Frequencies) -> Self {
+        Footprint::with_len(freqs, str::len)
+    }
+
+    /// bytes of tokens, each of which is encoded in `len` bytes, e.g. in UTF-16 by
+    /// `|token| token.encode_utf16().count() * 2`
+    pub fn with_len(freqs: &'a Frequencies, len: impl Fn(&str) -> usize) -> Self {
+        let mut tokens: Vec<_> = freqs
+            .iter()
+            .map(|(token, &count)| TokenBytes {
+                token,
+                count,
+                bytes: count as u128 * len(token) as u128,
+                share: 0.0,
+            })
+            .collect();
+        let total = tokens.iter().map(|token| token.bytes).sum();
+        for token in &mut tokens {
+            token.share = match total {
+                0 => 0.0,
+                total => token.bytes as f64 / total as f64,
+            };
+        }
+        tokens.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.token.cmp(b.token)));
+        Footprint { tokens, total }
+    }
+
+    /// bytes of all tokens
+    pub fn total(&self) -> u128 {
+        self.total
+    }
+
+    /// tokens, the most bytes first
+    pub fn tokens(&self) -> &[TokenBytes<'a>] {
+        &self.tokens
+    }
+
+    /// the `n` tokens taking the most bytes
+    pub fn top(&self, n: usize) -> &[TokenBytes<'a>] {
+        &self.tokens[..n.min(self.tokens.len())]
+    }
+
+    /// bytes of `token`, `None` if it is not counted
+    pub fn get(&self, token: &str) -> Option<&TokenBytes<'a>> {
+        self.tokens.iter().find(|bytes| bytes.token == token)
+    }
+}
+
+/// a line per token with bytes, share and count, and the total at the end
+impl fmt::Display for Footprint<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for token in &self.tokens {
+            writeln!(
+                f,
+                "{}\t{:.2}%\t{}\t{}",
+                token.bytes,
+                token.share * 100.0,
+                token.count,
+                token.token
+            )?;
+        }
+        writeln!(f, "{}\t100.00%\t\t(total)", self.total)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bytes_are_by_encoded_length() {
+        let freqs: Frequencies = vec![("日本".to_string(), 2), ("ab".to_string(), 3)]
+            .into_iter()
+            .collect();
+        let utf8 = Footprint::of(&freqs);
+        assert_eq!(utf8.total(), 12 + 6);
+        assert_eq!(utf8.top(1)[0].token, "日本");
+        assert_eq!(utf8.top(5).len(), 2);
+        let utf16 = Footprint::with_len(&freqs, |token| token.encode_utf16().count() * 2);
+        assert_eq!(utf16.get("日本").unwrap().bytes, 8);
+        assert_eq!(utf16.tokens()[0].token, "ab");
+        assert_eq!(
+            utf16.to_string(),
+            "12\t60.00%\t3\tab\n8\t40.00%\t2\t日本\n20\t100.00%\t\t(total)\n"
+        );
+        let empty = Frequencies::new();
+        assert_eq!(Footprint::of(&empty).to_string(), "0\t100.00%\t\t(total)\n");
+    }
+}
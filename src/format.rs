@@ -0,0 +1,407 @@
+//! output formats behind a trait, so that the library and the command line write all of them
+//! the same way, and new ones plug in by name, see [`OutputFormat`](trait.OutputFormat.html)
+//! and [`Formats`](struct.Formats.html)
+
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::output::{
+    write_csv, write_html_with, write_json, write_json_lines_with, write_latex_with,
+    write_markdown_with, write_snapshot, Bulk, Columns,
+};
+use crate::trie::Trie;
+use crate::Frequencies;
+
+/// a format of counts
+///
+/// # Examples
+///
+/// tab separated values as a format of its own:
+///
+/// ```
+/// use std::io::{self, Cursor, Write};
+/// use bicycle_book_wordcount::format::{Formats, OutputFormat};
+/// use bicycle_book_wordcount::{count, CountOption, Frequencies};
+///
+/// struct Tsv;
+///
+/// impl OutputFormat for Tsv {
+///     fn name(&self) -> &str {
+///         "tsv"
+///     }
+///
+///     fn write(&self, freqs: &Frequencies, out: &mut dyn Write) -> io::Result<()> {
+///         for (token, count) in freqs.sorted() {
+///             writeln!(out, "{}\t{}", token, count)?;
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let mut formats = Formats::builtin();
+/// formats.register(Tsv);
+/// let freqs = count(Cursor::new("b a b"), CountOption::Word);
+/// let mut out = Vec::new();
+/// formats.get("tsv").unwrap().write_top(&freqs, 1, &mut out).unwrap();
+/// assert_eq!(out, b"b\t2\n");
+/// ```
+pub trait OutputFormat: Send + Sync {
+    /// name of the format, of `--format` of the command line
+    fn name(&self) -> &str;
+
+    /// write all tokens of `freqs`
+    fn write(&self, freqs: &Frequencies, out: &mut dyn Write) -> io::Result<()>;
+
+    /// write the `n` most frequent tokens of `freqs`. by default they are written by
+    /// [`write`](#tymethod.write) as if they were all; tables override it to show shares of all
+    /// tokens
+    fn write_top(&self, freqs: &Frequencies, n: usize, out: &mut dyn Write) -> io::Result<()> {
+        if n >= freqs.len() {
+            return self.write(freqs, out);
+        }
+        let top: Frequencies = freqs
+            .top(n)
+            .into_iter()
+            .map(|(token, count)| (token.clone(), count))
+            .collect();
+        self.write(&top, out)
+    }
+
+    /// the derived columns of the format, `None` if it has none (by default)
+    fn columns(&self) -> Option<Columns> {
+        None
+    }
+
+    /// the same format with `columns`, `None` if it has no columns (by default)
+    fn with_columns(&self, columns: Columns) -> Option<Box<dyn OutputFormat>> {
+        let _ = columns;
+        None
+    }
+}
+
+/// a JSON object from tokens to counts, see [`write_json`](../output/fn.write_json.html)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Json;
+
+impl OutputFormat for Json {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn write(&self, freqs: &Frequencies, out: &mut dyn Write) -> io::Result<()> {
+        write_json(freqs, out)
+    }
+}
+
+/// a JSON object per token with `columns`, see
+/// [`write_json_lines_with`](../output/fn.write_json_lines_with.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonLines(pub Columns);
+
+/// without columns
+impl Default for JsonLines {
+    fn default() -> Self {
+        JsonLines(Columns::none())
+    }
+}
+
+impl OutputFormat for JsonLines {
+    fn name(&self) -> &str {
+        "jsonl"
+    }
+
+    fn write(&self, freqs: &Frequencies, out: &mut dyn Write) -> io::Result<()> {
+        write_json_lines_with(freqs, freqs.len(), self.0, out)
+    }
+
+    fn write_top(&self, freqs: &Frequencies, n: usize, out: &mut dyn Write) -> io::Result<()> {
+        write_json_lines_with(freqs, n, self.0, out)
+    }
+
+    fn columns(&self) -> Option<Columns> {
+        Some(self.0)
+    }
+
+    fn with_columns(&self, columns: Columns) -> Option<Box<dyn OutputFormat>> {
+        Some(Box::new(JsonLines(columns)))
+    }
+}
+
+/// the `Debug` representation of the counts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DebugText;
+
+impl OutputFormat for DebugText {
+    fn name(&self) -> &str {
+        "debug"
+    }
+
+    fn write(&self, freqs: &Frequencies, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{:?}", freqs.debug_sorted())
+    }
+}
+
+/// rows of `token,count`, see [`write_csv`](../output/fn.write_csv.html)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Csv;
+
+impl OutputFormat for Csv {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    fn write(&self, freqs: &Frequencies, out: &mut dyn Write) -> io::Result<()> {
+        write_csv(freqs, out)
+    }
+}
+
+/// a booktabs table with `columns`, see [`write_latex_with`](../output/fn.write_latex_with.html)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Latex(pub Columns);
+
+impl OutputFormat for Latex {
+    fn name(&self) -> &str {
+        "latex"
+    }
+
+    fn write(&self, freqs: &Frequencies, out: &mut dyn Write) -> io::Result<()> {
+        self.write_top(freqs, freqs.len(), out)
+    }
+
+    fn write_top(&self, freqs: &Frequencies, n: usize, out: &mut dyn Write) -> io::Result<()> {
+        write_latex_with(freqs, n, self.0, out)
+    }
+
+    fn columns(&self) -> Option<Columns> {
+        Some(self.0)
+    }
+
+    fn with_columns(&self, columns: Columns) -> Option<Box<dyn OutputFormat>> {
+        Some(Box::new(Latex(columns)))
+    }
+}
+
+/// a table of GitHub Flavored Markdown with `columns`, see
+/// [`write_markdown_with`](../output/fn.write_markdown_with.html)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Markdown(pub Columns);
+
+impl OutputFormat for Markdown {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+
+    fn write(&self, freqs: &Frequencies, out: &mut dyn Write) -> io::Result<()> {
+        self.write_top(freqs, freqs.len(), out)
+    }
+
+    fn write_top(&self, freqs: &Frequencies, n: usize, out: &mut dyn Write) -> io::Result<()> {
+        write_markdown_with(freqs, n, self.0, out)
+    }
+
+    fn columns(&self) -> Option<Columns> {
+        Some(self.0)
+    }
+
+    fn with_columns(&self, columns: Columns) -> Option<Box<dyn OutputFormat>> {
+        Some(Box::new(Markdown(columns)))
+    }
+}
+
+/// an HTML report with `columns`, see [`write_html_with`](../output/fn.write_html_with.html)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Html(pub Columns);
+
+impl OutputFormat for Html {
+    fn name(&self) -> &str {
+        "html"
+    }
+
+    fn write(&self, freqs: &Frequencies, out: &mut dyn Write) -> io::Result<()> {
+        self.write_top(freqs, freqs.len(), out)
+    }
+
+    fn write_top(&self, freqs: &Frequencies, n: usize, out: &mut dyn Write) -> io::Result<()> {
+        write_html_with(freqs, n, self.0, out)
+    }
+
+    fn columns(&self) -> Option<Columns> {
+        Some(self.0)
+    }
+
+    fn with_columns(&self, columns: Columns) -> Option<Box<dyn OutputFormat>> {
+        Some(Box::new(Html(columns)))
+    }
+}
+
+/// a prefix tree of the tokens as nested JSON, see
+/// [`Trie::write_json`](../trie/struct.Trie.html#method.write_json)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrefixTree;
+
+impl OutputFormat for PrefixTree {
+    fn name(&self) -> &str {
+        "trie"
+    }
+
+    fn write(&self, freqs: &Frequencies, out: &mut dyn Write) -> io::Result<()> {
+        Trie::of(freqs).write_json(out)
+    }
+}
+
+/// canonical text for snapshot tests, see [`write_snapshot`](../output/fn.write_snapshot.html)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Snapshot;
+
+impl OutputFormat for Snapshot {
+    fn name(&self) -> &str {
+        "snapshot"
+    }
+
+    fn write(&self, freqs: &Frequencies, out: &mut dyn Write) -> io::Result<()> {
+        write_snapshot(freqs, out)
+    }
+}
+
+/// Elasticsearch bulk requests, see [`Bulk`](../output/struct.Bulk.html)
+impl OutputFormat for Bulk {
+    fn name(&self) -> &str {
+        "bulk"
+    }
+
+    fn write(&self, freqs: &Frequencies, out: &mut dyn Write) -> io::Result<()> {
+        Bulk::write(self, freqs, out)
+    }
+}
+
+/// columns of Parquet, see [`write_parquet`](../parquet/fn.write_parquet.html). the output is
+/// binary
+#[cfg(feature = "parquet")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Parquet;
+
+#[cfg(feature = "parquet")]
+impl OutputFormat for Parquet {
+    fn name(&self) -> &str {
+        "parquet"
+    }
+
+    fn write(&self, freqs: &Frequencies, out: &mut dyn Write) -> io::Result<()> {
+        crate::parquet::write_parquet(freqs, out)
+    }
+}
+
+/// formats by name
+#[derive(Default)]
+pub struct Formats {
+    formats: Vec<Box<dyn OutputFormat>>,
+}
+
+impl Formats {
+    /// no formats
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// the formats of this crate with default columns: json, jsonl, debug, csv, latex,
+    /// markdown, html, trie, snapshot, bulk and parquet (with the feature `parquet`)
+    pub fn builtin() -> Self {
+        let mut formats = Formats::new();
+        formats.register(Json);
+        formats.register(JsonLines::default());
+        formats.register(DebugText);
+        formats.register(Csv);
+        formats.register(Latex::default());
+        formats.register(Markdown::default());
+        formats.register(Html::default());
+        formats.register(PrefixTree);
+        formats.register(Snapshot);
+        formats.register(Bulk::default());
+        #[cfg(feature = "parquet")]
+        formats.register(Parquet);
+        formats
+    }
+
+    /// add `format`, in place of a format of the same name
+    pub fn register(&mut self, format: impl OutputFormat + 'static) {
+        let format = Box::new(format);
+        match self.formats.iter_mut().find(|f| f.name() == format.name()) {
+            Some(registered) => *registered = format,
+            None => self.formats.push(format),
+        }
+    }
+
+    /// the format named `name`
+    pub fn get(&self, name: &str) -> Option<&dyn OutputFormat> {
+        self.formats
+            .iter()
+            .find(|format| format.name() == name)
+            .map(|format| &**format)
+    }
+
+    /// names of the formats, in order of registration
+    pub fn names(&self) -> Vec<&str> {
+        self.formats.iter().map(|format| format.name()).collect()
+    }
+}
+
+/// names of the formats
+impl fmt::Debug for Formats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn written(format: &dyn OutputFormat, freqs: &Frequencies, n: usize) -> String {
+        let mut out = Vec::new();
+        format.write_top(freqs, n, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn formats_are_registered_by_name() {
+        let mut formats = Formats::builtin();
+        assert_eq!(formats.names()[..4], ["json", "jsonl", "debug", "csv"]);
+        assert!(formats.get("xml").is_none());
+
+        let freqs: Frequencies = vec![("b".to_string(), 3), ("a".to_string(), 1)]
+            .into_iter()
+            .collect();
+        let json = formats.get("json").unwrap();
+        assert_eq!(written(json, &freqs, 1), "{\"b\":3}\n");
+        assert_eq!(written(json, &freqs, 5), "{\"b\":3,\"a\":1}\n");
+        // shares of tables are of all tokens
+        let markdown = formats.get("markdown").unwrap();
+        assert!(written(markdown, &freqs, 1).ends_with("| `b` | 3 | 75.00 |\n"));
+
+        let len = formats.names().len();
+        let snapshot = formats.get("snapshot").unwrap();
+        assert_eq!(
+            written(snapshot, &freqs, 5),
+            "wordcount-snapshot 1\ntotal 4\ndistinct 2\na\t1\nb\t3\n"
+        );
+        formats.register(JsonLines(Columns::default()));
+        assert_eq!(formats.names().len(), len);
+        let jsonl = formats.get("jsonl").unwrap();
+        assert_eq!(
+            written(jsonl, &freqs, 1),
+            "{\"token\":\"b\",\"count\":3,\"percent\":75.00}\n"
+        );
+        assert_eq!(jsonl.columns(), Some(Columns::default()));
+        let bare = jsonl.with_columns(Columns::none()).unwrap();
+        assert_eq!(
+            written(&*bare, &freqs, 1),
+            "{\"token\":\"b\",\"count\":3}\n"
+        );
+        assert!(formats
+            .get("csv")
+            .unwrap()
+            .with_columns(Columns::none())
+            .is_none());
+        assert!(Formats::new().names().is_empty());
+    }
+}
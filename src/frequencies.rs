@@ -0,0 +1,1086 @@
+//! result of counting, see [`Frequencies`](struct.Frequencies.html)
+
+use regex::Regex;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{hash_map, HashMap};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::hash::Hash;
+use std::io::{self, BufReader, BufWriter};
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{self, AtomicUsize};
+
+use crate::binary;
+use crate::collate::Collator;
+use crate::metadata::Metadata;
+use crate::rng::Rng;
+
+/// token => count of occurrences
+///
+/// it derefs to `HashMap<K, usize>`, so map methods and `freqs["token"]` are available.
+/// keys are `String` by default, other key types are used by e.g. [`count_chars`](../fn.count_chars.html).
+///
+/// # Ordering
+///
+/// iteration of the map is in arbitrary order, which changes between runs. but everything this
+/// crate prints or serializes ([`debug_sorted`](#method.debug_sorted),
+/// [`output`](../output/index.html), [`binary`](../binary/index.html), [`diff`](../diff/index.html))
+/// is ordered deterministically across runs and platforms: by count descending, then by key
+/// ascending (code point order for `String`), as [`sorted`](#method.sorted). keys are unique,
+/// so there are no further ties.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count, CountOption};
+/// let freqs = count(Cursor::new("pre1 pre2 post pre1"), CountOption::Word);
+/// let pre = freqs.starting_with("pre");
+/// assert_eq!(pre.len(), 2);
+/// assert_eq!(pre.total(), 3);
+/// ```
+#[derive(Clone)]
+pub struct Frequencies<K = String> {
+    map: HashMap<K, usize>,
+}
+
+impl<K: Eq + Hash> Frequencies<K> {
+    /// empty result
+    pub fn new() -> Self {
+        Frequencies {
+            map: HashMap::new(),
+        }
+    }
+
+    /// empty result with room for `capacity` distinct tokens without reallocating
+    pub fn with_capacity(capacity: usize) -> Self {
+        Frequencies {
+            map: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// release room reserved but not used, e.g. after counting with a capacity hint too large
+    /// or after removing many tokens
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::Frequencies;
+    /// let mut freqs: Frequencies = Frequencies::with_capacity(1000);
+    /// freqs.insert("a".to_string(), 1);
+    /// freqs.shrink_to_fit();
+    /// assert!(freqs.capacity() < 1000);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.map.shrink_to_fit()
+    }
+
+    /// sum of all counts, at most `usize::MAX` as counts saturate
+    pub fn total(&self) -> usize {
+        self.map
+            .values()
+            .fold(0, |total: usize, &n| total.saturating_add(n))
+    }
+
+    /// share of each token in all counts (0.0 to 1.0), to compare inputs of different sizes
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count, CountOption};
+    /// let shares = count(Cursor::new("a b a a"), CountOption::Word).proportions();
+    /// assert_eq!(shares["a"], 0.75);
+    /// assert_eq!(shares["b"], 0.25);
+    /// ```
+    pub fn proportions(&self) -> HashMap<K, f64>
+    where
+        K: Clone,
+    {
+        // in f64, so that the total does not overflow
+        let total: f64 = self.map.values().map(|&n| n as f64).sum();
+        self.map
+            .iter()
+            .map(|(token, &n)| (token.clone(), n as f64 / total))
+            .collect()
+    }
+
+    /// sub-map of tokens satisfying `predicate`
+    pub fn filter(&self, mut predicate: impl FnMut(&K, usize) -> bool) -> Self
+    where
+        K: Clone,
+    {
+        self.map
+            .iter()
+            .filter(|(token, &count)| predicate(token, count))
+            .map(|(token, &count)| (token.clone(), count))
+            .collect::<HashMap<_, _>>()
+            .into()
+    }
+
+    /// sub-map of tokens not counted in `other`, e.g. words not in a dictionary counted the
+    /// same way
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count, CountOption};
+    /// let dictionary = count(Cursor::new("the\ncat\n"), CountOption::Word);
+    /// let freqs = count(Cursor::new("the cat sat, the kat sat"), CountOption::Word);
+    /// let unknown = freqs.missing_from(&dictionary);
+    /// assert_eq!(unknown.sorted(), vec![(&"sat".to_string(), 2), (&"kat".to_string(), 1)]);
+    /// ```
+    pub fn missing_from(&self, other: &Self) -> Self
+    where
+        K: Clone,
+    {
+        self.filter(|token, _| !other.contains_key(token))
+    }
+
+    /// entries sorted by count (descending), ties are broken by key (ascending)
+    pub fn sorted(&self) -> Vec<(&K, usize)>
+    where
+        K: Ord,
+    {
+        let mut entries: Vec<_> = self.map.iter().map(|(k, &n)| (k, n)).collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        entries
+    }
+
+    /// formatted by `Debug` as a map in the order of [`sorted`](#method.sorted), the same in
+    /// any run, unlike `Debug` of the counts
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::{count_str, CountOption};
+    /// let freqs = count_str("b a b", CountOption::Word);
+    /// assert_eq!(format!("{:?}", freqs.debug_sorted()), r#"{"b": 2, "a": 1}"#);
+    /// ```
+    pub fn debug_sorted(&self) -> impl fmt::Debug + '_
+    where
+        K: fmt::Debug + Ord,
+    {
+        SortedEntries(self.sorted())
+    }
+
+    /// entries sorted by key (ascending)
+    pub fn sorted_by_key(&self) -> Vec<(&K, usize)>
+    where
+        K: Ord,
+    {
+        let mut entries: Vec<_> = self.map.iter().map(|(k, &n)| (k, n)).collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries
+    }
+
+    /// entries in pages of `page_size` in the order of `sort`, without sorting all of them.
+    /// each page is found by a scan of all entries, keeping at most `2 * page_size` of them, so
+    /// the first pages of a large map are cheap; to go through all pages,
+    /// [`sorted`](#method.sorted) once is faster
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::frequencies::Sort;
+    /// use bicycle_book_wordcount::{count_str, CountOption};
+    /// let freqs = count_str("c b a b c c d", CountOption::Word);
+    /// let mut pages = freqs.pages(2, Sort::Count);
+    /// assert_eq!(pages.next().unwrap(), vec![(&"c".to_string(), 3), (&"b".to_string(), 2)]);
+    /// assert_eq!(pages.next().unwrap(), vec![(&"a".to_string(), 1), (&"d".to_string(), 1)]);
+    /// assert_eq!(pages.next(), None);
+    /// let keys: Vec<_> = freqs.pages(3, Sort::Key).map(|page| page.len()).collect();
+    /// assert_eq!(keys, vec![3, 1]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// `page_size` is 0
+    pub fn pages(&self, page_size: usize, sort: Sort) -> Pages<'_, K>
+    where
+        K: Ord,
+    {
+        assert!(page_size > 0, "page size must not be 0");
+        Pages {
+            freqs: self,
+            size: page_size,
+            sort,
+            last: None,
+            done: false,
+        }
+    }
+
+    /// the `n` most frequent entries, in the order of [`sorted`](#method.sorted)
+    pub fn top(&self, n: usize) -> Vec<(&K, usize)>
+    where
+        K: Ord,
+    {
+        let mut entries = self.sorted();
+        entries.truncate(n);
+        entries
+    }
+
+    /// `n` tokens drawn at random with replacement, each with probability proportional to its
+    /// count. the same seed of `rng` draws the same tokens on any run, as keys are weighed in
+    /// their order
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::rng::Rng;
+    /// use bicycle_book_wordcount::{count_str, CountOption};
+    /// let freqs = count_str("the cat the dog the end", CountOption::Word);
+    /// let drawn = freqs.sample(&mut Rng::new(1), 1000);
+    /// let the = drawn.iter().filter(|&&token| token == "the").count();
+    /// assert!(400 < the && the < 600);
+    /// ```
+    pub fn sample(&self, rng: &mut Rng, n: usize) -> Vec<&K>
+    where
+        K: Ord,
+    {
+        let mut keys = Vec::with_capacity(self.len());
+        let mut cumulative = Vec::with_capacity(self.len());
+        let mut total = 0u64;
+        for (key, count) in self.sorted_by_key() {
+            if count > 0 {
+                total = total.saturating_add(count as u64);
+                keys.push(key);
+                cumulative.push(total);
+            }
+        }
+        if total == 0 {
+            return Vec::new();
+        }
+        (0..n)
+            .map(|_| {
+                // uniform in `0..total` by the high bits of the product
+                let target = ((rng.next_u64() as u128 * total as u128) >> 64) as u64;
+                keys[cumulative.partition_point(|&sum| sum <= target)]
+            })
+            .collect()
+    }
+
+    /// add counts of `other` (saturating at `usize::MAX`). keys of `other` are cloned only if
+    /// they are new
+    pub fn merge(&mut self, other: &Self)
+    where
+        K: Clone,
+    {
+        for (token, &count) in other {
+            match self.map.get_mut(token) {
+                Some(total) => *total = total.saturating_add(count),
+                None => {
+                    self.map.insert(token.clone(), count);
+                }
+            }
+        }
+    }
+
+    /// subtract counts of `other` (saturating at 0), and remove tokens counted 0, e.g. to
+    /// remove a background corpus
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::{count_str, CountOption};
+    /// let mut freqs = count_str("the cat the cat sat", CountOption::Word);
+    /// freqs.subtract(&count_str("the the the cat", CountOption::Word));
+    /// assert_eq!(freqs.sorted(), vec![(&"cat".to_string(), 1), (&"sat".to_string(), 1)]);
+    /// ```
+    pub fn subtract(&mut self, other: &Self) {
+        for (token, &count) in other.iter() {
+            if let Some(n) = self.map.get_mut(token) {
+                *n = n.saturating_sub(count);
+            }
+        }
+        self.map.retain(|_, n| *n > 0);
+    }
+
+    /// keep tokens counted in both, by the smaller of their counts, e.g. to keep terms
+    /// common to two corpora
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::{count_str, CountOption};
+    /// let mut freqs = count_str("a a a b c", CountOption::Word);
+    /// freqs.intersect(&count_str("a b b d", CountOption::Word));
+    /// assert_eq!(freqs.sorted(), vec![(&"a".to_string(), 1), (&"b".to_string(), 1)]);
+    /// ```
+    pub fn intersect(&mut self, other: &Self) {
+        self.map.retain(|token, n| match other.get(token) {
+            Some(&count) => {
+                *n = (*n).min(count);
+                true
+            }
+            None => false,
+        });
+    }
+
+    /// replace counts by the differences from counts of `other`, in either direction, and
+    /// remove tokens counted the same in both
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::{count_str, CountOption};
+    /// let mut freqs = count_str("a a a b c", CountOption::Word);
+    /// freqs.symmetric_difference(&count_str("a b d", CountOption::Word));
+    /// assert_eq!(freqs["a"], 2);
+    /// assert_eq!((freqs["c"], freqs["d"]), (1, 1));
+    /// assert_eq!(freqs.get("b"), None);
+    /// ```
+    pub fn symmetric_difference(&mut self, other: &Self)
+    where
+        K: Clone,
+    {
+        for (token, &count) in other.iter() {
+            match self.map.get_mut(token) {
+                Some(n) => *n = n.abs_diff(count),
+                None => {
+                    self.map.insert(token.clone(), count);
+                }
+            }
+        }
+        self.map.retain(|_, n| *n > 0);
+    }
+
+    /// unwrap to the inner map
+    pub fn into_map(self) -> HashMap<K, usize> {
+        self.map
+    }
+
+    /// totals and the `n` most frequent tokens as a table, see [`Summary`](struct.Summary.html)
+    pub fn summary(&self, n: usize) -> Summary<'_, K>
+    where
+        K: Ord,
+    {
+        Summary {
+            total: self.total(),
+            unique: self.len(),
+            top: self.top(n),
+        }
+    }
+}
+
+impl Frequencies<String> {
+    /// sub-map of tokens matching `re` (anywhere in the token)
+    pub fn matching(&self, re: &Regex) -> Frequencies {
+        self.filter(|token, _| re.is_match(token))
+    }
+
+    /// sub-map of tokens starting with `prefix`
+    pub fn starting_with(&self, prefix: &str) -> Frequencies {
+        self.filter(|token, _| token.starts_with(prefix))
+    }
+
+    /// sub-map of tokens containing `pattern`
+    pub fn containing(&self, pattern: &str) -> Frequencies {
+        self.filter(|token, _| token.contains(pattern))
+    }
+
+    /// sub-map of tokens in `set`, tokens not counted are not included
+    pub fn count_of_set<S: AsRef<str>>(&self, set: &[S]) -> Frequencies {
+        set.iter()
+            .filter_map(|token| self.map.get_key_value(token.as_ref()))
+            .map(|(token, &count)| (token.clone(), count))
+            .collect::<HashMap<_, _>>()
+            .into()
+    }
+
+    /// same as [`sorted`](#method.sorted), but ties are ordered by `collator` instead of code point
+    pub fn sorted_collated(&self, collator: &Collator) -> Vec<(&String, usize)> {
+        let mut entries: Vec<_> = self.map.iter().map(|(k, &n)| (k, n)).collect();
+        entries.sort_by_cached_key(|&(k, n)| (Reverse(n), collator.key(k)));
+        entries
+    }
+
+    /// approximate heap bytes used: the buffers of keys, and the table of the map (allocated
+    /// capacity, entries and a control byte per slot). allocator overhead is not included
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::{count_str, CountOption};
+    /// let small = count_str("a b", CountOption::Word);
+    /// let large = count_str("a b c d e f g h i j", CountOption::Word);
+    /// assert!(small.estimated_bytes() < large.estimated_bytes());
+    /// ```
+    pub fn estimated_bytes(&self) -> usize {
+        let keys: usize = self.map.keys().map(String::capacity).sum();
+        keys + self.table_bytes()
+    }
+
+    /// approximate heap bytes of the table of the map, without buffers of keys
+    pub(crate) fn table_bytes(&self) -> usize {
+        let slots = if self.map.capacity() == 0 {
+            0
+        } else {
+            // hashbrown keeps at least 1/8 of the slots empty, rounded to a power of two
+            (self.map.capacity() * 8 / 7).next_power_of_two()
+        };
+        slots * (mem::size_of::<(String, usize)>() + 1)
+    }
+
+    /// hash of all tokens and counts, independent of the order of the map, to check cheaply
+    /// that two results (e.g. of sequential and parallel counting) are the same. it is the
+    /// same across runs, platforms and versions of this crate, so it can be saved and compared
+    /// later
+    ///
+    /// equal results have equal fingerprints; different results have different ones except
+    /// for rare collisions of 64-bit hashes, so it is not for cryptography
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::concurrent::ConcurrentCounter;
+    /// use bicycle_book_wordcount::{count_str, CountOption};
+    /// let freqs = count_str("b a b", CountOption::Word);
+    /// let counter = ConcurrentCounter::new();
+    /// for token in &["a", "b", "b"] {
+    ///     counter.add(token);
+    /// }
+    /// assert_eq!(counter.snapshot().fingerprint(), freqs.fingerprint());
+    /// assert_ne!(count_str("b a", CountOption::Word).fingerprint(), freqs.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        let sum = self.map.iter().fold(0u64, |sum, (token, &count)| {
+            // FNV-1a of the token, then mixed with the count
+            let hash = fnv1a(token.as_bytes());
+            sum.wrapping_add(mix(hash ^ mix(count as u64)))
+        });
+        mix(sum ^ self.map.len() as u64)
+    }
+
+    /// save to a file in the [`binary`](../binary/index.html) format, replacing it only after
+    /// all counts are written
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io::Cursor;
+    /// use bicycle_book_wordcount::{count_into, CountOption, Frequencies};
+    /// let mut freqs = Frequencies::load("counts.bin").unwrap_or_default();
+    /// count_into(&mut freqs, Cursor::new("today's log"), CountOption::Word);
+    /// freqs.save("counts.bin").unwrap();
+    /// ```
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        save_by(path, |out| binary::write(self, out))
+    }
+
+    /// same as [`save`](#method.save), with `metadata`
+    pub fn save_with_metadata(
+        &self,
+        path: impl AsRef<Path>,
+        metadata: &Metadata,
+    ) -> io::Result<()> {
+        save_by(path, |out| binary::write_with_metadata(self, metadata, out))
+    }
+
+    /// load a file saved by [`save`](#method.save)
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        binary::read(BufReader::new(File::open(path)?))
+    }
+}
+
+/// temporary files created by this process, to name each apart
+static TEMPORARIES: AtomicUsize = AtomicUsize::new(0);
+
+/// write a file by `write` to a temporary file, which replaces the file after all is written
+/// and synced to the disk, so that a crash leaves either the old or the new file
+fn save_by(
+    path: impl AsRef<Path>,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let (temporary, file) = create_temporary(path)?;
+    let mut out = BufWriter::new(file);
+    let saved = write(&mut out)
+        .and_then(|()| out.into_inner().map_err(io::IntoInnerError::into_error))
+        .and_then(|file| file.sync_all())
+        .and_then(|()| fs::rename(&temporary, path));
+    if let Err(e) = saved {
+        // the error of saving is reported, not of cleaning up
+        let _ = fs::remove_file(&temporary);
+        return Err(e);
+    }
+    sync_parent(path)
+}
+
+/// create a new file in the directory of `path`, named by the process id and a counter, so
+/// that saves of the same path at once (of threads or processes) write files of their own
+fn create_temporary(path: &Path) -> io::Result<(PathBuf, File)> {
+    loop {
+        let n = TEMPORARIES.fetch_add(1, atomic::Ordering::Relaxed);
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(format!(".{}.{}.tmp", process::id(), n));
+        let temporary = PathBuf::from(temporary);
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temporary)
+        {
+            Ok(file) => return Ok((temporary, file)),
+            // left by a crashed process of the same id
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// sync the directory of `path`, so that its entry renamed last is on the disk
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(parent)?.sync_all()
+}
+
+/// directories can not be opened to be synced on other platforms
+#[cfg(not(unix))]
+fn sync_parent(_: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// order of entries of [`Frequencies::pages`](struct.Frequencies.html#method.pages)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Sort {
+    /// by count (descending), ties are broken by key (ascending), as
+    /// [`sorted`](struct.Frequencies.html#method.sorted)
+    #[default]
+    Count,
+    /// by key (ascending), as [`sorted_by_key`](struct.Frequencies.html#method.sorted_by_key)
+    Key,
+}
+
+impl Sort {
+    fn cmp<K: Ord>(self, a: &(&K, usize), b: &(&K, usize)) -> Ordering {
+        match self {
+            Sort::Count => b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)),
+            Sort::Key => a.0.cmp(b.0),
+        }
+    }
+}
+
+/// iterator of pages of entries, see [`Frequencies::pages`](struct.Frequencies.html#method.pages)
+#[derive(Clone)]
+pub struct Pages<'a, K> {
+    freqs: &'a Frequencies<K>,
+    size: usize,
+    sort: Sort,
+    /// the last entry of the previous page
+    last: Option<(&'a K, usize)>,
+    done: bool,
+}
+
+impl<'a, K: Ord> Iterator for Pages<'a, K> {
+    type Item = Vec<(&'a K, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let (size, sort, last) = (self.size, self.sort, self.last);
+        let mut page = Vec::with_capacity(size.saturating_mul(2).min(self.freqs.len()));
+        for (key, &count) in &self.freqs.map {
+            let entry = (key, count);
+            if last.is_some_and(|last| sort.cmp(&last, &entry) != Ordering::Less) {
+                continue;
+            }
+            page.push(entry);
+            if page.len() >= size.saturating_mul(2) {
+                page.select_nth_unstable_by(size, |a, b| sort.cmp(a, b));
+                page.truncate(size);
+            }
+        }
+        page.sort_unstable_by(|a, b| sort.cmp(a, b));
+        page.truncate(size);
+        match page.last() {
+            Some(&last) => self.last = Some(last),
+            None => {
+                self.done = true;
+                return None;
+            }
+        }
+        Some(page)
+    }
+}
+
+/// 64-bit FNV-1a hash of `bytes`, the same on any platform
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// finalizer of splitmix64, spreading each bit of `x` over all bits
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+impl<K: Eq + Hash> Default for Frequencies<K> {
+    fn default() -> Self {
+        Frequencies::new()
+    }
+}
+
+impl<K: Eq + Hash> PartialEq for Frequencies<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<K: Eq + Hash> Eq for Frequencies<K> {}
+
+impl<K> Deref for Frequencies<K> {
+    type Target = HashMap<K, usize>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl<K> DerefMut for Frequencies<K> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.map
+    }
+}
+
+impl<K> From<HashMap<K, usize>> for Frequencies<K> {
+    fn from(map: HashMap<K, usize>) -> Self {
+        Frequencies { map }
+    }
+}
+
+impl<K> From<Frequencies<K>> for HashMap<K, usize> {
+    fn from(freqs: Frequencies<K>) -> Self {
+        freqs.map
+    }
+}
+
+/// counts of the same key are summed (saturating at `usize::MAX`)
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::Frequencies;
+/// let mut freqs: Frequencies<&str> = vec![("aa", 2), ("bb", 1), ("aa", 1)].into_iter().collect();
+/// assert_eq!(freqs["aa"], 3);
+/// freqs.extend(vec![("bb", 4)]);
+/// let mut pairs: Vec<_> = freqs.into_iter().collect();
+/// pairs.sort();
+/// assert_eq!(pairs, vec![("aa", 3), ("bb", 5)]);
+/// ```
+impl<K: Eq + Hash> FromIterator<(K, usize)> for Frequencies<K> {
+    fn from_iter<I: IntoIterator<Item = (K, usize)>>(iter: I) -> Self {
+        let mut freqs = Frequencies::new();
+        freqs.extend(iter);
+        freqs
+    }
+}
+
+/// add counts (saturating at `usize::MAX`), e.g. of another result
+impl<K: Eq + Hash> Extend<(K, usize)> for Frequencies<K> {
+    fn extend<I: IntoIterator<Item = (K, usize)>>(&mut self, iter: I) {
+        for (token, count) in iter {
+            let total = self.map.entry(token).or_insert(0);
+            *total = total.saturating_add(count);
+        }
+    }
+}
+
+/// pairs of a token and its count, in arbitrary order
+impl<K> IntoIterator for Frequencies<K> {
+    type Item = (K, usize);
+    type IntoIter = hash_map::IntoIter<K, usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter()
+    }
+}
+
+/// same as `iter()` of the inner map
+impl<'a, K> IntoIterator for &'a Frequencies<K> {
+    type Item = (&'a K, &'a usize);
+    type IntoIter = hash_map::Iter<'a, K, usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.iter()
+    }
+}
+
+impl<K: Eq + Hash> PartialEq<HashMap<K, usize>> for Frequencies<K> {
+    fn eq(&self, other: &HashMap<K, usize>) -> bool {
+        self.map == *other
+    }
+}
+
+/// same as the inner map, in arbitrary order; see [`debug_sorted`](#method.debug_sorted)
+impl<K: fmt::Debug> fmt::Debug for Frequencies<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.map.fmt(f)
+    }
+}
+
+/// entries formatted as a map in their order
+struct SortedEntries<'a, K>(Vec<(&'a K, usize)>);
+
+impl<K: fmt::Debug> fmt::Debug for SortedEntries<'_, K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|&(k, n)| (k, n)))
+            .finish()
+    }
+}
+
+/// same as [`summary`](#method.summary) of the 10 most frequent tokens
+impl<K: fmt::Display + Ord + Hash> fmt::Display for Frequencies<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.summary(10))
+    }
+}
+
+/// totals and the most frequent tokens of a result, made by
+/// [`Frequencies::summary`](struct.Frequencies.html#method.summary)
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let freqs = count_str("to be or not to be to", CountOption::Word);
+/// let summary = freqs.summary(2);
+/// assert_eq!(summary.unique, 4);
+/// let lines: Vec<String> = summary.to_string().lines().map(String::from).collect();
+/// assert_eq!(lines, vec![
+///     "total: 7, unique: 4",
+///     "count  share  token",
+///     "    3  42.9%  to",
+///     "    2  28.6%  be",
+/// ]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary<'a, K> {
+    /// count of all tokens
+    pub total: usize,
+    /// count of distinct tokens
+    pub unique: usize,
+    /// the most frequent tokens
+    pub top: Vec<(&'a K, usize)>,
+}
+
+/// a line of totals, then a table of counts, shares and tokens with aligned columns
+impl<K: fmt::Display> fmt::Display for Summary<'_, K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "total: {}, unique: {}", self.total, self.unique)?;
+        let width = self
+            .top
+            .iter()
+            .map(|(_, n)| n.to_string().len())
+            .chain(Some("count".len()))
+            .max()
+            .unwrap_or(0);
+        writeln!(f, "{:>w$}  share  token", "count", w = width)?;
+        for (token, n) in &self.top {
+            let share = *n as f64 * 100.0 / self.total as f64;
+            writeln!(f, "{:>w$}  {:>4.1}%  {}", n, share, token, w = width)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn freqs() -> Frequencies {
+        let mut map = HashMap::new();
+        map.insert("error".to_string(), 3);
+        map.insert("errno".to_string(), 1);
+        map.insert("warn".to_string(), 2);
+        map.into()
+    }
+
+    #[test]
+    fn query_works() {
+        let freqs = freqs();
+        assert_eq!(freqs.total(), 6);
+        assert_eq!(freqs.matching(&Regex::new("^err").unwrap()).total(), 4);
+        assert_eq!(freqs.starting_with("erro").len(), 1);
+        assert_eq!(freqs.containing("r").len(), 3);
+        assert_eq!(freqs.filter(|_, count| count > 1).len(), 2);
+        let mut big = freqs.clone();
+        big.insert("big".to_string(), usize::MAX);
+        assert_eq!(big.total(), usize::MAX);
+    }
+
+    #[test]
+    fn algebra_works() {
+        let other: Frequencies = vec![("error".to_string(), 5), ("info".to_string(), 1)]
+            .into_iter()
+            .collect();
+        let mut subtracted = freqs();
+        subtracted.subtract(&other);
+        assert_eq!(subtracted.sorted_by_key().len(), 2);
+        assert_eq!(subtracted.get("error"), None);
+        let mut intersected = freqs();
+        intersected.intersect(&other);
+        assert_eq!(intersected.into_map(), [("error".to_string(), 3)].into());
+        let mut differences = freqs();
+        differences.symmetric_difference(&other);
+        assert_eq!(differences.total(), 2 + 1 + 2 + 1);
+        let mut same = freqs();
+        same.symmetric_difference(&freqs());
+        assert!(same.is_empty());
+        let mut empty = Frequencies::new();
+        empty.subtract(&other);
+        empty.intersect(&other);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn sample_weighs_by_count() {
+        let mut freqs = freqs();
+        freqs.insert("never".to_string(), 0);
+        let drawn = freqs.sample(&mut Rng::new(5), 6000);
+        let mut counted: Frequencies<&String> = Frequencies::new();
+        for token in &drawn {
+            *counted.entry(*token).or_insert(0) += 1;
+        }
+        assert_eq!(counted.len(), freqs.len() - 1);
+        for (token, &n) in freqs.iter().filter(|&(_, &n)| n > 0) {
+            let expected = 6000 * n / freqs.total();
+            assert!(
+                counted[&token].abs_diff(expected) < expected / 5,
+                "{}",
+                token
+            );
+        }
+        assert_eq!(drawn, freqs.sample(&mut Rng::new(5), 6000));
+        assert!(Frequencies::<String>::new()
+            .sample(&mut Rng::new(5), 3)
+            .is_empty());
+    }
+
+    #[test]
+    fn fingerprint_is_stable() {
+        let freqs = freqs();
+        let reversed: Frequencies = freqs
+            .sorted_by_key()
+            .into_iter()
+            .rev()
+            .map(|(k, n)| (k.clone(), n))
+            .collect();
+        assert_eq!(reversed.fingerprint(), freqs.fingerprint());
+        // fixed across runs and platforms
+        assert_eq!(Frequencies::new().fingerprint(), 0);
+        assert_eq!(freqs.fingerprint(), 0x70a6_ab62_91fc_8566);
+        let mut swapped = freqs.clone();
+        swapped.insert("error".to_string(), 1);
+        swapped.insert("errno".to_string(), 3);
+        assert_ne!(swapped.fingerprint(), freqs.fingerprint());
+        let mut extra = freqs.clone();
+        extra.insert(String::new(), 0);
+        assert_ne!(extra.fingerprint(), freqs.fingerprint());
+    }
+
+    #[test]
+    fn pages_are_sorted() {
+        let mut freqs = Frequencies::new();
+        for i in 0..50 {
+            freqs.insert(format!("{:02}", i), i % 7);
+        }
+        for &(sort, ref all) in &[
+            (Sort::Count, freqs.sorted()),
+            (Sort::Key, freqs.sorted_by_key()),
+        ] {
+            let pages: Vec<_> = freqs.pages(4, sort).collect();
+            assert_eq!(pages.len(), 13);
+            assert_eq!(&pages.concat(), all);
+            assert_eq!(freqs.pages(50, sort).count(), 1);
+        }
+        assert_eq!(
+            Frequencies::<String>::new().pages(1, Sort::Count).next(),
+            None
+        );
+    }
+
+    #[test]
+    fn sorted_works() {
+        let mut freqs = freqs();
+        freqs.insert("debug".to_string(), 2);
+        let sorted: Vec<_> = freqs
+            .sorted()
+            .into_iter()
+            .map(|(k, n)| (k.as_str(), n))
+            .collect();
+        assert_eq!(
+            sorted,
+            vec![("error", 3), ("debug", 2), ("warn", 2), ("errno", 1)]
+        );
+        assert_eq!(freqs.top(1), vec![(&"error".to_string(), 3)]);
+        assert_eq!(freqs.top(10).len(), 4);
+    }
+
+    #[test]
+    fn merge_works() {
+        let mut merged = freqs();
+        let mut other = HashMap::new();
+        other.insert("warn".to_string(), 1);
+        other.insert("fatal".to_string(), 1);
+        merged.merge(&other.into());
+
+        assert_eq!(merged.len(), 4);
+        assert_eq!(merged["warn"], 3);
+        assert_eq!(merged["fatal"], 1);
+        assert_eq!(merged.total(), 8);
+    }
+
+    #[test]
+    fn count_of_set_works() {
+        let set = freqs().count_of_set(&["warn", "error", "fatal"]);
+        let mut exp = HashMap::new();
+        exp.insert("error".to_string(), 3);
+        exp.insert("warn".to_string(), 2);
+        assert_eq!(set, exp);
+    }
+
+    #[test]
+    fn sorted_collated_works() {
+        let freqs: Frequencies = vec![("öl", 1), ("zoo", 1), ("oxe", 2)]
+            .into_iter()
+            .map(|(k, n)| (k.to_string(), n))
+            .collect::<HashMap<_, _>>()
+            .into();
+        let keys =
+            |v: Vec<(&String, usize)>| v.into_iter().map(|(k, _)| k.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            keys(freqs.sorted_collated(&Collator::new("de"))),
+            vec!["oxe", "öl", "zoo"]
+        );
+        assert_eq!(
+            keys(freqs.sorted_collated(&Collator::new("sv"))),
+            vec!["oxe", "zoo", "öl"]
+        );
+    }
+
+    #[test]
+    fn output_is_deterministic() {
+        let build = |tokens: &[&str]| {
+            let mut freqs = Frequencies::new();
+            for t in tokens {
+                *freqs.entry(t.to_string()).or_insert(0) += 1;
+            }
+            freqs
+        };
+        let a = build(&["b", "a", "c", "b", "é", "B"]);
+        let b = build(&["B", "é", "c", "b", "a", "b"]);
+        assert_eq!(
+            format!("{:?}", a.debug_sorted()),
+            r#"{"b": 2, "B": 1, "a": 1, "c": 1, "é": 1}"#
+        );
+        assert_eq!(
+            format!("{:?}", a.debug_sorted()),
+            format!("{:?}", b.debug_sorted())
+        );
+        let keys: Vec<_> = a
+            .sorted_by_key()
+            .into_iter()
+            .map(|(k, _)| k.as_str())
+            .collect();
+        assert_eq!(keys, vec!["B", "a", "b", "c", "é"]);
+    }
+
+    #[test]
+    fn proportions_works() {
+        let shares = freqs().proportions();
+        let sum: f64 = shares.values().sum();
+        assert!((sum - 1.0).abs() < 1e-12);
+        assert!(Frequencies::<String>::new().proportions().is_empty());
+
+        let mut big = Frequencies::new();
+        big.insert('a', usize::MAX);
+        big.insert('b', usize::MAX);
+        assert_eq!(big.proportions()[&'a'], 0.5);
+    }
+
+    #[test]
+    fn save_and_load() -> io::Result<()> {
+        let path = std::env::temp_dir().join("wordcount_frequencies_save.bin");
+        freqs().save(&path)?;
+        let mut loaded = Frequencies::load(&path)?;
+        assert_eq!(loaded, freqs());
+        loaded.merge(&freqs());
+        loaded.save(&path)?;
+        assert_eq!(Frequencies::load(&path)?["error"], 6);
+        fs::remove_file(&path)
+    }
+
+    #[test]
+    fn saves_at_once_do_not_collide() -> io::Result<()> {
+        let dir = std::env::temp_dir().join("wordcount_frequencies_saves");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("counts.bin");
+        let saves: Vec<_> = (0..8)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || freqs().save(path))
+            })
+            .collect();
+        for save in saves {
+            save.join().unwrap()?;
+        }
+        assert_eq!(Frequencies::load(&path)?, freqs());
+        // no temporary file is left
+        assert_eq!(fs::read_dir(&dir)?.count(), 1);
+        fs::remove_dir_all(&dir)
+    }
+
+    #[test]
+    fn display_works() {
+        let mut freqs = freqs();
+        freqs.insert("info".to_string(), 1994);
+        let text = freqs.to_string();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines[0], "total: 2000, unique: 4");
+        assert_eq!(lines[1], "count  share  token");
+        assert_eq!(lines[2], " 1994  99.7%  info");
+        assert_eq!(lines[5], "    1   0.1%  errno");
+        assert_eq!(freqs.summary(0).to_string().lines().count(), 2);
+        assert_eq!(
+            Frequencies::<String>::new().to_string(),
+            "total: 0, unique: 0\ncount  share  token\n"
+        );
+    }
+
+    #[test]
+    fn iterator_traits_work() {
+        let mut freqs: Frequencies = freqs().into_iter().filter(|(_, n)| *n > 1).collect();
+        assert_eq!(freqs.len(), 2);
+        freqs.extend(vec![("warn".to_string(), usize::MAX)]);
+        assert_eq!(freqs["warn"], usize::MAX);
+        let mut total = 0;
+        for (_, n) in &freqs {
+            total += n % 10;
+        }
+        assert_eq!(total, 3 + usize::MAX % 10);
+    }
+
+    #[test]
+    fn estimated_bytes_grows() {
+        assert_eq!(Frequencies::<String>::new().estimated_bytes(), 0);
+        let mut freqs = freqs();
+        let before = freqs.estimated_bytes();
+        assert!(before >= "error".len() + "errno".len() + "warn".len());
+        freqs.insert("x".repeat(1000), 1);
+        assert!(freqs.estimated_bytes() >= before + 1000);
+    }
+}
@@ -0,0 +1,145 @@
+//! lines keyed by a hash of them, of
+//! [`CountConfig::line_key_bytes`](../config/struct.CountConfig.html#method.line_key_bytes),
+//! and where they are in the input, see [`HashedLines`](struct.HashedLines.html)
+
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use crate::decode::{read_numbered_lines, Transcoder};
+use crate::tally::Tally;
+use crate::{CountConfig, CountError, CountOption, Frequencies};
+
+/// where the first line of a hashed key is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashedLine {
+    /// line number from 1
+    pub line: usize,
+    /// length of the line in bytes
+    pub bytes: usize,
+}
+
+/// the lines behind keys made by a hash, to tell which line a key of a report is
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::hashed::{count_with_hashed_lines, HashedLine, HashedLines};
+/// use bicycle_book_wordcount::{CountConfig, CountOption};
+/// let input = format!("ok\n{}\nok\n{}\n", "a".repeat(100), "a".repeat(100));
+/// let config = CountConfig::new(CountOption::Line).line_key_bytes(4);
+/// let mut hashed = HashedLines::new();
+/// let freqs = count_with_hashed_lines(Cursor::new(input), &config, &mut hashed).unwrap();
+/// assert_eq!(hashed.len(), 1);
+/// let (key, line) = hashed.lines()[0];
+/// assert_eq!(freqs[key], 2);
+/// assert_eq!(*line, HashedLine { line: 2, bytes: 100 });
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HashedLines {
+    lines: HashMap<String, HashedLine>,
+}
+
+impl HashedLines {
+    pub fn new() -> Self {
+        HashedLines::default()
+    }
+
+    /// the first line of `key`, `None` if it is not a hashed key
+    pub fn get(&self, key: &str) -> Option<&HashedLine> {
+        self.lines.get(key)
+    }
+
+    /// number of hashed keys
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// hashed keys and their first lines, in the order of lines
+    pub fn lines(&self) -> Vec<(&str, &HashedLine)> {
+        let mut lines: Vec<_> = self
+            .lines
+            .iter()
+            .map(|(key, line)| (key.as_str(), line))
+            .collect();
+        lines.sort_by_key(|(_, line)| line.line);
+        lines
+    }
+}
+
+/// the key is made of a line by a hash, `…#` and 32 hex digits at the end
+fn is_hashed(key: &str) -> bool {
+    let split = match key.len().checked_sub(32) {
+        Some(split) if key.is_char_boundary(split) => split,
+        _ => return false,
+    };
+    let (start, hash) = key.split_at(split);
+    start.ends_with("…#") && hash.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// count `input` by [`try_count_with`](../fn.try_count_with.html), recording the first line of
+/// each key made by a hash into `hashed`. keys already recorded keep their first lines
+pub fn count_with_hashed_lines(
+    input: impl BufRead,
+    config: &CountConfig,
+    hashed: &mut HashedLines,
+) -> Result<Frequencies, CountError> {
+    let mut tally = Tally::new(config);
+    let hashing = config.line_key_bytes.is_some() && config.option == CountOption::Line;
+    read_numbered_lines(Transcoder::new(input), config.reading(), |number, line| {
+        tally.line_with_tokens(line, |key, token| {
+            if hashing && is_hashed(key) && !hashed.lines.contains_key(key) {
+                let first = HashedLine {
+                    line: number,
+                    bytes: token.len(),
+                };
+                hashed.lines.insert(key.to_string(), first);
+            }
+        })
+    })?;
+    Ok(tally.finish())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn long_lines_are_hashed() {
+        let long = "é".repeat(10);
+        let input = format!("{}\n{}x\nshort\n", long, long);
+        let config = CountConfig::new(CountOption::Line).line_key_bytes(5);
+        let mut hashed = HashedLines::new();
+        let freqs = count_with_hashed_lines(Cursor::new(&input), &config, &mut hashed).unwrap();
+        assert_eq!(freqs.len(), 3);
+        assert_eq!(freqs["short"], 1);
+        let lines = hashed.lines();
+        assert_eq!(lines.len(), 2);
+        // cut at a char boundary
+        assert!(lines[0].0.starts_with("éé…#"));
+        assert_eq!(lines[1].1, &HashedLine { line: 2, bytes: 21 });
+        assert_eq!(hashed.get("short"), None);
+
+        let words = CountConfig::new(CountOption::Word).line_key_bytes(5);
+        let mut hashed = HashedLines::new();
+        count_with_hashed_lines(Cursor::new(&input), &words, &mut hashed).unwrap();
+        assert!(hashed.is_empty());
+    }
+
+    #[test]
+    fn skipped_lines_are_numbered() {
+        let mut input = b"b\xffd\n".to_vec();
+        input.extend_from_slice(b"aaaaaaaaaa\n");
+        let config = CountConfig::new(CountOption::Line)
+            .line_key_bytes(4)
+            .invalid_input(crate::decode::DecodePolicy::SkipLine);
+        let mut hashed = HashedLines::new();
+        count_with_hashed_lines(&input[..], &config, &mut hashed).unwrap();
+        assert_eq!(hashed.lines()[0].1.line, 2);
+    }
+}
@@ -0,0 +1,197 @@
+//! approximate most frequent tokens in bounded memory, see [`SpaceSaving`](struct.SpaceSaving.html)
+
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, BufRead};
+
+use crate::decode::{read_lines, Transcoder};
+use crate::tally::Scanner;
+use crate::{CountConfig, CountSink};
+
+/// an estimated count of a token by [`SpaceSaving`](struct.SpaceSaving.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Estimate {
+    pub token: String,
+    /// estimated count, never less than the true count
+    pub count: usize,
+    /// largest overestimation, the true count is between `count - error` and `count`
+    pub error: usize,
+}
+
+impl Estimate {
+    /// count the token surely has
+    pub fn guaranteed(&self) -> usize {
+        self.count - self.error
+    }
+}
+
+/// the Space-Saving algorithm, which tracks at most `capacity` tokens
+///
+/// when a new token comes and all counters are used, the token with the smallest count is
+/// replaced, and the new token inherits its count as the error. every token occurring more than
+/// `total / capacity` times is kept, and each count is overestimated by at most
+/// `total / capacity`.
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::heavy::SpaceSaving;
+/// let mut counter = SpaceSaving::new(2);
+/// for token in "a b a c a d".split(' ') {
+///     counter.add(token);
+/// }
+/// let top = counter.top(1);
+/// assert_eq!(top[0].token, "a");
+/// assert_eq!(top[0].count, 3);
+/// assert_eq!(top[0].error, 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpaceSaving {
+    capacity: usize,
+    total: usize,
+    /// token => (count, error)
+    counters: HashMap<String, (usize, usize)>,
+    /// (count, token), ordered to find the smallest count
+    order: BTreeSet<(usize, String)>,
+}
+
+impl SpaceSaving {
+    /// # Panics
+    ///
+    /// `capacity` is 0
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        SpaceSaving {
+            capacity,
+            total: 0,
+            counters: HashMap::new(),
+            order: BTreeSet::new(),
+        }
+    }
+
+    /// count a token once
+    pub fn add(&mut self, token: &str) {
+        self.add_n(token, 1)
+    }
+
+    /// count a token `n` times
+    pub fn add_n(&mut self, token: &str, n: usize) {
+        if n == 0 {
+            return;
+        }
+        self.total = self.total.saturating_add(n);
+        let (count, error) = match self.counters.get(token) {
+            Some(&(count, error)) => {
+                self.order.remove(&(count, token.to_string()));
+                (count, error)
+            }
+            None if self.counters.len() < self.capacity => (0, 0),
+            None => {
+                let (min, evicted) = self.order.pop_first().unwrap();
+                self.counters.remove(&evicted);
+                (min, min)
+            }
+        };
+        let count = count.saturating_add(n);
+        self.counters.insert(token.to_string(), (count, error));
+        self.order.insert((count, token.to_string()));
+    }
+
+    /// count of all tokens added
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// estimate of a token, `None` if it is not tracked
+    pub fn get(&self, token: &str) -> Option<Estimate> {
+        self.counters.get(token).map(|&(count, error)| Estimate {
+            token: token.to_string(),
+            count,
+            error,
+        })
+    }
+
+    /// the `n` tracked tokens with the largest estimates, ordered as
+    /// [`Frequencies::sorted`](../struct.Frequencies.html#method.sorted)
+    pub fn top(&self, n: usize) -> Vec<Estimate> {
+        let mut estimates: Vec<_> = self
+            .counters
+            .iter()
+            .map(|(token, &(count, error))| Estimate {
+                token: token.clone(),
+                count,
+                error,
+            })
+            .collect();
+        estimates.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.token.cmp(&b.token)));
+        estimates.truncate(n);
+        estimates
+    }
+}
+
+impl CountSink for SpaceSaving {
+    fn accept(&mut self, token: &str) {
+        self.add(token)
+    }
+}
+
+/// approximate `k` most frequent tokens read from input, tracking `capacity` tokens
+///
+/// # Errors
+///
+/// input is neither UTF-8 nor UTF-16 with a byte order mark (unless
+/// [`CountConfig::invalid_input`](../config/struct.CountConfig.html#method.invalid_input) is set),
+/// or reading fails
+///
+/// # Panics
+///
+/// `capacity` is 0
+pub fn approx_top(
+    input: impl BufRead,
+    config: &CountConfig,
+    k: usize,
+    capacity: usize,
+) -> io::Result<Vec<Estimate>> {
+    let mut counter = SpaceSaving::new(capacity);
+    let mut scanner = Scanner::new(config);
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        scanner.line(line, |key, _| counter.accept(key));
+        Ok::<_, io::Error>(())
+    })?;
+    Ok(counter.top(k))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn estimates_are_bounded() {
+        let text = "a b a c a d e a b f g a b h";
+        let mut counter = SpaceSaving::new(3);
+        for token in text.split(' ') {
+            counter.add(token);
+        }
+        assert_eq!(counter.total(), 14);
+        let tracked = counter.top(3);
+        assert_eq!(tracked.len(), 3);
+        for estimate in &tracked {
+            let actual = text.split(' ').filter(|t| *t == estimate.token).count();
+            assert!(estimate.guaranteed() <= actual && actual <= estimate.count);
+            assert!(estimate.error <= counter.total() / 3);
+        }
+        assert_eq!(tracked[0].token, "a");
+        assert_eq!(counter.get("a").unwrap().guaranteed(), 5);
+    }
+
+    #[test]
+    fn approx_top_works() {
+        let config = CountConfig::default();
+        let top = approx_top(Cursor::new("x y x\nz x y"), &config, 2, 2).unwrap();
+        assert_eq!(top[0].token, "x");
+        assert_eq!(top[0].count, 3);
+        assert_eq!(top.len(), 2);
+        let err = approx_top(Cursor::new(b"x\n\xff"), &config, 2, 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
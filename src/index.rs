@@ -0,0 +1,131 @@
+//! inverted index from tokens to files containing them
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use crate::decode::{strip_bom, Transcoder};
+use crate::{count_line, walk, word_regex, CountOption};
+
+/// maps each token to the files (and counts) containing it
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{index::Index, CountOption};
+/// let mut index = Index::new(CountOption::Word);
+/// index.add("a.txt", Cursor::new("aa bb")).unwrap();
+/// index.add("b.txt", Cursor::new("bb bb")).unwrap();
+///
+/// let found: Vec<_> = index.lookup("bb").map(|(p, n)| (p.to_str().unwrap(), n)).collect();
+/// assert_eq!(found, vec![("a.txt", 1), ("b.txt", 2)]);
+/// assert_eq!(index.lookup("cc").count(), 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Index {
+    option: CountOption,
+    files: Vec<PathBuf>,
+    postings: HashMap<String, Vec<(usize, usize)>>,
+}
+
+impl Index {
+    /// empty index which counts by `option`
+    pub fn new(option: CountOption) -> Self {
+        Index {
+            option,
+            files: Vec::new(),
+            postings: HashMap::new(),
+        }
+    }
+
+    /// index of all files under `root`
+    pub fn build(root: impl AsRef<Path>, option: CountOption) -> io::Result<Self> {
+        let mut index = Index::new(option);
+        for path in walk::files(root)? {
+            let reader = BufReader::new(File::open(&path)?);
+            index.add(path, reader)?;
+        }
+        Ok(index)
+    }
+
+    /// add a file read from input.
+    /// returns an error with kind `InvalidData` if the input is neither UTF-8 nor UTF-16
+    /// with a byte order mark
+    pub fn add(&mut self, path: impl Into<PathBuf>, input: impl BufRead) -> io::Result<()> {
+        let re = word_regex();
+        let mut freqs = HashMap::new();
+        for (i, line) in Transcoder::new(input).lines().enumerate() {
+            let line = line?;
+            let line = if i == 0 { strip_bom(&line) } else { &line };
+            count_line(&re, line, &self.option, &mut freqs);
+        }
+
+        let id = self.files.len();
+        self.files.push(path.into());
+        for (token, count) in freqs {
+            self.postings.entry(token).or_default().push((id, count));
+        }
+        Ok(())
+    }
+
+    /// files containing `token` and its count in each file, in the order of addition
+    pub fn lookup<'a>(&'a self, token: &str) -> impl Iterator<Item = (&'a Path, usize)> + 'a {
+        self.postings
+            .get(token)
+            .into_iter()
+            .flatten()
+            .map(move |&(id, count)| (self.files[id].as_path(), count))
+    }
+
+    /// indexed files
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// count of distinct tokens
+    pub fn len(&self) -> usize {
+        self.postings.len()
+    }
+
+    /// no token is indexed
+    pub fn is_empty(&self) -> bool {
+        self.postings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn build_works() -> io::Result<()> {
+        let root = std::env::temp_dir().join("wordcount_index_build_works");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub"))?;
+        fs::write(root.join("a.txt"), "aa bb\naa")?;
+        fs::write(root.join("sub").join("b.txt"), "bb cc")?;
+
+        let index = Index::build(&root, CountOption::Word)?;
+        fs::remove_dir_all(&root)?;
+
+        assert_eq!(index.files().len(), 2);
+        assert_eq!(index.len(), 3);
+        let aa: Vec<_> = index.lookup("aa").collect();
+        assert_eq!(aa, vec![(root.join("a.txt").as_path(), 2)]);
+        assert_eq!(index.lookup("bb").count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn add_fails_on_invalid_utf8() {
+        let mut index = Index::new(CountOption::Word);
+        let err = index
+            .add("bad", io::Cursor::new(vec![b'a', 0xf9, 0x90]))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(index.files().is_empty());
+    }
+}
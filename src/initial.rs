@@ -0,0 +1,204 @@
+//! counts grouped by the first letter of tokens, see [`Initials`](struct.Initials.html)
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::normalize::base;
+use crate::Frequencies;
+
+/// what a token starts with
+///
+/// variants are ordered as they are listed, so letters come first in a summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Initial {
+    /// a letter of a cased (or unknown) script, uppercased and without diacritics
+    Letter(char),
+    /// a letter of a script without case, grouped as a whole, e.g. "Han" or "Hiragana"
+    Script(&'static str),
+    /// a digit of any script
+    Digit,
+    /// punctuation, symbols and the empty token
+    Other,
+}
+
+impl Initial {
+    /// initial of `token`
+    pub fn of(token: &str) -> Self {
+        let c = match token.chars().next() {
+            Some(c) => c,
+            None => return Initial::Other,
+        };
+        if c.is_numeric() {
+            return Initial::Digit;
+        }
+        if !c.is_alphabetic() {
+            return Initial::Other;
+        }
+        if let Some(name) = script(c) {
+            return Initial::Script(name);
+        }
+        // 'ß' is uppercased to "SS"
+        let upper = c.to_uppercase().next().unwrap_or(c);
+        Initial::Letter(base(upper).unwrap_or(upper))
+    }
+}
+
+impl fmt::Display for Initial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Initial::Letter(c) => write!(f, "{}", c),
+            Initial::Script(name) => f.write_str(name),
+            Initial::Digit => f.write_str("0-9"),
+            Initial::Other => f.write_str("other"),
+        }
+    }
+}
+
+/// scripts without case, by blocks
+fn script(c: char) -> Option<&'static str> {
+    let name = match c {
+        '\u{590}'..='\u{5ff}' => "Hebrew",
+        '\u{600}'..='\u{6ff}' | '\u{750}'..='\u{77f}' => "Arabic",
+        '\u{900}'..='\u{97f}' => "Devanagari",
+        '\u{e00}'..='\u{e7f}' => "Thai",
+        '\u{1100}'..='\u{11ff}' | '\u{3130}'..='\u{318f}' | '\u{ac00}'..='\u{d7af}' => "Hangul",
+        '\u{3040}'..='\u{309f}' => "Hiragana",
+        '\u{30a0}'..='\u{30ff}' | '\u{31f0}'..='\u{31ff}' | '\u{ff66}'..='\u{ff9f}' => "Katakana",
+        '\u{3005}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{f900}'..='\u{faff}' => "Han",
+        '\u{20000}'..='\u{3ffff}' => "Han",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// tokens and counts of an initial
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Group {
+    pub initial: Initial,
+    /// count of distinct tokens
+    pub tokens: usize,
+    /// sum of counts of the tokens
+    pub count: usize,
+}
+
+/// counts grouped by [`Initial`](enum.Initial.html), in order of initials
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::initial::{Initial, Initials};
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let freqs = count_str("apple Avocado banana élan 日本 にほん 42", CountOption::Word);
+/// let initials = Initials::of(&freqs);
+/// let a = initials.get(Initial::Letter('A')).unwrap();
+/// assert_eq!((a.tokens, a.count), (2, 2));
+/// assert_eq!(initials.get(Initial::Letter('E')).unwrap().count, 1);
+/// assert_eq!(initials.get(Initial::Script("Han")).unwrap().count, 1);
+/// assert_eq!(initials.get(Initial::Script("Hiragana")).unwrap().count, 1);
+/// assert_eq!(initials.get(Initial::Digit).unwrap().tokens, 1);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Initials {
+    pub groups: Vec<Group>,
+}
+
+impl Initials {
+    /// group `freqs` by initials of tokens
+    pub fn of(freqs: &Frequencies) -> Self {
+        let mut groups = BTreeMap::new();
+        for (token, &n) in freqs.iter() {
+            let (tokens, count) = groups.entry(Initial::of(token)).or_insert((0, 0));
+            *tokens += 1;
+            *count += n;
+        }
+        let groups = groups.into_iter().map(|(initial, (tokens, count))| Group {
+            initial,
+            tokens,
+            count,
+        });
+        Initials {
+            groups: groups.collect(),
+        }
+    }
+
+    /// group of `initial`, `None` if no token starts with it
+    pub fn get(&self, initial: Initial) -> Option<&Group> {
+        self.groups
+            .binary_search_by_key(&initial, |group| group.initial)
+            .ok()
+            .map(|i| &self.groups[i])
+    }
+}
+
+/// a table of initials, counts of distinct tokens and sums of counts with aligned columns
+impl fmt::Display for Initials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = self.groups.iter().map(|g| g.initial.to_string()).collect();
+        let width = names
+            .iter()
+            .map(|name| name.chars().count())
+            .chain(Some("initial".len()))
+            .max()
+            .unwrap_or(0);
+        writeln!(f, "{:<w$}  tokens  count", "initial", w = width)?;
+        for (name, group) in names.iter().zip(&self.groups) {
+            writeln!(
+                f,
+                "{:<w$}  {:>6}  {:>5}",
+                name,
+                group.tokens,
+                group.count,
+                w = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{count_str, CountOption};
+
+    #[test]
+    fn initial_of_works() {
+        assert_eq!(Initial::of("zeta"), Initial::Letter('Z'));
+        assert_eq!(Initial::of("Ölfass"), Initial::Letter('O'));
+        assert_eq!(Initial::of("ßig"), Initial::Letter('S'));
+        assert_eq!(Initial::of("ωμέγα"), Initial::Letter('Ω'));
+        assert_eq!(Initial::of("カナ"), Initial::Script("Katakana"));
+        assert_eq!(Initial::of("한국"), Initial::Script("Hangul"));
+        assert_eq!(Initial::of("٣"), Initial::Digit);
+        assert_eq!(Initial::of("#tag"), Initial::Other);
+        assert_eq!(Initial::of(""), Initial::Other);
+    }
+
+    #[test]
+    fn initials_work() {
+        let freqs = count_str("bb aa aa Ab 1st 日本", CountOption::Word);
+        let initials = Initials::of(&freqs);
+        let order: Vec<_> = initials.groups.iter().map(|g| g.initial).collect();
+        assert_eq!(
+            order,
+            vec![
+                Initial::Letter('A'),
+                Initial::Letter('B'),
+                Initial::Script("Han"),
+                Initial::Digit
+            ]
+        );
+        assert_eq!(initials.get(Initial::Other), None);
+        assert_eq!(
+            initials.to_string(),
+            "initial  tokens  count\n\
+             A             2      3\n\
+             B             1      1\n\
+             Han           1      1\n\
+             0-9           1      1\n"
+        );
+        assert_eq!(Initials::of(&Frequencies::new()), Initials::default());
+    }
+}
@@ -0,0 +1,286 @@
+//! compact storage of many distinct tokens, see [`InternedCounts`](struct.InternedCounts.html)
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::io::BufRead;
+
+use crate::decode::{read_lines, Transcoder};
+use crate::tally::Scanner;
+use crate::{CountConfig, CountError, Frequencies};
+
+/// id of an interned string, valid only for the [`Interner`](struct.Interner.html) which made it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Id(u32);
+
+impl Id {
+    /// index of the id, ids are numbered from 0 in the order of interning
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// marks an empty slot of the table
+const EMPTY: u32 = u32::MAX;
+
+/// set of strings stored in a single buffer, each identified by an [`Id`](struct.Id.html)
+///
+/// a string costs its bytes and about 16 bytes, without an allocation per string.
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::intern::Interner;
+/// let mut interner = Interner::new();
+/// let a = interner.intern("aa");
+/// let b = interner.intern("bb");
+/// assert_eq!(interner.intern("aa"), a);
+/// assert_eq!(interner.resolve(b), "bb");
+/// assert_eq!(interner.get("cc"), None);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    text: String,
+    /// end offset in `text` of each string
+    ends: Vec<usize>,
+    /// open addressing table of ids, the length is 0 or a power of 2
+    table: Vec<u32>,
+    hasher: RandomState,
+}
+
+impl Interner {
+    /// empty interner
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// id of `s`, interning it if it is new
+    ///
+    /// # Panics
+    ///
+    /// more than `u32::MAX - 1` strings are interned
+    pub fn intern(&mut self, s: &str) -> Id {
+        if let Some(id) = self.get(s) {
+            return id;
+        }
+        if (self.ends.len() + 1) * 4 > self.table.len() * 3 {
+            self.grow();
+        }
+        let slot = self.slot(s);
+        let id = self.ends.len() as u32;
+        assert!(id != EMPTY, "too many strings are interned");
+        self.text.push_str(s);
+        self.ends.push(self.text.len());
+        self.table[slot] = id;
+        Id(id)
+    }
+
+    /// id of `s` if it is interned
+    pub fn get(&self, s: &str) -> Option<Id> {
+        if self.table.is_empty() {
+            return None;
+        }
+        match self.table[self.slot(s)] {
+            EMPTY => None,
+            id => Some(Id(id)),
+        }
+    }
+
+    /// the string of `id`
+    ///
+    /// # Panics
+    ///
+    /// `id` is not made by this interner
+    pub fn resolve(&self, id: Id) -> &str {
+        let i = id.index();
+        let start = if i == 0 { 0 } else { self.ends[i - 1] };
+        &self.text[start..self.ends[i]]
+    }
+
+    /// count of strings
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// no string is interned
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// all strings with their ids, in the order of interning
+    pub fn iter(&self) -> impl Iterator<Item = (Id, &str)> {
+        (0..self.ends.len() as u32).map(move |id| (Id(id), self.resolve(Id(id))))
+    }
+
+    /// slot of `s`, or the empty slot to put it in
+    fn slot(&self, s: &str) -> usize {
+        let mask = self.table.len() - 1;
+        let mut hasher = self.hasher.build_hasher();
+        s.hash(&mut hasher);
+        let mut slot = hasher.finish() as usize & mask;
+        loop {
+            let id = self.table[slot];
+            if id == EMPTY || self.resolve(Id(id)) == s {
+                return slot;
+            }
+            slot = (slot + 1) & mask;
+        }
+    }
+
+    fn grow(&mut self) {
+        let size = (self.table.len() * 2).max(16);
+        self.table = vec![EMPTY; size];
+        for id in 0..self.ends.len() as u32 {
+            let slot = self.slot(self.resolve(Id(id)));
+            self.table[slot] = id;
+        }
+    }
+}
+
+/// counts keyed by interned tokens, for inputs with very many distinct tokens
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::intern::count_interned;
+/// use bicycle_book_wordcount::CountConfig;
+/// let counts = count_interned(Cursor::new("aa bb aa"), &CountConfig::default()).unwrap();
+/// assert_eq!(counts.count("aa"), 2);
+/// let freqs = counts.resolve();
+/// assert_eq!(freqs["bb"], 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InternedCounts {
+    keys: Interner,
+    counts: Vec<usize>,
+}
+
+impl InternedCounts {
+    /// empty counts
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// add an occurrence of `token` (saturating at `usize::MAX`), returns its id
+    pub fn add(&mut self, token: &str) -> Id {
+        let id = self.keys.intern(token);
+        match self.counts.get_mut(id.index()) {
+            Some(count) => *count = count.saturating_add(1),
+            None => self.counts.push(1),
+        }
+        id
+    }
+
+    /// count of `token`, 0 if not counted
+    pub fn count(&self, token: &str) -> usize {
+        self.keys.get(token).map_or(0, |id| self.counts[id.index()])
+    }
+
+    /// the interned tokens
+    pub fn keys(&self) -> &Interner {
+        &self.keys
+    }
+
+    /// count of an id of [`keys`](#method.keys)
+    pub fn count_of(&self, id: Id) -> usize {
+        self.counts[id.index()]
+    }
+
+    /// all tokens with their counts, in the order first seen
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.keys
+            .iter()
+            .map(move |(id, token)| (token, self.counts[id.index()]))
+    }
+
+    /// convert to [`Frequencies`](../frequencies/struct.Frequencies.html), allocating each key
+    pub fn resolve(&self) -> Frequencies {
+        let mut freqs = Frequencies::new();
+        freqs.reserve(self.counts.len());
+        freqs.extend(self.iter().map(|(token, n)| (token.to_string(), n)));
+        freqs
+    }
+}
+
+/// same as [`try_count_with`](../fn.try_count_with.html), but keys are interned
+///
+/// `original_case` and `max_keys` of the config are not applied.
+pub fn count_interned(
+    input: impl BufRead,
+    config: &CountConfig,
+) -> Result<InternedCounts, CountError> {
+    let mut scanner = Scanner::new(config);
+    let mut counts = InternedCounts::new();
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        let mut overflowed = None;
+        scanner.line(line, |key, _| {
+            let id = counts.keys.intern(key);
+            match counts.counts.get_mut(id.index()) {
+                Some(count) => match config.overflow.add(*count, 1) {
+                    Some(n) => *count = n,
+                    None => overflowed = Some(key.to_string()),
+                },
+                None => counts.counts.push(1),
+            }
+        });
+        if let Some(token) = overflowed {
+            return Err(CountError::Overflow(token));
+        }
+        scanner.check_time()
+    })?;
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn interner_grows() {
+        let mut interner = Interner::new();
+        let ids: Vec<_> = (0..1000).map(|i| interner.intern(&i.to_string())).collect();
+        assert_eq!(interner.len(), 1000);
+        for (i, &id) in ids.iter().enumerate() {
+            assert_eq!(id.index(), i);
+            assert_eq!(interner.resolve(id), i.to_string());
+            assert_eq!(interner.get(&i.to_string()), Some(id));
+        }
+        assert_eq!(interner.intern(""), Id(1000));
+        assert_eq!(interner.resolve(Id(1000)), "");
+        assert_eq!(Interner::new().get(""), None);
+    }
+
+    #[test]
+    fn interned_strings_are_not_stored_again() {
+        let mut interner = Interner::new();
+        for i in 0..12 {
+            interner.intern(&i.to_string());
+        }
+        let (text, table) = (interner.text.len(), interner.table.len());
+        assert_eq!(interner.intern("0"), Id(0));
+        assert_eq!((interner.text.len(), interner.table.len()), (text, table));
+    }
+
+    #[test]
+    fn count_interned_equals_count_with() {
+        let text = "Aa bb aa\ncc Bb";
+        let config = CountConfig::default().ignore_case(true);
+        let counts = count_interned(Cursor::new(text), &config).unwrap();
+        assert_eq!(
+            counts.resolve(),
+            crate::count_with(Cursor::new(text), &config)
+        );
+        let order: Vec<_> = counts.iter().collect();
+        assert_eq!(order, vec![("aa", 2), ("bb", 2), ("cc", 1)]);
+
+        let input: &[u8] = b"\xef\xbb\xbfaa b\xffb\nB\xc3\xa9";
+        let config = config
+            .strip_diacritics(true)
+            .invalid_input(crate::decode::DecodePolicy::SkipLine);
+        let counts = count_interned(input, &config).unwrap();
+        assert_eq!(counts.resolve(), crate::count_with(input, &config));
+        assert_eq!(counts.count("be"), 1);
+        assert!(count_interned(&b"b\xffb"[..], &CountConfig::default()).is_err());
+    }
+}
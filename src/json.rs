@@ -0,0 +1,442 @@
+//! minimal JSON support for outputs, and for reading them back
+
+use std::fmt::Write;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// JSON string literal of `s`, with quotes
+pub(crate) fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                write!(out, "\\u{:04x}", c as u32).unwrap();
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// entries of an object from strings to non-negative integers, like `{"foo":42,"bar":1}`
+pub(crate) fn counts(text: &str) -> Result<Vec<(String, usize)>, String> {
+    let mut chars = text.chars().peekable();
+    let entries = parse_counts(&mut chars)?;
+    expect_end(&mut chars)?;
+    Ok(entries)
+}
+
+/// file and counts of an object like `{"file":"a.txt","counts":{"foo":42}}`, in any order
+pub(crate) fn file_counts(text: &str) -> Result<(String, Vec<(String, usize)>), String> {
+    let mut chars = text.chars().peekable();
+    let (mut file, mut counts) = (None, None);
+    expect(&mut chars, '{')?;
+    loop {
+        skip_whitespace(&mut chars);
+        let key = parse_string(&mut chars)?;
+        expect(&mut chars, ':')?;
+        skip_whitespace(&mut chars);
+        match key.as_str() {
+            "file" => file = Some(parse_string(&mut chars)?),
+            "counts" => counts = Some(parse_counts(&mut chars)?),
+            _ => return Err(format!("unexpected key {:?}", key)),
+        }
+        match skip_whitespace(&mut chars) {
+            Some(',') => {
+                chars.next();
+            }
+            Some('}') => {
+                chars.next();
+                break;
+            }
+            _ => return Err("expected ',' or '}'".to_string()),
+        }
+    }
+    expect_end(&mut chars)?;
+    match (file, counts) {
+        (Some(file), Some(counts)) => Ok((file, counts)),
+        _ => Err("expected \"file\" and \"counts\"".to_string()),
+    }
+}
+
+/// a value of metadata in [`counts_with_metadata`](fn.counts_with_metadata.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Value {
+    /// a string unescaped, or a number, boolean or null as written
+    Scalar(String),
+    Strings(Vec<String>),
+}
+
+/// keys and values of metadata, in the order written
+pub(crate) type Fields = Vec<(String, Value)>;
+
+/// metadata and counts of an object like
+/// `{"metadata":{"mode":"word","inputs":["a.txt"]},"counts":{"foo":42}}`, in any order. values
+/// of metadata are scalars or arrays of strings, and objects among them are skipped
+pub(crate) fn counts_with_metadata(text: &str) -> Result<(Fields, Vec<(String, usize)>), String> {
+    let mut chars = text.chars().peekable();
+    let (mut metadata, mut counts) = (None, None);
+    expect(&mut chars, '{')?;
+    loop {
+        skip_whitespace(&mut chars);
+        let key = parse_string(&mut chars)?;
+        expect(&mut chars, ':')?;
+        skip_whitespace(&mut chars);
+        match key.as_str() {
+            "metadata" => metadata = Some(parse_metadata(&mut chars)?),
+            "counts" => counts = Some(parse_counts(&mut chars)?),
+            _ => return Err(format!("unexpected key {:?}", key)),
+        }
+        match skip_whitespace(&mut chars) {
+            Some(',') => {
+                chars.next();
+            }
+            Some('}') => {
+                chars.next();
+                break;
+            }
+            _ => return Err("expected ',' or '}'".to_string()),
+        }
+    }
+    expect_end(&mut chars)?;
+    match (metadata, counts) {
+        (Some(metadata), Some(counts)) => Ok((metadata, counts)),
+        _ => Err("expected \"metadata\" and \"counts\"".to_string()),
+    }
+}
+
+/// value of `key` of an object like `{"level":"info","status":200}`: unescaped of a string,
+/// as written of a number, boolean or null. `None` if there is no such key, or its value is
+/// an object or an array. the first value is taken of a repeated key
+pub(crate) fn field(text: &str, key: &str) -> Result<Option<String>, String> {
+    let mut chars = text.chars().peekable();
+    let mut found = None;
+    expect(&mut chars, '{')?;
+    if skip_whitespace(&mut chars) == Some('}') {
+        chars.next();
+    } else {
+        loop {
+            skip_whitespace(&mut chars);
+            let name = parse_string(&mut chars)?;
+            expect(&mut chars, ':')?;
+            let value = parse_value(&mut chars)?;
+            if found.is_none() && name == key {
+                found = value;
+            }
+            match skip_whitespace(&mut chars) {
+                Some(',') => {
+                    chars.next();
+                }
+                Some('}') => {
+                    chars.next();
+                    break;
+                }
+                _ => return Err("expected ',' or '}'".to_string()),
+            }
+        }
+    }
+    expect_end(&mut chars)?;
+    Ok(found)
+}
+
+/// a value of any type, `None` of an object or an array, which is skipped
+fn parse_value(chars: &mut Input) -> Result<Option<String>, String> {
+    match skip_whitespace(chars) {
+        Some('"') => parse_string(chars).map(Some),
+        Some('{') | Some('[') => skip_nested(chars).map(|_| None),
+        Some(_) => {
+            let mut literal = String::new();
+            while let Some(&c) = chars.peek() {
+                if matches!(c, ',' | '}' | ']') || c.is_ascii_whitespace() {
+                    break;
+                }
+                literal.push(c);
+                chars.next();
+            }
+            let number = literal.starts_with(|c: char| c == '-' || c.is_ascii_digit());
+            match literal.as_str() {
+                "true" | "false" | "null" => Ok(Some(literal)),
+                _ if number => Ok(Some(literal)),
+                _ => Err(format!("unexpected {:?}", literal)),
+            }
+        }
+        None => Err("expected a value, found the end".to_string()),
+    }
+}
+
+/// skip an object or an array, of which strings may contain brackets
+fn skip_nested(chars: &mut Input) -> Result<(), String> {
+    let mut depth = 0;
+    loop {
+        match chars.peek() {
+            Some('"') => {
+                parse_string(chars)?;
+                continue;
+            }
+            Some('{') | Some('[') => depth += 1,
+            Some('}') | Some(']') => depth -= 1,
+            Some(_) => {}
+            None => return Err("unterminated object or array".to_string()),
+        }
+        chars.next();
+        if depth == 0 {
+            return Ok(());
+        }
+    }
+}
+
+fn parse_metadata(chars: &mut Input) -> Result<Fields, String> {
+    let mut entries = Vec::new();
+    expect(chars, '{')?;
+    if skip_whitespace(chars) == Some('}') {
+        chars.next();
+        return Ok(entries);
+    }
+    loop {
+        skip_whitespace(chars);
+        let key = parse_string(chars)?;
+        expect(chars, ':')?;
+        if skip_whitespace(chars) == Some('[') {
+            entries.push((key, Value::Strings(parse_strings(chars)?)));
+        } else if let Some(value) = parse_value(chars)? {
+            entries.push((key, Value::Scalar(value)));
+        }
+        match skip_whitespace(chars) {
+            Some(',') => {
+                chars.next();
+            }
+            Some('}') => {
+                chars.next();
+                return Ok(entries);
+            }
+            _ => return Err("expected ',' or '}'".to_string()),
+        }
+    }
+}
+
+fn parse_strings(chars: &mut Input) -> Result<Vec<String>, String> {
+    let mut strings = Vec::new();
+    expect(chars, '[')?;
+    if skip_whitespace(chars) == Some(']') {
+        chars.next();
+        return Ok(strings);
+    }
+    loop {
+        strings.push(parse_string(chars)?);
+        match skip_whitespace(chars) {
+            Some(',') => {
+                chars.next();
+            }
+            Some(']') => {
+                chars.next();
+                return Ok(strings);
+            }
+            _ => return Err("expected ',' or ']'".to_string()),
+        }
+    }
+}
+
+fn parse_counts(chars: &mut Input) -> Result<Vec<(String, usize)>, String> {
+    let mut entries = Vec::new();
+    expect(chars, '{')?;
+    if skip_whitespace(chars) == Some('}') {
+        chars.next();
+        return Ok(entries);
+    }
+    loop {
+        skip_whitespace(chars);
+        let key = parse_string(chars)?;
+        expect(chars, ':')?;
+        skip_whitespace(chars);
+        entries.push((key, parse_count(chars)?));
+        match skip_whitespace(chars) {
+            Some(',') => {
+                chars.next();
+            }
+            Some('}') => {
+                chars.next();
+                return Ok(entries);
+            }
+            _ => return Err("expected ',' or '}'".to_string()),
+        }
+    }
+}
+
+fn expect_end(chars: &mut Input) -> Result<(), String> {
+    match skip_whitespace(chars) {
+        None => Ok(()),
+        Some(c) => Err(format!("unexpected {:?} after the object", c)),
+    }
+}
+
+type Input<'a> = Peekable<Chars<'a>>;
+
+/// skip whitespace, and peek the next char
+fn skip_whitespace(chars: &mut Input) -> Option<char> {
+    while chars.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+        chars.next();
+    }
+    chars.peek().cloned()
+}
+
+fn expect(chars: &mut Input, expected: char) -> Result<(), String> {
+    match skip_whitespace(chars) {
+        Some(c) if c == expected => {
+            chars.next();
+            Ok(())
+        }
+        Some(c) => Err(format!("expected {:?}, found {:?}", expected, c)),
+        None => Err(format!("expected {:?}, found the end", expected)),
+    }
+}
+
+fn parse_string(chars: &mut Input) -> Result<String, String> {
+    expect(chars, '"')?;
+    let mut s = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(s),
+            Some('\\') => match chars.next() {
+                Some('"') => s.push('"'),
+                Some('\\') => s.push('\\'),
+                Some('/') => s.push('/'),
+                Some('b') => s.push('\u{8}'),
+                Some('f') => s.push('\u{c}'),
+                Some('n') => s.push('\n'),
+                Some('r') => s.push('\r'),
+                Some('t') => s.push('\t'),
+                Some('u') => s.push(parse_escape(chars)?),
+                _ => return Err("bad escape".to_string()),
+            },
+            Some(c) => s.push(c),
+            None => return Err("unterminated string".to_string()),
+        }
+    }
+}
+
+/// `XXXX` of `\uXXXX`, and the low surrogate if it is a high surrogate
+fn parse_escape(chars: &mut Input) -> Result<char, String> {
+    let hex = |chars: &mut Input| -> Result<u32, String> {
+        let digits: String = chars.take(4).collect();
+        u32::from_str_radix(&digits, 16).map_err(|_| "bad \\u escape".to_string())
+    };
+    let high = hex(chars)?;
+    let code = if (0xd800..0xdc00).contains(&high) {
+        if chars.next() != Some('\\') || chars.next() != Some('u') {
+            return Err("unpaired surrogate".to_string());
+        }
+        let low = hex(chars)?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err("unpaired surrogate".to_string());
+        }
+        0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+    } else {
+        high
+    };
+    std::char::from_u32(code).ok_or_else(|| "unpaired surrogate".to_string())
+}
+
+fn parse_count(chars: &mut Input) -> Result<usize, String> {
+    let mut digits = String::new();
+    while let Some(&c) = chars.peek() {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        digits.push(c);
+        chars.next();
+    }
+    digits
+        .parse()
+        .map_err(|_| "expected a non-negative integer".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn string_works() {
+        assert_eq!(string("foo"), r#""foo""#);
+        assert_eq!(string("a\"b\\c\nd\u{1}"), r#""a\"b\\c\nd\u0001""#);
+        assert_eq!(string("日本"), "\"日本\"");
+    }
+
+    #[test]
+    fn counts_works() {
+        let pair = |k: &str, n| (k.to_string(), n);
+        assert_eq!(
+            counts(" {\"a\" : 1,\n\"b\\\"\\u00e9\\ud83d\\ude00\":22 }\n"),
+            Ok(vec![pair("a", 1), pair("b\"é😀", 22)])
+        );
+        assert_eq!(counts("{}"), Ok(vec![]));
+        for bad in &[
+            "",
+            "{",
+            "{\"a\":-1}",
+            "{\"a\":1,}",
+            "{\"a\":1} x",
+            "{\"\\ud800\":1}",
+        ] {
+            assert!(counts(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn file_counts_works() {
+        let expected = ("a.txt".to_string(), vec![("x".to_string(), 2)]);
+        assert_eq!(
+            file_counts("{\"file\":\"a.txt\",\"counts\":{\"x\":2}}"),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            file_counts("{ \"counts\": {\"x\": 2}, \"file\": \"a.txt\" }"),
+            Ok(expected)
+        );
+        assert!(file_counts("{\"file\":\"a.txt\"}").is_err());
+        assert!(file_counts("{\"file\":1,\"counts\":{}}").is_err());
+    }
+
+    #[test]
+    fn counts_with_metadata_works() {
+        let text = r#"{"metadata":{"mode":"word","at":7,"x":{"y":[]},"inputs":["a","b"]},
+            "counts":{"x":2}}"#;
+        let (metadata, counts) = counts_with_metadata(text).unwrap();
+        assert_eq!(
+            metadata,
+            vec![
+                ("mode".to_string(), Value::Scalar("word".to_string())),
+                ("at".to_string(), Value::Scalar("7".to_string())),
+                (
+                    "inputs".to_string(),
+                    Value::Strings(vec!["a".to_string(), "b".to_string()])
+                ),
+            ]
+        );
+        assert_eq!(counts, vec![("x".to_string(), 2)]);
+        assert!(counts_with_metadata(r#"{"counts":{"x":2}}"#).is_err());
+        assert!(counts_with_metadata(r#"{"x":2}"#).is_err());
+        assert!(counts_with_metadata(r#"{"metadata":{"a":[1]},"counts":{}}"#).is_err());
+    }
+
+    #[test]
+    fn field_works() {
+        let line =
+            r#"{"ctx":{"level":"x","a":["}"]},"level":"warn","n":-1.5,"ok":true,"level":"info"}"#;
+        assert_eq!(field(line, "level"), Ok(Some("warn".to_string())));
+        assert_eq!(field(line, "n"), Ok(Some("-1.5".to_string())));
+        assert_eq!(field(line, "ok"), Ok(Some("true".to_string())));
+        assert_eq!(field(line, "ctx"), Ok(None));
+        assert_eq!(field(line, "user"), Ok(None));
+        assert_eq!(field(" { } ", "level"), Ok(None));
+        assert!(field(r#"{"level":info}"#, "level").is_err());
+        assert!(field(r#"{"a":[1,2}"#, "a").is_err());
+        assert!(field("level=info", "level").is_err());
+    }
+}
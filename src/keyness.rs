@@ -0,0 +1,131 @@
+//! what is distinctive about a text compared to a reference corpus, see [`keyness`](fn.keyness.html)
+
+use std::cmp::Ordering;
+
+use crate::Frequencies;
+
+/// statistic of [`keyness`](fn.keyness.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Measure {
+    /// Dunning's log-likelihood (G²)
+    LogLikelihood,
+    /// Pearson's chi-square of the 2×2 contingency table
+    ChiSquare,
+}
+
+/// option default value
+impl Default for Measure {
+    fn default() -> Self {
+        Measure::LogLikelihood
+    }
+}
+
+/// keyness of a token
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyword {
+    pub token: String,
+    /// the statistic, negative if the token is relatively less frequent in the target
+    pub score: f64,
+    /// count in the target
+    pub target: usize,
+    /// count in the reference
+    pub reference: usize,
+}
+
+/// keyness of each token of `target` or `reference`, from the most distinctive of the target
+/// (the largest score) to the most distinctive of the reference (the smallest score).
+/// ties are ordered by token
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::keyness::{keyness, Measure};
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let target = count_str("the whale the sea the whale", CountOption::Word);
+/// let reference = count_str("the cat the dog the sea the house", CountOption::Word);
+/// let keywords = keyness(&target, &reference, Measure::LogLikelihood);
+/// assert_eq!(keywords[0].token, "whale");
+/// assert_eq!(keywords[0].target, 2);
+/// assert!(keywords.last().unwrap().score < 0.0);
+/// ```
+pub fn keyness(target: &Frequencies, reference: &Frequencies, measure: Measure) -> Vec<Keyword> {
+    let c = target.total() as f64;
+    let d = reference.total() as f64;
+    let tokens = target.keys().chain(
+        reference
+            .keys()
+            .filter(|token| !target.contains_key(*token)),
+    );
+    let mut keywords: Vec<_> = tokens
+        .map(|token| {
+            let count = |freqs: &Frequencies| freqs.get(token).cloned().unwrap_or(0);
+            let (a, b) = (count(target), count(reference));
+            let score = match measure {
+                Measure::LogLikelihood => log_likelihood(a as f64, b as f64, c, d),
+                Measure::ChiSquare => chi_square(a as f64, b as f64, c, d),
+            };
+            let less = (a as f64) * d < (b as f64) * c;
+            Keyword {
+                token: token.clone(),
+                score: if less { -score } else { score },
+                target: a,
+                reference: b,
+            }
+        })
+        .collect();
+    keywords.sort_by(|x, y| {
+        y.score
+            .partial_cmp(&x.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| x.token.cmp(&y.token))
+    });
+    keywords
+}
+
+/// G² of counts `a` and `b` in corpora of `c` and `d` tokens
+fn log_likelihood(a: f64, b: f64, c: f64, d: f64) -> f64 {
+    let e1 = c * (a + b) / (c + d);
+    let e2 = d * (a + b) / (c + d);
+    let term = |o: f64, e: f64| if o > 0.0 { o * (o / e).ln() } else { 0.0 };
+    2.0 * (term(a, e1) + term(b, e2))
+}
+
+/// χ² of counts `a` and `b` in corpora of `c` and `d` tokens
+fn chi_square(a: f64, b: f64, c: f64, d: f64) -> f64 {
+    let denominator = (a + b) * (c + d - a - b) * c * d;
+    if denominator == 0.0 {
+        return 0.0;
+    }
+    let difference = a * (d - b) - b * (c - a);
+    (c + d) * difference * difference / denominator
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn statistics_work() {
+        // 10 in 1000 tokens vs 20 in 10000 tokens
+        assert!((log_likelihood(10.0, 20.0, 1000.0, 10000.0) - 13.579).abs() < 0.001);
+        assert!((chi_square(10.0, 20.0, 1000.0, 10000.0) - 21.392).abs() < 0.001);
+        assert_eq!(log_likelihood(5.0, 50.0, 100.0, 1000.0), 0.0);
+        assert_eq!(chi_square(0.0, 0.0, 10.0, 10.0), 0.0);
+    }
+
+    #[test]
+    fn keyness_is_signed() {
+        let target: Frequencies = vec![("a".to_string(), 8), ("b".to_string(), 2)]
+            .into_iter()
+            .collect();
+        let reference: Frequencies = vec![("a".to_string(), 5), ("c".to_string(), 5)]
+            .into_iter()
+            .collect();
+        let keywords = keyness(&target, &reference, Measure::ChiSquare);
+        let tokens: Vec<_> = keywords.iter().map(|k| k.token.as_str()).collect();
+        assert_eq!(tokens, vec!["b", "a", "c"]);
+        assert!(keywords[0].score > 0.0);
+        assert!(keywords[2].score < 0.0);
+        assert_eq!(keywords[2].reference, 5);
+    }
+}
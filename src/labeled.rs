@@ -0,0 +1,124 @@
+//! counts of many inputs kept apart by a label of each (a file name, a URL, "stdin"), to tell
+//! which inputs contributed a token to the total, see [`LabeledCounts`](struct.LabeledCounts.html)
+
+use std::cmp::Reverse;
+use std::io::BufRead;
+
+use crate::{try_count_with, CountConfig, CountError, Frequencies};
+
+/// sum of counts of inputs, and counts of each input by its label
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::labeled::LabeledCounts;
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let mut counts = LabeledCounts::new();
+/// counts.add("a.txt", &count_str("aa bb", CountOption::Word));
+/// counts.add("https://example.com/", &count_str("aa aa aa", CountOption::Word));
+/// counts.add("stdin", &count_str("bb", CountOption::Word));
+/// assert_eq!(counts.total()["aa"], 4);
+/// assert_eq!(
+///     counts.contributions("aa"),
+///     vec![("https://example.com/", 3), ("a.txt", 1)]
+/// );
+/// assert_eq!(counts.top_contributor("bb"), Some(("a.txt", 1)));
+/// assert_eq!(counts.get("stdin").unwrap()["bb"], 1);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabeledCounts {
+    total: Frequencies,
+    /// counts of each label, in the order of addition
+    inputs: Vec<(String, Frequencies)>,
+}
+
+impl LabeledCounts {
+    pub fn new() -> Self {
+        LabeledCounts::default()
+    }
+
+    /// add counts of an input labeled `label`. counts of a label added before are merged
+    pub fn add(&mut self, label: impl Into<String>, counts: &Frequencies) {
+        self.total.merge(counts);
+        let label = label.into();
+        match self.inputs.iter_mut().find(|(l, _)| *l == label) {
+            Some((_, freqs)) => freqs.merge(counts),
+            None => self.inputs.push((label, counts.clone())),
+        }
+    }
+
+    /// sum of counts of all inputs
+    pub fn total(&self) -> &Frequencies {
+        &self.total
+    }
+
+    /// labels in the order of addition
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.inputs.iter().map(|(label, _)| label.as_str())
+    }
+
+    /// counts of the input labeled `label`
+    pub fn get(&self, label: &str) -> Option<&Frequencies> {
+        self.inputs
+            .iter()
+            .find(|(l, _)| l == label)
+            .map(|(_, freqs)| freqs)
+    }
+
+    /// labels of inputs containing `token` with its count in each, the largest count first
+    /// (ties in the order of addition)
+    pub fn contributions(&self, token: &str) -> Vec<(&str, usize)> {
+        let mut counts: Vec<_> = self
+            .inputs
+            .iter()
+            .filter_map(|(label, freqs)| freqs.get(token).map(|&n| (label.as_str(), n)))
+            .collect();
+        counts.sort_by_key(|&(_, n)| Reverse(n));
+        counts
+    }
+
+    /// the label of the input containing `token` the most times, and its count
+    pub fn top_contributor(&self, token: &str) -> Option<(&str, usize)> {
+        self.contributions(token).into_iter().next()
+    }
+
+    /// the labels and their counts
+    pub fn into_inner(self) -> Vec<(String, Frequencies)> {
+        self.inputs
+    }
+}
+
+/// counts of inputs by their labels, by [`try_count_with`](../fn.try_count_with.html)
+pub fn count_labeled<L: Into<String>, R: BufRead>(
+    inputs: impl IntoIterator<Item = (L, R)>,
+    config: &CountConfig,
+) -> Result<LabeledCounts, CountError> {
+    let mut counts = LabeledCounts::new();
+    for (label, input) in inputs {
+        counts.add(label, &try_count_with(input, config)?);
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn count_labeled_works() {
+        let inputs = vec![
+            ("a", Cursor::new("x y")),
+            ("b", Cursor::new("y y")),
+            ("a", Cursor::new("y")),
+        ];
+        let counts = count_labeled(inputs, &CountConfig::default()).unwrap();
+        assert_eq!(counts.labels().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(counts.total()["y"], 4);
+        assert_eq!(counts.contributions("y"), vec![("a", 2), ("b", 2)]);
+        assert_eq!(counts.contributions("x"), vec![("a", 1)]);
+        assert_eq!(counts.top_contributor("z"), None);
+        assert_eq!(counts.get("c"), None);
+        assert_eq!(counts.into_inner()[1].1["y"], 2);
+    }
+}
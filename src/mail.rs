@@ -0,0 +1,198 @@
+//! counting of email in mbox or EML files, see [`count_mail`](fn.count_mail.html)
+
+use std::io::BufRead;
+
+use crate::decode::{read_lines, Transcoder};
+use crate::tally::Tally;
+use crate::{CountConfig, CountError, Frequencies};
+
+/// part of messages to be counted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Part {
+    /// values of headers of the names (case-insensitive), or of all headers if no names
+    Headers(Vec<String>),
+    /// body text, without quoted lines of replies (starting with `>`) if `strip_quotes`
+    Body { strip_quotes: bool },
+}
+
+/// result of [`count_mail`](fn.count_mail.html)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mail {
+    /// count of messages
+    pub messages: usize,
+    pub freqs: Frequencies,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Start,
+    Headers,
+    Body,
+}
+
+/// count a part of messages of an mbox file, or of an EML file of a message
+///
+/// messages of mbox start at `From ` lines, at the start of input or after an empty line.
+/// header lines folded with leading whitespace are joined, and `>From ` lines of bodies are
+/// unescaped. bodies are counted as they are, MIME parts are not decoded.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::mail::{count_mail, Part};
+/// use bicycle_book_wordcount::CountConfig;
+/// let mbox = "From alice Mon Oct 14 10:00:00 2024\n\
+///             Subject: release plan\n\
+///             \n\
+///             ship it\n\
+///             \n\
+///             From bob Mon Oct 14 11:00:00 2024\n\
+///             Subject: Re: release plan\n\
+///             \n\
+///             > ship it\n\
+///             agreed\n";
+/// let config = CountConfig::default();
+/// let subjects = Part::Headers(vec!["subject".to_string()]);
+/// let mail = count_mail(Cursor::new(mbox), &config, &subjects).unwrap();
+/// assert_eq!(mail.messages, 2);
+/// assert_eq!(mail.freqs["release"], 2);
+/// let body = Part::Body { strip_quotes: true };
+/// let mail = count_mail(Cursor::new(mbox), &config, &body).unwrap();
+/// assert_eq!(mail.freqs["ship"], 1);
+/// assert_eq!(mail.freqs["agreed"], 1);
+/// ```
+pub fn count_mail(
+    input: impl BufRead,
+    config: &CountConfig,
+    part: &Part,
+) -> Result<Mail, CountError> {
+    let names: Vec<_> = match part {
+        Part::Headers(names) => names.iter().map(|name| name.to_lowercase()).collect(),
+        Part::Body { .. } => Vec::new(),
+    };
+    let counted = |name: &str| names.is_empty() || names.contains(&name.to_lowercase());
+    let mut tally = Tally::new(config);
+    let mut messages = 0;
+    let mut state = State::Start;
+    let mut previous_empty = true;
+    // name and value of the header being read, which may be folded
+    let mut header: Option<(String, String)> = None;
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        let empty = std::mem::replace(&mut previous_empty, line.is_empty());
+        if line.starts_with("From ") && empty && state != State::Headers {
+            messages += 1;
+            state = State::Headers;
+            return Ok(());
+        }
+        if state == State::Start {
+            // an EML file, or an mbox without a leading `From ` line
+            messages += 1;
+            state = State::Headers;
+        }
+        match (state, part) {
+            (State::Headers, _) if line.starts_with(&[' ', '\t'][..]) => {
+                if let Some((_, value)) = &mut header {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                Ok(())
+            }
+            (State::Headers, _) => {
+                if let Some((name, value)) = header.take() {
+                    if let Part::Headers(_) = part {
+                        if counted(&name) {
+                            tally.line(&value)?;
+                        }
+                    }
+                }
+                if line.is_empty() {
+                    state = State::Body;
+                } else if let Some(i) = line.find(':') {
+                    header = Some((line[..i].to_string(), line[i + 1..].trim().to_string()));
+                }
+                Ok(())
+            }
+            (_, Part::Body { strip_quotes }) => {
+                let unescaped = line.trim_start_matches('>').starts_with("From ");
+                let line = if unescaped && line.starts_with('>') {
+                    &line[1..]
+                } else {
+                    line
+                };
+                if *strip_quotes && line.starts_with('>') {
+                    return Ok(());
+                }
+                tally.line(line)
+            }
+            (_, Part::Headers(_)) => Ok(()),
+        }
+    })?;
+    if let (Some((name, value)), Part::Headers(_)) = (header, part) {
+        if counted(&name) {
+            tally.line(&value)?;
+        }
+    }
+    Ok(Mail {
+        messages,
+        freqs: tally.finish(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CountOption;
+    use std::io::Cursor;
+
+    const EML: &str = "From: Alice <alice@example.com>\r\n\
+                       To: list@example.com\r\n\
+                       Subject: a long\r\n\
+                       \tsubject\r\n\
+                       \r\n\
+                       body line\r\n\
+                       >From the start\r\n\
+                       > quoted\r\n";
+
+    #[test]
+    fn eml_headers_are_counted() {
+        let config = CountConfig::new(CountOption::Line);
+        let from = Part::Headers(vec!["FROM".to_string()]);
+        let mail = count_mail(Cursor::new(EML), &config, &from).unwrap();
+        assert_eq!(mail.messages, 1);
+        assert_eq!(mail.freqs.len(), 1);
+        assert_eq!(mail.freqs["Alice <alice@example.com>"], 1);
+        let all = count_mail(Cursor::new(EML), &config, &Part::Headers(vec![])).unwrap();
+        assert_eq!(all.freqs.len(), 3);
+        assert_eq!(all.freqs["a long subject"], 1);
+    }
+
+    #[test]
+    fn bodies_are_unescaped() {
+        let config = CountConfig::new(CountOption::Line);
+        let body = Part::Body {
+            strip_quotes: false,
+        };
+        let mail = count_mail(Cursor::new(EML), &config, &body).unwrap();
+        assert_eq!(mail.freqs.len(), 3);
+        assert_eq!(mail.freqs["From the start"], 1);
+        assert_eq!(mail.freqs["> quoted"], 1);
+        let body = Part::Body { strip_quotes: true };
+        let mail = count_mail(Cursor::new(EML), &config, &body).unwrap();
+        assert_eq!(mail.freqs.get("> quoted"), None);
+        assert_eq!(mail.freqs["From the start"], 1);
+    }
+
+    #[test]
+    fn from_lines_in_bodies_do_not_split() {
+        let mbox = "From a\nSubject: x\n\nsee\nFrom here on\n";
+        let body = Part::Body {
+            strip_quotes: false,
+        };
+        let mail = count_mail(Cursor::new(mbox), &CountConfig::default(), &body).unwrap();
+        assert_eq!(mail.messages, 1);
+        assert_eq!(mail.freqs["here"], 1);
+        let empty = count_mail(Cursor::new(""), &CountConfig::default(), &body).unwrap();
+        assert_eq!(empty, Mail::default());
+    }
+}
@@ -1,14 +1,299 @@
+mod cli;
+
 use std::env;
+use std::ffi::{OsStr, OsString};
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bicycle_book_wordcount::diff::Diff;
+use bicycle_book_wordcount::heavy::approx_top;
+use bicycle_book_wordcount::output::write_json_lines;
+use bicycle_book_wordcount::{count, CountConfig, Frequencies};
+
+use cli::metrics::Metrics;
+
+const USAGE: &str =
+    "usage: wordcount count [--format json|jsonl|debug|csv|latex|markdown|html|trie|snapshot|parquet|bulk]
+                       [--index NAME] [--id TEMPLATE] [-o FILE] [-n N] [--config FILE] [--include-binary] [--saved] [--detect-encoding]
+                       [--sample RATE [--seed N]] [--df FILE] [--access-log FIELD]
+                       [--log-key KEY]
+                       [--clipboard] [--per-file] [--encoding ENCODING] [--fold-typos]
+                       [--exclude-pattern REGEX]...
+                       [--check-quality] [--symlinks follow|skip|error] [--jobs N]
+                       [--by-extension] [--min-words N] [--max-words N] [--partial-on-interrupt]
+                       [--metadata] [--columns COLUMNS] [--precision N] FILE...
+       wordcount top [-n N] [--percent | --bytes]
+                     [--format json|jsonl|debug|csv|latex|markdown|html|trie|snapshot|parquet|bulk]
+                     [--encoding ENCODING] [--columns COLUMNS] [--precision N]
+                     [--numbers STYLE] FILE
+       wordcount merge [-o FILE] FILE...
+       wordcount merge OUT.bin FILE...
+       wordcount diff OLD NEW
+       wordcount compare [-n N] A B
+       wordcount unknown [--config FILE] DICTIONARY FILE...
+       wordcount stats [--numbers STYLE] [--unwrap] FILE...
+       wordcount uniq [FILE]
+       wordcount duplicates [--normalize-whitespace] [--min-chars N] FILE...
+       wordcount columns PATTERN [FILE]...
+       wordcount progress [--config FILE] [--save] BASELINE FILE...
+       wordcount [--format debug|jsonl] FILENAME
+       wordcount --follow [--top N] [--metrics ADDR] FILENAME
+       wordcount --approx-top K FILENAME
+       wordcount --diff OLD NEW
+       wordcount serve [--addr ADDR] [--allow-files DIR]
+       wordcount tui FILE
+       wordcount repl [--delimiter LINE]
+       wordcount completions bash|zsh|fish
+       wordcount man";
 
-use bicycle_book_wordcount::count;
+/// counters per token of `--approx-top`
+const APPROX_CAPACITY: usize = 10;
+
+/// output format of counts
+enum Format {
+    Debug,
+    JsonLines,
+}
 
 fn main() {
-    let filename = env::args().nth(1).expect("1 argument FILENAME required");
+    // not `env::args`, which panics on paths which are not UTF-8. a path is not a subcommand
+    let name = env::args_os().nth(1).and_then(string).unwrap_or_default();
+    if cli::command::NAMES.contains(&name.as_str()) {
+        let command = cli::command::Command::parse(&name, env::args_os().skip(2));
+        match command.unwrap_or_else(|| usage()).run() {
+            Ok(report) => {
+                if report.interrupted {
+                    eprintln!(
+                        "wordcount {}: interrupted, counts are of {} files counted",
+                        name, report.counted
+                    );
+                }
+                if !report.failures.is_empty() {
+                    eprint!("wordcount {}: {}", name, report);
+                }
+                for violation in &report.out_of_bounds {
+                    eprintln!("wordcount {}: {}", name, violation);
+                }
+                if report.exit_code() != 0 {
+                    process::exit(report.exit_code());
+                }
+            }
+            // the reader of stdout has gone, e.g. of `| head`
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+            Err(e) => {
+                eprintln!("wordcount {}: {}", name, e);
+                process::exit(cli::command::EXIT_FAILURE);
+            }
+        }
+        return;
+    }
+    if name == "completions" || name == "man" {
+        let output = match (name.as_str(), arg(2)) {
+            ("completions", Some(shell)) => cli::completions::script(&shell),
+            ("man", None) => Some(cli::completions::man_page()),
+            _ => None,
+        };
+        print!("{}", output.unwrap_or_else(|| usage()));
+        return;
+    }
+    if name == "tui" {
+        let file = match (env::args_os().nth(2), env::args_os().nth(3)) {
+            (Some(file), None) => PathBuf::from(file),
+            _ => usage(),
+        };
+        if let Err(e) = cli::tui::run(&file, &CountConfig::default()) {
+            eprintln!("wordcount tui: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+    if name == "serve" {
+        serve(env::args_os().skip(2));
+        return;
+    }
+    if name == "repl" {
+        let delimiter = match (arg(2).as_deref(), arg(3), arg(4)) {
+            (None, _, _) => cli::repl::DELIMITER.to_string(),
+            (Some("--delimiter"), Some(delimiter), None) => delimiter,
+            _ => usage(),
+        };
+        if let Err(e) = cli::repl::run(&delimiter) {
+            eprintln!("wordcount repl: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let mut format = Format::Debug;
+    let mut diff = false;
+    let mut follow = false;
+    let mut top = 10;
+    let mut approx = None;
+    let mut metrics_addr = None;
+    let mut files = Vec::new();
+
+    let mut args = env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--diff") => diff = true,
+            Some("--follow") => follow = true,
+            Some("--top") => {
+                top = match args.next().and_then(|n| n.to_str()?.parse().ok()) {
+                    Some(n) => n,
+                    None => usage(),
+                }
+            }
+            Some("--metrics") => {
+                metrics_addr = Some(args.next().and_then(string).unwrap_or_else(|| usage()))
+            }
+            Some("--approx-top") => {
+                approx = match args.next().and_then(|k| k.to_str()?.parse().ok()) {
+                    Some(k) if k > 0 => Some(k),
+                    _ => usage(),
+                }
+            }
+            Some("--format") => {
+                format = match args.next().as_deref().and_then(OsStr::to_str) {
+                    Some("debug") => Format::Debug,
+                    Some("jsonl") => Format::JsonLines,
+                    _ => usage(),
+                }
+            }
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+
+    if follow {
+        match files.as_slice() {
+            [filename] => {
+                let interval = Duration::from_secs(1);
+                let metrics = Arc::new(Metrics::default());
+                if let Some(addr) = metrics_addr {
+                    if let Err(e) = cli::serve::spawn_metrics(&addr, Arc::clone(&metrics)) {
+                        eprintln!("failed to serve metrics on {}: {}", addr, e);
+                        process::exit(1);
+                    }
+                }
+                let config = CountConfig::default();
+                if let Err(e) = cli::follow::run(filename, &config, top, interval, &metrics) {
+                    eprintln!("failed to follow {}: {}", filename.display(), e);
+                    process::exit(1);
+                }
+            }
+            _ => usage(),
+        }
+        return;
+    }
+
+    if let Some(k) = approx {
+        match files.as_slice() {
+            [filename] => {
+                let reader = match File::open(filename) {
+                    Ok(file) => BufReader::new(file),
+                    Err(e) => fail(filename, e),
+                };
+                // enough counters that the top k are rarely evicted
+                let top = approx_top(reader, &CountConfig::default(), k, k * APPROX_CAPACITY)
+                    .unwrap_or_else(|e| fail(filename, e));
+                let mut out = io::stdout().lock();
+                let written = top.iter().try_for_each(|estimate| {
+                    writeln!(
+                        out,
+                        "{}\t±{}\t{}",
+                        estimate.count, estimate.error, estimate.token
+                    )
+                });
+                exit_on_error(written);
+            }
+            _ => usage(),
+        }
+        return;
+    }
+
+    let mut out = io::stdout().lock();
+    let written = match (diff, files.as_slice()) {
+        (true, [old, new]) => {
+            let diff = Diff::between(&count_file(old), &count_file(new));
+            write!(out, "{}", diff)
+        }
+        (false, [filename]) => {
+            let freqs = count_file(filename);
+            match format {
+                Format::Debug => writeln!(out, "{:?}", freqs.debug_sorted()),
+                Format::JsonLines => write_json_lines(&freqs, out),
+            }
+        }
+        _ => usage(),
+    };
+    exit_on_error(written);
+}
+
+/// exit with 1 on an error writing stdout, but quietly with 0 if its reader has gone (e.g. of
+/// `| head`), instead of panicking as `println!`
+fn exit_on_error(written: io::Result<()>) {
+    match written {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+        Err(e) => {
+            eprintln!("wordcount: {}", e);
+            process::exit(1);
+        }
+        Ok(()) => {}
+    }
+}
+
+/// report an error of reading `file` and exit
+fn fail(file: &Path, e: io::Error) -> ! {
+    eprintln!("wordcount: {}: {}", file.display(), e);
+    process::exit(1)
+}
+
+fn serve(mut args: impl Iterator<Item = OsString>) {
+    let mut addr = "127.0.0.1:8080".to_string();
+    let mut options = cli::serve::Options::default();
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--addr") => addr = args.next().and_then(string).unwrap_or_else(|| usage()),
+            Some("--allow-files") => {
+                let dir = PathBuf::from(args.next().unwrap_or_else(|| usage()));
+                match dir.canonicalize() {
+                    Ok(dir) => options.files = Some(dir),
+                    Err(e) => {
+                        eprintln!("{}: {}", dir.display(), e);
+                        process::exit(1);
+                    }
+                }
+            }
+            _ => usage(),
+        }
+    }
+    if let Err(e) = cli::serve::run(&addr, options) {
+        eprintln!("failed to serve on {}: {}", addr, e);
+        process::exit(1);
+    }
+}
+
+/// the argument `n`, which is not a path, so it is UTF-8 or else wrong
+fn arg(n: usize) -> Option<String> {
+    env::args_os()
+        .nth(n)
+        .map(|arg| string(arg).unwrap_or_else(|| usage()))
+}
+
+fn string(arg: OsString) -> Option<String> {
+    arg.into_string().ok()
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn count_file(filename: &Path) -> Frequencies {
     let file = File::open(filename).unwrap();
     let reader = BufReader::new(&file);
-    let freqs = count(reader, Default::default());
-    println!("{:?}", freqs);
+    count(reader, Default::default())
 }
-
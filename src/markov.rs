@@ -0,0 +1,161 @@
+//! counts of tokens following each token, for Markov text generation and predictive text,
+//! see [`count_with_transitions`](fn.count_with_transitions.html)
+
+use std::collections::{hash_map, HashMap};
+use std::io::BufRead;
+
+use crate::decode::{read_lines, Transcoder};
+use crate::rng::Rng;
+use crate::tally::Tally;
+use crate::{CountConfig, CountError, Frequencies};
+
+/// token => counts of the tokens right after it, a Markov chain of the first order
+///
+/// keys are normalized as configured, but before `original_case` is applied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transitions {
+    map: HashMap<String, Frequencies>,
+}
+
+impl Transitions {
+    /// no transitions
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// count `to` right after `from` once more (saturating at `usize::MAX`)
+    pub fn add(&mut self, from: &str, to: &str) {
+        let next = match self.map.get_mut(from) {
+            Some(next) => next,
+            None => self.map.entry(from.to_string()).or_default(),
+        };
+        match next.get_mut(to) {
+            Some(count) => *count = count.saturating_add(1),
+            None => {
+                next.insert(to.to_string(), 1);
+            }
+        }
+    }
+
+    /// counts of the tokens right after `token`, `None` if it is never followed
+    pub fn following(&self, token: &str) -> Option<&Frequencies> {
+        self.map.get(token)
+    }
+
+    /// share of `to` in the tokens right after `from`, 0 if `from` is never followed
+    pub fn probability(&self, from: &str, to: &str) -> f64 {
+        match self.map.get(from) {
+            Some(next) => next
+                .get(to)
+                .map_or(0.0, |&n| n as f64 / next.total() as f64),
+            None => 0.0,
+        }
+    }
+
+    /// count of distinct tokens followed by any token
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// tokens with counts of the tokens after them, in arbitrary order
+    pub fn iter(&self) -> hash_map::Iter<'_, String, Frequencies> {
+        self.map.iter()
+    }
+
+    /// up to `max` tokens starting with `start`, each drawn after the previous one weighted by
+    /// the counts (see [`Frequencies::sample`](../frequencies/struct.Frequencies.html#method.sample)).
+    /// it ends early at a token never followed
+    pub fn generate<'a>(&'a self, start: &'a str, rng: &mut Rng, max: usize) -> Vec<&'a str> {
+        let mut tokens = Vec::new();
+        let mut token = start;
+        while tokens.len() < max {
+            tokens.push(token);
+            token = match self
+                .map
+                .get(token)
+                .and_then(|next| next.sample(rng, 1).pop())
+            {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        tokens
+    }
+}
+
+/// count tokens of input as configured and, in the same pass, the transitions from each token
+/// to the next one. transitions go across lines, as sentences may be wrapped
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::markov::count_with_transitions;
+/// use bicycle_book_wordcount::rng::Rng;
+/// use bicycle_book_wordcount::CountConfig;
+/// let text = "the cat sat\nthe cat ran\nthe dog sat";
+/// let (freqs, transitions) = count_with_transitions(Cursor::new(text), &CountConfig::default()).unwrap();
+/// assert_eq!(freqs["the"], 3);
+/// assert_eq!(transitions.following("the").unwrap()["cat"], 2);
+/// assert_eq!(transitions.following("sat").unwrap()["the"], 1);
+/// assert!((transitions.probability("the", "dog") - 1.0 / 3.0).abs() < 1e-9);
+///
+/// let generated = transitions.generate("dog", &mut Rng::new(1), 3);
+/// assert_eq!(generated, vec!["dog", "sat", "the"]);
+/// ```
+pub fn count_with_transitions(
+    input: impl BufRead,
+    config: &CountConfig,
+) -> Result<(Frequencies, Transitions), CountError> {
+    let mut tally = Tally::new(config);
+    let mut transitions = Transitions::new();
+    let mut previous = String::new();
+    let mut first = true;
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        tally.line_with(line, |key| {
+            if !first {
+                transitions.add(&previous, key);
+            }
+            first = false;
+            previous.clear();
+            previous.push_str(key);
+        })
+    })?;
+    Ok((tally.finish(), transitions))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CountOption;
+    use std::io::Cursor;
+
+    #[test]
+    fn transitions_follow_filtered_tokens() {
+        let config = CountConfig::new(CountOption::Word)
+            .ignore_case(true)
+            .stop_words(&["a"]);
+        let text = "A Cat and a cat\n\nand a dog";
+        let (freqs, transitions) = count_with_transitions(Cursor::new(text), &config).unwrap();
+        assert_eq!(freqs["cat"], 2);
+        assert_eq!(transitions.len(), 2);
+        assert_eq!(transitions.following("cat").unwrap()["and"], 2);
+        assert_eq!(transitions.following("and").unwrap().total(), 2);
+        assert_eq!(transitions.following("dog"), None);
+        assert_eq!(transitions.probability("and", "dog"), 0.5);
+        assert_eq!(transitions.probability("dog", "cat"), 0.0);
+
+        let generated = transitions.generate("dog", &mut Rng::new(3), 5);
+        assert_eq!(generated, vec!["dog"]);
+        let generated = transitions.generate("cat", &mut Rng::new(3), 50);
+        assert!(generated.len() > 1);
+        assert!(generated
+            .windows(2)
+            .all(|pair| transitions.probability(pair[0], pair[1]) > 0.0));
+        assert!(transitions.generate("cat", &mut Rng::new(3), 0).is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+//! metadata of saved counts (tool version, mode, hash of the configuration, inputs and
+//! timestamp), so that archived counts can be told apart, and counts of incompatible
+//! configurations are not merged, see [`Metadata`](struct.Metadata.html)
+//!
+//! metadata is written by [`output::write_json_with_metadata`](../output/fn.write_json_with_metadata.html),
+//! [`output::write_csv_with_metadata`](../output/fn.write_csv_with_metadata.html) and
+//! [`binary::write_with_metadata`](../binary/fn.write_with_metadata.html), and read back along
+//! with counts
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::frequencies::fnv1a;
+use crate::json::{self, Fields, Value};
+use crate::{CountConfig, CountOption};
+
+/// version of this crate, written in metadata
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// how counts were made
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::metadata::Metadata;
+/// use bicycle_book_wordcount::{CountConfig, CountOption};
+/// let config = CountConfig::new(CountOption::Word);
+/// let mut a = Metadata::new(&config, vec!["a.txt".to_string()]);
+/// let b = Metadata::new(&config, vec!["b.txt".to_string()]);
+/// a.merge(&b).unwrap();
+/// assert_eq!(a.mode, "word");
+/// assert_eq!(a.inputs, vec!["a.txt", "b.txt"]);
+///
+/// let lines = Metadata::new(&CountConfig::new(CountOption::Line), vec![]);
+/// assert!(a.merge(&lines).is_err());
+/// let ignoring_case = Metadata::new(&config.clone().ignore_case(true), vec![]);
+/// assert!(a.merge(&ignoring_case).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    /// version of the tool which counted
+    pub version: String,
+    /// what is counted, like `word` or `access-log path`
+    pub mode: String,
+    /// hash of the configuration, see
+    /// [`CountConfig::fingerprint`](../config/struct.CountConfig.html#method.fingerprint)
+    pub config: u64,
+    /// names of inputs, e.g. paths of files
+    pub inputs: Vec<String>,
+    /// seconds since the Unix epoch when the counts were written
+    pub timestamp: u64,
+}
+
+impl Metadata {
+    /// metadata of counts of `inputs` by `config`, written now by this version
+    pub fn new(config: &CountConfig, inputs: Vec<String>) -> Self {
+        let mode = match config.option() {
+            CountOption::Char => "char",
+            CountOption::Word => "word",
+            CountOption::Line => "line",
+            CountOption::Custom(_) => "pattern",
+        };
+        Metadata {
+            version: VERSION.to_string(),
+            mode: mode.to_string(),
+            config: config.fingerprint(),
+            inputs,
+            timestamp: now(),
+        }
+    }
+
+    /// replace the mode, e.g. of values of a field of logs instead of tokens
+    pub fn mode(mut self, mode: &str) -> Self {
+        self.mode = mode.to_string();
+        self
+    }
+
+    /// mix a setting besides `CountConfig` into the hash of the configuration, e.g. a minimum
+    /// count of the output
+    pub fn setting(mut self, name: &str, value: &str) -> Self {
+        let text = format!("{:016x} {}={}", self.config, name, value);
+        self.config = fnv1a(text.as_bytes());
+        self
+    }
+
+    /// whether counts of `other` can be merged with these: of the same mode and configuration.
+    /// versions may differ
+    pub fn check_compatible(&self, other: &Metadata) -> Result<(), String> {
+        if self.mode == other.mode && self.config == other.config {
+            return Ok(());
+        }
+        Err(format!(
+            "counted as {} with config {:016x}, not as {} with config {:016x}",
+            other.mode, other.config, self.mode, self.config
+        ))
+    }
+
+    /// metadata of the sum of counts of these and of `other`, written now by this version.
+    /// an error if they are not [compatible](#method.check_compatible)
+    pub fn merge(&mut self, other: &Metadata) -> Result<(), String> {
+        self.check_compatible(other)?;
+        self.inputs.extend(other.inputs.iter().cloned());
+        self.version = VERSION.to_string();
+        self.timestamp = now();
+        Ok(())
+    }
+
+    /// JSON object of the metadata
+    pub(crate) fn json(&self) -> String {
+        let inputs: Vec<_> = self
+            .inputs
+            .iter()
+            .map(|input| json::string(input))
+            .collect();
+        format!(
+            "{{\"version\":{},\"mode\":{},\"config\":\"{:016x}\",\"inputs\":[{}],\"timestamp\":{}}}",
+            json::string(&self.version),
+            json::string(&self.mode),
+            self.config,
+            inputs.join(","),
+            self.timestamp
+        )
+    }
+
+    /// metadata of entries of a JSON object written by [`json`](#method.json). unknown keys
+    /// are ignored
+    pub(crate) fn from_json(entries: Fields) -> Result<Self, String> {
+        let (mut version, mut mode, mut config, mut inputs, mut timestamp) =
+            (None, None, None, None, None);
+        for (key, value) in entries {
+            match (key.as_str(), value) {
+                ("version", Value::Scalar(value)) => version = Some(value),
+                ("mode", Value::Scalar(value)) => mode = Some(value),
+                ("config", Value::Scalar(value)) => config = u64::from_str_radix(&value, 16).ok(),
+                ("inputs", Value::Strings(values)) => inputs = Some(values),
+                ("timestamp", Value::Scalar(value)) => timestamp = value.parse().ok(),
+                ("version", _) | ("mode", _) | ("config", _) | ("inputs", _) | ("timestamp", _) => {
+                    return Err(format!("bad metadata {:?}", key))
+                }
+                _ => {}
+            }
+        }
+        match (version, mode, config, inputs, timestamp) {
+            (Some(version), Some(mode), Some(config), Some(inputs), Some(timestamp)) => {
+                Ok(Metadata {
+                    version,
+                    mode,
+                    config,
+                    inputs,
+                    timestamp,
+                })
+            }
+            _ => Err("expected version, mode, config, inputs and timestamp".to_string()),
+        }
+    }
+}
+
+/// a line per field like `mode: word`, and an `input:` line per input
+impl fmt::Display for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "version: {}", self.version)?;
+        writeln!(f, "mode: {}", self.mode)?;
+        writeln!(f, "config: {:016x}", self.config)?;
+        for input in &self.inputs {
+            writeln!(f, "input: {}", input)?;
+        }
+        writeln!(f, "timestamp: {}", self.timestamp)
+    }
+}
+
+/// seconds since the Unix epoch, 0 if the clock is before it
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn json_is_read_back() {
+        let metadata = Metadata {
+            version: "0.1.0".to_string(),
+            mode: "access-log path".to_string(),
+            config: 0xab,
+            inputs: vec!["a \"1\".log".to_string()],
+            timestamp: 1_760_000_000,
+        };
+        let text = metadata.json();
+        assert_eq!(
+            text,
+            r#"{"version":"0.1.0","mode":"access-log path","config":"00000000000000ab","inputs":["a \"1\".log"],"timestamp":1760000000}"#
+        );
+        let (entries, _) =
+            json::counts_with_metadata(&format!("{{\"metadata\":{},\"counts\":{{}}}}", text))
+                .unwrap();
+        assert_eq!(Metadata::from_json(entries), Ok(metadata.clone()));
+        let missing = vec![("mode".to_string(), Value::Scalar("word".to_string()))];
+        assert!(Metadata::from_json(missing).is_err());
+        assert_eq!(
+            metadata.to_string(),
+            "version: 0.1.0\nmode: access-log path\nconfig: 00000000000000ab\n\
+             input: a \"1\".log\ntimestamp: 1760000000\n"
+        );
+    }
+
+    #[test]
+    fn settings_change_the_config() {
+        let config = CountConfig::default();
+        let metadata = Metadata::new(&config, vec![]);
+        assert_eq!(metadata.config, config.fingerprint());
+        let filtered = metadata.clone().setting("min_count", "2");
+        assert_ne!(filtered.config, metadata.config);
+        assert_eq!(filtered, metadata.clone().setting("min_count", "2"));
+        let err = metadata.check_compatible(&filtered).unwrap_err();
+        assert!(err.starts_with("counted as word with config "), "{}", err);
+        assert!(metadata
+            .check_compatible(&metadata.clone().mode("word"))
+            .is_ok());
+    }
+}
@@ -0,0 +1,955 @@
+//! normalization of tokens before counting
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+use crate::decode::{read_lines, strip_bom, Transcoder};
+use crate::frequencies::fnv1a;
+use crate::tally::Tally;
+use crate::{CountConfig, CountError, Frequencies};
+
+/// remove diacritics of Latin letters (café => cafe)
+///
+/// combining marks are removed, and precomposed letters (Latin-1 Supplement, Latin Extended-A/B
+/// and Latin Extended Additional) are replaced by the base letter of their canonical decomposition.
+/// letters without decomposition, like "ø" or "ł", are kept.
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::normalize::strip_diacritics;
+/// assert_eq!(strip_diacritics("café"), "cafe");
+/// assert_eq!(strip_diacritics("cafe\u{301}"), "cafe");
+/// assert_eq!(strip_diacritics("Ångström"), "Angstrom");
+/// ```
+pub fn strip_diacritics(token: &str) -> Cow<'_, str> {
+    if token.is_ascii() || !token.chars().any(|c| is_combining(c) || base(c).is_some()) {
+        return Cow::Borrowed(token);
+    }
+    Cow::Owned(
+        token
+            .chars()
+            .filter(|&c| !is_combining(c))
+            .map(|c| base(c).unwrap_or(c))
+            .collect(),
+    )
+}
+
+/// remove leading and trailing whitespace and make other runs of whitespace one space
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::normalize::collapse_whitespace;
+/// assert_eq!(collapse_whitespace("  GET \t/index.html   200 "), "GET /index.html 200");
+/// ```
+pub fn collapse_whitespace(text: &str) -> Cow<'_, str> {
+    let collapsed = !text.starts_with(' ')
+        && !text.ends_with(' ')
+        && !text.contains("  ")
+        && !text.chars().any(|c| c.is_whitespace() && c != ' ');
+    if collapsed {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// replace a roman numeral by its arabic numeral (XIV => 14)
+///
+/// only numerals in uppercase and in the canonical form (IV, not IIII) from 1 to 3999 are
+/// replaced, and "I" alone is kept as it is the pronoun more often. words spelled like
+/// numerals in uppercase (e.g. "MIX" or "CD") are replaced too
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::normalize::roman_numerals;
+/// assert_eq!(roman_numerals("XIV"), "14");
+/// assert_eq!(roman_numerals("MCMLXXXIV"), "1984");
+/// assert_eq!(roman_numerals("IIII"), "IIII");
+/// assert_eq!(roman_numerals("xiv"), "xiv");
+/// assert_eq!(roman_numerals("I"), "I");
+/// ```
+pub fn roman_numerals(token: &str) -> Cow<'_, str> {
+    match roman_value(token) {
+        Some(value) if token != "I" => Cow::Owned(value.to_string()),
+        _ => Cow::Borrowed(token),
+    }
+}
+
+/// replace an ordinal in digits or spelled in English by digits and a suffix (third, 3RD and
+/// 3th => 3rd)
+///
+/// ordinals are spelled up to "ninety-ninth", and "hundredth" and "thousandth". case is
+/// ignored. note that "second" is also the unit of time
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::normalize::ordinals;
+/// assert_eq!(ordinals("Third"), "3rd");
+/// assert_eq!(ordinals("twenty-first"), "21st");
+/// assert_eq!(ordinals("112TH"), "112th");
+/// assert_eq!(ordinals("third-party"), "third-party");
+/// ```
+pub fn ordinals(token: &str) -> Cow<'_, str> {
+    match ordinal_value(token) {
+        Some(value) => Cow::Owned(format!("{}{}", value, ordinal_suffix(value))),
+        None => Cow::Borrowed(token),
+    }
+}
+
+const ROMAN: &[(u32, &str)] = &[
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+];
+
+/// value of a canonical roman numeral in uppercase
+fn roman_value(token: &str) -> Option<u32> {
+    let (mut value, mut rest) = (0, token);
+    for &(n, numeral) in ROMAN {
+        while let Some(after) = rest.strip_prefix(numeral) {
+            value += n;
+            rest = after;
+        }
+    }
+    if !rest.is_empty() || !(1..=3999).contains(&value) || roman(value) != token {
+        return None;
+    }
+    Some(value)
+}
+
+fn roman(mut value: u32) -> String {
+    let mut numeral = String::new();
+    for &(n, letters) in ROMAN {
+        while value >= n {
+            numeral.push_str(letters);
+            value -= n;
+        }
+    }
+    numeral
+}
+
+const UNITS: &[&str] = &[
+    "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth",
+];
+
+const TEENS: &[&str] = &[
+    "tenth",
+    "eleventh",
+    "twelfth",
+    "thirteenth",
+    "fourteenth",
+    "fifteenth",
+    "sixteenth",
+    "seventeenth",
+    "eighteenth",
+    "nineteenth",
+];
+
+/// tens from twenty as cardinals and as ordinals
+const TENS: &[(&str, &str)] = &[
+    ("twenty", "twentieth"),
+    ("thirty", "thirtieth"),
+    ("forty", "fortieth"),
+    ("fifty", "fiftieth"),
+    ("sixty", "sixtieth"),
+    ("seventy", "seventieth"),
+    ("eighty", "eightieth"),
+    ("ninety", "ninetieth"),
+];
+
+/// value of an ordinal like 21st, 21ST or twenty-first
+fn ordinal_value(token: &str) -> Option<u64> {
+    let digits = token.bytes().take_while(u8::is_ascii_digit).count();
+    if digits > 0 {
+        let suffix = &token[digits..];
+        let suffixed = ["st", "nd", "rd", "th"]
+            .iter()
+            .any(|s| s.eq_ignore_ascii_case(suffix));
+        return token[..digits].parse().ok().filter(|_| suffixed);
+    }
+    let position = |words: &[&str], word: &str| {
+        words
+            .iter()
+            .position(|w| w.eq_ignore_ascii_case(word))
+            .map(|i| i as u64)
+    };
+    if let Some((tens, unit)) = token.split_once('-') {
+        let tens = TENS
+            .iter()
+            .position(|(t, _)| t.eq_ignore_ascii_case(tens))? as u64;
+        return Some(20 + tens * 10 + 1 + position(UNITS, unit)?);
+    }
+    let tens: Vec<_> = TENS.iter().map(|&(_, ordinal)| ordinal).collect();
+    position(UNITS, token)
+        .map(|i| 1 + i)
+        .or_else(|| position(TEENS, token).map(|i| 10 + i))
+        .or_else(|| position(&tens, token).map(|i| 20 + i * 10))
+        .or_else(|| {
+            ["hundredth", "thousandth"]
+                .iter()
+                .position(|w| w.eq_ignore_ascii_case(token))
+                .map(|i| 100 * 10u64.pow(i as u32))
+        })
+}
+
+fn ordinal_suffix(value: u64) -> &'static str {
+    match (value % 10, value % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+/// same as [`try_count_with`](../fn.try_count_with.html), but also the count of distinct raw
+/// tokens merged into each key by normalization, such as lines differing only in whitespace
+/// and case with [`collapse_whitespace`](../config/struct.CountConfig.html#method.collapse_whitespace)
+/// and [`ignore_case`](../config/struct.CountConfig.html#method.ignore_case)
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::normalize::count_variants;
+/// use bicycle_book_wordcount::{CountConfig, CountOption};
+/// let config = CountConfig::new(CountOption::Line)
+///     .collapse_whitespace(true)
+///     .ignore_case(true);
+/// let log = "Disk full on /dev/sda\ndisk full  on /dev/sda\nDisk full on /dev/sda\nok";
+/// let (freqs, variants) = count_variants(Cursor::new(log), &config).unwrap();
+/// assert_eq!(freqs["disk full on /dev/sda"], 3);
+/// assert_eq!(variants["disk full on /dev/sda"], 2);
+/// assert_eq!(variants["ok"], 1);
+/// ```
+pub fn count_variants(
+    input: impl BufRead,
+    config: &CountConfig,
+) -> Result<(Frequencies, Frequencies), CountError> {
+    let mut tally = Tally::with_spellings(config);
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        tally.line(line)
+    })?;
+    let (freqs, spellings) = tally.finish_with_spellings();
+    let variants = spellings
+        .into_iter()
+        .map(|(key, spellings)| (key, spellings.len()))
+        .collect::<HashMap<_, _>>();
+    Ok((freqs, variants.into()))
+}
+
+/// lemmas of inflected forms, to count the forms as their lemma, see
+/// [`CountConfig::lemmas`](../config/struct.CountConfig.html#method.lemmas)
+///
+/// no table is bundled, load one from a linguistic resource.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::normalize::Lemmas;
+/// let lemmas = Lemmas::from_tsv(Cursor::new("# form\tlemma\nran\trun\nrunning\trun\n")).unwrap();
+/// assert_eq!(lemmas.lemma("ran"), Some("run"));
+/// assert_eq!(lemmas.lemma("run"), None);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Lemmas {
+    table: HashMap<String, String>,
+}
+
+impl Lemmas {
+    /// table of `(form, lemma)` pairs
+    pub fn new<S: AsRef<str>>(pairs: &[(S, S)]) -> Self {
+        let table = pairs
+            .iter()
+            .map(|(form, lemma)| (form.as_ref().to_string(), lemma.as_ref().to_string()));
+        Lemmas {
+            table: table.collect(),
+        }
+    }
+
+    /// hash of the table, the same in any order of insertion
+    pub(crate) fn fingerprint(&self) -> u64 {
+        let mut pairs: Vec<_> = self
+            .table
+            .iter()
+            .map(|(form, lemma)| format!("{}\t{}", form, lemma))
+            .collect();
+        pairs.sort_unstable();
+        fnv1a(pairs.join("\n").as_bytes())
+    }
+
+    /// table of tab separated lines `form\tlemma`. empty lines and lines starting with `#`
+    /// are ignored, and a line without a tab is an error of kind `InvalidData`
+    pub fn from_tsv(input: impl BufRead) -> io::Result<Self> {
+        let mut table = HashMap::new();
+        for (i, line) in Transcoder::new(input).lines().enumerate() {
+            let line = line?;
+            let line = if i == 0 { strip_bom(&line) } else { &line };
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once('\t') {
+                Some((form, lemma)) => {
+                    table.insert(form.to_string(), lemma.trim().to_string());
+                }
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line {}: no tab between form and lemma", i + 1),
+                    ))
+                }
+            }
+        }
+        Ok(Lemmas { table })
+    }
+
+    /// lemma of `form`, `None` if it is not in the table
+    pub fn lemma(&self, form: &str) -> Option<&str> {
+        self.table.get(form).map(String::as_str)
+    }
+
+    /// count of forms
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+}
+
+/// combining diacritical marks
+pub(crate) fn is_combining(c: char) -> bool {
+    matches!(
+        c,
+        '\u{300}'..='\u{36f}'
+        | '\u{1ab0}'..='\u{1aff}'
+        | '\u{1dc0}'..='\u{1dff}'
+        | '\u{20d0}'..='\u{20ff}'
+        | '\u{fe20}'..='\u{fe2f}'
+    )
+}
+
+/// base letter of a precomposed Latin letter
+pub(crate) fn base(c: char) -> Option<char> {
+    DECOMPOSED
+        .binary_search_by_key(&c, |&(composed, _)| composed)
+        .ok()
+        .map(|i| DECOMPOSED[i].1)
+}
+
+/// precomposed letter => base letter, sorted by precomposed letter
+const DECOMPOSED: &[(char, char)] = &[
+    ('À', 'A'),
+    ('Á', 'A'),
+    ('Â', 'A'),
+    ('Ã', 'A'),
+    ('Ä', 'A'),
+    ('Å', 'A'),
+    ('Ç', 'C'),
+    ('È', 'E'),
+    ('É', 'E'),
+    ('Ê', 'E'),
+    ('Ë', 'E'),
+    ('Ì', 'I'),
+    ('Í', 'I'),
+    ('Î', 'I'),
+    ('Ï', 'I'),
+    ('Ñ', 'N'),
+    ('Ò', 'O'),
+    ('Ó', 'O'),
+    ('Ô', 'O'),
+    ('Õ', 'O'),
+    ('Ö', 'O'),
+    ('Ù', 'U'),
+    ('Ú', 'U'),
+    ('Û', 'U'),
+    ('Ü', 'U'),
+    ('Ý', 'Y'),
+    ('à', 'a'),
+    ('á', 'a'),
+    ('â', 'a'),
+    ('ã', 'a'),
+    ('ä', 'a'),
+    ('å', 'a'),
+    ('ç', 'c'),
+    ('è', 'e'),
+    ('é', 'e'),
+    ('ê', 'e'),
+    ('ë', 'e'),
+    ('ì', 'i'),
+    ('í', 'i'),
+    ('î', 'i'),
+    ('ï', 'i'),
+    ('ñ', 'n'),
+    ('ò', 'o'),
+    ('ó', 'o'),
+    ('ô', 'o'),
+    ('õ', 'o'),
+    ('ö', 'o'),
+    ('ù', 'u'),
+    ('ú', 'u'),
+    ('û', 'u'),
+    ('ü', 'u'),
+    ('ý', 'y'),
+    ('ÿ', 'y'),
+    ('Ā', 'A'),
+    ('ā', 'a'),
+    ('Ă', 'A'),
+    ('ă', 'a'),
+    ('Ą', 'A'),
+    ('ą', 'a'),
+    ('Ć', 'C'),
+    ('ć', 'c'),
+    ('Ĉ', 'C'),
+    ('ĉ', 'c'),
+    ('Ċ', 'C'),
+    ('ċ', 'c'),
+    ('Č', 'C'),
+    ('č', 'c'),
+    ('Ď', 'D'),
+    ('ď', 'd'),
+    ('Ē', 'E'),
+    ('ē', 'e'),
+    ('Ĕ', 'E'),
+    ('ĕ', 'e'),
+    ('Ė', 'E'),
+    ('ė', 'e'),
+    ('Ę', 'E'),
+    ('ę', 'e'),
+    ('Ě', 'E'),
+    ('ě', 'e'),
+    ('Ĝ', 'G'),
+    ('ĝ', 'g'),
+    ('Ğ', 'G'),
+    ('ğ', 'g'),
+    ('Ġ', 'G'),
+    ('ġ', 'g'),
+    ('Ģ', 'G'),
+    ('ģ', 'g'),
+    ('Ĥ', 'H'),
+    ('ĥ', 'h'),
+    ('Ĩ', 'I'),
+    ('ĩ', 'i'),
+    ('Ī', 'I'),
+    ('ī', 'i'),
+    ('Ĭ', 'I'),
+    ('ĭ', 'i'),
+    ('Į', 'I'),
+    ('į', 'i'),
+    ('İ', 'I'),
+    ('Ĵ', 'J'),
+    ('ĵ', 'j'),
+    ('Ķ', 'K'),
+    ('ķ', 'k'),
+    ('Ĺ', 'L'),
+    ('ĺ', 'l'),
+    ('Ļ', 'L'),
+    ('ļ', 'l'),
+    ('Ľ', 'L'),
+    ('ľ', 'l'),
+    ('Ń', 'N'),
+    ('ń', 'n'),
+    ('Ņ', 'N'),
+    ('ņ', 'n'),
+    ('Ň', 'N'),
+    ('ň', 'n'),
+    ('Ō', 'O'),
+    ('ō', 'o'),
+    ('Ŏ', 'O'),
+    ('ŏ', 'o'),
+    ('Ő', 'O'),
+    ('ő', 'o'),
+    ('Ŕ', 'R'),
+    ('ŕ', 'r'),
+    ('Ŗ', 'R'),
+    ('ŗ', 'r'),
+    ('Ř', 'R'),
+    ('ř', 'r'),
+    ('Ś', 'S'),
+    ('ś', 's'),
+    ('Ŝ', 'S'),
+    ('ŝ', 's'),
+    ('Ş', 'S'),
+    ('ş', 's'),
+    ('Š', 'S'),
+    ('š', 's'),
+    ('Ţ', 'T'),
+    ('ţ', 't'),
+    ('Ť', 'T'),
+    ('ť', 't'),
+    ('Ũ', 'U'),
+    ('ũ', 'u'),
+    ('Ū', 'U'),
+    ('ū', 'u'),
+    ('Ŭ', 'U'),
+    ('ŭ', 'u'),
+    ('Ů', 'U'),
+    ('ů', 'u'),
+    ('Ű', 'U'),
+    ('ű', 'u'),
+    ('Ų', 'U'),
+    ('ų', 'u'),
+    ('Ŵ', 'W'),
+    ('ŵ', 'w'),
+    ('Ŷ', 'Y'),
+    ('ŷ', 'y'),
+    ('Ÿ', 'Y'),
+    ('Ź', 'Z'),
+    ('ź', 'z'),
+    ('Ż', 'Z'),
+    ('ż', 'z'),
+    ('Ž', 'Z'),
+    ('ž', 'z'),
+    ('Ơ', 'O'),
+    ('ơ', 'o'),
+    ('Ư', 'U'),
+    ('ư', 'u'),
+    ('Ǎ', 'A'),
+    ('ǎ', 'a'),
+    ('Ǐ', 'I'),
+    ('ǐ', 'i'),
+    ('Ǒ', 'O'),
+    ('ǒ', 'o'),
+    ('Ǔ', 'U'),
+    ('ǔ', 'u'),
+    ('Ǖ', 'U'),
+    ('ǖ', 'u'),
+    ('Ǘ', 'U'),
+    ('ǘ', 'u'),
+    ('Ǚ', 'U'),
+    ('ǚ', 'u'),
+    ('Ǜ', 'U'),
+    ('ǜ', 'u'),
+    ('Ǟ', 'A'),
+    ('ǟ', 'a'),
+    ('Ǡ', 'A'),
+    ('ǡ', 'a'),
+    ('Ǣ', 'Æ'),
+    ('ǣ', 'æ'),
+    ('Ǧ', 'G'),
+    ('ǧ', 'g'),
+    ('Ǩ', 'K'),
+    ('ǩ', 'k'),
+    ('Ǫ', 'O'),
+    ('ǫ', 'o'),
+    ('Ǭ', 'O'),
+    ('ǭ', 'o'),
+    ('Ǯ', 'Ʒ'),
+    ('ǯ', 'ʒ'),
+    ('ǰ', 'j'),
+    ('Ǵ', 'G'),
+    ('ǵ', 'g'),
+    ('Ǹ', 'N'),
+    ('ǹ', 'n'),
+    ('Ǻ', 'A'),
+    ('ǻ', 'a'),
+    ('Ǽ', 'Æ'),
+    ('ǽ', 'æ'),
+    ('Ǿ', 'Ø'),
+    ('ǿ', 'ø'),
+    ('Ȁ', 'A'),
+    ('ȁ', 'a'),
+    ('Ȃ', 'A'),
+    ('ȃ', 'a'),
+    ('Ȅ', 'E'),
+    ('ȅ', 'e'),
+    ('Ȇ', 'E'),
+    ('ȇ', 'e'),
+    ('Ȉ', 'I'),
+    ('ȉ', 'i'),
+    ('Ȋ', 'I'),
+    ('ȋ', 'i'),
+    ('Ȍ', 'O'),
+    ('ȍ', 'o'),
+    ('Ȏ', 'O'),
+    ('ȏ', 'o'),
+    ('Ȑ', 'R'),
+    ('ȑ', 'r'),
+    ('Ȓ', 'R'),
+    ('ȓ', 'r'),
+    ('Ȕ', 'U'),
+    ('ȕ', 'u'),
+    ('Ȗ', 'U'),
+    ('ȗ', 'u'),
+    ('Ș', 'S'),
+    ('ș', 's'),
+    ('Ț', 'T'),
+    ('ț', 't'),
+    ('Ȟ', 'H'),
+    ('ȟ', 'h'),
+    ('Ȧ', 'A'),
+    ('ȧ', 'a'),
+    ('Ȩ', 'E'),
+    ('ȩ', 'e'),
+    ('Ȫ', 'O'),
+    ('ȫ', 'o'),
+    ('Ȭ', 'O'),
+    ('ȭ', 'o'),
+    ('Ȯ', 'O'),
+    ('ȯ', 'o'),
+    ('Ȱ', 'O'),
+    ('ȱ', 'o'),
+    ('Ȳ', 'Y'),
+    ('ȳ', 'y'),
+    ('Ḁ', 'A'),
+    ('ḁ', 'a'),
+    ('Ḃ', 'B'),
+    ('ḃ', 'b'),
+    ('Ḅ', 'B'),
+    ('ḅ', 'b'),
+    ('Ḇ', 'B'),
+    ('ḇ', 'b'),
+    ('Ḉ', 'C'),
+    ('ḉ', 'c'),
+    ('Ḋ', 'D'),
+    ('ḋ', 'd'),
+    ('Ḍ', 'D'),
+    ('ḍ', 'd'),
+    ('Ḏ', 'D'),
+    ('ḏ', 'd'),
+    ('Ḑ', 'D'),
+    ('ḑ', 'd'),
+    ('Ḓ', 'D'),
+    ('ḓ', 'd'),
+    ('Ḕ', 'E'),
+    ('ḕ', 'e'),
+    ('Ḗ', 'E'),
+    ('ḗ', 'e'),
+    ('Ḙ', 'E'),
+    ('ḙ', 'e'),
+    ('Ḛ', 'E'),
+    ('ḛ', 'e'),
+    ('Ḝ', 'E'),
+    ('ḝ', 'e'),
+    ('Ḟ', 'F'),
+    ('ḟ', 'f'),
+    ('Ḡ', 'G'),
+    ('ḡ', 'g'),
+    ('Ḣ', 'H'),
+    ('ḣ', 'h'),
+    ('Ḥ', 'H'),
+    ('ḥ', 'h'),
+    ('Ḧ', 'H'),
+    ('ḧ', 'h'),
+    ('Ḩ', 'H'),
+    ('ḩ', 'h'),
+    ('Ḫ', 'H'),
+    ('ḫ', 'h'),
+    ('Ḭ', 'I'),
+    ('ḭ', 'i'),
+    ('Ḯ', 'I'),
+    ('ḯ', 'i'),
+    ('Ḱ', 'K'),
+    ('ḱ', 'k'),
+    ('Ḳ', 'K'),
+    ('ḳ', 'k'),
+    ('Ḵ', 'K'),
+    ('ḵ', 'k'),
+    ('Ḷ', 'L'),
+    ('ḷ', 'l'),
+    ('Ḹ', 'L'),
+    ('ḹ', 'l'),
+    ('Ḻ', 'L'),
+    ('ḻ', 'l'),
+    ('Ḽ', 'L'),
+    ('ḽ', 'l'),
+    ('Ḿ', 'M'),
+    ('ḿ', 'm'),
+    ('Ṁ', 'M'),
+    ('ṁ', 'm'),
+    ('Ṃ', 'M'),
+    ('ṃ', 'm'),
+    ('Ṅ', 'N'),
+    ('ṅ', 'n'),
+    ('Ṇ', 'N'),
+    ('ṇ', 'n'),
+    ('Ṉ', 'N'),
+    ('ṉ', 'n'),
+    ('Ṋ', 'N'),
+    ('ṋ', 'n'),
+    ('Ṍ', 'O'),
+    ('ṍ', 'o'),
+    ('Ṏ', 'O'),
+    ('ṏ', 'o'),
+    ('Ṑ', 'O'),
+    ('ṑ', 'o'),
+    ('Ṓ', 'O'),
+    ('ṓ', 'o'),
+    ('Ṕ', 'P'),
+    ('ṕ', 'p'),
+    ('Ṗ', 'P'),
+    ('ṗ', 'p'),
+    ('Ṙ', 'R'),
+    ('ṙ', 'r'),
+    ('Ṛ', 'R'),
+    ('ṛ', 'r'),
+    ('Ṝ', 'R'),
+    ('ṝ', 'r'),
+    ('Ṟ', 'R'),
+    ('ṟ', 'r'),
+    ('Ṡ', 'S'),
+    ('ṡ', 's'),
+    ('Ṣ', 'S'),
+    ('ṣ', 's'),
+    ('Ṥ', 'S'),
+    ('ṥ', 's'),
+    ('Ṧ', 'S'),
+    ('ṧ', 's'),
+    ('Ṩ', 'S'),
+    ('ṩ', 's'),
+    ('Ṫ', 'T'),
+    ('ṫ', 't'),
+    ('Ṭ', 'T'),
+    ('ṭ', 't'),
+    ('Ṯ', 'T'),
+    ('ṯ', 't'),
+    ('Ṱ', 'T'),
+    ('ṱ', 't'),
+    ('Ṳ', 'U'),
+    ('ṳ', 'u'),
+    ('Ṵ', 'U'),
+    ('ṵ', 'u'),
+    ('Ṷ', 'U'),
+    ('ṷ', 'u'),
+    ('Ṹ', 'U'),
+    ('ṹ', 'u'),
+    ('Ṻ', 'U'),
+    ('ṻ', 'u'),
+    ('Ṽ', 'V'),
+    ('ṽ', 'v'),
+    ('Ṿ', 'V'),
+    ('ṿ', 'v'),
+    ('Ẁ', 'W'),
+    ('ẁ', 'w'),
+    ('Ẃ', 'W'),
+    ('ẃ', 'w'),
+    ('Ẅ', 'W'),
+    ('ẅ', 'w'),
+    ('Ẇ', 'W'),
+    ('ẇ', 'w'),
+    ('Ẉ', 'W'),
+    ('ẉ', 'w'),
+    ('Ẋ', 'X'),
+    ('ẋ', 'x'),
+    ('Ẍ', 'X'),
+    ('ẍ', 'x'),
+    ('Ẏ', 'Y'),
+    ('ẏ', 'y'),
+    ('Ẑ', 'Z'),
+    ('ẑ', 'z'),
+    ('Ẓ', 'Z'),
+    ('ẓ', 'z'),
+    ('Ẕ', 'Z'),
+    ('ẕ', 'z'),
+    ('ẖ', 'h'),
+    ('ẗ', 't'),
+    ('ẘ', 'w'),
+    ('ẙ', 'y'),
+    ('ẛ', 'ſ'),
+    ('Ạ', 'A'),
+    ('ạ', 'a'),
+    ('Ả', 'A'),
+    ('ả', 'a'),
+    ('Ấ', 'A'),
+    ('ấ', 'a'),
+    ('Ầ', 'A'),
+    ('ầ', 'a'),
+    ('Ẩ', 'A'),
+    ('ẩ', 'a'),
+    ('Ẫ', 'A'),
+    ('ẫ', 'a'),
+    ('Ậ', 'A'),
+    ('ậ', 'a'),
+    ('Ắ', 'A'),
+    ('ắ', 'a'),
+    ('Ằ', 'A'),
+    ('ằ', 'a'),
+    ('Ẳ', 'A'),
+    ('ẳ', 'a'),
+    ('Ẵ', 'A'),
+    ('ẵ', 'a'),
+    ('Ặ', 'A'),
+    ('ặ', 'a'),
+    ('Ẹ', 'E'),
+    ('ẹ', 'e'),
+    ('Ẻ', 'E'),
+    ('ẻ', 'e'),
+    ('Ẽ', 'E'),
+    ('ẽ', 'e'),
+    ('Ế', 'E'),
+    ('ế', 'e'),
+    ('Ề', 'E'),
+    ('ề', 'e'),
+    ('Ể', 'E'),
+    ('ể', 'e'),
+    ('Ễ', 'E'),
+    ('ễ', 'e'),
+    ('Ệ', 'E'),
+    ('ệ', 'e'),
+    ('Ỉ', 'I'),
+    ('ỉ', 'i'),
+    ('Ị', 'I'),
+    ('ị', 'i'),
+    ('Ọ', 'O'),
+    ('ọ', 'o'),
+    ('Ỏ', 'O'),
+    ('ỏ', 'o'),
+    ('Ố', 'O'),
+    ('ố', 'o'),
+    ('Ồ', 'O'),
+    ('ồ', 'o'),
+    ('Ổ', 'O'),
+    ('ổ', 'o'),
+    ('Ỗ', 'O'),
+    ('ỗ', 'o'),
+    ('Ộ', 'O'),
+    ('ộ', 'o'),
+    ('Ớ', 'O'),
+    ('ớ', 'o'),
+    ('Ờ', 'O'),
+    ('ờ', 'o'),
+    ('Ở', 'O'),
+    ('ở', 'o'),
+    ('Ỡ', 'O'),
+    ('ỡ', 'o'),
+    ('Ợ', 'O'),
+    ('ợ', 'o'),
+    ('Ụ', 'U'),
+    ('ụ', 'u'),
+    ('Ủ', 'U'),
+    ('ủ', 'u'),
+    ('Ứ', 'U'),
+    ('ứ', 'u'),
+    ('Ừ', 'U'),
+    ('ừ', 'u'),
+    ('Ử', 'U'),
+    ('ử', 'u'),
+    ('Ữ', 'U'),
+    ('ữ', 'u'),
+    ('Ự', 'U'),
+    ('ự', 'u'),
+    ('Ỳ', 'Y'),
+    ('ỳ', 'y'),
+    ('Ỵ', 'Y'),
+    ('ỵ', 'y'),
+    ('Ỷ', 'Y'),
+    ('ỷ', 'y'),
+    ('Ỹ', 'Y'),
+    ('ỹ', 'y'),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn strip_diacritics_works() {
+        assert_eq!(strip_diacritics("naïve façade"), "naive facade");
+        assert_eq!(strip_diacritics("Ǖ"), "U");
+        assert_eq!(strip_diacritics("ø"), "ø");
+        assert_eq!(strip_diacritics("日本語"), "日本語");
+        assert!(match strip_diacritics("plain") {
+            Cow::Borrowed(_) => true,
+            Cow::Owned(_) => false,
+        });
+    }
+
+    #[test]
+    fn collapse_whitespace_works() {
+        assert!(matches!(collapse_whitespace("a b"), Cow::Borrowed("a b")));
+        assert_eq!(collapse_whitespace("a  b"), "a b");
+        assert_eq!(collapse_whitespace("a\u{3000}b\t"), "a b");
+        assert_eq!(collapse_whitespace(" "), "");
+        assert!(matches!(collapse_whitespace(""), Cow::Borrowed("")));
+    }
+
+    #[test]
+    fn numerals_are_normalized() {
+        let romans: Vec<_> = [
+            "IV",
+            "IX",
+            "XL",
+            "MMMCMXCIX",
+            "MMMM",
+            "VX",
+            "IL",
+            "DID",
+            "X1",
+        ]
+        .iter()
+        .map(|numeral| roman_numerals(numeral).into_owned())
+        .collect();
+        assert_eq!(
+            romans,
+            ["4", "9", "40", "3999", "MMMM", "VX", "IL", "DID", "X1"]
+        );
+        let spelled: Vec<_> = [
+            "1ST",
+            "2nd",
+            "13th",
+            "23th",
+            "second",
+            "TWELFTH",
+            "Fortieth",
+            "ninety-ninth",
+            "hundredth",
+            "twenty-twenty",
+            "1",
+            "st",
+            "4s",
+        ]
+        .iter()
+        .map(|ordinal| ordinals(ordinal).into_owned())
+        .collect();
+        assert_eq!(
+            spelled,
+            [
+                "1st",
+                "2nd",
+                "13th",
+                "23rd",
+                "2nd",
+                "12th",
+                "40th",
+                "99th",
+                "100th",
+                "twenty-twenty",
+                "1",
+                "st",
+                "4s"
+            ]
+        );
+        assert!(matches!(ordinals("word"), Cow::Borrowed("word")));
+    }
+
+    #[test]
+    fn lemmas_work() {
+        let lemmas = Lemmas::from_tsv(io::Cursor::new("\nmice\tmouse\r\n")).unwrap();
+        assert_eq!(lemmas.len(), 1);
+        assert_eq!(lemmas.lemma("mice"), Some("mouse"));
+        let err = Lemmas::from_tsv(io::Cursor::new("mice\tmouse\ngeese goose")).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: no tab between form and lemma");
+    }
+
+    #[test]
+    fn table_is_sorted() {
+        assert!(DECOMPOSED.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+}
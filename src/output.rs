@@ -0,0 +1,1095 @@
+//! output formats of results
+
+use std::borrow::Cow;
+use std::io::{self, BufRead, Read, Write};
+use std::str::FromStr;
+
+use crate::coverage::Coverage;
+use crate::decode::{strip_bom, Transcoder};
+use crate::metadata::Metadata;
+use crate::{json, Frequencies};
+
+/// write one JSON object per token (JSON Lines), like `{"token":"foo","count":42}`
+///
+/// tokens are written in the order of [`Frequencies::sorted`](../frequencies/struct.Frequencies.html#method.sorted),
+/// and each line is written as soon as it is formatted, so the whole document is not buffered.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count, output::write_json_lines, CountOption};
+/// let freqs = count(Cursor::new("b a b"), CountOption::Word);
+/// let mut out = Vec::new();
+/// write_json_lines(&freqs, &mut out).unwrap();
+/// assert_eq!(
+///     String::from_utf8(out).unwrap(),
+///     "{\"token\":\"b\",\"count\":2}\n{\"token\":\"a\",\"count\":1}\n"
+/// );
+/// ```
+pub fn write_json_lines(freqs: &Frequencies, mut out: impl Write) -> io::Result<()> {
+    for (token, count) in freqs.sorted() {
+        writeln!(
+            out,
+            "{{\"token\":{},\"count\":{}}}",
+            json::string(token),
+            count
+        )?;
+    }
+    Ok(())
+}
+
+/// same as [`write_json_lines`](fn.write_json_lines.html), but of the `n` most frequent
+/// tokens, with `columns` as fields `percent` and `cumulative` (numbers in percent of all
+/// tokens)
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::output::{write_json_lines_with, Columns};
+/// use bicycle_book_wordcount::{count, CountOption};
+/// let freqs = count(Cursor::new("b a b"), CountOption::Word);
+/// let mut out = Vec::new();
+/// let columns = Columns::default().cumulative(true).precision(1);
+/// write_json_lines_with(&freqs, 10, columns, &mut out).unwrap();
+/// assert_eq!(
+///     String::from_utf8(out).unwrap().lines().nth(1),
+///     Some("{\"token\":\"a\",\"count\":1,\"percent\":33.3,\"cumulative\":100.0}")
+/// );
+/// ```
+pub fn write_json_lines_with(
+    freqs: &Frequencies,
+    n: usize,
+    columns: Columns,
+    mut out: impl Write,
+) -> io::Result<()> {
+    let names = columns.headers(&["percent", "cumulative"]);
+    for row in columns.rows(freqs, n) {
+        write!(
+            out,
+            "{{\"token\":{},\"count\":{}",
+            json::string(row.token),
+            row.count
+        )?;
+        for (name, value) in names.iter().zip(&row.values) {
+            write!(out, ",\"{}\":{}", name, value)?;
+        }
+        writeln!(out, "}}")?;
+    }
+    Ok(())
+}
+
+/// write a JSON object from tokens to counts, like `{"foo":42,"bar":1}`
+///
+/// tokens are in the order of [`Frequencies::sorted`](../frequencies/struct.Frequencies.html#method.sorted).
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count, output::write_json, CountOption};
+/// let freqs = count(Cursor::new("b a b"), CountOption::Word);
+/// let mut out = Vec::new();
+/// write_json(&freqs, &mut out).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), "{\"b\":2,\"a\":1}\n");
+/// ```
+pub fn write_json(freqs: &Frequencies, mut out: impl Write) -> io::Result<()> {
+    write_object(freqs, &mut out)?;
+    out.write_all(b"\n")
+}
+
+/// write an object of `metadata` and counts, like
+/// `{"metadata":{"version":"0.1.0",...},"counts":{"foo":42}}`, which
+/// [`read_json`](fn.read_json.html) reads as well
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::metadata::Metadata;
+/// use bicycle_book_wordcount::output::{read_json_with_metadata, write_json_with_metadata};
+/// use bicycle_book_wordcount::{count, CountConfig, CountOption};
+/// let freqs = count(Cursor::new("b a b"), CountOption::Word);
+/// let metadata = Metadata::new(&CountConfig::default(), vec!["a.txt".to_string()]);
+/// let mut out = Vec::new();
+/// write_json_with_metadata(&freqs, &metadata, &mut out).unwrap();
+/// let text = String::from_utf8(out).unwrap();
+/// assert!(text.starts_with("{\"metadata\":{\"version\":"));
+/// assert!(text.ends_with("},\"counts\":{\"b\":2,\"a\":1}}\n"));
+/// let (read, read_metadata) = read_json_with_metadata(Cursor::new(text)).unwrap();
+/// assert_eq!((read, read_metadata), (freqs, Some(metadata)));
+/// ```
+pub fn write_json_with_metadata(
+    freqs: &Frequencies,
+    metadata: &Metadata,
+    mut out: impl Write,
+) -> io::Result<()> {
+    write!(out, "{{\"metadata\":{},\"counts\":", metadata.json())?;
+    write_object(freqs, &mut out)?;
+    out.write_all(b"}\n")
+}
+
+/// write CSV (RFC 4180) of a header `token,count` and a row per token, in the order of
+/// [`Frequencies::sorted`](../frequencies/struct.Frequencies.html#method.sorted). tokens with
+/// commas, quotes or line breaks are quoted
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count, output::write_csv, CountOption};
+/// let freqs = count(Cursor::new("b\na,\"1\"\nb"), CountOption::Line);
+/// let mut out = Vec::new();
+/// write_csv(&freqs, &mut out).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), "token,count\nb,2\n\"a,\"\"1\"\"\",1\n");
+/// ```
+pub fn write_csv(freqs: &Frequencies, mut out: impl Write) -> io::Result<()> {
+    writeln!(out, "token,count")?;
+    for (token, count) in freqs.sorted() {
+        if token.contains([',', '"', '\n', '\r']) {
+            writeln!(out, "\"{}\",{}", token.replace('"', "\"\""), count)?;
+        } else {
+            writeln!(out, "{},{}", token, count)?;
+        }
+    }
+    Ok(())
+}
+
+/// write [`write_csv`](fn.write_csv.html) after comment lines of `metadata`, like
+/// `# mode: word`. readers of CSV may need to be told to skip lines starting with `#`
+pub fn write_csv_with_metadata(
+    freqs: &Frequencies,
+    metadata: &Metadata,
+    mut out: impl Write,
+) -> io::Result<()> {
+    for line in metadata.to_string().lines() {
+        writeln!(out, "# {}", line)?;
+    }
+    write_csv(freqs, out)
+}
+
+/// version of the format of [`write_snapshot`](fn.write_snapshot.html), raised when the format
+/// changes
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// write counts in a canonical text for snapshot (golden file) tests, which is the same for
+/// the same counts in any run and on any platform, and changes by a line per changed token
+///
+/// the first line is `wordcount-snapshot` and [`SNAPSHOT_VERSION`](constant.SNAPSHOT_VERSION.html),
+/// followed by lines of the total and of the count of distinct tokens, and a line of a token
+/// and its count separated by a tab per token, in the order of tokens (by bytes, not by
+/// count, so that changes of counts do not move lines). backslashes, tabs, line breaks and
+/// other control chars of tokens are escaped, like `\\`, `\t`, `\n` and `\u{7f}`
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count, output::write_snapshot, CountOption};
+/// let freqs = count(Cursor::new("b\na\tc\nb"), CountOption::Line);
+/// let mut out = Vec::new();
+/// write_snapshot(&freqs, &mut out).unwrap();
+/// assert_eq!(
+///     String::from_utf8(out).unwrap(),
+///     "wordcount-snapshot 1\ntotal 3\ndistinct 2\na\\tc\t1\nb\t2\n"
+/// );
+/// ```
+pub fn write_snapshot(freqs: &Frequencies, mut out: impl Write) -> io::Result<()> {
+    writeln!(out, "wordcount-snapshot {}", SNAPSHOT_VERSION)?;
+    writeln!(out, "total {}", freqs.total())?;
+    writeln!(out, "distinct {}", freqs.len())?;
+    for (token, count) in freqs.sorted_by_key() {
+        writeln!(out, "{}\t{}", escape_snapshot(token), count)?;
+    }
+    Ok(())
+}
+
+/// the text of [`write_snapshot`](fn.write_snapshot.html), to compare with a golden file
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::{count_str, output::snapshot, CountOption};
+/// let freqs = count_str("the cat the", CountOption::Word);
+/// let golden = "wordcount-snapshot 1\ntotal 3\ndistinct 2\ncat\t1\nthe\t2\n";
+/// assert_eq!(snapshot(&freqs), golden);
+/// ```
+pub fn snapshot(freqs: &Frequencies) -> String {
+    let mut out = Vec::new();
+    write_snapshot(freqs, &mut out).expect("writing to a Vec does not fail");
+    String::from_utf8(out).expect("snapshots are UTF-8")
+}
+
+fn escape_snapshot(token: &str) -> Cow<'_, str> {
+    if !token.contains(|c: char| c == '\\' || c.is_control()) {
+        return Cow::Borrowed(token);
+    }
+    let mut escaped = String::with_capacity(token.len() + 2);
+    for c in token.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+/// write counts of a file as a JSON object on a line, like
+/// `{"file":"a.txt","counts":{"foo":42}}`, to write a line per file as soon as it is counted
+/// (JSON Lines)
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count, output::write_file_json, CountOption};
+/// let freqs = count(Cursor::new("b a b"), CountOption::Word);
+/// let mut out = Vec::new();
+/// write_file_json("docs/a.txt", &freqs, &mut out).unwrap();
+/// assert_eq!(
+///     String::from_utf8(out).unwrap(),
+///     "{\"file\":\"docs/a.txt\",\"counts\":{\"b\":2,\"a\":1}}\n"
+/// );
+/// ```
+pub fn write_file_json(file: &str, freqs: &Frequencies, mut out: impl Write) -> io::Result<()> {
+    write!(out, "{{\"file\":{},\"counts\":", json::string(file))?;
+    write_object(freqs, &mut out)?;
+    out.write_all(b"}\n")
+}
+
+fn write_object(freqs: &Frequencies, mut out: impl Write) -> io::Result<()> {
+    out.write_all(b"{")?;
+    for (i, (token, count)) in freqs.sorted().into_iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        write!(out, "{}:{}", json::string(token), count)?;
+    }
+    out.write_all(b"}")
+}
+
+/// write a booktabs LaTeX table of the `n` most frequent tokens with counts and shares of all
+/// tokens in percent, like `foo & 42 & 84.00 \\`. special characters of tokens are escaped
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count, output::write_latex, CountOption};
+/// let freqs = count(Cursor::new("b a_1 b b"), CountOption::Word);
+/// let mut out = Vec::new();
+/// write_latex(&freqs, 10, &mut out).unwrap();
+/// let expected = r"\begin{tabular}{lrr}
+/// \toprule
+/// Token & Count & \% \\
+/// \midrule
+/// b & 3 & 75.00 \\
+/// a\_1 & 1 & 25.00 \\
+/// \bottomrule
+/// \end{tabular}
+/// ";
+/// assert_eq!(String::from_utf8(out).unwrap(), expected);
+/// ```
+pub fn write_latex(freqs: &Frequencies, n: usize, out: impl Write) -> io::Result<()> {
+    write_latex_with(freqs, n, Columns::default(), out)
+}
+
+/// same as [`write_latex`](fn.write_latex.html), but with `columns` after counts
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::output::{write_latex_with, Columns};
+/// use bicycle_book_wordcount::{count, CountOption};
+/// let freqs = count(Cursor::new("b a b b"), CountOption::Word);
+/// let mut out = Vec::new();
+/// let columns = "percent,cumulative".parse::<Columns>().unwrap().precision(1);
+/// write_latex_with(&freqs, 10, columns, &mut out).unwrap();
+/// let table = String::from_utf8(out).unwrap();
+/// assert!(table.starts_with("\\begin{tabular}{lrrr}"));
+/// assert!(table.contains("Token & Count & \\% & Cum.\\ \\% \\\\"));
+/// assert!(table.contains("a & 1 & 25.0 & 100.0 \\\\"));
+/// ```
+pub fn write_latex_with(
+    freqs: &Frequencies,
+    n: usize,
+    columns: Columns,
+    mut out: impl Write,
+) -> io::Result<()> {
+    let align = "r".repeat(columns.len());
+    writeln!(out, "\\begin{{tabular}}{{lr{}}}", align)?;
+    writeln!(out, "\\toprule")?;
+    write!(out, "Token & Count")?;
+    for header in columns.headers(&["\\%", "Cum.\\ \\%"]) {
+        write!(out, " & {}", header)?;
+    }
+    writeln!(out, " \\\\")?;
+    writeln!(out, "\\midrule")?;
+    for row in columns.rows(freqs, n) {
+        write!(out, "{} & {}", latex_escape(row.token), row.count)?;
+        for value in &row.values {
+            write!(out, " & {}", value)?;
+        }
+        writeln!(out, " \\\\")?;
+    }
+    writeln!(out, "\\bottomrule")?;
+    writeln!(out, "\\end{{tabular}}")
+}
+
+/// write a GitHub Flavored Markdown table of the `n` most frequent tokens with counts and
+/// shares of all tokens in percent. tokens are in code spans, pipes in them are escaped
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count, output::write_markdown, CountOption};
+/// let freqs = count(Cursor::new("b|bb"), CountOption::Char);
+/// let mut out = Vec::new();
+/// write_markdown(&freqs, 2, &mut out).unwrap();
+/// let expected = "\
+/// | Token | Count | % |
+/// | :--- | ---: | ---: |
+/// | `b` | 3 | 75.00 |
+/// | `\\|` | 1 | 25.00 |
+/// ";
+/// assert_eq!(String::from_utf8(out).unwrap(), expected);
+/// ```
+pub fn write_markdown(freqs: &Frequencies, n: usize, out: impl Write) -> io::Result<()> {
+    write_markdown_with(freqs, n, Columns::default(), out)
+}
+
+/// same as [`write_markdown`](fn.write_markdown.html), but with `columns` after counts
+pub fn write_markdown_with(
+    freqs: &Frequencies,
+    n: usize,
+    columns: Columns,
+    mut out: impl Write,
+) -> io::Result<()> {
+    write!(out, "| Token | Count |")?;
+    for header in columns.headers(&["%", "Cum. %"]) {
+        write!(out, " {} |", header)?;
+    }
+    writeln!(out)?;
+    writeln!(out, "| :--- | ---: |{}", " ---: |".repeat(columns.len()))?;
+    for row in columns.rows(freqs, n) {
+        let token = row.token.replace('|', "\\|");
+        // a code span containing backticks is delimited by double backticks and spaces
+        let fence = if token.contains('`') { "`` " } else { "`" };
+        let end: String = fence.chars().rev().collect();
+        write!(out, "| {}{}{} | {} |", fence, token, end, row.count)?;
+        for value in &row.values {
+            write!(out, " {} |", value)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// write a self-contained HTML document (no scripts or external files) with the totals, the
+/// coverage by top tokens, and a table of the `n` most frequent tokens with bars of counts
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count, output::write_html, CountOption};
+/// let freqs = count(Cursor::new("<b> a b"), CountOption::Word);
+/// let mut out = Vec::new();
+/// write_html(&freqs, 10, &mut out).unwrap();
+/// let html = String::from_utf8(out).unwrap();
+/// assert!(html.starts_with("<!DOCTYPE html>"));
+/// assert!(html.contains("<td>b</td><td>2</td>"));
+/// ```
+pub fn write_html(freqs: &Frequencies, n: usize, out: impl Write) -> io::Result<()> {
+    write_html_with(freqs, n, Columns::default(), out)
+}
+
+/// same as [`write_html`](fn.write_html.html), but with `columns` after counts
+pub fn write_html_with(
+    freqs: &Frequencies,
+    n: usize,
+    columns: Columns,
+    mut out: impl Write,
+) -> io::Result<()> {
+    let total = freqs.total();
+    let coverage = Coverage::of(freqs);
+    let rows = columns.rows(freqs, n);
+    let max = rows.first().map_or(1, |row| row.count.max(1));
+    out.write_all(HTML_HEAD.as_bytes())?;
+    writeln!(out, "<dl>")?;
+    writeln!(out, "<dt>tokens</dt><dd>{}</dd>", total)?;
+    writeln!(out, "<dt>distinct tokens</dt><dd>{}</dd>", freqs.len())?;
+    for &percent in &[50, 90] {
+        let tokens = coverage.tokens_for(percent as f64 / 100.0);
+        writeln!(
+            out,
+            "<dt>tokens covering {}%</dt><dd>{}</dd>",
+            percent, tokens
+        )?;
+    }
+    writeln!(out, "</dl>")?;
+    writeln!(out, "<table>")?;
+    write!(out, "<tr><th>#</th><th>token</th><th>count</th>")?;
+    for header in columns.headers(&["%", "cum. %"]) {
+        write!(out, "<th>{}</th>", header)?;
+    }
+    writeln!(out, "<th></th></tr>")?;
+    for (i, row) in rows.iter().enumerate() {
+        write!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td>",
+            i + 1,
+            html_escape(row.token),
+            row.count
+        )?;
+        for value in &row.values {
+            write!(out, "<td>{}</td>", value)?;
+        }
+        writeln!(
+            out,
+            "<td><div class=\"bar\" style=\"width:{:.1}%\"></div></td></tr>",
+            row.count as f64 * 100.0 / max as f64
+        )?;
+    }
+    writeln!(out, "</table>")?;
+    writeln!(out, "</body>\n</html>")
+}
+
+const HTML_HEAD: &str = "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>wordcount report</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+dl { display: grid; grid-template-columns: max-content auto; gap: 0.2em 1em; }
+dd { margin: 0; }
+table { border-collapse: collapse; }
+th, td { padding: 0.2em 0.6em; text-align: right; }
+td:nth-child(2) { text-align: left; white-space: pre; }
+td:last-child { width: 20em; }
+.bar { height: 1em; background: #4a7fc1; }
+</style>
+</head>
+<body>
+<h1>wordcount report</h1>
+";
+
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn latex_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// settings of Elasticsearch bulk requests (NDJSON) to index counts, an `index` action and a
+/// document like `{"token":"foo","count":42}` per token
+///
+/// the id of a document is made of a template, in which `{token}` is replaced by the token
+/// and `{file}` by the file of [`write_file`](#method.write_file), so that indexing again
+/// updates the same documents. without a template, Elasticsearch generates ids
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count, output::Bulk, CountOption};
+/// let freqs = count(Cursor::new("b a b"), CountOption::Word);
+/// let mut out = Vec::new();
+/// Bulk::new("words").id(Some("{file}/{token}")).write_file("a.txt", &freqs, &mut out).unwrap();
+/// let lines: Vec<_> = std::str::from_utf8(&out).unwrap().lines().collect();
+/// assert_eq!(lines[0], r#"{"index":{"_index":"words","_id":"a.txt/b"}}"#);
+/// assert_eq!(lines[1], r#"{"file":"a.txt","token":"b","count":2}"#);
+/// assert_eq!(lines.len(), 4);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bulk {
+    index: String,
+    id: Option<String>,
+}
+
+impl Default for Bulk {
+    fn default() -> Self {
+        Bulk::new("wordcount")
+    }
+}
+
+impl Bulk {
+    /// index into `index`, with tokens as ids
+    pub fn new(index: &str) -> Self {
+        Bulk {
+            index: index.to_string(),
+            id: Some("{token}".to_string()),
+        }
+    }
+
+    /// template of ids, or none to let Elasticsearch generate them
+    pub fn id(mut self, template: Option<&str>) -> Self {
+        self.id = template.map(str::to_string);
+        self
+    }
+
+    /// write actions of the tokens of `freqs`, in the order of
+    /// [`Frequencies::sorted`](../frequencies/struct.Frequencies.html#method.sorted)
+    pub fn write(&self, freqs: &Frequencies, out: impl Write) -> io::Result<()> {
+        self.write_documents(None, freqs, out)
+    }
+
+    /// write actions of the tokens of `freqs` counted in `file`, with the field `file`
+    pub fn write_file(&self, file: &str, freqs: &Frequencies, out: impl Write) -> io::Result<()> {
+        self.write_documents(Some(file), freqs, out)
+    }
+
+    fn write_documents(
+        &self,
+        file: Option<&str>,
+        freqs: &Frequencies,
+        mut out: impl Write,
+    ) -> io::Result<()> {
+        let index = json::string(&self.index);
+        let file_field = file.map(|file| format!("\"file\":{},", json::string(file)));
+        for (token, count) in freqs.sorted() {
+            write!(out, "{{\"index\":{{\"_index\":{}", index)?;
+            if let Some(template) = &self.id {
+                let id = bulk_id(template, token, file.unwrap_or(""));
+                write!(out, ",\"_id\":{}", json::string(&id))?;
+            }
+            writeln!(out, "}}}}")?;
+            writeln!(
+                out,
+                "{{{}\"token\":{},\"count\":{}}}",
+                file_field.as_deref().unwrap_or(""),
+                json::string(token),
+                count
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// `template` with `{token}` and `{file}` replaced in one pass, so that placeholders in tokens
+/// and files are left as they are
+fn bulk_id(template: &str, token: &str, file: &str) -> String {
+    let mut id = String::with_capacity(template.len() + token.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        id.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("{token}") {
+            id.push_str(token);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("{file}") {
+            id.push_str(file);
+            rest = after;
+        } else {
+            id.push('{');
+            rest = &rest[1..];
+        }
+    }
+    id.push_str(rest);
+    id
+}
+
+/// read a JSON object written by [`write_json`](fn.write_json.html), or the counts of one
+/// written by [`write_json_with_metadata`](fn.write_json_with_metadata.html)
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::output::read_json;
+/// let freqs = read_json(Cursor::new("{\"b\":2,\"a\":1}\n")).unwrap();
+/// assert_eq!(freqs["b"], 2);
+/// ```
+///
+/// # Errors
+///
+/// an error of kind `InvalidData` if the input is not such an object
+pub fn read_json(input: impl Read) -> io::Result<Frequencies> {
+    read_json_with_metadata(input).map(|(freqs, _)| freqs)
+}
+
+/// read a JSON object written by [`write_json`](fn.write_json.html) without metadata, or by
+/// [`write_json_with_metadata`](fn.write_json_with_metadata.html) with it
+///
+/// # Errors
+///
+/// an error of kind `InvalidData` if the input is not such an object
+pub fn read_json_with_metadata(
+    mut input: impl Read,
+) -> io::Result<(Frequencies, Option<Metadata>)> {
+    let mut text = String::new();
+    input.read_to_string(&mut text)?;
+    let invalid = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+    let (metadata, entries) = match json::counts(&text) {
+        Ok(entries) => (None, entries),
+        // values of counts are numbers, and ones of an object with metadata are objects
+        Err(e) => match json::counts_with_metadata(&text) {
+            Ok((metadata, entries)) => (
+                Some(Metadata::from_json(metadata).map_err(invalid)?),
+                entries,
+            ),
+            Err(_) => return Err(invalid(e)),
+        },
+    };
+    let mut freqs = Frequencies::new();
+    for (token, count) in entries {
+        let total = freqs.entry(token).or_insert(0);
+        *total = total.saturating_add(count);
+    }
+    Ok((freqs, metadata))
+}
+
+/// read lines written by [`write_file_json`](fn.write_file_json.html), as files and their
+/// counts in the order of lines. empty lines are skipped
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::output::read_file_json;
+/// let text = "{\"file\":\"a.txt\",\"counts\":{\"b\":2}}\n{\"file\":\"c.txt\",\"counts\":{}}\n";
+/// let files = read_file_json(Cursor::new(text)).unwrap();
+/// assert_eq!(files[0].0, "a.txt");
+/// assert_eq!(files[0].1["b"], 2);
+/// assert!(files[1].1.is_empty());
+/// ```
+///
+/// # Errors
+///
+/// an error of kind `InvalidData` if a line is not such an object
+pub fn read_file_json(input: impl BufRead) -> io::Result<Vec<(String, Frequencies)>> {
+    let mut files = Vec::new();
+    for (i, line) in Transcoder::new(input).lines().enumerate() {
+        let line = line?;
+        let line = if i == 0 { strip_bom(&line) } else { &line };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (file, entries) =
+            json::file_counts(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut freqs = Frequencies::new();
+        freqs.extend(entries);
+        files.push((file, freqs));
+    }
+    Ok(files)
+}
+
+/// columns derived from counts in tables and JSON Lines: the share of all tokens in percent,
+/// and the cumulative share of the tokens up to the row. the default has shares with 2
+/// decimals, as tables without settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Columns {
+    percent: bool,
+    cumulative: bool,
+    precision: usize,
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Columns {
+            percent: true,
+            cumulative: false,
+            precision: 2,
+        }
+    }
+}
+
+/// a row of a table, with formatted derived values
+struct Row<'a> {
+    token: &'a str,
+    count: usize,
+    values: Vec<String>,
+}
+
+impl Columns {
+    /// no derived columns
+    pub fn none() -> Self {
+        Columns {
+            percent: false,
+            cumulative: false,
+            ..Columns::default()
+        }
+    }
+
+    pub fn percent(mut self, yes: bool) -> Self {
+        self.percent = yes;
+        self
+    }
+
+    pub fn cumulative(mut self, yes: bool) -> Self {
+        self.cumulative = yes;
+        self
+    }
+
+    /// digits after the decimal point of shares
+    pub fn precision(mut self, digits: usize) -> Self {
+        self.precision = digits;
+        self
+    }
+
+    /// count of derived columns
+    pub fn len(&self) -> usize {
+        self.percent as usize + self.cumulative as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// headers of the columns of `[percent, cumulative]`
+    fn headers<'a>(&self, names: &[&'a str; 2]) -> Vec<&'a str> {
+        let shown = [self.percent, self.cumulative];
+        (0..2).filter(|&i| shown[i]).map(|i| names[i]).collect()
+    }
+
+    /// the `n` most frequent tokens, with shares of all tokens
+    fn rows<'a>(&self, freqs: &'a Frequencies, n: usize) -> Vec<Row<'a>> {
+        let total = freqs.total() as f64;
+        let mut sum = 0;
+        let share = |count: usize| format!("{:.*}", self.precision, count as f64 * 100.0 / total);
+        freqs
+            .top(n)
+            .into_iter()
+            .map(|(token, count)| {
+                sum += count;
+                let mut values = Vec::with_capacity(self.len());
+                if self.percent {
+                    values.push(share(count));
+                }
+                if self.cumulative {
+                    values.push(share(sum));
+                }
+                Row {
+                    token,
+                    count,
+                    values,
+                }
+            })
+            .collect()
+    }
+}
+
+/// columns by names separated by commas, `percent` and `cumulative`, or `none`
+impl FromStr for Columns {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut columns = Columns::none();
+        for name in s.split(',').map(str::trim) {
+            match name {
+                "percent" => columns.percent = true,
+                "cumulative" => columns.cumulative = true,
+                "none" => {}
+                _ => return Err(format!("unknown column {:?}", name)),
+            }
+        }
+        Ok(columns)
+    }
+}
+
+/// digit grouping of counts in output for people. machine formats are always written plain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum NumberStyle {
+    /// `1234567`
+    #[default]
+    Plain,
+    /// `1,234,567`, as in English and Japanese
+    Comma,
+    /// `1.234.567`, as in German and Italian
+    Period,
+    /// `1 234 567` with no-break spaces, as in French and Russian
+    Space,
+    /// `1’234’567`, as in Switzerland
+    Apostrophe,
+    /// `12,34,567`, groups of two digits above thousands, as in India
+    Indian,
+}
+
+impl NumberStyle {
+    /// style of a POSIX locale name such as `de_DE.UTF-8`, `Plain` for `C`, `POSIX`
+    /// and unknown languages
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::output::NumberStyle;
+    /// assert_eq!(NumberStyle::of_locale("ja_JP.UTF-8"), NumberStyle::Comma);
+    /// assert_eq!(NumberStyle::of_locale("de_CH"), NumberStyle::Apostrophe);
+    /// assert_eq!(NumberStyle::of_locale("C"), NumberStyle::Plain);
+    /// ```
+    pub fn of_locale(locale: &str) -> Self {
+        let name = locale.split(&['.', '@'][..]).next().unwrap_or("");
+        let mut parts = name.split(&['_', '-'][..]);
+        let language = parts.next().unwrap_or("").to_lowercase();
+        let country = parts.next().unwrap_or("").to_uppercase();
+        match (language.as_str(), country.as_str()) {
+            (_, "CH") | (_, "LI") => NumberStyle::Apostrophe,
+            (_, "IN") => NumberStyle::Indian,
+            ("en", _) | ("ja", _) | ("zh", _) | ("ko", _) | ("th", _) | ("he", _) => {
+                NumberStyle::Comma
+            }
+            ("de", _)
+            | ("it", _)
+            | ("es", _)
+            | ("nl", _)
+            | ("pt", _)
+            | ("da", _)
+            | ("id", _)
+            | ("tr", _)
+            | ("el", _) => NumberStyle::Period,
+            ("fr", _)
+            | ("ru", _)
+            | ("pl", _)
+            | ("cs", _)
+            | ("sk", _)
+            | ("sv", _)
+            | ("fi", _)
+            | ("nb", _)
+            | ("uk", _)
+            | ("hu", _)
+            | ("bg", _) => NumberStyle::Space,
+            _ => NumberStyle::Plain,
+        }
+    }
+
+    /// `n` in the style
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::output::NumberStyle;
+    /// assert_eq!(NumberStyle::Comma.format(184467440737), "184,467,440,737");
+    /// assert_eq!(NumberStyle::Indian.format(1234567), "12,34,567");
+    /// assert_eq!(NumberStyle::Period.format(999), "999");
+    /// ```
+    pub fn format(self, n: usize) -> String {
+        let digits = n.to_string();
+        let separator = match self {
+            NumberStyle::Plain => return digits,
+            NumberStyle::Comma | NumberStyle::Indian => ',',
+            NumberStyle::Period => '.',
+            NumberStyle::Space => '\u{a0}',
+            NumberStyle::Apostrophe => '’',
+        };
+        // lengths of groups from the right
+        let mut groups = Vec::new();
+        let mut rest = digits.len();
+        while rest > 0 {
+            let len = match (self, groups.len()) {
+                (NumberStyle::Indian, n) if n > 0 => 2,
+                _ => 3,
+            };
+            groups.push(len.min(rest));
+            rest -= len.min(rest);
+        }
+        let mut formatted = String::with_capacity(digits.len() + groups.len() * 3);
+        let mut start = 0;
+        for (i, len) in groups.iter().rev().enumerate() {
+            if i > 0 {
+                formatted.push(separator);
+            }
+            formatted.push_str(&digits[start..start + len]);
+            start += len;
+        }
+        formatted
+    }
+}
+
+/// a style by name: `plain`, `comma`, `period`, `space`, `apostrophe` or `indian`
+impl FromStr for NumberStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let style = match s {
+            "plain" => NumberStyle::Plain,
+            "comma" => NumberStyle::Comma,
+            "period" => NumberStyle::Period,
+            "space" => NumberStyle::Space,
+            "apostrophe" => NumberStyle::Apostrophe,
+            "indian" => NumberStyle::Indian,
+            _ => return Err(format!("unknown number style {:?}", s)),
+        };
+        Ok(style)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{count, CountOption};
+    use std::io::Cursor;
+
+    #[test]
+    fn json_of_empty() {
+        let mut out = Vec::new();
+        write_json(&Frequencies::new(), &mut out).unwrap();
+        assert_eq!(out, b"{}\n");
+    }
+
+    #[test]
+    fn json_lines_escapes_tokens() {
+        let freqs = count(Cursor::new("say \"hi\"\nsay \"hi\""), CountOption::Line);
+        let mut out = Vec::new();
+        write_json_lines(&freqs, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"token\":\"say \\\"hi\\\"\",\"count\":2}\n"
+        );
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let freqs = count(Cursor::new("a \"b\" a\nあ\t"), CountOption::Char);
+        let mut out = Vec::new();
+        write_json(&freqs, &mut out).unwrap();
+        assert_eq!(read_json(Cursor::new(out)).unwrap(), freqs);
+        let err = read_json(Cursor::new("[1]")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn bulk_ids_are_optional() {
+        let freqs = count(Cursor::new("\"a\""), CountOption::Line);
+        let mut out = Vec::new();
+        Bulk::default().write(&freqs, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"index\":{\"_index\":\"wordcount\",\"_id\":\"\\\"a\\\"\"}}\n\
+             {\"token\":\"\\\"a\\\"\",\"count\":1}\n"
+        );
+        let mut out = Vec::new();
+        Bulk::new("logs").id(None).write(&freqs, &mut out).unwrap();
+        assert!(out.starts_with(b"{\"index\":{\"_index\":\"logs\"}}\n"));
+    }
+
+    #[test]
+    fn bulk_ids_are_substituted_once() {
+        assert_eq!(bulk_id("{file}/{token}", "{file}", "a.txt"), "a.txt/{file}");
+        assert_eq!(bulk_id("{{token}}{x", "{token}", "f"), "{{token}}{x");
+        assert_eq!(bulk_id("id", "t", "f"), "id");
+    }
+
+    #[test]
+    fn columns_are_derived() {
+        assert_eq!("none".parse(), Ok(Columns::none()));
+        assert_eq!(
+            "cumulative, percent".parse(),
+            Ok(Columns::default().cumulative(true))
+        );
+        assert!("median".parse::<Columns>().is_err());
+        let freqs = count(Cursor::new("a b b c c c"), CountOption::Word);
+        let mut out = Vec::new();
+        let columns = Columns::none().cumulative(true).precision(0);
+        write_markdown_with(&freqs, 2, columns, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "| Token | Count | Cum. % |\n| :--- | ---: | ---: |\n| `c` | 3 | 50 |\n| `b` | 2 | 83 |\n"
+        );
+        let mut out = Vec::new();
+        write_json_lines_with(&freqs, 3, Columns::none(), &mut out).unwrap();
+        let mut plain = Vec::new();
+        write_json_lines(&freqs, &mut plain).unwrap();
+        assert_eq!(out, plain);
+    }
+
+    #[test]
+    fn latex_escapes_tokens() {
+        assert_eq!(
+            latex_escape(r"50% a&b ~^\"),
+            r"50\% a\&b \textasciitilde{}\textasciicircum{}\textbackslash{}"
+        );
+        let mut out = Vec::new();
+        write_latex(&count(Cursor::new("a b c"), CountOption::Word), 1, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap().lines().nth(4),
+            Some(r"a & 1 & 33.33 \\")
+        );
+    }
+
+    #[test]
+    fn markdown_quotes_backticks() {
+        let freqs = count(Cursor::new("`a`"), CountOption::Line);
+        let mut out = Vec::new();
+        write_markdown(&freqs, 10, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.lines().nth(2), Some("| `` `a` `` | 1 | 100.00 |"));
+    }
+
+    #[test]
+    fn snapshot_escapes_tokens() {
+        let freqs = count(
+            Cursor::new("c:\\dir\r\n\u{7}bell\nZ\nc:\\dir"),
+            CountOption::Line,
+        );
+        assert_eq!(
+            snapshot(&freqs),
+            "wordcount-snapshot 1\ntotal 4\ndistinct 3\n\\u{7}bell\t1\nZ\t1\nc:\\\\dir\t2\n"
+        );
+        assert_eq!(
+            snapshot(&Frequencies::new()),
+            "wordcount-snapshot 1\ntotal 0\ndistinct 0\n"
+        );
+    }
+
+    #[test]
+    fn html_report_works() {
+        let freqs = count(Cursor::new("a & b\na"), CountOption::Line);
+        let mut out = Vec::new();
+        write_html(&freqs, 10, &mut out).unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert!(html.contains("<dt>tokens</dt><dd>2</dd>"));
+        assert!(html.contains("<td>a &amp; b</td><td>1</td><td>50.00</td>"));
+        assert!(html.contains("style=\"width:100.0%\""));
+        assert!(html.ends_with("</html>\n"));
+        let mut empty = Vec::new();
+        write_html(&Frequencies::new(), 10, &mut empty).unwrap();
+    }
+
+    #[test]
+    fn numbers_are_grouped() {
+        assert_eq!(NumberStyle::Plain.format(1234567), "1234567");
+        assert_eq!(NumberStyle::Space.format(1234567), "1\u{a0}234\u{a0}567");
+        assert_eq!(NumberStyle::Apostrophe.format(1000), "1’000");
+        assert_eq!(NumberStyle::Indian.format(123), "123");
+        assert_eq!(NumberStyle::Indian.format(100000), "1,00,000");
+        assert_eq!(NumberStyle::Comma.format(0), "0");
+        assert_eq!(NumberStyle::Comma.format(100000), "100,000");
+        assert_eq!(
+            NumberStyle::of_locale("fr_FR.UTF-8@euro"),
+            NumberStyle::Space
+        );
+        assert_eq!("indian".parse(), Ok(NumberStyle::Indian));
+    }
+}
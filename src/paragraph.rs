@@ -0,0 +1,235 @@
+//! counting of hard-wrapped prose (e.g. mail or Markdown wrapped at 72 columns) as continuous
+//! text, see [`Paragraphs`](struct.Paragraphs.html)
+
+use std::io::{self, BufRead, Read};
+
+/// a reader of the lines of `R` with each paragraph joined into a line, so that single line
+/// breaks inside paragraphs do not end sentences or lines of whatever reads it
+///
+/// lines of a paragraph are joined by a space. a paragraph ends at a blank line, which is
+/// passed through, at a line ending with two spaces (a hard break of Markdown), and before
+/// a line starting a block of Markdown: a heading (`#`), an item of a list (`-`, `*`, `+`, or
+/// a number and `.`), a quote (`>`) or a fence of code (```` ``` ```` or `~~~`). headings are
+/// lines of their own, and lines in fenced code are passed through unjoined. the input is of
+/// UTF-8, or of another encoding compatible with ASCII
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{BufRead, Cursor};
+/// use bicycle_book_wordcount::paragraph::Paragraphs;
+/// use bicycle_book_wordcount::sentence_lengths;
+/// let text = "# Notes\nThis sentence is\nwrapped at a narrow\nwidth. Short one.\n\n- an item\n";
+/// let lines: Vec<_> = Paragraphs::new(Cursor::new(text)).lines().map(Result::unwrap).collect();
+/// assert_eq!(
+///     lines,
+///     ["# Notes", "This sentence is wrapped at a narrow width. Short one.", "", "- an item"]
+/// );
+/// let lengths = sentence_lengths(Paragraphs::new(Cursor::new(text)));
+/// assert_eq!(lengths.max(), Some(8));
+/// ```
+#[derive(Debug)]
+pub struct Paragraphs<R> {
+    inner: R,
+    /// the next line of output, and how much of it is consumed
+    out: Vec<u8>,
+    pos: usize,
+    /// a line read but not joined, since it ends the paragraph before it
+    pending: Option<Vec<u8>>,
+    in_fence: bool,
+}
+
+impl<R> Paragraphs<R> {
+    pub fn new(inner: R) -> Self {
+        Paragraphs {
+            inner,
+            out: Vec::new(),
+            pos: 0,
+            pending: None,
+            in_fence: false,
+        }
+    }
+
+    /// the wrapped reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: BufRead> Paragraphs<R> {
+    /// a line of input without its terminator, `None` at the end
+    fn next_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if let Some(line) = self.pending.take() {
+            return Ok(Some(line));
+        }
+        let mut line = Vec::new();
+        if self.inner.read_until(b'\n', &mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.ends_with(b"\n") {
+            line.pop();
+            if line.ends_with(b"\r") {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+
+    /// make the next line of output, empty at the end of input
+    fn fill_out(&mut self) -> io::Result<()> {
+        self.out.clear();
+        self.pos = 0;
+        let (mut any, mut open) = (false, false);
+        while let Some(line) = self.next_line()? {
+            any = true;
+            let block = Block::of(&line);
+            if self.in_fence || (!open && block != Block::Text) {
+                self.in_fence ^= block == Block::Fence;
+                self.out.extend_from_slice(&line);
+                // an item or a quote starts a paragraph
+                open = matches!(block, Block::Start) && !self.in_fence;
+                if !open {
+                    break;
+                }
+            } else if open && block != Block::Text {
+                self.pending = Some(line);
+                break;
+            } else {
+                if open {
+                    while self.out.last().is_some_and(u8::is_ascii_whitespace) {
+                        self.out.pop();
+                    }
+                    self.out.push(b' ');
+                    self.out.extend_from_slice(trim_start(&line));
+                } else {
+                    self.out.extend_from_slice(&line);
+                }
+                open = true;
+            }
+            if line.ends_with(b"  ") {
+                while self.out.last().is_some_and(u8::is_ascii_whitespace) {
+                    self.out.pop();
+                }
+                break;
+            }
+        }
+        if any {
+            self.out.push(b'\n');
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for Paragraphs<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Paragraphs<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos >= self.out.len() {
+            self.fill_out()?;
+        }
+        Ok(&self.out[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.out.len());
+    }
+}
+
+/// kind of a line for joining
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Block {
+    /// prose, joined to the paragraph
+    Text,
+    Blank,
+    /// a heading, a line of its own
+    Heading,
+    /// an item of a list or a quote, starting a paragraph
+    Start,
+    Fence,
+}
+
+impl Block {
+    fn of(line: &[u8]) -> Self {
+        let line = trim_start(line);
+        let marker = |prefix: &[u8]| {
+            line.starts_with(prefix)
+                && line
+                    .get(prefix.len())
+                    .map_or(true, |b| b.is_ascii_whitespace())
+        };
+        let hashes = line.iter().take_while(|&&b| b == b'#').count();
+        let digits = line.iter().take_while(|b| b.is_ascii_digit()).count();
+        let numbered = digits > 0 && line.get(digits) == Some(&b'.') && marker(&line[..=digits]);
+        if line.iter().all(u8::is_ascii_whitespace) {
+            Block::Blank
+        } else if line.starts_with(b"```") || line.starts_with(b"~~~") {
+            Block::Fence
+        } else if (1..=6).contains(&hashes) && marker(&line[..hashes]) {
+            Block::Heading
+        } else if marker(b"-") || marker(b"*") || marker(b"+") || line.starts_with(b">") || numbered
+        {
+            Block::Start
+        } else {
+            Block::Text
+        }
+    }
+}
+
+fn trim_start(line: &[u8]) -> &[u8] {
+    let start = line.iter().take_while(|b| b.is_ascii_whitespace()).count();
+    &line[start..]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    fn lines(text: &str) -> Vec<String> {
+        Paragraphs::new(Cursor::new(text))
+            .lines()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn paragraphs_are_joined() {
+        assert_eq!(lines("a\n  b\r\nc"), ["a b c"]);
+        assert_eq!(lines("a\n\n\nb\n"), ["a", "", "", "b"]);
+        assert_eq!(lines("hard  \nbreak\n"), ["hard", "break"]);
+        assert_eq!(
+            lines("intro\n- one\n  more\n- two\n> quoted\n> text\n1. first\n12.5 is\na number\n"),
+            [
+                "intro",
+                "- one more",
+                "- two",
+                "> quoted",
+                "> text",
+                "1. first 12.5 is a number"
+            ]
+        );
+        assert_eq!(
+            lines("code:\n```\nlet a\n= 1;\n```\nafter\n#tag\n## end\n"),
+            [
+                "code:",
+                "```",
+                "let a",
+                "= 1;",
+                "```",
+                "after #tag",
+                "## end"
+            ]
+        );
+        assert_eq!(lines(""), Vec::<String>::new());
+        assert_eq!(lines("\n"), [""]);
+        assert_eq!(lines("12\n#\n"), ["12", "#"]);
+    }
+}
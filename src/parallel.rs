@@ -0,0 +1,225 @@
+//! counting of many inputs on threads, of a pool of the caller or of its own, see
+//! [`count_parallel`](fn.count_parallel.html)
+
+use std::io::BufRead;
+use std::sync::Mutex;
+use std::thread;
+
+use crate::concurrent::lock;
+use crate::decode::{read_lines_with, Transcoder};
+use crate::tally::Tally;
+use crate::{CountConfig, CountError, Frequencies};
+
+/// a task run by a [`Pool`](trait.Pool.html), borrowing data of the caller
+pub type Task<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+/// threads to run tasks on, so that an application managing its own pool (e.g. of rayon) and
+/// CPU budget can count on it
+///
+/// # Examples
+///
+/// a pool of rayon:
+///
+/// ```ignore
+/// use bicycle_book_wordcount::parallel::{Pool, Task};
+///
+/// struct Rayon<'a>(&'a rayon::ThreadPool);
+///
+/// impl Pool for Rayon<'_> {
+///     fn threads(&self) -> usize {
+///         self.0.current_num_threads()
+///     }
+///
+///     fn run(&self, tasks: Vec<Task<'_>>) {
+///         self.0.scope(|scope| {
+///             for task in tasks {
+///                 scope.spawn(move |_| task());
+///             }
+///         })
+///     }
+/// }
+/// ```
+pub trait Pool {
+    /// count of tasks worth running at once, at least 1
+    fn threads(&self) -> usize;
+
+    /// run all of `tasks`, returning after all of them have finished
+    fn run(&self, tasks: Vec<Task<'_>>);
+}
+
+/// a pool of its own threads, started for each run (scoped threads of std)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Threads(usize);
+
+impl Threads {
+    /// `n` threads, at least 1. with 1, tasks run on the current thread
+    pub fn new(n: usize) -> Self {
+        Threads(n.max(1))
+    }
+
+    /// as many threads as the available parallelism, or 1 if it is unknown
+    pub fn available() -> Self {
+        Threads::new(thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+}
+
+/// [`Threads::available`](struct.Threads.html#method.available)
+impl Default for Threads {
+    fn default() -> Self {
+        Threads::available()
+    }
+}
+
+impl Pool for Threads {
+    fn threads(&self) -> usize {
+        self.0
+    }
+
+    fn run(&self, mut tasks: Vec<Task<'_>>) {
+        // the last one on the current thread, which would only wait otherwise
+        let last = tasks.pop();
+        thread::scope(|scope| {
+            for task in tasks {
+                scope.spawn(task);
+            }
+            if let Some(task) = last {
+                task();
+            }
+        })
+    }
+}
+
+/// `f` of each of `items` on up to [`threads`](trait.Pool.html#tymethod.threads) tasks of
+/// `pool`, in order of `items`. each task takes items one by one, with its own state made by
+/// `init`
+///
+/// # Panics
+///
+/// `pool` did not run all tasks
+pub fn map_with<T, S, R>(
+    items: impl IntoIterator<Item = T>,
+    pool: &impl Pool,
+    init: impl Fn() -> S + Sync,
+    f: impl Fn(&mut S, T) -> R + Sync,
+) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+{
+    let items: Vec<_> = items.into_iter().enumerate().collect();
+    let len = items.len();
+    let queue = Mutex::new(items.into_iter());
+    let results = Mutex::new(Vec::with_capacity(len));
+    let (queue, results, init, f) = (&queue, &results, &init, &f);
+    let tasks = (0..pool.threads().clamp(1, len.max(1)))
+        .map(|_| {
+            Box::new(move || {
+                let mut state = init();
+                // not `while let`, which would hold the lock while `f` runs
+                loop {
+                    let next = lock(queue).next();
+                    let (i, item) = match next {
+                        Some(next) => next,
+                        None => break,
+                    };
+                    let result = f(&mut state, item);
+                    lock(results).push((i, result));
+                }
+            }) as Task
+        })
+        .collect();
+    pool.run(tasks);
+    let mut results = std::mem::take(&mut *lock(results));
+    assert_eq!(results.len(), len, "the pool did not run all tasks");
+    results.sort_unstable_by_key(|&(i, _)| i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// same as [`try_count_with`](../fn.try_count_with.html) of each input, counted on threads of
+/// `pool`, in order of inputs. the setup is shared by the inputs of each thread, as
+/// [`count_many_with`](../fn.count_many_with.html)
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::parallel::{count_parallel, Threads};
+/// use bicycle_book_wordcount::CountConfig;
+/// let docs = vec![Cursor::new("aa bb"), Cursor::new("bb bb"), Cursor::new("cc")];
+/// let freqs = count_parallel(docs, &CountConfig::default(), &Threads::new(2));
+/// assert_eq!(freqs[1].as_ref().unwrap()["bb"], 2);
+/// assert_eq!(freqs[2].as_ref().unwrap()["cc"], 1);
+/// ```
+pub fn count_parallel<R: BufRead + Send>(
+    inputs: impl IntoIterator<Item = R>,
+    config: &CountConfig,
+    pool: &impl Pool,
+) -> Vec<Result<Frequencies, CountError>> {
+    map_with(
+        inputs,
+        pool,
+        || (Tally::new(config), Vec::new()),
+        |(tally, buf), input| {
+            let read = read_lines_with(buf, Transcoder::new(input), config.reading(), |line| {
+                tally.line(line)
+            });
+            // restarted even after an error, not to add the rest of the input to the next
+            let freqs = tally.restart();
+            read.map(|_| freqs)
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{count_many_with, CountOption};
+    use std::io::Cursor;
+
+    /// a pool running tasks in reverse order on the current thread
+    struct Reversed;
+
+    impl Pool for Reversed {
+        fn threads(&self) -> usize {
+            3
+        }
+
+        fn run(&self, tasks: Vec<Task<'_>>) {
+            for task in tasks.into_iter().rev() {
+                task();
+            }
+        }
+    }
+
+    #[test]
+    fn counts_are_in_order_of_inputs() {
+        let docs: Vec<String> = (0..50).map(|i| "a b ".repeat(i) + "end").collect();
+        let config = CountConfig::new(CountOption::Word).ignore_case(true);
+        let expected = count_many_with(docs.iter().map(|doc| doc.as_bytes()), &config);
+        for threads in &[1, 4, 100] {
+            let counted: Vec<_> = count_parallel(
+                docs.iter().map(|doc| doc.as_bytes()),
+                &config,
+                &Threads::new(*threads),
+            )
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+            assert_eq!(counted, expected);
+        }
+        let counted = count_parallel(docs.iter().map(|doc| doc.as_bytes()), &config, &Reversed);
+        assert_eq!(counted[49].as_ref().unwrap()["b"], 49);
+        assert!(count_parallel(Vec::<&[u8]>::new(), &config, &Threads::new(2)).is_empty());
+    }
+
+    #[test]
+    fn errors_stay_with_their_input() {
+        let inputs = vec![Cursor::new(&b"aa\n\xff bb"[..]), Cursor::new(&b"cc"[..])];
+        let counted = count_parallel(inputs, &CountConfig::default(), &Threads::new(1));
+        assert!(counted[0].is_err());
+        let freqs = counted[1].as_ref().unwrap();
+        assert_eq!(freqs.len(), 1);
+        assert_eq!(freqs["cc"], 1);
+        assert_eq!(Threads::new(0), Threads::new(1));
+    }
+}
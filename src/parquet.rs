@@ -0,0 +1,361 @@
+//! Parquet files of counts, to be read by DuckDB, Spark or pandas without conversion,
+//! see [`write_parquet`](fn.write_parquet.html)
+//!
+//! files have one row group of required columns in plain encoding without compression, which
+//! every reader supports.
+
+use std::convert::TryFrom;
+use std::io::{self, Write};
+
+use crate::Frequencies;
+
+const MAGIC: &[u8] = b"PAR1";
+
+/// a page ends after this many values, or bytes of values
+const PAGE_ROWS: usize = 64 * 1024;
+const PAGE_BYTES: usize = 1024 * 1024;
+
+// physical types, converted types, encodings and types of thrift fields of the format
+const INT64: i32 = 2;
+const BYTE_ARRAY: i32 = 6;
+const REQUIRED: i32 = 0;
+const UTF8: i32 = 0;
+const PLAIN: i32 = 0;
+const RLE: i32 = 3;
+const UNCOMPRESSED: i32 = 0;
+const DATA_PAGE: i32 = 0;
+const I32: u8 = 5;
+const I64: u8 = 6;
+const BINARY: u8 = 8;
+const LIST: u8 = 9;
+const STRUCT: u8 = 12;
+
+/// write counts as a Parquet file of columns `token` (string) and `count` (int64), in the
+/// order of [`Frequencies::sorted`](../frequencies/struct.Frequencies.html#method.sorted)
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count, parquet::write_parquet, CountOption};
+/// let freqs = count(Cursor::new("b a b"), CountOption::Word);
+/// let mut out = Vec::new();
+/// write_parquet(&freqs, &mut out).unwrap();
+/// assert!(out.starts_with(b"PAR1") && out.ends_with(b"PAR1"));
+/// ```
+pub fn write_parquet(freqs: &Frequencies, out: impl Write) -> io::Result<()> {
+    let sorted = freqs.sorted();
+    let tokens: Vec<&str> = sorted.iter().map(|(token, _)| token.as_str()).collect();
+    let counts: Vec<i64> = sorted.iter().map(|&(_, count)| int64(count)).collect();
+    write_columns(
+        &[
+            ("token", Values::Strings(&tokens)),
+            ("count", Values::Ints(&counts)),
+        ],
+        out,
+    )
+}
+
+/// write counts of files as a Parquet file of columns `file`, `token` and `count`, a row per
+/// token of each file, in the order of files and then of
+/// [`Frequencies::sorted`](../frequencies/struct.Frequencies.html#method.sorted)
+pub fn write_files_parquet<'a>(
+    files: impl IntoIterator<Item = (&'a str, &'a Frequencies)>,
+    out: impl Write,
+) -> io::Result<()> {
+    let (mut names, mut tokens, mut counts) = (Vec::new(), Vec::new(), Vec::new());
+    for (file, freqs) in files {
+        for (token, count) in freqs.sorted() {
+            names.push(file);
+            tokens.push(token.as_str());
+            counts.push(int64(count));
+        }
+    }
+    write_columns(
+        &[
+            ("file", Values::Strings(&names)),
+            ("token", Values::Strings(&tokens)),
+            ("count", Values::Ints(&counts)),
+        ],
+        out,
+    )
+}
+
+/// counts beyond `i64::MAX` saturate, as the format has no unsigned physical type
+fn int64(count: usize) -> i64 {
+    i64::try_from(count).unwrap_or(i64::MAX)
+}
+
+/// values of a column
+enum Values<'a> {
+    Strings(&'a [&'a str]),
+    Ints(&'a [i64]),
+}
+
+impl Values<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Values::Strings(values) => values.len(),
+            Values::Ints(values) => values.len(),
+        }
+    }
+
+    fn physical_type(&self) -> i32 {
+        match self {
+            Values::Strings(_) => BYTE_ARRAY,
+            Values::Ints(_) => INT64,
+        }
+    }
+
+    /// plain encoded pages, with counts of their values
+    fn pages(&self) -> Vec<(usize, Vec<u8>)> {
+        let mut pages = Vec::new();
+        let mut page = (0, Vec::new());
+        for i in 0..self.len() {
+            match self {
+                Values::Strings(values) => {
+                    page.1
+                        .extend_from_slice(&(values[i].len() as u32).to_le_bytes());
+                    page.1.extend_from_slice(values[i].as_bytes());
+                }
+                Values::Ints(values) => page.1.extend_from_slice(&values[i].to_le_bytes()),
+            }
+            page.0 += 1;
+            if page.0 == PAGE_ROWS || page.1.len() >= PAGE_BYTES {
+                pages.push(std::mem::take(&mut page));
+            }
+        }
+        if page.0 > 0 {
+            pages.push(page);
+        }
+        pages
+    }
+}
+
+/// a column chunk written
+struct Chunk {
+    offset: usize,
+    size: usize,
+}
+
+/// write a file of a row group of `columns` of the same length
+fn write_columns(columns: &[(&str, Values)], mut out: impl Write) -> io::Result<()> {
+    let rows = columns.first().map_or(0, |(_, values)| values.len());
+    out.write_all(MAGIC)?;
+    let mut offset = MAGIC.len();
+    let mut chunks = Vec::new();
+    if rows > 0 {
+        for (_, values) in columns {
+            let start = offset;
+            for (n, data) in values.pages() {
+                let mut header = Thrift::default();
+                header.i32(1, DATA_PAGE);
+                header.i32(2, data.len() as i32);
+                header.i32(3, data.len() as i32);
+                header.begin(5);
+                header.i32(1, n as i32);
+                header.i32(2, PLAIN);
+                header.i32(3, RLE);
+                header.i32(4, RLE);
+                header.end();
+                header.end();
+                out.write_all(&header.buf)?;
+                out.write_all(&data)?;
+                offset += header.buf.len() + data.len();
+            }
+            chunks.push(Chunk {
+                offset: start,
+                size: offset - start,
+            });
+        }
+    }
+
+    let mut meta = Thrift::default();
+    meta.i32(1, 1);
+    meta.list(2, STRUCT, columns.len() + 1);
+    meta.element();
+    meta.binary(4, b"schema");
+    meta.i32(5, columns.len() as i32);
+    meta.end();
+    for (name, values) in columns {
+        meta.element();
+        meta.i32(1, values.physical_type());
+        meta.i32(3, REQUIRED);
+        meta.binary(4, name.as_bytes());
+        if values.physical_type() == BYTE_ARRAY {
+            meta.i32(6, UTF8);
+        }
+        meta.end();
+    }
+    meta.i64(3, rows as i64);
+    // a file of no rows has no row groups
+    meta.list(4, STRUCT, chunks.len().min(1));
+    if !chunks.is_empty() {
+        meta.element();
+        meta.list(1, STRUCT, chunks.len());
+        for ((name, values), chunk) in columns.iter().zip(&chunks) {
+            meta.element();
+            meta.i64(2, chunk.offset as i64);
+            meta.begin(3);
+            meta.i32(1, values.physical_type());
+            meta.list(2, I32, 2);
+            meta.zigzag(PLAIN as i64);
+            meta.zigzag(RLE as i64);
+            meta.list(3, BINARY, 1);
+            meta.bytes(name.as_bytes());
+            meta.i32(4, UNCOMPRESSED);
+            meta.i64(5, rows as i64);
+            meta.i64(6, chunk.size as i64);
+            meta.i64(7, chunk.size as i64);
+            meta.i64(9, chunk.offset as i64);
+            meta.end();
+            meta.end();
+        }
+        meta.i64(
+            2,
+            chunks.iter().map(|chunk| chunk.size).sum::<usize>() as i64,
+        );
+        meta.i64(3, rows as i64);
+        meta.end();
+    }
+    meta.binary(
+        6,
+        concat!("bicycle-book-wordcount ", env!("CARGO_PKG_VERSION")).as_bytes(),
+    );
+    meta.end();
+    out.write_all(&meta.buf)?;
+    out.write_all(&(meta.buf.len() as u32).to_le_bytes())?;
+    out.write_all(MAGIC)?;
+    out.flush()
+}
+
+/// a struct in the thrift compact protocol, in which the metadata of the format is written
+#[derive(Default)]
+struct Thrift {
+    buf: Vec<u8>,
+    /// the last field id of the current struct, and of the outer ones
+    field: i16,
+    outer: Vec<i16>,
+}
+
+impl Thrift {
+    fn varint(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.buf.push(n as u8 | 0x80);
+            n >>= 7;
+        }
+        self.buf.push(n as u8);
+    }
+
+    fn zigzag(&mut self, n: i64) {
+        self.varint(((n << 1) ^ (n >> 63)) as u64);
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.varint(bytes.len() as u64);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn field(&mut self, id: i16, kind: u8) {
+        match id - self.field {
+            delta @ 1..=15 => self.buf.push((delta as u8) << 4 | kind),
+            _ => {
+                self.buf.push(kind);
+                self.zigzag(id as i64);
+            }
+        }
+        self.field = id;
+    }
+
+    fn i32(&mut self, id: i16, n: i32) {
+        self.field(id, I32);
+        self.zigzag(n as i64);
+    }
+
+    fn i64(&mut self, id: i16, n: i64) {
+        self.field(id, I64);
+        self.zigzag(n);
+    }
+
+    fn binary(&mut self, id: i16, bytes: &[u8]) {
+        self.field(id, BINARY);
+        self.bytes(bytes);
+    }
+
+    /// a list field of `len` elements of `kind`, written next
+    fn list(&mut self, id: i16, kind: u8, len: usize) {
+        self.field(id, LIST);
+        if len < 15 {
+            self.buf.push((len as u8) << 4 | kind);
+        } else {
+            self.buf.push(0xf0 | kind);
+            self.varint(len as u64);
+        }
+    }
+
+    /// a struct field, ended by [`end`](#method.end)
+    fn begin(&mut self, id: i16) {
+        self.field(id, STRUCT);
+        self.element();
+    }
+
+    /// a struct element of a list, ended by [`end`](#method.end)
+    fn element(&mut self) {
+        self.outer.push(self.field);
+        self.field = 0;
+    }
+
+    /// end the current struct, or the whole one
+    fn end(&mut self) {
+        self.buf.push(0);
+        self.field = self.outer.pop().unwrap_or(0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{count_str, CountOption};
+
+    /// the footer of a file
+    fn metadata(file: &[u8]) -> &[u8] {
+        assert!(file.starts_with(MAGIC) && file.ends_with(MAGIC));
+        let len = &file[file.len() - 8..file.len() - 4];
+        let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        &file[file.len() - 8 - len..file.len() - 8]
+    }
+
+    #[test]
+    fn thrift_is_compact() {
+        let mut thrift = Thrift::default();
+        thrift.i32(1, -1);
+        thrift.begin(3);
+        thrift.i64(20, 300);
+        thrift.end();
+        thrift.binary(4, b"ab");
+        thrift.end();
+        assert_eq!(
+            thrift.buf,
+            vec![0x15, 0x01, 0x2c, 0x06, 0x28, 0xd8, 0x04, 0x00, 0x18, 0x02, b'a', b'b', 0x00]
+        );
+    }
+
+    #[test]
+    fn columns_are_paged() {
+        let text: String = (0..PAGE_ROWS + 1).map(|i| format!("w{} ", i)).collect();
+        let freqs = count_str(&text, CountOption::Word);
+        let mut out = Vec::new();
+        write_parquet(&freqs, &mut out).unwrap();
+        let first = b"\x02\x00\x00\x00w0";
+        assert!(out.windows(first.len()).any(|w| w == first));
+        // a row group of two columns of two pages each
+        let headers = out.windows(2).filter(|w| w == b"\x15\x00").count();
+        assert!(headers >= 4);
+        assert!(metadata(&out).ends_with(concat!(env!("CARGO_PKG_VERSION"), "\0").as_bytes()));
+
+        let mut empty = Vec::new();
+        write_files_parquet(vec![("a", &Frequencies::new())], &mut empty).unwrap();
+        assert_eq!(&empty[4..6], b"\x15\x02");
+        assert!(metadata(&empty).windows(4).any(|w| w == b"file"));
+    }
+}
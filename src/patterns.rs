@@ -0,0 +1,266 @@
+//! counting many named patterns in one pass, see [`Patterns`](struct.Patterns.html)
+
+use regex::{Regex, RegexBuilder, RegexSet, RegexSetBuilder};
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use crate::decode::{strip_bom, Transcoder};
+use crate::Frequencies;
+
+/// named patterns compiled with the same flags, both one by one and as a set finding the
+/// patterns matching a line at once, so that others are not run
+#[derive(Debug, Clone)]
+pub struct Patterns {
+    names: Vec<String>,
+    regexes: Vec<Regex>,
+    set: RegexSet,
+}
+
+impl Patterns {
+    /// compile `(name, pattern)`s with the default flags
+    ///
+    /// # Errors
+    ///
+    /// a pattern is not valid, or is larger than the size limit
+    pub fn new<S, P>(patterns: impl IntoIterator<Item = (S, P)>) -> Result<Self, regex::Error>
+    where
+        S: AsRef<str>,
+        P: AsRef<str>,
+    {
+        PatternsBuilder::new(patterns).build()
+    }
+
+    /// number of patterns
+    pub fn len(&self) -> usize {
+        self.regexes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regexes.is_empty()
+    }
+}
+
+/// flags of [`Patterns`](struct.Patterns.html), same as of
+/// [`RegexSetBuilder`](https://docs.rs/regex/1/regex/struct.RegexSetBuilder.html)
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::patterns::{count_patterns, PatternsBuilder};
+/// let patterns = PatternsBuilder::new(vec![("errors", "error")])
+///     .case_insensitive(true)
+///     .build()
+///     .unwrap();
+/// let counts = count_patterns("ERROR a\nerror b".as_bytes(), &patterns);
+/// assert_eq!(counts["errors"], 2);
+/// let big = PatternsBuilder::new(vec![("words", r"\w{1000}")]).size_limit(1 << 10);
+/// assert!(big.build().is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct PatternsBuilder {
+    patterns: Vec<(String, String)>,
+    case_insensitive: bool,
+    multi_line: bool,
+    size_limit: Option<usize>,
+    dfa_size_limit: Option<usize>,
+}
+
+impl PatternsBuilder {
+    /// `(name, pattern)`s to compile. patterns of the same name are counted together
+    pub fn new<S, P>(patterns: impl IntoIterator<Item = (S, P)>) -> Self
+    where
+        S: AsRef<str>,
+        P: AsRef<str>,
+    {
+        PatternsBuilder {
+            patterns: patterns
+                .into_iter()
+                .map(|(name, pattern)| (name.as_ref().to_string(), pattern.as_ref().to_string()))
+                .collect(),
+            case_insensitive: false,
+            multi_line: false,
+            size_limit: None,
+            dfa_size_limit: None,
+        }
+    }
+
+    pub fn case_insensitive(mut self, yes: bool) -> Self {
+        self.case_insensitive = yes;
+        self
+    }
+
+    pub fn multi_line(mut self, yes: bool) -> Self {
+        self.multi_line = yes;
+        self
+    }
+
+    /// bytes of each compiled pattern, and of the set of them
+    pub fn size_limit(mut self, bytes: usize) -> Self {
+        self.size_limit = Some(bytes);
+        self
+    }
+
+    /// bytes of the cache of the lazy DFA of each pattern, and of the set of them
+    pub fn dfa_size_limit(mut self, bytes: usize) -> Self {
+        self.dfa_size_limit = Some(bytes);
+        self
+    }
+
+    /// compile the patterns one by one and as a set
+    ///
+    /// # Errors
+    ///
+    /// a pattern is not valid, or a pattern or the set is larger than the size limit
+    pub fn build(&self) -> Result<Patterns, regex::Error> {
+        let mut set = RegexSetBuilder::new(self.patterns.iter().map(|(_, pattern)| pattern));
+        set.case_insensitive(self.case_insensitive)
+            .multi_line(self.multi_line);
+        if let Some(bytes) = self.size_limit {
+            set.size_limit(bytes);
+        }
+        if let Some(bytes) = self.dfa_size_limit {
+            set.dfa_size_limit(bytes);
+        }
+        let regexes = self
+            .patterns
+            .iter()
+            .map(|(_, pattern)| {
+                let mut regex = RegexBuilder::new(pattern);
+                regex
+                    .case_insensitive(self.case_insensitive)
+                    .multi_line(self.multi_line);
+                if let Some(bytes) = self.size_limit {
+                    regex.size_limit(bytes);
+                }
+                if let Some(bytes) = self.dfa_size_limit {
+                    regex.dfa_size_limit(bytes);
+                }
+                regex.build()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Patterns {
+            names: self.patterns.iter().map(|(name, _)| name.clone()).collect(),
+            regexes,
+            set: set.build()?,
+        })
+    }
+}
+
+/// count of matches of each named pattern, read from input in one pass
+///
+/// every pattern gets a key, even if it has no match.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::patterns::{count_patterns, Patterns};
+/// let patterns = Patterns::new(vec![
+///     ("errors", "ERROR"),
+///     ("warnings", "WARN"),
+///     ("fatal", "FATAL"),
+/// ])
+/// .unwrap();
+/// let input = Cursor::new("ERROR a\nWARN b\nERROR c ERROR d");
+/// let counts = count_patterns(input, &patterns);
+/// assert_eq!(counts["errors"], 3);
+/// assert_eq!(counts["warnings"], 1);
+/// assert_eq!(counts["fatal"], 0);
+/// ```
+///
+/// # Panics
+///
+/// input is neither UTF-8 nor UTF-16 with a byte order mark
+pub fn count_patterns(input: impl BufRead, patterns: &Patterns) -> Frequencies {
+    count_pattern_texts(input, patterns)
+        .into_iter()
+        .map(|(name, texts)| (name, texts.total()))
+        .collect::<HashMap<_, _>>()
+        .into()
+}
+
+/// same as [`count_patterns`](fn.count_patterns.html), but also keyed by the matched text
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::patterns::{count_pattern_texts, Patterns};
+/// let patterns = Patterns::new(vec![("status", r"\b[45]\d\d\b")]).unwrap();
+/// let counts = count_pattern_texts(Cursor::new("GET 404\nGET 500\nPOST 404"), &patterns);
+/// assert_eq!(counts["status"]["404"], 2);
+/// assert_eq!(counts["status"]["500"], 1);
+/// ```
+///
+/// # Panics
+///
+/// input is neither UTF-8 nor UTF-16 with a byte order mark
+pub fn count_pattern_texts(
+    input: impl BufRead,
+    patterns: &Patterns,
+) -> HashMap<String, Frequencies> {
+    let mut counts: Vec<Frequencies> = patterns
+        .regexes
+        .iter()
+        .map(|_| Frequencies::new())
+        .collect();
+
+    for (n, line) in Transcoder::new(input).lines().enumerate() {
+        let line = line.unwrap();
+        let line = if n == 0 { strip_bom(&line) } else { &line };
+        for i in patterns.set.matches(line).into_iter() {
+            for m in patterns.regexes[i].find_iter(line) {
+                *counts[i].entry(m.as_str().to_string()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut named: HashMap<String, Frequencies> = HashMap::new();
+    for (name, texts) in patterns.names.iter().zip(counts) {
+        let merged = named.entry(name.clone()).or_default();
+        for (text, count) in texts.into_map() {
+            *merged.entry(text).or_insert(0) += count;
+        }
+    }
+    named
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn same_names_are_merged() {
+        let patterns =
+            Patterns::new(vec![("bad", "error"), ("bad", "fail(ed)?"), ("good", "ok")]).unwrap();
+        let input = Cursor::new("error failed\nok fail\nerror");
+        let texts = count_pattern_texts(input, &patterns);
+
+        assert_eq!(texts.len(), 2);
+        assert_eq!(texts["bad"].len(), 3);
+        assert_eq!(texts["bad"]["error"], 2);
+        assert_eq!(texts["bad"].total(), 4);
+        assert_eq!(texts["good"]["ok"], 1);
+    }
+
+    #[test]
+    fn no_patterns() {
+        let patterns = Patterns::new(Vec::<(&str, &str)>::new()).unwrap();
+        assert!(patterns.is_empty());
+        assert!(count_patterns(Cursor::new("a"), &patterns).is_empty());
+    }
+
+    #[test]
+    fn flags_are_of_the_set_too() {
+        let patterns = PatternsBuilder::new(vec![("start", "^b"), ("any", "B")])
+            .case_insensitive(true)
+            .multi_line(true)
+            .build()
+            .unwrap();
+        assert_eq!(patterns.len(), 2);
+        let texts = count_pattern_texts(Cursor::new("a b\nB"), &patterns);
+        assert_eq!(texts["start"]["B"], 1);
+        assert_eq!(texts["any"].total(), 2);
+    }
+}
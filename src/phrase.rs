@@ -0,0 +1,109 @@
+//! multi-word phrases counted as single tokens
+
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::{tokens, word_regex, CountConfig, CountOption};
+
+/// phrases by their first word, longest first
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Phrases {
+    by_first: HashMap<String, Vec<Vec<String>>>,
+}
+
+impl Phrases {
+    /// phrases of the config, normalized as its tokens
+    pub(crate) fn new(config: &CountConfig) -> Self {
+        let re = word_regex();
+        let mut by_first: HashMap<String, Vec<Vec<String>>> = HashMap::new();
+        for phrase in &config.phrases {
+            let mut words = Vec::new();
+            tokens(&re, phrase, &CountOption::Word, |word, _| {
+                words.push(config.normalize(word).into_owned())
+            });
+            if words.len() > 1 {
+                by_first.entry(words[0].clone()).or_default().push(words);
+            }
+        }
+        for candidates in by_first.values_mut() {
+            candidates.sort_by_key(|phrase| Reverse(phrase.len()));
+        }
+        Phrases { by_first }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.by_first.is_empty()
+    }
+
+    /// call `f` with the key and the original text of each word or phrase.
+    /// `words` are words of `segment` with their byte offsets, and `keys` are their keys
+    pub(crate) fn group<'a>(
+        &self,
+        segment: &'a str,
+        words: &[(&'a str, usize)],
+        keys: &[Cow<str>],
+        mut f: impl FnMut(&str, &'a str),
+    ) {
+        let mut i = 0;
+        while i < words.len() {
+            let matched = self.by_first.get(keys[i].as_ref()).and_then(|candidates| {
+                candidates.iter().find(|phrase| {
+                    phrase.len() <= keys.len() - i
+                        && phrase.iter().zip(&keys[i..]).all(|(p, k)| p == k)
+                })
+            });
+            match matched {
+                Some(phrase) => {
+                    let (last, offset) = words[i + phrase.len() - 1];
+                    let original = &segment[words[i].1..offset + last.len()];
+                    f(&phrase.join(" "), original);
+                    i += phrase.len();
+                }
+                None => {
+                    f(&keys[i], words[i].0);
+                    i += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn group(config: &CountConfig, segment: &str) -> Vec<(String, String)> {
+        let phrases = Phrases::new(config);
+        let mut words = Vec::new();
+        tokens(&word_regex(), segment, &CountOption::Word, |w, i| {
+            words.push((w, i))
+        });
+        let keys: Vec<_> = words.iter().map(|(w, _)| config.normalize(w)).collect();
+
+        let mut found = Vec::new();
+        phrases.group(segment, &words, &keys, |key, original| {
+            found.push((key.to_string(), original.to_string()))
+        });
+        found
+    }
+
+    #[test]
+    fn longest_match_wins() {
+        let config = CountConfig::new(CountOption::Word)
+            .ignore_case(true)
+            .phrases(&["new york", "New York  City", "york city", "single"]);
+        let found = group(&config, "New York City, and new york; york city");
+
+        let pair = |k: &str, o: &str| (k.to_string(), o.to_string());
+        assert_eq!(
+            found,
+            vec![
+                pair("new york city", "New York City"),
+                pair("and", "and"),
+                pair("new york", "new york"),
+                pair("york city", "york city"),
+            ]
+        );
+    }
+}
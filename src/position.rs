@@ -0,0 +1,362 @@
+//! where tokens appear, see [`count_positions`](fn.count_positions.html)
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::mem;
+
+use crate::decode::{read_lines, read_numbered_lines, strip_bom, DecodePolicy, Transcoder};
+use crate::tally::Tally;
+use crate::{tokens, word_regex, CountConfig, CountError, CountOption, Frequencies};
+
+/// position of a token occurrence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// line number, starts from 1
+    pub line: usize,
+    /// column by Unicode char, starts from 1
+    pub column: usize,
+}
+
+/// positions of chars or words or lines, read from input
+///
+/// each key holds all of its positions in the order of appearance,
+/// so the count of the token is the length of it.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count_positions, CountOption, Position};
+/// let positions = count_positions(Cursor::new("aa bb\nbb"), CountOption::Word);
+/// assert_eq!(positions["aa"], vec![Position { line: 1, column: 1 }]);
+/// assert_eq!(
+///     positions["bb"],
+///     vec![Position { line: 1, column: 4 }, Position { line: 2, column: 1 }]
+/// );
+/// ```
+///
+/// # Panics
+///
+/// input is neither UTF-8 nor UTF-16 with a byte order mark
+pub fn count_positions(input: impl BufRead, option: CountOption) -> HashMap<String, Vec<Position>> {
+    let re = word_regex();
+    let mut positions: HashMap<_, Vec<_>> = HashMap::new();
+
+    for (i, line) in Transcoder::new(input).lines().enumerate() {
+        let line = line.unwrap();
+        let line = if i == 0 { strip_bom(&line) } else { &line };
+        let mut columns = Columns::new(line);
+        tokens(&re, line, &option, |token, offset| {
+            let position = Position {
+                line: i + 1,
+                column: columns.column(offset),
+            };
+            positions
+                .entry(token.to_string())
+                .or_default()
+                .push(position);
+        });
+    }
+    positions
+}
+
+/// first and last line numbers (start from 1) a token appears on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LineSpan {
+    /// line of the first occurrence
+    pub first: usize,
+    /// line of the last occurrence
+    pub last: usize,
+}
+
+/// same as [`try_count_with`](../fn.try_count_with.html), but also record the first and last line
+/// each token appears on
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count_with_line_spans, CountConfig, LineSpan};
+/// let input = Cursor::new("ok\ndisk error\nok\ndisk error\nok");
+/// let (freqs, spans) = count_with_line_spans(input, &CountConfig::default()).unwrap();
+/// assert_eq!(freqs["error"], 2);
+/// assert_eq!(spans["error"], LineSpan { first: 2, last: 4 });
+/// assert_eq!(spans["ok"], LineSpan { first: 1, last: 5 });
+/// ```
+pub fn count_with_line_spans(
+    input: impl BufRead,
+    config: &CountConfig,
+) -> Result<(Frequencies, HashMap<String, LineSpan>), CountError> {
+    let mut tally = Tally::new(config);
+    let mut spans: HashMap<String, LineSpan> = HashMap::new();
+
+    read_numbered_lines(Transcoder::new(input), config.reading(), |line_no, line| {
+        tally.line_with(line, |key| match spans.get_mut(key) {
+            Some(span) => span.last = line_no,
+            None => {
+                let span = LineSpan {
+                    first: line_no,
+                    last: line_no,
+                };
+                spans.insert(key.to_string(), span);
+            }
+        })
+    })?;
+    Ok(tally.finish_with(spans))
+}
+
+/// counts of columns (by Unicode char, from 1) of matches of a pattern across lines, e.g. of
+/// a separator of fixed-width logs, to find lines out of alignment
+///
+/// # Examples
+///
+/// ```
+/// use regex::Regex;
+/// use bicycle_book_wordcount::position::ColumnHistogram;
+/// let mut histogram = ColumnHistogram::of_char('|');
+/// for line in &["a  | b", "aa | b", "aaa| b", "no bars"] {
+///     histogram.line(line);
+/// }
+/// assert_eq!(histogram.get(4), 3);
+/// assert_eq!(histogram.mode(), Some(4));
+/// assert_eq!((histogram.lines(), histogram.lines_matching()), (4, 3));
+///
+/// let mut dates = ColumnHistogram::new(Regex::new(r"\d{4}-\d{2}-\d{2}").unwrap());
+/// dates.line("INFO  2024-10-10 started");
+/// dates.line("WARN 2024-10-10 slow");
+/// assert_eq!(dates.columns(), vec![(6, 1), (7, 1)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ColumnHistogram {
+    pattern: Regex,
+    /// counts of columns 1, 2, ...
+    counts: Vec<usize>,
+    lines: usize,
+    lines_matching: usize,
+}
+
+impl ColumnHistogram {
+    /// counts of columns of matches of `pattern`
+    pub fn new(pattern: Regex) -> Self {
+        ColumnHistogram {
+            pattern,
+            counts: Vec::new(),
+            lines: 0,
+            lines_matching: 0,
+        }
+    }
+
+    /// counts of columns of `c`
+    pub fn of_char(c: char) -> Self {
+        let pattern = regex::escape(c.encode_utf8(&mut [0; 4]));
+        ColumnHistogram::new(Regex::new(&pattern).expect("an escaped char is a pattern"))
+    }
+
+    /// add columns of matches in `line`
+    pub fn line(&mut self, line: &str) {
+        self.lines += 1;
+        let mut columns = Columns::new(line);
+        let mut matched = false;
+        for m in self.pattern.find_iter(line) {
+            let column = columns.column(m.start());
+            if self.counts.len() < column {
+                self.counts.resize(column, 0);
+            }
+            self.counts[column - 1] += 1;
+            matched = true;
+        }
+        self.lines_matching += matched as usize;
+    }
+
+    /// add columns of matches in lines of `input`, without a byte order mark at its start
+    pub fn read(&mut self, input: impl BufRead) -> io::Result<()> {
+        let mut first = true;
+        read_lines(
+            Transcoder::new(input),
+            DecodePolicy::Strict,
+            |line| -> io::Result<()> {
+                self.line(if mem::take(&mut first) {
+                    strip_bom(line)
+                } else {
+                    line
+                });
+                Ok(())
+            },
+        )
+        .map(|_| ())
+    }
+
+    /// count of matches at `column`
+    pub fn get(&self, column: usize) -> usize {
+        match column {
+            0 => 0,
+            column => self.counts.get(column - 1).copied().unwrap_or(0),
+        }
+    }
+
+    /// columns with matches and their counts, in the order of columns
+    pub fn columns(&self) -> Vec<(usize, usize)> {
+        (1..)
+            .zip(self.counts.iter().copied())
+            .filter(|&(_, n)| n > 0)
+            .collect()
+    }
+
+    /// the column with the most matches (the first of ties), `None` without matches
+    pub fn mode(&self) -> Option<usize> {
+        let (column, _) = self.columns().into_iter().rev().max_by_key(|&(_, n)| n)?;
+        Some(column)
+    }
+
+    /// count of all matches
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// count of lines added
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    /// count of lines with a match
+    pub fn lines_matching(&self) -> usize {
+        self.lines_matching
+    }
+}
+
+/// a line per column with matches: the column, the count and a bar of up to 40 `#` scaled to
+/// the largest count
+impl fmt::Display for ColumnHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let columns = self.columns();
+        let max = columns.iter().map(|&(_, n)| n).max().unwrap_or(0);
+        for (column, n) in columns {
+            let bar = (n * 40 + max - 1) / max;
+            writeln!(f, "{}\t{}\t{}", column, n, "#".repeat(bar))?;
+        }
+        Ok(())
+    }
+}
+
+/// same as [`try_count_with`](../fn.try_count_with.html), but also add columns of each line to
+/// `histogram` in the same pass
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::position::{count_with_columns, ColumnHistogram};
+/// use bicycle_book_wordcount::CountConfig;
+/// let mut histogram = ColumnHistogram::of_char(':');
+/// let input = Cursor::new("12:00 up\n12:05 down\n9:10 up");
+/// let freqs = count_with_columns(input, &CountConfig::default(), &mut histogram).unwrap();
+/// assert_eq!(freqs["up"], 2);
+/// assert_eq!(histogram.columns(), vec![(2, 1), (3, 2)]);
+/// ```
+pub fn count_with_columns(
+    input: impl BufRead,
+    config: &CountConfig,
+    histogram: &mut ColumnHistogram,
+) -> Result<Frequencies, CountError> {
+    let mut tally = Tally::new(config);
+    let mut first = !config.keep_bom;
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        histogram.line(if mem::take(&mut first) {
+            strip_bom(line)
+        } else {
+            line
+        });
+        tally.line(line)
+    })?;
+    Ok(tally.finish())
+}
+
+/// byte offset to char column, offsets must be given in ascending order
+pub(crate) struct Columns<'a> {
+    line: &'a str,
+    offset: usize,
+    column: usize,
+}
+
+impl<'a> Columns<'a> {
+    pub(crate) fn new(line: &'a str) -> Self {
+        Columns {
+            line,
+            offset: 0,
+            column: 1,
+        }
+    }
+
+    pub(crate) fn column(&mut self, offset: usize) -> usize {
+        self.column += self.line[self.offset..offset].chars().count();
+        self.offset = offset;
+        self.column
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn count_positions_works() {
+        let positions = count_positions(Cursor::new("あい aa\n\naa い"), CountOption::Char);
+
+        assert_eq!(
+            positions["a"],
+            vec![
+                Position { line: 1, column: 4 },
+                Position { line: 1, column: 5 },
+                Position { line: 3, column: 1 },
+                Position { line: 3, column: 2 },
+            ]
+        );
+        assert_eq!(
+            positions["い"],
+            vec![
+                Position { line: 1, column: 2 },
+                Position { line: 3, column: 4 }
+            ]
+        );
+    }
+
+    #[test]
+    fn line_spans_follow_original_case() {
+        let config = CountConfig::new(CountOption::Word)
+            .ignore_case(true)
+            .original_case(true);
+        let input = Cursor::new("nasa\nNASA NASA\n\nNasa");
+        let (freqs, spans) = count_with_line_spans(input, &config).unwrap();
+
+        assert_eq!(freqs["NASA"], 4);
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans["NASA"], LineSpan { first: 1, last: 4 });
+    }
+
+    #[test]
+    fn columns_are_counted() {
+        let mut histogram = ColumnHistogram::of_char('.');
+        histogram
+            .read(Cursor::new("\u{feff}a.b\nあ.い.\n\nxx.\n"))
+            .unwrap();
+        assert_eq!(histogram.columns(), vec![(2, 2), (3, 1), (4, 1)]);
+        assert_eq!((histogram.total(), histogram.lines()), (4, 4));
+        assert_eq!((histogram.get(0), histogram.get(99)), (0, 0));
+        let (full, half) = ("#".repeat(40), "#".repeat(20));
+        assert_eq!(
+            histogram.to_string(),
+            format!("2\t2\t{}\n3\t1\t{}\n4\t1\t{}\n", full, half, half)
+        );
+        assert_eq!(ColumnHistogram::of_char('.').mode(), None);
+    }
+
+    #[test]
+    fn line_positions_works() {
+        let positions = count_positions(Cursor::new("x\ny\nx"), CountOption::Line);
+        let lines: Vec<_> = positions["x"].iter().map(|p| p.line).collect();
+        assert_eq!(lines, vec![1, 3]);
+    }
+}
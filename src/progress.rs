@@ -0,0 +1,112 @@
+//! words added and removed per file since a baseline, such as of chapters written today,
+//! see [`since`](fn.since.html)
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::diff::Diff;
+use crate::Frequencies;
+
+/// changes of a file since the baseline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileProgress {
+    pub file: String,
+    /// occurrences of tokens added, see [`Diff::added`](../diff/struct.Diff.html#method.added)
+    pub added: usize,
+    /// occurrences of tokens removed, see
+    /// [`Diff::removed`](../diff/struct.Diff.html#method.removed)
+    pub removed: usize,
+}
+
+impl FileProgress {
+    /// change of the count of all tokens
+    pub fn net(&self) -> i64 {
+        self.added as i64 - self.removed as i64
+    }
+}
+
+/// formatted like `+523 -12 chapter1.md`
+impl fmt::Display for FileProgress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "+{} -{} {}", self.added, self.removed, self.file)
+    }
+}
+
+/// changes of files from counts of `baseline` to `current`, in the order of `current` and
+/// then of files removed since the baseline. unchanged files are not included; new files are
+/// all added, and removed ones all removed
+///
+/// a rewritten word counts as both added and removed, so `added` is the amount written even
+/// if about as much was deleted.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::progress::since;
+/// use bicycle_book_wordcount::{count, CountOption};
+/// let words = |text: &str| count(Cursor::new(text.to_string()), CountOption::Word);
+/// let baseline = vec![("1.md".to_string(), words("It was a dark night."))];
+/// let current = vec![
+///     ("1.md".to_string(), words("It was a dark and stormy night.")),
+///     ("2.md".to_string(), words("The end.")),
+/// ];
+/// let progress = since(&baseline, &current);
+/// assert_eq!(progress[0].to_string(), "+2 -0 1.md");
+/// assert_eq!(progress[1].added, 2);
+/// ```
+pub fn since(
+    baseline: &[(String, Frequencies)],
+    current: &[(String, Frequencies)],
+) -> Vec<FileProgress> {
+    let empty = Frequencies::new();
+    let old: HashMap<&str, &Frequencies> = baseline
+        .iter()
+        .map(|(file, freqs)| (file.as_str(), freqs))
+        .collect();
+    let new: HashSet<&str> = current.iter().map(|(file, _)| file.as_str()).collect();
+    let current_files = current.iter().map(|(file, freqs)| {
+        let old = old.get(file.as_str()).copied().unwrap_or(&empty);
+        (file, Diff::between(old, freqs))
+    });
+    let removed_files = baseline
+        .iter()
+        .filter(|(file, _)| !new.contains(file.as_str()))
+        .map(|(file, freqs)| (file, Diff::between(freqs, &empty)));
+    current_files
+        .chain(removed_files)
+        .filter(|(_, diff)| !diff.is_empty())
+        .map(|(file, diff)| FileProgress {
+            file: file.clone(),
+            added: diff.added(),
+            removed: diff.removed(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{count_str, CountOption};
+
+    #[test]
+    fn files_are_compared_by_name() {
+        let words = |text: &str| count_str(text, CountOption::Word);
+        let baseline = vec![
+            ("a".to_string(), words("one two three")),
+            ("b".to_string(), words("same")),
+            ("c".to_string(), words("cut cut")),
+        ];
+        let current = vec![
+            ("b".to_string(), words("same")),
+            ("a".to_string(), words("one 2 three four")),
+        ];
+        let progress = since(&baseline, &current);
+        let summary: Vec<_> = progress
+            .iter()
+            .map(|p| (p.file.as_str(), p.added, p.removed, p.net()))
+            .collect();
+        assert_eq!(summary, vec![("a", 2, 1, 1), ("c", 0, 2, -2)]);
+        assert!(since(&current, &current).is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+//! diagnostics of text which is probably broken (mojibake, lossy conversions, binary data),
+//! see [`check_quality`](fn.check_quality.html)
+
+use std::fmt;
+use std::io::{self, BufRead};
+use std::str;
+
+use crate::decode::Transcoder;
+
+/// signs of broken text found in input
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextQuality {
+    /// count of lines read
+    pub lines: usize,
+    /// count of U+FFFD, left by a lossy conversion before
+    pub replacement_chars: usize,
+    /// count of sequences which are not UTF-8, which lossy decoding replaces by U+FFFD
+    pub invalid_sequences: usize,
+    /// count of invalid sequences which look like overlong encodings, such as `C0 AF` for `/`
+    pub overlong_sequences: usize,
+    /// count of control chars other than tab and form feed, such as NUL, ESC and C1 controls
+    /// (often bytes of Windows-1252 read as Latin-1)
+    pub control_chars: usize,
+    /// the first line with any of them, starting from 1
+    pub first_line: Option<usize>,
+}
+
+impl TextQuality {
+    /// no sign of broken text
+    pub fn is_clean(&self) -> bool {
+        self.first_line.is_none()
+    }
+}
+
+/// counts of signs, like `2 replacement chars, 1 invalid sequence (1 overlong), first at line 3`
+impl fmt::Display for TextQuality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let line = match self.first_line {
+            Some(line) => line,
+            None => return f.write_str("clean"),
+        };
+        let plural = |n: usize, what: &str| match n {
+            0 => None,
+            1 => Some(format!("1 {}", what)),
+            n => Some(format!("{} {}s", n, what)),
+        };
+        let invalid = plural(self.invalid_sequences, "invalid sequence").map(|part| {
+            match self.overlong_sequences {
+                0 => part,
+                n => format!("{} ({} overlong)", part, n),
+            }
+        });
+        let parts: Vec<_> = vec![
+            plural(self.replacement_chars, "replacement char"),
+            invalid,
+            plural(self.control_chars, "control char"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        write!(f, "{}, first at line {}", parts.join(", "), line)
+    }
+}
+
+/// read input and count signs of broken text. input may also be UTF-16 starting with a byte
+/// order mark, as [`count`](../fn.count.html)
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::quality::check_quality;
+/// let quality = check_quality(&b"ok\ncaf\xc3\xa9\nbad \xff and \xef\xbf\xbd\n\x1b[0m"[..]).unwrap();
+/// assert_eq!(quality.invalid_sequences, 1);
+/// assert_eq!(quality.replacement_chars, 1);
+/// assert_eq!(quality.control_chars, 1);
+/// assert_eq!(quality.first_line, Some(3));
+/// assert_eq!(
+///     quality.to_string(),
+///     "1 replacement char, 1 invalid sequence, 1 control char, first at line 3"
+/// );
+/// ```
+pub fn check_quality(input: impl BufRead) -> io::Result<TextQuality> {
+    let mut input = Transcoder::new(input);
+    let mut quality = TextQuality::default();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if input.read_until(b'\n', &mut buf)? == 0 {
+            return Ok(quality);
+        }
+        quality.lines += 1;
+        let before = quality;
+        let mut bytes = &buf[..];
+        if let Some(rest) = bytes.strip_suffix(b"\n") {
+            bytes = rest.strip_suffix(b"\r").unwrap_or(rest);
+        }
+        while !bytes.is_empty() {
+            let (valid, rest) = match str::from_utf8(bytes) {
+                Ok(text) => (text, &[][..]),
+                Err(e) => {
+                    let (valid, rest) = bytes.split_at(e.valid_up_to());
+                    quality.invalid_sequences += 1;
+                    if is_overlong(rest) {
+                        quality.overlong_sequences += 1;
+                    }
+                    let len = e.error_len().unwrap_or(rest.len());
+                    (str::from_utf8(valid).unwrap(), &rest[len..])
+                }
+            };
+            for c in valid.chars() {
+                match c {
+                    '\u{fffd}' => quality.replacement_chars += 1,
+                    '\t' | '\u{c}' => {}
+                    c if c.is_control() => quality.control_chars += 1,
+                    _ => {}
+                }
+            }
+            bytes = rest;
+        }
+        if quality.first_line.is_none() && quality != before {
+            quality.first_line = Some(quality.lines);
+        }
+    }
+}
+
+/// an invalid sequence starts with a lead byte of a char encoded in more bytes than needed
+fn is_overlong(bytes: &[u8]) -> bool {
+    match bytes {
+        [0xc0, ..] | [0xc1, ..] => true,
+        [0xe0, next, ..] => *next < 0xa0,
+        [0xf0, next, ..] => *next < 0x90,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn signs_are_counted() {
+        let input = b"\xc0\xaf etc\xe0\x80\xaf\r\n\xc2\x85\tok\n\xed\xa0\x80";
+        let quality = check_quality(&input[..]).unwrap();
+        assert_eq!(quality.lines, 3);
+        assert_eq!(quality.invalid_sequences, 8);
+        assert_eq!(quality.overlong_sequences, 2);
+        assert_eq!(quality.control_chars, 1);
+        assert_eq!(quality.first_line, Some(1));
+        assert_eq!(
+            quality.to_string(),
+            "8 invalid sequences (2 overlong), 1 control char, first at line 1"
+        );
+
+        let clean = check_quality("\u{feff}tab\tand\u{c}page\r\n".as_bytes()).unwrap();
+        assert!(clean.is_clean());
+        assert_eq!(clean.to_string(), "clean");
+    }
+}
@@ -0,0 +1,121 @@
+//! keyphrases of a document by RAKE (Rapid Automatic Keyword Extraction), see
+//! [`keyphrases`](fn.keyphrases.html)
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use crate::{tokens, word_regex, CountConfig, CountOption};
+
+/// a candidate phrase and its score
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyphrase {
+    /// normalized words joined by a space
+    pub phrase: String,
+    /// sum of degree / frequency of the words
+    pub score: f64,
+}
+
+/// the `n` best keyphrases of `text`, by descending score (ties by phrase)
+///
+/// candidates are runs of words split by the stop words of `config` (see
+/// [`CountConfig::stop_words`](../config/struct.CountConfig.html#method.stop_words)), line
+/// breaks, and punctuation other than spaces between words. each word is scored by its degree
+/// (total length of the candidates it appears in) divided by its frequency, and each
+/// candidate by the sum of the scores of its words.
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::rake::keyphrases;
+/// use bicycle_book_wordcount::CountConfig;
+/// let config = CountConfig::default()
+///     .ignore_case(true)
+///     .stop_words(&["of", "a", "and", "is", "the", "for"]);
+/// let text = "Compatibility of systems of linear constraints. \
+///             Linear constraints and a minimal set of natural numbers";
+/// let phrases = keyphrases(text, &config, 10);
+/// assert_eq!(phrases[0].phrase, "linear constraints");
+/// assert_eq!(phrases[0].score, 4.0);
+/// assert_eq!(phrases.len(), 5);
+/// assert_eq!(phrases[4].phrase, "systems");
+/// ```
+pub fn keyphrases(text: &str, config: &CountConfig, n: usize) -> Vec<Keyphrase> {
+    let stop_words: HashSet<_> = config
+        .stop_words
+        .iter()
+        .map(|word| config.normalize(word).into_owned())
+        .collect();
+    let re = word_regex();
+    let mut candidates: Vec<Vec<String>> = Vec::new();
+    for line in text.lines() {
+        let mut phrase = Vec::new();
+        let mut end = 0;
+        tokens(&re, line, &CountOption::Word, |word, start| {
+            let delimited = !line[end..start].trim().is_empty();
+            end = start + word.len();
+            let key = config.normalize(word);
+            if delimited || stop_words.contains(key.as_ref()) {
+                candidates.push(std::mem::take(&mut phrase));
+            }
+            if !stop_words.contains(key.as_ref()) {
+                phrase.push(key.into_owned());
+            }
+        });
+        candidates.push(phrase);
+    }
+    candidates.retain(|phrase| !phrase.is_empty());
+
+    // word => (degree, frequency)
+    let mut words: HashMap<&str, (usize, usize)> = HashMap::new();
+    for phrase in &candidates {
+        for word in phrase {
+            let (degree, frequency) = words.entry(word).or_default();
+            *degree += phrase.len();
+            *frequency += 1;
+        }
+    }
+    let mut seen = HashSet::new();
+    let mut phrases: Vec<_> = candidates
+        .iter()
+        .filter(|phrase| seen.insert(*phrase))
+        .map(|phrase| Keyphrase {
+            phrase: phrase.join(" "),
+            score: phrase
+                .iter()
+                .map(|word| {
+                    let (degree, frequency) = words[word.as_str()];
+                    degree as f64 / frequency as f64
+                })
+                .sum(),
+        })
+        .collect();
+    phrases.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.phrase.cmp(&b.phrase))
+    });
+    phrases.truncate(n);
+    phrases
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn candidates_are_split() {
+        let config = CountConfig::default().stop_words(&["and"]);
+        let phrases = keyphrases("red apple, green apple and pear\nred apple", &config, 10);
+        let scores: Vec<_> = phrases
+            .iter()
+            .map(|p| (p.phrase.as_str(), p.score))
+            .collect();
+        // apple: degree 6 / frequency 3, red: 4 / 2, green: 2 / 1, pear: 1 / 1
+        assert_eq!(
+            scores,
+            vec![("green apple", 4.0), ("red apple", 4.0), ("pear", 1.0)]
+        );
+        assert!(keyphrases("and", &config, 10).is_empty());
+    }
+}
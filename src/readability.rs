@@ -0,0 +1,147 @@
+//! readability scores (Flesch-Kincaid, Gunning-Fog), see [`readability`](fn.readability.html)
+
+use std::io::BufRead;
+
+use crate::decode::{strip_bom, Transcoder};
+use crate::sentence::{Event, Splitter};
+
+/// counts needed for readability scores
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Readability {
+    /// count of words
+    pub words: usize,
+    /// count of sentences
+    pub sentences: usize,
+    /// estimated count of syllables
+    pub syllables: usize,
+    /// count of words with 3 or more syllables
+    pub complex_words: usize,
+}
+
+impl Readability {
+    /// Flesch reading ease, higher is easier
+    pub fn flesch_reading_ease(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        206.835 - 1.015 * self.words_per_sentence() - 84.6 * self.syllables_per_word()
+    }
+
+    /// Flesch-Kincaid grade level
+    pub fn flesch_kincaid_grade(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        0.39 * self.words_per_sentence() + 11.8 * self.syllables_per_word() - 15.59
+    }
+
+    /// Gunning-Fog index
+    pub fn gunning_fog(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        let complex = self.complex_words as f64 / self.words as f64;
+        0.4 * (self.words_per_sentence() + 100.0 * complex)
+    }
+
+    fn words_per_sentence(&self) -> f64 {
+        self.words as f64 / self.sentences.max(1) as f64
+    }
+
+    fn syllables_per_word(&self) -> f64 {
+        self.syllables as f64 / self.words as f64
+    }
+
+    pub(crate) fn add_word(&mut self, word: &str) {
+        let syllables = syllables(word);
+        self.words += 1;
+        self.syllables += syllables;
+        if syllables >= 3 {
+            self.complex_words += 1;
+        }
+    }
+}
+
+/// count of words, sentences and syllables, read from input, in one pass
+///
+/// syllables are estimated by groups of vowels (English), so scores for other languages are meaningless.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::readability;
+/// let r = readability(Cursor::new("The cat sat. It was happy."));
+/// assert_eq!(r.words, 6);
+/// assert_eq!(r.sentences, 2);
+/// assert!(r.flesch_reading_ease() > 100.0);
+/// ```
+///
+/// # Panics
+///
+/// input is neither UTF-8 nor UTF-16 with a byte order mark
+pub fn readability(input: impl BufRead) -> Readability {
+    let mut splitter = Splitter::new();
+    let mut result = Readability::default();
+
+    for (i, line) in Transcoder::new(input).lines().enumerate() {
+        let line = line.unwrap();
+        let line = if i == 0 { strip_bom(&line) } else { &line };
+        splitter.feed(line, |event| match event {
+            Event::Word(word) => result.add_word(word),
+            Event::End(_) => result.sentences += 1,
+        });
+    }
+    result
+}
+
+/// estimated count of syllables in a word, at least 1
+pub(crate) fn syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let mut count = 0;
+    let mut prev_vowel = false;
+    for c in word.chars() {
+        let vowel = "aeiouy".contains(c);
+        if vowel && !prev_vowel {
+            count += 1;
+        }
+        prev_vowel = vowel;
+    }
+    // silent "e" like "make", but not "table"
+    if count > 1 && word.ends_with('e') && !word.ends_with("le") {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn syllables_works() {
+        assert_eq!(syllables("cat"), 1);
+        assert_eq!(syllables("make"), 1);
+        assert_eq!(syllables("table"), 2);
+        assert_eq!(syllables("Readability"), 5);
+        assert_eq!(syllables("rhythm"), 1);
+        assert_eq!(syllables("42"), 1);
+    }
+
+    #[test]
+    fn readability_works() {
+        let r = readability(Cursor::new("Readability is important.\nCats sit"));
+        assert_eq!(
+            r,
+            Readability {
+                words: 5,
+                sentences: 2,
+                syllables: 11,
+                complex_words: 2,
+            }
+        );
+        assert_eq!(r.gunning_fog(), 0.4 * (2.5 + 40.0));
+        assert_eq!(readability(Cursor::new("")).flesch_kincaid_grade(), 0.0);
+    }
+}
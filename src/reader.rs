@@ -0,0 +1,275 @@
+//! counting of lines, words and chars of a stream while it is read for another purpose, see
+//! [`CountingReader`](struct.CountingReader.html)
+
+use std::io::{self, BufRead, Read};
+
+/// a reader passing the data of `R` through unchanged, with running totals of it like `wc`
+///
+/// chars are the chars of UTF-8, counted by their leading bytes (an invalid byte other than a
+/// continuation byte counts as a char, too). words are runs of chars other than whitespace
+/// (including such as U+3000). lines are ended by `\n`, and an unterminated last line counts.
+///
+/// only the data read through the reader is counted; with
+/// [`BufRead`](https://doc.rust-lang.org/std/io/trait.BufRead.html), the data
+/// [`consume`](https://doc.rust-lang.org/std/io/trait.BufRead.html#tymethod.consume)d.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{self, Cursor};
+/// use bicycle_book_wordcount::reader::CountingReader;
+/// let mut reader = CountingReader::new(Cursor::new("日本語 text\nline two\n"));
+/// let mut copy = Vec::new();
+/// io::copy(&mut reader, &mut copy).unwrap();
+/// assert_eq!(copy, "日本語 text\nline two\n".as_bytes());
+/// let totals = reader.totals();
+/// assert_eq!((totals.lines, totals.words, totals.chars, totals.bytes), (2, 4, 18, 24));
+/// ```
+#[derive(Debug)]
+pub struct CountingReader<R> {
+    inner: R,
+    state: State,
+    /// bytes of the buffer returned by `fill_buf` not consumed yet
+    filled: usize,
+}
+
+/// totals of a [`CountingReader`](struct.CountingReader.html)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub bytes: usize,
+    /// including a char whose last bytes are not read yet
+    pub chars: usize,
+    /// a word starting with a char whose last bytes are not read yet is counted once they are
+    pub words: usize,
+    /// including an unterminated last line
+    pub lines: usize,
+}
+
+impl<R> CountingReader<R> {
+    /// wrap `inner` with no data counted yet
+    pub fn new(inner: R) -> Self {
+        CountingReader {
+            inner,
+            state: State::default(),
+            filled: 0,
+        }
+    }
+
+    /// totals of the data read so far
+    pub fn totals(&self) -> Totals {
+        let state = &self.state;
+        Totals {
+            bytes: state.bytes,
+            chars: state.chars,
+            words: state.words,
+            lines: match state.last {
+                Some(b'\n') | None => state.lines,
+                Some(_) => state.lines + 1,
+            },
+        }
+    }
+
+    /// the wrapped reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// the wrapped reader, mutably. data read from it directly is not counted
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// the wrapped reader, discarding the totals
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.state.feed(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for CountingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let buf = self.inner.fill_buf()?;
+        self.filled = buf.len();
+        Ok(buf)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let counted = amt.min(self.filled);
+        if counted > 0 {
+            // the buffer is not empty, so it is returned again without reading
+            if let Ok(buf) = self.inner.fill_buf() {
+                self.state.feed(&buf[..counted.min(buf.len())]);
+            }
+            self.filled -= counted;
+        }
+        self.inner.consume(amt)
+    }
+}
+
+/// running totals, with the bytes of a char not read to the end
+#[derive(Debug, Default)]
+struct State {
+    bytes: usize,
+    chars: usize,
+    words: usize,
+    lines: usize,
+    last: Option<u8>,
+    in_word: bool,
+    /// bytes of the current char, of `expected` bytes in total
+    pending: [u8; 4],
+    pending_len: usize,
+    expected: usize,
+}
+
+impl State {
+    fn feed(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.byte(byte);
+        }
+        self.bytes += bytes.len();
+        if let Some(&byte) = bytes.last() {
+            self.last = Some(byte);
+        }
+    }
+
+    fn byte(&mut self, byte: u8) {
+        if byte == b'\n' {
+            self.lines += 1;
+        }
+        if byte & 0xc0 == 0x80 {
+            if self.pending_len == 0 {
+                // a stray continuation byte, not a char of its own
+                self.visit(false);
+            } else {
+                self.pending[self.pending_len] = byte;
+                self.pending_len += 1;
+                if self.pending_len == self.expected {
+                    self.finish();
+                }
+            }
+            return;
+        }
+        if self.pending_len > 0 {
+            // a truncated char
+            self.pending_len = 0;
+            self.visit(false);
+        }
+        self.chars += 1;
+        self.expected = match byte {
+            0x00..=0x7f => return self.visit((byte as char).is_whitespace()),
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return self.visit(false),
+        };
+        self.pending[0] = byte;
+        self.pending_len = 1;
+    }
+
+    fn finish(&mut self) {
+        let space = match std::str::from_utf8(&self.pending[..self.pending_len]) {
+            Ok(s) => s.chars().any(char::is_whitespace),
+            Err(_) => false,
+        };
+        self.pending_len = 0;
+        self.visit(space);
+    }
+
+    fn visit(&mut self, space: bool) {
+        if space {
+            self.in_word = false;
+        } else if !self.in_word {
+            self.in_word = true;
+            self.words += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// a reader returning a byte per read
+    struct Bytewise<'a>(&'a [u8]);
+
+    impl Read for Bytewise<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match (self.0.split_first(), buf.is_empty()) {
+                (Some((&byte, rest)), false) => {
+                    buf[0] = byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn chars_are_counted_across_reads() {
+        let text = "全角\u{3000}空白 é\nlast";
+        let mut reader = CountingReader::new(Bytewise(text.as_bytes()));
+        let mut read = String::new();
+        reader.read_to_string(&mut read).unwrap();
+        assert_eq!(read, text);
+        let totals = reader.totals();
+        assert_eq!(totals.chars, text.chars().count());
+        assert_eq!((totals.words, totals.lines), (4, 2));
+
+        let mut invalid = CountingReader::new(&b"a\xff\x80 \xe6\x97"[..]);
+        io::copy(&mut invalid, &mut io::sink()).unwrap();
+        let totals = invalid.totals();
+        assert_eq!((totals.chars, totals.words, totals.bytes), (4, 1, 6));
+    }
+
+    #[test]
+    fn consumed_data_is_counted() {
+        let mut reader = CountingReader::new(io::BufReader::with_capacity(3, &b"ab cd\n\nef"[..]));
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "ab cd\n");
+        let totals = reader.totals();
+        assert_eq!((totals.lines, totals.words, totals.bytes), (1, 2, 6));
+        let lines: Vec<_> = reader.by_ref().lines().collect::<io::Result<_>>().unwrap();
+        assert_eq!(lines, vec!["", "ef"]);
+        let totals = reader.totals();
+        assert_eq!((totals.lines, totals.words, totals.bytes), (3, 3, 9));
+        assert_eq!(CountingReader::new(io::empty()).totals(), Totals::default());
+    }
+
+    /// a buffered reader counting its reads
+    struct Reads(usize);
+
+    impl Read for Reads {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            self.0 += 1;
+            Ok(0)
+        }
+    }
+
+    impl BufRead for Reads {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            self.0 += 1;
+            Ok(&[])
+        }
+
+        fn consume(&mut self, _: usize) {}
+    }
+
+    #[test]
+    fn consume_does_not_read() {
+        let mut reader = CountingReader::new(Reads(0));
+        reader.consume(0);
+        assert_eq!(reader.get_ref().0, 0);
+        assert!(reader.fill_buf().unwrap().is_empty());
+        reader.consume(0);
+        assert_eq!(reader.get_ref().0, 1);
+    }
+}
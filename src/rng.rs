@@ -0,0 +1,61 @@
+//! small deterministic pseudo random generator (xorshift64*), for sampling
+
+/// xorshift64* generator, not for cryptography. the same seed gives the same numbers on any
+/// platform
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::rng::Rng;
+/// let (mut a, mut b) = (Rng::new(7), Rng::new(7));
+/// assert_eq!(a.below(10), b.below(10));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// generator of `seed`
+    pub fn new(seed: u64) -> Self {
+        let state = (seed ^ 0x9e37_79b9_7f4a_7c15).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        // state must not be 0
+        Rng(state.max(1))
+    }
+
+    /// uniform in all of `u64`
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// uniform in `[0, 1)`
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// uniform in `0..n`, n must not be 0
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn below_is_in_range_and_deterministic() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for n in 1..100 {
+            let x = a.below(n);
+            assert!(x < n);
+            assert_eq!(x, b.below(n));
+        }
+        assert_ne!(Rng::new(0).next_u64(), Rng::new(1).next_u64());
+        assert!((0..100).all(|_| (0.0..1.0).contains(&a.unit())));
+    }
+}
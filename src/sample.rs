@@ -0,0 +1,188 @@
+//! sample lines of each token, see [`count_with_samples`](fn.count_with_samples.html), and
+//! counting a sample of lines, see [`count_sampled`](fn.count_sampled.html)
+
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use crate::decode::{read_lines, read_numbered_lines, Transcoder};
+use crate::rng::Rng;
+use crate::tally::Tally;
+use crate::{CountConfig, CountError, Frequencies};
+
+/// seed of sampling, so that samples are reproducible
+const SEED: u64 = 0x5eed;
+
+/// uniform sample of up to `capacity` items from a stream (reservoir sampling)
+#[derive(Debug, Clone)]
+pub(crate) struct Reservoir<T> {
+    capacity: usize,
+    seen: usize,
+    items: Vec<T>,
+}
+
+impl<T> Reservoir<T> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Reservoir {
+            capacity,
+            seen: 0,
+            items: Vec::new(),
+        }
+    }
+
+    /// offer an item, `item` is called only if it is kept
+    pub(crate) fn offer(&mut self, rng: &mut Rng, item: impl FnOnce() -> T) {
+        self.seen += 1;
+        if self.items.len() < self.capacity {
+            self.items.push(item());
+        } else if self.capacity > 0 {
+            let i = rng.below(self.seen);
+            if i < self.capacity {
+                self.items[i] = item();
+            }
+        }
+    }
+
+    pub(crate) fn into_items(self) -> Vec<T> {
+        self.items
+    }
+}
+
+/// same as [`try_count_with`](../fn.try_count_with.html), but also keep up to `k` example lines
+/// of each token, sampled uniformly from the lines it appears on
+///
+/// samples are in no particular order, and reproducible for the same input.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::sample::count_with_samples;
+/// use bicycle_book_wordcount::CountConfig;
+/// let input = Cursor::new("disk error 1\nok\ndisk error 2\ndisk error 3");
+/// let (freqs, samples) = count_with_samples(input, &CountConfig::default(), 2).unwrap();
+/// assert_eq!(freqs["error"], 3);
+/// assert_eq!(samples["error"].len(), 2);
+/// assert_eq!(samples["ok"], vec!["ok"]);
+/// ```
+pub fn count_with_samples(
+    input: impl BufRead,
+    config: &CountConfig,
+    k: usize,
+) -> Result<(Frequencies, HashMap<String, Vec<String>>), CountError> {
+    let mut tally = Tally::new(config);
+    let mut rng = Rng::new(SEED);
+    // reservoir and the last line offered to it
+    let mut samples: HashMap<String, (Reservoir<String>, usize)> = HashMap::new();
+
+    read_numbered_lines(Transcoder::new(input), config.reading(), |i, line| {
+        tally.line_with(line, |key| {
+            if !samples.contains_key(key) {
+                samples.insert(key.to_string(), (Reservoir::new(k), usize::MAX));
+            }
+            let (reservoir, last) = samples.get_mut(key).unwrap();
+            if *last != i {
+                *last = i;
+                reservoir.offer(&mut rng, || line.to_string());
+            }
+        })
+    })?;
+    let (freqs, samples) = tally.finish_with(samples);
+    let samples = samples
+        .into_iter()
+        .map(|(key, (reservoir, _))| (key, reservoir.into_items()))
+        .collect();
+    Ok((freqs, samples))
+}
+
+/// same as [`try_count_with`](../fn.try_count_with.html), but only lines picked at random
+/// with probability `rate` are counted, and counts are scaled by `1 / rate` to estimate
+/// counts of all lines. picking is reproducible for the same `seed`
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::sample::count_sampled;
+/// use bicycle_book_wordcount::CountConfig;
+/// let input = "aa bb\n".repeat(10_000);
+/// let freqs = count_sampled(Cursor::new(input), &CountConfig::default(), 0.1, 1).unwrap();
+/// assert!(freqs["aa"] > 9_000 && freqs["aa"] < 11_000);
+/// ```
+///
+/// # Panics
+///
+/// `rate` is not in `(0.0, 1.0]`
+pub fn count_sampled(
+    input: impl BufRead,
+    config: &CountConfig,
+    rate: f64,
+    seed: u64,
+) -> Result<Frequencies, CountError> {
+    assert!(rate > 0.0 && rate <= 1.0, "rate must be in (0.0, 1.0]");
+    let mut tally = Tally::new(config);
+    let mut rng = Rng::new(seed);
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        if rng.unit() < rate {
+            tally.line(line)
+        } else {
+            Ok(())
+        }
+    })?;
+    let freqs = tally.finish();
+    let scaled = freqs
+        .into_iter()
+        .map(|(token, n)| (token, (n as f64 / rate).round() as usize));
+    Ok(scaled.collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn reservoir_is_uniform() {
+        let mut rng = Rng::new(1);
+        let mut hits = [0; 10];
+        for _ in 0..2000 {
+            let mut reservoir = Reservoir::new(3);
+            for n in 0..10 {
+                reservoir.offer(&mut rng, || n);
+            }
+            let items = reservoir.into_items();
+            assert_eq!(items.len(), 3);
+            for n in items {
+                hits[n] += 1;
+            }
+        }
+        // expected 600 each
+        assert!(hits.iter().all(|&h| h > 450 && h < 750), "{:?}", hits);
+    }
+
+    #[test]
+    fn samples_are_distinct_lines() {
+        let input = Cursor::new("a a a\nb a\nc");
+        let (freqs, samples) = count_with_samples(input, &CountConfig::default(), 5).unwrap();
+        assert_eq!(freqs["a"], 4);
+        let mut lines = samples["a"].clone();
+        lines.sort();
+        assert_eq!(lines, vec!["a a a", "b a"]);
+
+        let input = Cursor::new("a\nb");
+        let (_, samples) = count_with_samples(input, &CountConfig::default(), 0).unwrap();
+        assert!(samples["a"].is_empty());
+    }
+
+    #[test]
+    fn count_sampled_works() {
+        let input = || Cursor::new("aa bb\ncc\n".repeat(1000));
+        let config = CountConfig::default();
+        let all = count_sampled(input(), &config, 1.0, 0).unwrap();
+        assert_eq!(all["aa"], 1000);
+        let a = count_sampled(input(), &config, 0.5, 7).unwrap();
+        assert_eq!(a, count_sampled(input(), &config, 0.5, 7).unwrap());
+        assert!(a["cc"] > 800 && a["cc"] < 1200, "{:?}", a);
+        // counts are multiples of 1 / rate
+        assert_eq!(a["aa"] % 2, 0);
+    }
+}
@@ -0,0 +1,205 @@
+//! parts of the input to be counted
+
+use crate::CountConfig;
+
+/// selects parts of lines to be counted as configured
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Scope {
+    quoted: bool,
+    code: Option<CodeBlocks>,
+}
+
+impl Scope {
+    pub(crate) fn new(config: &CountConfig) -> Self {
+        Scope {
+            quoted: config.quoted_only,
+            code: if config.skip_code_blocks {
+                Some(CodeBlocks::default())
+            } else {
+                None
+            },
+        }
+    }
+
+    /// call `f` with each part of a line to be counted and its byte offset
+    pub(crate) fn segments<'a>(&mut self, line: &'a str, mut f: impl FnMut(&'a str, usize)) {
+        if let Some(code) = &mut self.code {
+            if code.is_code(line) {
+                return;
+            }
+        }
+        if self.quoted {
+            quoted(line, f);
+        } else {
+            f(line, 0);
+        }
+    }
+}
+
+/// finds code blocks of Markdown (or reStructuredText) line by line
+///
+/// * fenced blocks: from a line starting with 3 or more "`" or "~" to the same fence
+/// * indented blocks: lines indented by 4 or more spaces (or a tab) after a blank line,
+///   like Markdown indented code and reStructuredText literal blocks
+#[derive(Debug, Clone)]
+struct CodeBlocks {
+    fence: Option<(char, usize)>,
+    indented: bool,
+    blank: bool,
+}
+
+impl Default for CodeBlocks {
+    fn default() -> Self {
+        CodeBlocks {
+            fence: None,
+            indented: false,
+            blank: true,
+        }
+    }
+}
+
+impl CodeBlocks {
+    /// the line is a part of a code block (including fences)
+    fn is_code(&mut self, line: &str) -> bool {
+        if let Some((c, n)) = self.fence {
+            if fence(line).is_some_and(|(d, m)| d == c && m >= n && is_bare_fence(line)) {
+                self.fence = None;
+                self.blank = false;
+            }
+            return true;
+        }
+        if let Some(fence) = fence(line) {
+            self.fence = Some(fence);
+            self.indented = false;
+            return true;
+        }
+
+        if line.trim().is_empty() {
+            self.blank = true;
+            return self.indented;
+        }
+        let indented = line.starts_with('\t') || line.starts_with("    ");
+        self.indented = indented && (self.indented || self.blank);
+        self.blank = false;
+        self.indented
+    }
+}
+
+/// fence char and its length, if the line starts a fence (indented up to 3 spaces)
+fn fence(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let c = trimmed.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let n = trimmed.chars().take_while(|&d| d == c).count();
+    if n >= 3 {
+        Some((c, n))
+    } else {
+        None
+    }
+}
+
+/// the fence line has no info string, so it can close a block
+fn is_bare_fence(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.chars().all(|c| c == '`') || trimmed.chars().all(|c| c == '~')
+}
+
+/// call `f` with the contents of single or double quoted strings in a line.
+/// an escaped char (by backslash) splits the contents, and unclosed quote ends at the end of line
+fn quoted<'a>(line: &'a str, mut f: impl FnMut(&'a str, usize)) {
+    let mut quote = None;
+    let mut start = 0;
+    let mut chars = line.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match quote {
+            None => {
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                    start = i + 1;
+                }
+            }
+            Some(q) => {
+                if c == '\\' || c == q {
+                    if start < i {
+                        f(&line[start..i], start);
+                    }
+                    if c == q {
+                        quote = None;
+                    } else {
+                        let escaped = chars.next().map(|(j, e)| j + e.len_utf8());
+                        start = escaped.unwrap_or(line.len());
+                    }
+                }
+            }
+        }
+    }
+    if quote.is_some() && start < line.len() {
+        f(&line[start..], start);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn quoted_of(line: &str) -> Vec<(&str, usize)> {
+        let mut found = Vec::new();
+        quoted(line, |s, i| found.push((s, i)));
+        found
+    }
+
+    fn prose_of(text: &str) -> Vec<&str> {
+        let mut code = CodeBlocks::default();
+        text.lines().filter(|line| !code.is_code(line)).collect()
+    }
+
+    #[test]
+    fn code_blocks_works() {
+        let text = "# Title\n\
+                    \n\
+                    ```rust\n\
+                    fn main() {}\n\
+                    ```\n\
+                    text\n    \
+                    not code, continues a paragraph\n\
+                    \n    \
+                    code\n\
+                    \n    \
+                    more code\n\
+                    end\n\
+                    ~~~~\n\
+                    ~~~ not closed\n\
+                    ~~~~\n\
+                    last";
+        assert_eq!(
+            prose_of(text),
+            vec![
+                "# Title",
+                "",
+                "text",
+                "    not code, continues a paragraph",
+                "",
+                "end",
+                "last"
+            ]
+        );
+    }
+
+    #[test]
+    fn quoted_works() {
+        assert_eq!(
+            quoted_of(r#"print("hello", 'world')"#),
+            vec![("hello", 7), ("world", 16)]
+        );
+        assert_eq!(quoted_of(r#"x = "it's""#), vec![("it's", 5)]);
+        assert_eq!(
+            quoted_of(r#""a\nb\"c" d"#),
+            vec![("a", 1), ("b", 4), ("c", 7)]
+        );
+        assert_eq!(quoted_of(r#"no quotes"#), vec![]);
+        assert_eq!(quoted_of(r#"open "end"#), vec![("end", 6)]);
+        assert_eq!(quoted_of(r#""""#), vec![]);
+    }
+}
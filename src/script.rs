@@ -0,0 +1,158 @@
+//! Unicode scripts (writing systems) of counted tokens, for finding documents of mixed
+//! languages or mojibake, see [`Scripts`](struct.Scripts.html)
+
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::Frequencies;
+
+/// scripts told apart, in the order of groups of the regex. chars of other scripts are
+/// `Other`
+const SCRIPTS: &[&str] = &[
+    "Latin",
+    "Greek",
+    "Cyrillic",
+    "Armenian",
+    "Hebrew",
+    "Arabic",
+    "Devanagari",
+    "Bengali",
+    "Thai",
+    "Georgian",
+    "Hangul",
+    "Hiragana",
+    "Katakana",
+    "Han",
+    "Common",
+    "Inherited",
+];
+
+/// classifier of chars by their Unicode script, with the tables of the `regex` crate
+///
+/// `Common` is of chars used by many scripts, such as digits, punctuation and `ー`, and
+/// `Inherited` is of combining marks, which take the script of the char before them.
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::script::Scripts;
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let scripts = Scripts::new();
+/// assert_eq!(scripts.of_char('ж'), "Cyrillic");
+/// assert_eq!(scripts.of_word("コーヒー"), "Katakana");
+/// assert_eq!(scripts.of_word("pаypal"), "Mixed");
+///
+/// let freqs = count_str("日本 web web", CountOption::Word);
+/// let breakdown = scripts.breakdown(&freqs);
+/// assert_eq!(breakdown["Latin"], 6);
+/// assert_eq!(breakdown["Han"], 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scripts {
+    /// a group per script
+    re: Regex,
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        let groups: Vec<_> = SCRIPTS
+            .iter()
+            .map(|script| format!(r"(\p{{{}}})", script))
+            .collect();
+        Scripts {
+            re: Regex::new(&format!("^(?:{})", groups.join("|"))).unwrap(),
+        }
+    }
+}
+
+impl Scripts {
+    /// classifier of the major scripts, compiling the regex of them
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// script of a char, `Other` if it is not of the known scripts
+    pub fn of_char(&self, c: char) -> &'static str {
+        if c.is_ascii() {
+            return if c.is_ascii_alphabetic() {
+                "Latin"
+            } else {
+                "Common"
+            };
+        }
+        let mut buf = [0; 4];
+        let caps = match self.re.captures(c.encode_utf8(&mut buf)) {
+            Some(caps) => caps,
+            None => return "Other",
+        };
+        (1..caps.len())
+            .find(|&i| caps.get(i).is_some())
+            .map_or("Other", |i| SCRIPTS[i - 1])
+    }
+
+    /// script of the chars of a word other than `Common` and `Inherited`, `Mixed` if they are
+    /// of different scripts, or `Common` if there is none
+    pub fn of_word(&self, word: &str) -> &'static str {
+        let mut found = "Common";
+        for c in word.chars() {
+            match self.of_char(c) {
+                "Common" | "Inherited" => {}
+                script if found == "Common" => found = script,
+                script if script != found => return "Mixed",
+                _ => {}
+            }
+        }
+        found
+    }
+
+    /// count of chars of each script in counted tokens, a token of count `n` adding its chars
+    /// `n` times
+    pub fn breakdown(&self, freqs: &Frequencies) -> Frequencies<&'static str> {
+        let mut cache: HashMap<char, &'static str> = HashMap::new();
+        let mut breakdown = Frequencies::new();
+        for (token, &count) in freqs.iter() {
+            for c in token.chars() {
+                let script = *cache.entry(c).or_insert_with(|| self.of_char(c));
+                let total = breakdown.entry(script).or_insert(0);
+                *total = count.saturating_add(*total);
+            }
+        }
+        breakdown
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn chars_are_classified() {
+        let scripts = Scripts::new();
+        let classified: Vec<_> = "aΩשع한ひカ漢1ー\u{301}\u{10900}"
+            .chars()
+            .map(|c| scripts.of_char(c))
+            .collect();
+        assert_eq!(
+            classified,
+            vec![
+                "Latin",
+                "Greek",
+                "Hebrew",
+                "Arabic",
+                "Hangul",
+                "Hiragana",
+                "Katakana",
+                "Han",
+                "Common",
+                "Common",
+                "Inherited",
+                "Other"
+            ]
+        );
+        assert_eq!(scripts.of_word("2021"), "Common");
+        assert_eq!(scripts.of_word("cafe\u{301}"), "Latin");
+        // mojibake of UTF-8 read as Windows-1252 is still Latin, but of Shift_JIS is mixed
+        assert_eq!(scripts.of_word("ã“ã‚“"), "Latin");
+        assert_eq!(scripts.of_word("縺薙ｓ"), "Mixed");
+    }
+}
@@ -0,0 +1,118 @@
+//! counting of each section, see [`count_sections`](fn.count_sections.html)
+
+use regex::Regex;
+use std::io::BufRead;
+
+use crate::decode::{read_numbered_lines, Transcoder};
+use crate::tally::Tally;
+use crate::{CountConfig, CountError, Frequencies};
+
+/// a section of the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// the delimiter line starting the section, `None` for lines before the first delimiter
+    pub title: Option<String>,
+    /// line number (starts from 1) the section starts at
+    pub line: usize,
+    /// count of the section, including its delimiter line
+    pub freqs: Frequencies,
+}
+
+/// counts of each section and of the whole input
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sections {
+    /// sections in order
+    pub sections: Vec<Section>,
+    /// sum of all sections
+    pub total: Frequencies,
+}
+
+/// count each section of the input, which starts at a line matching `delimiter`
+///
+/// lines before the first delimiter are a section without title, if there are any.
+///
+/// # Examples
+///
+/// ```
+/// use regex::Regex;
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{section::count_sections, CountConfig};
+/// let input = Cursor::new("# One\naa bb\n# Two\naa");
+/// let heading = Regex::new("^#").unwrap();
+/// let sections = count_sections(input, &CountConfig::default(), &heading).unwrap();
+///
+/// assert_eq!(sections.sections.len(), 2);
+/// assert_eq!(sections.sections[1].title.as_deref(), Some("# Two"));
+/// assert_eq!(sections.sections[1].freqs["aa"], 1);
+/// assert_eq!(sections.total["aa"], 2);
+/// ```
+pub fn count_sections(
+    input: impl BufRead,
+    config: &CountConfig,
+    delimiter: &Regex,
+) -> Result<Sections, CountError> {
+    let mut result = Sections::default();
+    let mut tally = Tally::new(config);
+    let mut current: Option<Section> = None;
+
+    read_numbered_lines(Transcoder::new(input), config.reading(), |number, line| {
+        let is_delimiter = delimiter.is_match(line);
+        if is_delimiter || current.is_none() {
+            if let Some(done) = current.take() {
+                result.push(done, tally.restart());
+            }
+            let title = if is_delimiter {
+                Some(line.to_string())
+            } else {
+                None
+            };
+            let section = Section {
+                title,
+                line: number,
+                freqs: Frequencies::new(),
+            };
+            current = Some(section);
+        }
+        tally.line(line)
+    })?;
+    if let Some(done) = current {
+        result.push(done, tally.finish());
+    }
+    Ok(result)
+}
+
+impl Sections {
+    fn push(&mut self, mut section: Section, freqs: Frequencies) {
+        section.freqs = freqs;
+        self.total.merge(&section.freqs);
+        self.sections.push(section);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn preamble_is_a_section() {
+        let input = Cursor::new("intro\n2019-01-01 start\nok\n2019-01-02 start\nok ok");
+        let date = Regex::new(r"^\d{4}-\d\d-\d\d").unwrap();
+        let result = count_sections(input, &CountConfig::default(), &date).unwrap();
+
+        let lines: Vec<_> = result.sections.iter().map(|s| s.line).collect();
+        assert_eq!(lines, vec![1, 2, 4]);
+        assert_eq!(result.sections[0].title, None);
+        assert_eq!(result.sections[0].freqs["intro"], 1);
+        assert_eq!(result.sections[2].freqs["ok"], 2);
+        assert_eq!(result.total["ok"], 3);
+        assert_eq!(result.total["start"], 2);
+    }
+
+    #[test]
+    fn empty_input_has_no_section() {
+        let re = Regex::new("^#").unwrap();
+        let result = count_sections(Cursor::new(""), &CountConfig::default(), &re).unwrap();
+        assert_eq!(result, Sections::default());
+    }
+}
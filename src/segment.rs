@@ -0,0 +1,163 @@
+//! dictionary-based word segmentation for scripts written without spaces (Thai, Lao, Khmer,
+//! Myanmar), see [`Dictionary`](struct.Dictionary.html)
+
+use std::collections::HashSet;
+use std::io::{self, BufRead};
+
+use crate::decode::{strip_bom, Transcoder};
+use crate::frequencies::fnv1a;
+
+/// words of a language, to split runs of letters into words
+///
+/// runs are split into the fewest unknown chars, then the fewest words. consecutive unknown
+/// chars are a single word. no dictionary is bundled, load one from a word list.
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::segment::Dictionary;
+/// let dict = Dictionary::new(&["ไป", "ไม่", "ไหน"]);
+/// assert_eq!(dict.segment("ไม่ไปไหน"), vec!["ไม่", "ไป", "ไหน"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+    /// length of the longest word in chars
+    longest: usize,
+}
+
+impl Dictionary {
+    /// dictionary of `words`
+    pub fn new<S: AsRef<str>>(words: &[S]) -> Self {
+        let mut dict = Dictionary::default();
+        for word in words {
+            dict.insert(word.as_ref());
+        }
+        dict
+    }
+
+    /// dictionary of a word list, a word per line. empty lines are ignored
+    pub fn from_reader(input: impl BufRead) -> io::Result<Self> {
+        let mut dict = Dictionary::default();
+        for (i, line) in Transcoder::new(input).lines().enumerate() {
+            let line = line?;
+            dict.insert(if i == 0 { strip_bom(&line) } else { &line }.trim());
+        }
+        Ok(dict)
+    }
+
+    fn insert(&mut self, word: &str) {
+        if !word.is_empty() {
+            self.longest = self.longest.max(word.chars().count());
+            self.words.insert(word.to_string());
+        }
+    }
+
+    /// count of words
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// no word is in the dictionary
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// hash of the words, the same in any order of insertion
+    pub(crate) fn fingerprint(&self) -> u64 {
+        let mut words: Vec<_> = self.words.iter().map(String::as_str).collect();
+        words.sort_unstable();
+        fnv1a(words.join("\n").as_bytes())
+    }
+
+    /// split `text` into words
+    pub fn segment<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let bounds: Vec<usize> = text
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(Some(text.len()))
+            .collect();
+        let n = bounds.len() - 1;
+
+        // best[j]: (unknown chars, words) to split the first j chars, and where the last word starts
+        let mut best = vec![((usize::MAX, usize::MAX), 0); n + 1];
+        best[0].0 = (0, 0);
+        for j in 1..=n {
+            let from = j.saturating_sub(self.longest);
+            for i in from..j {
+                if self.words.contains(&text[bounds[i]..bounds[j]]) {
+                    let (unknown, words) = best[i].0;
+                    best[j] = best[j].min(((unknown, words + 1), i));
+                }
+            }
+            let (unknown, words) = best[j - 1].0;
+            best[j] = best[j].min(((unknown + 1, words + 1), j - 1));
+        }
+
+        let mut words = Vec::new();
+        let mut j = n;
+        while j > 0 {
+            let i = best[j].1;
+            words.push((i, j));
+            j = i;
+        }
+        words.reverse();
+        merge_unknown(&self.words, text, &bounds, words)
+    }
+}
+
+/// join consecutive unknown chars into a word
+fn merge_unknown<'a>(
+    known: &HashSet<String>,
+    text: &'a str,
+    bounds: &[usize],
+    words: Vec<(usize, usize)>,
+) -> Vec<&'a str> {
+    let mut merged: Vec<(usize, usize, bool)> = Vec::new();
+    for (i, j) in words {
+        let is_known = known.contains(&text[bounds[i]..bounds[j]]);
+        match merged.last_mut() {
+            Some(last) if !last.2 && !is_known => last.1 = j,
+            _ => merged.push((i, j, is_known)),
+        }
+    }
+    merged
+        .into_iter()
+        .map(|(i, j, _)| &text[bounds[i]..bounds[j]])
+        .collect()
+}
+
+/// `token` contains a letter of a script written without spaces
+pub(crate) fn needs_segmentation(token: &str) -> bool {
+    token.chars().any(|c| {
+        matches!(
+            c,
+            '\u{e00}'..='\u{eff}' // Thai, Lao
+            | '\u{1000}'..='\u{109f}' // Myanmar
+            | '\u{1780}'..='\u{17ff}' // Khmer
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn fewest_words_wins() {
+        let dict = Dictionary::new(&["ab", "abc", "cd", "d"]);
+        assert_eq!(dict.segment("abcd"), vec!["ab", "cd"]);
+        assert_eq!(dict.segment("abcxyd"), vec!["abc", "xy", "d"]);
+        assert_eq!(dict.segment("xyz"), vec!["xyz"]);
+        assert!(dict.segment("").is_empty());
+    }
+
+    #[test]
+    fn from_reader_works() -> io::Result<()> {
+        let dict = Dictionary::from_reader(Cursor::new("ສະບາຍ\n\nດີ\n"))?;
+        assert_eq!(dict.len(), 2);
+        assert_eq!(dict.segment("ສະບາຍດີ"), vec!["ສະບາຍ", "ດີ"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+//! sentence segmentation
+//!
+//! a sentence ends with "." "!" "?" (or the full-width "。" "！" "？"), or at the end of a line.
+
+use regex::Regex;
+use std::io::BufRead;
+
+use crate::decode::{strip_bom, Transcoder};
+use crate::stats::Distribution;
+
+/// distribution of sentence lengths in words, read from input
+///
+/// summary stats are available from [`Distribution`](../stats/struct.Distribution.html).
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::sentence_lengths;
+/// let lengths = sentence_lengths(Cursor::new("One two. One two three! One"));
+/// assert_eq!(lengths.len(), 3);
+/// assert_eq!(lengths.median(), 2.0);
+/// assert_eq!(lengths.max(), Some(3));
+/// ```
+///
+/// # Panics
+///
+/// input is neither UTF-8 nor UTF-16 with a byte order mark
+pub fn sentence_lengths(input: impl BufRead) -> Distribution {
+    let mut splitter = Splitter::new();
+    let mut lengths = Distribution::new();
+
+    for (i, line) in Transcoder::new(input).lines().enumerate() {
+        let line = line.unwrap();
+        let line = if i == 0 { strip_bom(&line) } else { &line };
+        splitter.feed(line, |event| {
+            if let Event::End(words) = event {
+                lengths.add(words);
+            }
+        });
+    }
+    lengths
+}
+
+/// found on a line by [`Splitter`](struct.Splitter.html)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Event<'a> {
+    /// a word (regex "\w+")
+    Word(&'a str),
+    /// end of a sentence with its count of words
+    End(usize),
+}
+
+/// splits lines into sentences
+#[derive(Debug, Clone)]
+pub(crate) struct Splitter {
+    re: Regex,
+    words: usize,
+}
+
+impl Splitter {
+    pub(crate) fn new() -> Self {
+        Splitter {
+            re: Regex::new(r"\w+|[.!?。！？]+").unwrap(),
+            words: 0,
+        }
+    }
+
+    /// feed a line, events are notified to `f` in order
+    pub(crate) fn feed<'a>(&mut self, line: &'a str, mut f: impl FnMut(Event<'a>)) {
+        let words = &mut self.words;
+        for m in self.re.find_iter(line) {
+            let text = m.as_str();
+            if is_terminator(text) {
+                end(words, &mut f);
+            } else {
+                *words += 1;
+                f(Event::Word(text));
+            }
+        }
+        end(words, &mut f);
+    }
+}
+
+/// close the current sentence if it has any words
+fn end<'a>(words: &mut usize, f: &mut impl FnMut(Event<'a>)) {
+    if *words > 0 {
+        f(Event::End(*words));
+        *words = 0;
+    }
+}
+
+fn is_terminator(text: &str) -> bool {
+    text.chars().all(|c| ".!?。！？".contains(c))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sentence_lengths_works() {
+        use std::io::Cursor;
+
+        let lengths = sentence_lengths(Cursor::new("a b c. d e\n\n... f g h i?"));
+        assert_eq!(
+            lengths.histogram().iter().collect::<Vec<_>>(),
+            vec![(&2, &1), (&3, &1), (&4, &1)]
+        );
+        assert_eq!(lengths.mean(), 3.0);
+    }
+
+    #[test]
+    fn splitter_works() {
+        let mut splitter = Splitter::new();
+        let mut events = Vec::new();
+        splitter.feed("Hi there. How are you?! fine", |e| events.push(e));
+        splitter.feed("", |e| events.push(e));
+
+        use self::Event::*;
+        assert_eq!(
+            events,
+            vec![
+                Word("Hi"),
+                Word("there"),
+                End(2),
+                Word("How"),
+                Word("are"),
+                Word("you"),
+                End(3),
+                Word("fine"),
+                End(1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! similarity between counts of two documents, see [`jaccard`](fn.jaccard.html) and
+//! [`cosine`](fn.cosine.html)
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::Frequencies;
+
+/// Jaccard index of the sets of tokens: shared tokens per tokens of either (0.0 to 1.0).
+/// two empty counts are the same
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::similarity::jaccard;
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let a = count_str("aa bb cc", CountOption::Word);
+/// let b = count_str("bb cc dd dd", CountOption::Word);
+/// assert_eq!(jaccard(&a, &b), 0.5);
+/// ```
+pub fn jaccard<K: Hash + Eq>(a: &Frequencies<K>, b: &Frequencies<K>) -> f64 {
+    let shared = a.keys().filter(|token| b.contains_key(token)).count();
+    let union = a.len() + b.len() - shared;
+    if union == 0 {
+        1.0
+    } else {
+        shared as f64 / union as f64
+    }
+}
+
+/// weighted Jaccard index (Ruzicka similarity) over counts: sum of the smaller counts per sum
+/// of the larger counts of each token (0.0 to 1.0)
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::similarity::weighted_jaccard;
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let a = count_str("aa aa aa bb", CountOption::Word);
+/// let b = count_str("aa bb", CountOption::Word);
+/// // (1 + 1) / (3 + 1)
+/// assert_eq!(weighted_jaccard(&a, &b), 0.5);
+/// ```
+pub fn weighted_jaccard<K: Hash + Eq>(a: &Frequencies<K>, b: &Frequencies<K>) -> f64 {
+    // in f64, so that sums do not overflow
+    let (mut min, mut max) = (0.0, 0.0);
+    for (token, &n) in a.iter() {
+        let m = b.get(token).copied().unwrap_or(0);
+        min += n.min(m) as f64;
+        max += n.max(m) as f64;
+    }
+    max += b
+        .iter()
+        .filter(|(token, _)| !a.contains_key(token))
+        .map(|(_, &m)| m as f64)
+        .sum::<f64>();
+    if max == 0.0 {
+        1.0
+    } else {
+        min / max
+    }
+}
+
+/// sparse vector of a document, a weight per token
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Vector<K: Hash + Eq> {
+    weights: HashMap<K, f64>,
+}
+
+impl<K: Hash + Eq> Vector<K> {
+    /// vector of counts
+    pub fn of(freqs: &Frequencies<K>) -> Self
+    where
+        K: Clone,
+    {
+        let weights = freqs.iter().map(|(k, &n)| (k.clone(), n as f64));
+        Vector {
+            weights: weights.collect(),
+        }
+    }
+
+    /// weight of `token`, 0.0 if not in the document
+    pub fn get(&self, token: &K) -> f64 {
+        self.weights.get(token).copied().unwrap_or(0.0)
+    }
+
+    /// count of tokens with weights
+    pub fn len(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    /// weights by token
+    pub fn iter(&self) -> impl Iterator<Item = (&K, f64)> {
+        self.weights.iter().map(|(k, &w)| (k, w))
+    }
+
+    /// dot product
+    pub fn dot(&self, other: &Self) -> f64 {
+        let (small, large) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        small.iter().map(|(k, w)| w * large.get(k)).sum()
+    }
+
+    /// euclidean length
+    pub fn norm(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    /// cosine of the angle between vectors (0.0 to 1.0 for non-negative weights),
+    /// 0.0 if either is zero
+    pub fn cosine(&self, other: &Self) -> f64 {
+        let norms = self.norm() * other.norm();
+        if norms == 0.0 {
+            0.0
+        } else {
+            self.dot(other) / norms
+        }
+    }
+}
+
+/// cosine similarity of counts, see [`Vector::cosine`](struct.Vector.html#method.cosine)
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::similarity::cosine;
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let a = count_str("aa bb", CountOption::Word);
+/// let b = count_str("aa aa bb bb", CountOption::Word);
+/// assert!((cosine(&a, &b) - 1.0).abs() < 1e-12);
+/// ```
+pub fn cosine<K: Hash + Eq + Clone>(a: &Frequencies<K>, b: &Frequencies<K>) -> f64 {
+    Vector::of(a).cosine(&Vector::of(b))
+}
+
+/// inverse document frequencies of a collection of documents, to weight vectors by TF-IDF
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::similarity::Idf;
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let docs = [
+///     count_str("the cat sat", CountOption::Word),
+///     count_str("the dog sat", CountOption::Word),
+///     count_str("the cat ran", CountOption::Word),
+/// ];
+/// let idf = Idf::new(&docs);
+/// assert!(idf.idf(&"cat".to_string()) > idf.idf(&"the".to_string()));
+/// let (a, b) = (idf.weigh(&docs[0]), idf.weigh(&docs[2]));
+/// // "the" shared by all documents counts for less
+/// assert!(a.cosine(&b) < bicycle_book_wordcount::similarity::cosine(&docs[0], &docs[2]));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Idf<K: Hash + Eq> {
+    documents: usize,
+    /// count of documents containing each token
+    frequencies: HashMap<K, usize>,
+}
+
+impl<K: Hash + Eq + Clone> Idf<K> {
+    /// frequencies of `documents`
+    pub fn new(documents: &[Frequencies<K>]) -> Self {
+        let mut idf = Idf {
+            documents: 0,
+            frequencies: HashMap::new(),
+        };
+        for document in documents {
+            idf.add(document);
+        }
+        idf
+    }
+
+    /// frequencies of `documents` documents, of which `frequencies[token]` contain each token
+    pub fn with_frequencies(documents: usize, frequencies: &Frequencies<K>) -> Self {
+        let frequencies = frequencies.iter().map(|(k, &n)| (k.clone(), n));
+        Idf {
+            documents,
+            frequencies: frequencies.collect(),
+        }
+    }
+
+    /// add a document to the collection
+    pub fn add(&mut self, document: &Frequencies<K>) {
+        self.documents += 1;
+        for token in document.keys() {
+            *self.frequencies.entry(token.clone()).or_default() += 1;
+        }
+    }
+
+    /// smoothed inverse document frequency `ln((1 + N) / (1 + df)) + 1`, which is also
+    /// defined for tokens in no document
+    pub fn idf(&self, token: &K) -> f64 {
+        let df = self.frequencies.get(token).copied().unwrap_or(0);
+        ((1 + self.documents) as f64 / (1 + df) as f64).ln() + 1.0
+    }
+
+    /// vector of counts times the inverse document frequencies
+    pub fn weigh(&self, freqs: &Frequencies<K>) -> Vector<K> {
+        let weights = freqs
+            .iter()
+            .map(|(k, &n)| (k.clone(), n as f64 * self.idf(k)));
+        Vector {
+            weights: weights.collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{count_str, CountOption};
+
+    #[test]
+    fn jaccard_works() {
+        let empty = Frequencies::<String>::new();
+        let a = count_str("aa bb", CountOption::Word);
+        assert_eq!(jaccard(&empty, &empty), 1.0);
+        assert_eq!(jaccard(&a, &empty), 0.0);
+        assert_eq!(jaccard(&a, &a), 1.0);
+        assert_eq!(weighted_jaccard(&empty, &empty), 1.0);
+        assert_eq!(weighted_jaccard(&a, &empty), 0.0);
+        let b = count_str("bb bb cc", CountOption::Word);
+        // (0 + 1 + 0) / (1 + 2 + 1)
+        assert_eq!(weighted_jaccard(&a, &b), 0.25);
+        assert_eq!(weighted_jaccard(&b, &a), 0.25);
+    }
+
+    #[test]
+    fn cosine_works() {
+        let a = count_str("aa bb", CountOption::Word);
+        let b = count_str("cc", CountOption::Word);
+        let empty = Frequencies::<String>::new();
+        assert_eq!(cosine(&a, &b), 0.0);
+        assert_eq!(cosine(&a, &empty), 0.0);
+        let c = count_str("aa cc", CountOption::Word);
+        assert!((cosine(&a, &c) - 0.5).abs() < 1e-12);
+
+        let idf = Idf::new(&[a.clone(), c.clone()]);
+        assert_eq!(idf.idf(&"aa".to_string()), 1.0);
+        assert!((idf.idf(&"zz".to_string()) - (1.0 + 3f64.ln())).abs() < 1e-12);
+        assert_eq!(idf.weigh(&a).get(&"aa".to_string()), 1.0);
+    }
+}
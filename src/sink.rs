@@ -0,0 +1,156 @@
+//! destinations of counted tokens, see [`CountSink`](trait.CountSink.html)
+
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+use crate::decode::{read_lines, read_numbered_lines, strip_bom, DecodePolicy, Transcoder};
+use crate::tally::Scanner;
+use crate::{tokens, word_regex, CountConfig, CountOption, Frequencies};
+
+/// receives each counted token (after normalization) from a counting pass
+///
+/// maps count the token (saturating at `usize::MAX`), and closures are called with it.
+/// implement it to stream tokens into sketches, databases, or channels without an intermediate map.
+pub trait CountSink {
+    /// a token is found
+    fn accept(&mut self, token: &str);
+}
+
+impl CountSink for HashMap<String, usize> {
+    fn accept(&mut self, token: &str) {
+        match self.get_mut(token) {
+            Some(count) => *count = count.saturating_add(1),
+            None => {
+                self.insert(token.to_string(), 1);
+            }
+        }
+    }
+}
+
+impl CountSink for Frequencies {
+    fn accept(&mut self, token: &str) {
+        (**self).accept(token)
+    }
+}
+
+impl<F: FnMut(&str)> CountSink for F {
+    fn accept(&mut self, token: &str) {
+        self(token)
+    }
+}
+
+/// feed tokens read from input to `sink`, as configured
+///
+/// `original_case` of the config is not applied, because keys are not known in advance.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count_to_sink, CountConfig, CountOption};
+/// let mut tokens = Vec::new();
+/// count_to_sink(
+///     Cursor::new("aa bb\naa"),
+///     &CountConfig::new(CountOption::Word),
+///     &mut |token: &str| tokens.push(token.to_string()),
+/// );
+/// assert_eq!(tokens, vec!["aa", "bb", "aa"]);
+/// ```
+///
+/// # Panics
+///
+/// input is neither UTF-8 nor UTF-16 with a byte order mark, unless
+/// [`CountConfig::invalid_input`](../config/struct.CountConfig.html#method.invalid_input) is set
+pub fn count_to_sink(input: impl BufRead, config: &CountConfig, sink: &mut impl CountSink) {
+    let mut scanner = Scanner::new(config);
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        scanner.line(line, |key, _| sink.accept(key));
+        Ok::<_, io::Error>(())
+    })
+    .unwrap();
+}
+
+/// call `f` with each token read from input and its line number (start from 1),
+/// without building any map
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{for_each_token, CountOption};
+/// let mut errors = Vec::new();
+/// for_each_token(Cursor::new("ok\nan error\nerror"), CountOption::Word, |token, line| {
+///     if token == "error" {
+///         errors.push(line);
+///     }
+/// })
+/// .unwrap();
+/// assert_eq!(errors, vec![2, 3]);
+/// ```
+///
+/// # Errors
+///
+/// input is neither UTF-8 nor UTF-16 with a byte order mark, or reading fails
+pub fn for_each_token(
+    input: impl BufRead,
+    option: CountOption,
+    mut f: impl FnMut(&str, usize),
+) -> io::Result<()> {
+    let re = word_regex();
+    let input = Transcoder::new(input);
+    read_numbered_lines(input, DecodePolicy::Strict, |number, line| {
+        let line = if number == 1 { strip_bom(line) } else { line };
+        tokens(&re, line, &option, |token, _| f(token, number));
+        Ok::<_, io::Error>(())
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn count_to_sink_works() {
+        let config = CountConfig::new(CountOption::Word).ignore_case(true);
+        let mut map = HashMap::new();
+        count_to_sink(Cursor::new("Aa bb\naa"), &config, &mut map);
+
+        let mut exp = HashMap::new();
+        exp.insert("aa".to_string(), 2);
+        exp.insert("bb".to_string(), 1);
+        assert_eq!(map, exp);
+    }
+
+    #[test]
+    fn custom_sink_works() {
+        struct Longest(String);
+        impl CountSink for Longest {
+            fn accept(&mut self, token: &str) {
+                if token.len() > self.0.len() {
+                    self.0 = token.to_string();
+                }
+            }
+        }
+
+        let mut longest = Longest(String::new());
+        count_to_sink(
+            Cursor::new("a abc ab"),
+            &CountConfig::new(CountOption::Word),
+            &mut longest,
+        );
+        assert_eq!(longest.0, "abc");
+    }
+
+    #[test]
+    fn for_each_token_works() {
+        let mut found = Vec::new();
+        for_each_token(Cursor::new("ab\n\nc"), CountOption::Char, |token, line| {
+            found.push((token.to_string(), line))
+        })
+        .unwrap();
+        let pair = |t: &str, l| (t.to_string(), l);
+        assert_eq!(found, vec![pair("a", 1), pair("b", 1), pair("c", 3)]);
+    }
+}
@@ -0,0 +1,204 @@
+//! pairs of words near each other, see [`count_skip_grams`](fn.count_skip_grams.html), and
+//! their association, see [`collocations`](fn.collocations.html)
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::io::BufRead;
+
+use crate::decode::{read_lines, Transcoder};
+use crate::tally::Scanner;
+use crate::{CountConfig, CountError};
+
+/// weight of a pair by the distance `d` (1 for adjacent words) in a window of `k`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Weighting {
+    /// 1 for every distance
+    Uniform,
+    /// `1 / d`, as GloVe
+    Harmonic,
+    /// `(k - d + 1) / k`, as word2vec with random window sizes on average
+    Linear,
+}
+
+/// option default value
+impl Default for Weighting {
+    fn default() -> Self {
+        Weighting::Uniform
+    }
+}
+
+impl Weighting {
+    /// weight of distance `d` in a window of `k`
+    pub fn weight(self, d: usize, k: usize) -> f64 {
+        match self {
+            Weighting::Uniform => 1.0,
+            Weighting::Harmonic => 1.0 / d as f64,
+            Weighting::Linear => (k - d + 1) as f64 / k as f64,
+        }
+    }
+}
+
+/// weighted counts of pairs `(word, later word)` where the later word is at most `window`
+/// tokens after the word (across lines), for building co-occurrence matrices
+///
+/// with [`Weighting::Uniform`](enum.Weighting.html#variant.Uniform), values are counts of
+/// the pairs. pairs are ordered, add `(b, a)` to `(a, b)` for symmetric counts.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::skipgram::{count_skip_grams, Weighting};
+/// use bicycle_book_wordcount::CountConfig;
+/// let config = CountConfig::default();
+/// let pairs = count_skip_grams(Cursor::new("a b\nc a"), &config, 2, Weighting::Harmonic);
+/// let pairs = pairs.unwrap();
+/// let pair = |a: &str, b: &str| pairs[&(a.to_string(), b.to_string())];
+/// assert_eq!(pair("a", "b"), 1.0);
+/// assert_eq!(pair("a", "c"), 0.5);
+/// assert_eq!(pair("b", "a"), 0.5);
+/// assert_eq!(pairs.len(), 5);
+/// ```
+///
+/// # Panics
+///
+/// `window` is 0
+pub fn count_skip_grams(
+    input: impl BufRead,
+    config: &CountConfig,
+    window: usize,
+    weighting: Weighting,
+) -> Result<HashMap<(String, String), f64>, CountError> {
+    assert!(window > 0, "window size must be positive");
+    let mut scanner = Scanner::new(config);
+    let mut recent: VecDeque<String> = VecDeque::with_capacity(window + 1);
+    let mut pairs = HashMap::new();
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        scanner.line(line, |key, _| {
+            for (i, word) in recent.iter().rev().enumerate() {
+                let weight = weighting.weight(i + 1, window);
+                *pairs.entry((word.clone(), key.to_string())).or_insert(0.0) += weight;
+            }
+            recent.push_back(key.to_string());
+            if recent.len() > window {
+                recent.pop_front();
+            }
+        });
+        scanner.check_time()
+    })?;
+    Ok(pairs)
+}
+
+/// association of a pair of words
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collocation {
+    pub pair: (String, String),
+    /// (weighted) count of the pair
+    pub count: f64,
+    /// pointwise mutual information in bits, `log2(p(a, b) / (p(a) p(b)))`
+    pub pmi: f64,
+    /// `(observed - expected) / sqrt(observed)`, which favors frequent pairs
+    pub t_score: f64,
+}
+
+/// PMI and t-score of pairs counted at least `min_count` times, by descending PMI
+/// (ties by pair)
+///
+/// probabilities are of the co-occurrence counts `pairs` (e.g. by
+/// [`count_skip_grams`](fn.count_skip_grams.html)): `p(a)` is of pairs starting with `a`, and
+/// `p(b)` is of pairs ending with `b`. PMI overrates rare pairs, so filter them by `min_count`.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::skipgram::{collocations, count_skip_grams, Weighting};
+/// use bicycle_book_wordcount::CountConfig;
+/// let text = "new york is big\nnew york is old\nthe city is big";
+/// let pairs = count_skip_grams(Cursor::new(text), &CountConfig::default(), 1, Weighting::Uniform);
+/// let pairs: Vec<_> = collocations(&pairs.unwrap(), 2.0)
+///     .into_iter()
+///     .map(|c| (c.pair.0, c.pair.1))
+///     .collect();
+/// assert_eq!(pairs[0], ("new".to_string(), "york".to_string()));
+/// ```
+pub fn collocations(pairs: &HashMap<(String, String), f64>, min_count: f64) -> Vec<Collocation> {
+    let mut firsts: HashMap<&str, f64> = HashMap::new();
+    let mut seconds: HashMap<&str, f64> = HashMap::new();
+    let mut total = 0.0;
+    for ((a, b), &n) in pairs {
+        *firsts.entry(a).or_insert(0.0) += n;
+        *seconds.entry(b).or_insert(0.0) += n;
+        total += n;
+    }
+    let mut collocations: Vec<_> = pairs
+        .iter()
+        .filter(|(_, &n)| n >= min_count && n > 0.0)
+        .map(|((a, b), &n)| {
+            let expected = firsts[a.as_str()] * seconds[b.as_str()] / total;
+            Collocation {
+                pair: (a.clone(), b.clone()),
+                count: n,
+                pmi: (n / expected).log2(),
+                t_score: (n - expected) / n.sqrt(),
+            }
+        })
+        .collect();
+    collocations.sort_by(|x, y| {
+        y.pmi
+            .partial_cmp(&x.pmi)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| x.pair.cmp(&y.pair))
+    });
+    collocations
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    fn pairs(text: &str, window: usize, weighting: Weighting) -> Vec<(String, String, f64)> {
+        let config = CountConfig::default();
+        let mut pairs: Vec<_> = count_skip_grams(Cursor::new(text), &config, window, weighting)
+            .unwrap()
+            .into_iter()
+            .map(|((a, b), n)| (a, b, n))
+            .collect();
+        pairs.sort_by(|x, y| (&x.0, &x.1).cmp(&(&y.0, &y.1)));
+        pairs
+    }
+
+    #[test]
+    fn window_works() {
+        let s = |a: &str, b: &str, n| (a.to_string(), b.to_string(), n);
+        assert_eq!(
+            pairs("x y x y", 1, Weighting::Uniform),
+            vec![s("x", "y", 2.0), s("y", "x", 1.0)]
+        );
+        assert_eq!(
+            pairs("x y z", 2, Weighting::Linear),
+            vec![s("x", "y", 1.0), s("x", "z", 0.5), s("y", "z", 1.0)]
+        );
+        assert!(pairs("x", 3, Weighting::Uniform).is_empty());
+    }
+
+    #[test]
+    fn collocations_work() {
+        let mut pairs = HashMap::new();
+        let mut add = |a: &str, b: &str, n| {
+            pairs.insert((a.to_string(), b.to_string()), n);
+        };
+        add("a", "b", 2.0);
+        add("a", "c", 2.0);
+        add("d", "b", 4.0);
+        let collocations = collocations(&pairs, 0.0);
+        assert_eq!(collocations.len(), 3);
+        // p(a, c) = 0.25, p(a) = 0.5, p(c) = 0.25
+        assert_eq!(collocations[0].pair, ("a".to_string(), "c".to_string()));
+        assert_eq!(collocations[0].pmi, 1.0);
+        assert_eq!(collocations[0].t_score, 1.0 / 2f64.sqrt());
+        assert_eq!(collocations[2].pmi, (2.0f64 / 3.0).log2());
+        assert_eq!(super::collocations(&pairs, 3.0).len(), 1);
+    }
+}
@@ -0,0 +1,288 @@
+//! per-line statistics, gathered in the same pass as [`count`](../fn.count.html)
+
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::mem;
+
+use crate::decode::{read_lines, strip_bom, Transcoder};
+use crate::tally::Tally;
+use crate::{count_line, word_regex, CountConfig, CountError, CountOption, Frequencies};
+
+/// distribution of non-negative integer samples (kept as a histogram)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Distribution {
+    histogram: BTreeMap<usize, usize>,
+    samples: usize,
+    sum: usize,
+}
+
+impl Distribution {
+    /// empty distribution
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// add a sample
+    pub fn add(&mut self, value: usize) {
+        *self.histogram.entry(value).or_insert(0) += 1;
+        self.samples += 1;
+        self.sum += value;
+    }
+
+    /// count of samples
+    pub fn len(&self) -> usize {
+        self.samples
+    }
+
+    /// no sample is added
+    pub fn is_empty(&self) -> bool {
+        self.samples == 0
+    }
+
+    /// sum of all samples
+    pub fn sum(&self) -> usize {
+        self.sum
+    }
+
+    /// sample value => count of samples, ordered by value
+    pub fn histogram(&self) -> &BTreeMap<usize, usize> {
+        &self.histogram
+    }
+
+    /// arithmetic mean, 0 if empty
+    pub fn mean(&self) -> f64 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            self.sum as f64 / self.samples as f64
+        }
+    }
+
+    /// median, the mean of the two middle samples if the count is even. 0 if empty
+    pub fn median(&self) -> f64 {
+        if self.samples == 0 {
+            return 0.0;
+        }
+        let lower = self.nth((self.samples - 1) / 2);
+        let upper = self.nth(self.samples / 2);
+        (lower + upper) as f64 / 2.0
+    }
+
+    /// smallest sample
+    pub fn min(&self) -> Option<usize> {
+        self.histogram.keys().next().cloned()
+    }
+
+    /// largest sample
+    pub fn max(&self) -> Option<usize> {
+        self.histogram.keys().next_back().cloned()
+    }
+
+    /// n-th smallest sample (0 origin), n must be less than `len()`
+    fn nth(&self, n: usize) -> usize {
+        let mut seen = 0;
+        for (&value, &count) in &self.histogram {
+            seen += count;
+            if n < seen {
+                return value;
+            }
+        }
+        unreachable!("n is out of range")
+    }
+}
+
+/// statistics of each line
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineStats {
+    /// words (regex "\w+") per line
+    pub words: Distribution,
+    /// chars (Unicode) per line
+    pub chars: Distribution,
+}
+
+impl LineStats {
+    /// count of lines
+    pub fn lines(&self) -> usize {
+        self.words.len()
+    }
+
+    /// words per char over the whole input, 0 if there are no chars
+    pub fn density(&self) -> f64 {
+        if self.chars.sum() == 0 {
+            0.0
+        } else {
+            self.words.sum() as f64 / self.chars.sum() as f64
+        }
+    }
+
+    /// add a line
+    pub(crate) fn add_line(&mut self, words: usize, line: &str) {
+        self.words.add(words);
+        self.chars.add(line.chars().count());
+    }
+}
+
+/// same as [`count`](../fn.count.html), but also gather [`LineStats`](struct.LineStats.html) in the same pass
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::{count_with_stats, CountOption};
+/// let (freq, stats) = count_with_stats(Cursor::new("aa bb\ncc\n"), CountOption::Word);
+/// assert_eq!(freq["aa"], 1);
+/// assert_eq!(stats.lines(), 2);
+/// assert_eq!(stats.words.max(), Some(2));
+/// assert_eq!(stats.words.mean(), 1.5);
+/// ```
+///
+/// # Panics
+///
+/// input is neither UTF-8 nor UTF-16 with a byte order mark
+pub fn count_with_stats(input: impl BufRead, option: CountOption) -> (Frequencies, LineStats) {
+    let re = word_regex();
+    let mut freqs = Frequencies::new();
+    let mut stats = LineStats::default();
+
+    for (i, line) in Transcoder::new(input).lines().enumerate() {
+        let line = line.unwrap();
+        let line = if i == 0 { strip_bom(&line) } else { &line };
+        count_line(&re, line, &option, &mut freqs);
+        stats.add_line(re.find_iter(line).count(), line);
+    }
+    (freqs, stats)
+}
+
+/// same as [`try_count_with`](../fn.try_count_with.html), but also gather
+/// [`LineStats`](struct.LineStats.html) in the same pass. lines skipped by the decode policy of
+/// `config` are not in the statistics
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::stats::try_count_with_stats;
+/// use bicycle_book_wordcount::{CountConfig, CountOption};
+/// let config = CountConfig::new(CountOption::Word);
+/// let (freqs, stats) = try_count_with_stats(&b"aa bb\ncc\n"[..], &config).unwrap();
+/// assert_eq!(freqs["cc"], 1);
+/// assert_eq!(stats.words.max(), Some(2));
+/// assert!(try_count_with_stats(&b"\xff\n"[..], &config).is_err());
+/// ```
+pub fn try_count_with_stats(
+    input: impl BufRead,
+    config: &CountConfig,
+) -> Result<(Frequencies, LineStats), CountError> {
+    let re = word_regex();
+    let mut tally = Tally::new(config);
+    let mut stats = LineStats::default();
+    let mut first = !config.keep_bom;
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        let text = if mem::take(&mut first) {
+            strip_bom(line)
+        } else {
+            line
+        };
+        stats.add_line(re.find_iter(text).count(), text);
+        tally.line(line)
+    })?;
+    Ok((tally.finish(), stats))
+}
+
+/// count of lines by length (Unicode chars, without the line terminator), read from input
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::count_line_lengths;
+/// let lengths = count_line_lengths(Cursor::new("abc\nxyz\n\nあいう"));
+/// assert_eq!(lengths[&3], 3);
+/// assert_eq!(lengths[&0], 1);
+/// ```
+///
+/// # Panics
+///
+/// input is neither UTF-8 nor UTF-16 with a byte order mark
+pub fn count_line_lengths(input: impl BufRead) -> Frequencies<usize> {
+    let mut lengths = Frequencies::new();
+    for (i, line) in Transcoder::new(input).lines().enumerate() {
+        let line = line.unwrap();
+        let line = if i == 0 { strip_bom(&line) } else { &line };
+        *lengths.entry(line.chars().count()).or_insert(0) += 1;
+    }
+    lengths
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn distribution_works() {
+        let mut dist = Distribution::new();
+        assert_eq!(dist.median(), 0.0);
+        assert_eq!(dist.max(), None);
+
+        for &v in &[3, 1, 4, 1, 5] {
+            dist.add(v);
+        }
+        assert_eq!(dist.len(), 5);
+        assert_eq!(dist.mean(), 2.8);
+        assert_eq!(dist.median(), 3.0);
+        assert_eq!(dist.min(), Some(1));
+        assert_eq!(dist.max(), Some(5));
+
+        dist.add(9);
+        assert_eq!(dist.median(), 3.5);
+    }
+
+    #[test]
+    fn count_line_lengths_works() {
+        let lengths = count_line_lengths(Cursor::new("12345\r\n12345\n1\n"));
+        let mut exp = std::collections::HashMap::new();
+        exp.insert(5, 2);
+        exp.insert(1, 1);
+        assert_eq!(lengths, exp);
+    }
+
+    #[test]
+    fn utf16_is_transcoded() {
+        let input = b"\xff\xfea\x00a\x00\n\x00";
+        let (freqs, stats) = count_with_stats(Cursor::new(input), CountOption::Word);
+        assert_eq!(freqs["aa"], 1);
+        assert_eq!(stats.lines(), 1);
+        assert_eq!(count_line_lengths(Cursor::new(input))[&2], 1);
+    }
+
+    #[test]
+    fn bom_is_stripped() {
+        let input = "\u{feff}ab\nab";
+        let (freqs, stats) = count_with_stats(Cursor::new(input), CountOption::Line);
+        assert_eq!(freqs["ab"], 2);
+        assert_eq!(stats.chars.max(), Some(2));
+        assert_eq!(count_line_lengths(Cursor::new(input))[&2], 2);
+
+        let config = CountConfig::new(CountOption::Line);
+        let (_, stats) = try_count_with_stats(input.as_bytes(), &config).unwrap();
+        assert_eq!(stats.chars.max(), Some(2));
+        let config = config.keep_bom(true);
+        let (freqs, stats) = try_count_with_stats(input.as_bytes(), &config).unwrap();
+        assert_eq!(freqs["\u{feff}ab"], 1);
+        assert_eq!(stats.chars.max(), Some(3));
+    }
+
+    #[test]
+    fn line_stats_works() {
+        let (freqs, stats) =
+            count_with_stats(Cursor::new("aa bb cc\n\nbb dd\n"), CountOption::Word);
+
+        assert_eq!(freqs["bb"], 2);
+        assert_eq!(stats.lines(), 3);
+        assert_eq!(stats.words.median(), 2.0);
+        assert_eq!(stats.words.max(), Some(3));
+        assert_eq!(stats.chars.max(), Some(8));
+        assert_eq!(stats.chars.sum(), 13);
+        assert_eq!(stats.density(), 5.0 / 13.0);
+    }
+}
@@ -0,0 +1,197 @@
+//! values of a key of structured logs, of lines in logfmt (`level=info msg="disk full"`) or
+//! JSON objects (`{"level":"info"}`), see [`count_key`](fn.count_key.html)
+
+use std::borrow::Cow;
+use std::io::BufRead;
+
+use crate::accesslog::FieldCounts;
+use crate::decode::{read_lines, Transcoder};
+use crate::tally::Tally;
+use crate::{json, CountConfig, CountError};
+
+/// value of `key` of a line, read as a JSON object if it starts with `{` and as logfmt
+/// otherwise. `None` if the line does not have the key, its value is an object or an array, or
+/// the line is not a JSON object
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::structured::value;
+/// let line = r#"time=2024-10-10T13:55:36Z level=warn msg="disk \"/\" full" cached"#;
+/// assert_eq!(value(line, "level").as_deref(), Some("warn"));
+/// assert_eq!(value(line, "msg").as_deref(), Some(r#"disk "/" full"#));
+/// assert_eq!(value(line, "cached").as_deref(), Some("true"));
+/// assert_eq!(value(r#"{"level": "error", "status": 500}"#, "status").as_deref(), Some("500"));
+/// assert_eq!(value("level=info", "status"), None);
+/// ```
+pub fn value<'a>(line: &'a str, key: &str) -> Option<Cow<'a, str>> {
+    if line.trim_start().starts_with('{') {
+        json::field(line, key).ok().flatten().map(Cow::Owned)
+    } else {
+        logfmt_value(line, key)
+    }
+}
+
+/// value of `key` of a logfmt line, pairs of `key=value` separated by whitespace. a value may
+/// be quoted with `"`, in which `\"` and `\\` are escaped, and a key without `=` is `true`
+fn logfmt_value<'a>(line: &'a str, key: &str) -> Option<Cow<'a, str>> {
+    let mut rest = line;
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return None;
+        }
+        let end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let (name, after) = rest.split_at(end);
+        let value = match after.strip_prefix('=') {
+            Some(after) if after.starts_with('"') => {
+                let (value, after) = quoted(&after[1..])?;
+                rest = after;
+                value
+            }
+            Some(after) => {
+                let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                rest = &after[end..];
+                Cow::Borrowed(&after[..end])
+            }
+            None => {
+                rest = after;
+                Cow::Borrowed("true")
+            }
+        };
+        if name == key {
+            return Some(value);
+        }
+    }
+}
+
+/// a value up to the closing quote, unescaped, and the rest after the quote
+fn quoted(text: &str) -> Option<(Cow<'_, str>, &str)> {
+    let mut unescaped = None::<String>;
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                let value = unescaped.map_or(Cow::Borrowed(&text[..i]), Cow::Owned);
+                return Some((value, &text[i + 1..]));
+            }
+            '\\' => {
+                let (_, escaped) = chars.next()?;
+                let value = unescaped.get_or_insert_with(|| text[..i].to_string());
+                value.push(match escaped {
+                    'n' => '\n',
+                    't' => '\t',
+                    c => c,
+                });
+            }
+            c => {
+                if let Some(value) = &mut unescaped {
+                    value.push(c);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// count values of `key` of each line of a structured log, see [`value`](fn.value.html).
+/// values are counted whole, normalized and filtered as configured (e.g. by `ignore_case` and
+/// `stop_words`), within `overflow` and `limits` of `config`, and lines without the key are
+/// skipped
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::structured::count_key;
+/// use bicycle_book_wordcount::CountConfig;
+/// let log = r#"level=info msg=started
+/// {"level":"error","msg":"disk full"}
+/// level=info msg="request done" status=200
+/// plain text"#;
+/// let counts = count_key(Cursor::new(log), &CountConfig::default(), "level").unwrap();
+/// assert_eq!(counts.values["info"], 2);
+/// assert_eq!(counts.values["error"], 1);
+/// assert_eq!(counts.skipped, 1);
+/// ```
+pub fn count_key(
+    input: impl BufRead,
+    config: &CountConfig,
+    key: &str,
+) -> Result<FieldCounts, CountError> {
+    let mut tally = Tally::new(config);
+    let mut skipped = 0;
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        match value(line, key) {
+            Some(value) => return tally.token(&value),
+            None if line.trim().is_empty() => {}
+            None => skipped += 1,
+        }
+        tally.skip_line();
+        Ok(())
+    })?;
+    Ok(FieldCounts {
+        values: tally.finish(),
+        skipped,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn logfmt_is_parsed() {
+        let line = r#"  a=1 b= c="x \\ y=2" d="" e f=g=h"#;
+        assert_eq!(logfmt_value(line, "a").as_deref(), Some("1"));
+        assert_eq!(logfmt_value(line, "b").as_deref(), Some(""));
+        assert_eq!(logfmt_value(line, "c").as_deref(), Some(r#"x \ y=2"#));
+        assert_eq!(logfmt_value(line, "y"), None);
+        assert_eq!(logfmt_value(line, "d").as_deref(), Some(""));
+        assert_eq!(logfmt_value(line, "e").as_deref(), Some("true"));
+        assert_eq!(logfmt_value(line, "f").as_deref(), Some("g=h"));
+        assert_eq!(logfmt_value(r#"a="unterminated"#, "a"), None);
+        assert!(matches!(
+            logfmt_value("a=\"x\"", "a"),
+            Some(Cow::Borrowed("x"))
+        ));
+    }
+
+    #[test]
+    fn logfmt_is_split_by_whitespace() {
+        let line = "a=1\tb=2   c=\"x=1 y=2\"\t\td e=3";
+        assert_eq!(logfmt_value(line, "a").as_deref(), Some("1"));
+        assert_eq!(logfmt_value(line, "b").as_deref(), Some("2"));
+        assert_eq!(logfmt_value(line, "c").as_deref(), Some("x=1 y=2"));
+        assert_eq!(logfmt_value(line, "y"), None);
+        assert_eq!(logfmt_value(line, "d").as_deref(), Some("true"));
+        assert_eq!(logfmt_value(line, "e").as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn values_are_counted_within_limits() {
+        use crate::config::Limits;
+        let log = "level=info\nno key\nlevel=warn\nlevel=debug\n";
+        let config = CountConfig::default().limits(Limits::new().keys(1));
+        match count_key(log.as_bytes(), &config, "level") {
+            Err(CountError::Limit { line, .. }) => assert_eq!(line, 3),
+            other => panic!("{:?}", other),
+        }
+        let config = CountConfig::default().stop_words(&["debug"]);
+        let counts = count_key(log.as_bytes(), &config, "level").unwrap();
+        assert_eq!(counts.values.len(), 2);
+        assert_eq!(counts.skipped, 1);
+    }
+
+    #[test]
+    fn broken_json_is_skipped() {
+        let log = "{\"level\":\"Info\"}\n{\"level\":\n\n{\"ctx\":{\"level\":\"x\"}}\n";
+        let config = CountConfig::default().ignore_case(true);
+        let counts = count_key(log.as_bytes(), &config, "level").unwrap();
+        assert_eq!(counts.values.len(), 1);
+        assert_eq!(counts.values["info"], 1);
+        assert_eq!(counts.skipped, 2);
+    }
+}
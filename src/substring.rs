@@ -0,0 +1,98 @@
+//! counting of all substrings of lines up to a length, for finding repeated patterns in logs
+//! or sequences such as DNA, see [`count_substrings`](fn.count_substrings.html)
+
+use std::io::BufRead;
+
+use crate::decode::{read_lines, strip_bom, Transcoder};
+use crate::{CountConfig, CountError, Frequencies};
+
+/// count every substring of `min_len` to `max_len` chars of each line, such as "ab", "b" and
+/// "a" of "ab". substrings do not span lines, and overlapping occurrences are all counted
+///
+/// lines are normalized as configured (e.g. by
+/// [`ignore_case`](../config/struct.CountConfig.html#method.ignore_case)), but other settings
+/// of tokens such as the option and stop words do not apply. a line of `n` chars has about
+/// `n * max_len` substrings, so the count of distinct keys can be large for a large `max_len`
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::substring::count_substrings;
+/// use bicycle_book_wordcount::CountConfig;
+/// let freqs = count_substrings(Cursor::new("GATTACA\nTACA"), &CountConfig::default(), 2, 3).unwrap();
+/// assert_eq!(freqs["TA"], 2);
+/// assert_eq!(freqs["ACA"], 2);
+/// assert_eq!(freqs["CA"], 2);
+/// assert_eq!(freqs.get("A"), None);
+/// ```
+pub fn count_substrings(
+    input: impl BufRead,
+    config: &CountConfig,
+    min_len: usize,
+    max_len: usize,
+) -> Result<Frequencies, CountError> {
+    let mut freqs = Frequencies::new();
+    let policy = config.overflow;
+    let mut first = !config.keep_bom;
+    // byte offsets of chars of the current line, with the end of the line
+    let mut offsets = Vec::new();
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        let line = if std::mem::replace(&mut first, false) {
+            strip_bom(line)
+        } else {
+            line
+        };
+        let line = config.normalize(line);
+        offsets.clear();
+        offsets.extend(line.char_indices().map(|(i, _)| i));
+        offsets.push(line.len());
+        let chars = offsets.len() - 1;
+        for start in 0..chars {
+            for len in min_len.max(1)..=max_len.min(chars - start) {
+                let key = &line[offsets[start]..offsets[start + len]];
+                match freqs.get_mut(key) {
+                    Some(count) => match policy.add(*count, 1) {
+                        Some(n) => *count = n,
+                        None => return Err(CountError::Overflow(key.to_string())),
+                    },
+                    None => {
+                        freqs.insert(key.to_string(), 1);
+                    }
+                }
+            }
+        }
+        Ok(())
+    })?;
+    Ok(freqs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn every_substring_is_counted() {
+        let config = CountConfig::default();
+        let freqs = count_substrings("abab".as_bytes(), &config, 1, 4).unwrap();
+        assert_eq!(freqs["a"], 2);
+        assert_eq!(freqs["ab"], 2);
+        assert_eq!(freqs["ba"], 1);
+        assert_eq!(freqs["aba"], 1);
+        assert_eq!(freqs["abab"], 1);
+        // 4 + 3 + 2 + 1 substrings
+        assert_eq!(freqs.total(), 10);
+        assert!(count_substrings("ab".as_bytes(), &config, 3, 2)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn lines_are_normalized() {
+        let config = CountConfig::default().ignore_case(true);
+        let freqs = count_substrings("\u{feff}ÉrrOr\nerror".as_bytes(), &config, 5, 5).unwrap();
+        assert_eq!(freqs.len(), 2);
+        assert_eq!(freqs["error"], 1);
+        assert_eq!(freqs["érror"], 1);
+    }
+}
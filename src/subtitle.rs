@@ -0,0 +1,139 @@
+//! counting of text of subtitle files (SRT and WebVTT), see [`count_subtitles`](fn.count_subtitles.html)
+
+use std::borrow::Cow;
+use std::io::BufRead;
+
+use crate::decode::{read_lines, Transcoder};
+use crate::tally::Tally;
+use crate::{CountConfig, CountError, Frequencies};
+
+/// text of a line of a cue without markup: tags like `<i>`, `<c.yellow>` and `<00:01.000>`,
+/// override tags like `{\an8}`, and entities like `&amp;` are removed or decoded
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::subtitle::strip_markup;
+/// assert_eq!(strip_markup("{\\an8}<i>Tom &amp; <b>Jerry</b></i>"), "Tom & Jerry");
+/// assert_eq!(strip_markup("plain"), "plain");
+/// ```
+pub fn strip_markup(line: &str) -> Cow<'_, str> {
+    if !line.contains(&['<', '{', '&'][..]) {
+        return Cow::Borrowed(line);
+    }
+    let mut text = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(i) = rest.find(&['<', '{', '&'][..]) {
+        text.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let close = match rest.as_bytes()[0] {
+            b'<' => '>',
+            b'{' => '}',
+            _ => ';',
+        };
+        match rest.find(close) {
+            Some(end) if close == ';' => {
+                match entity(&rest[1..end]) {
+                    Some(c) => text.push(c),
+                    None => text.push_str(&rest[..=end]),
+                }
+                rest = &rest[end + 1..];
+            }
+            Some(end) => rest = &rest[end + 1..],
+            None => {
+                text.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    Cow::Owned(text)
+}
+
+/// char of an entity of WebVTT
+fn entity(name: &str) -> Option<char> {
+    let c = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "lrm" => '\u{200e}',
+        "rlm" => '\u{200f}',
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// count text of cues of an SRT or WebVTT file
+///
+/// a cue is a block of lines separated by empty lines with a timing line (`... --> ...`), and its
+/// text is the lines after the timing line. sequence numbers, identifiers and timings of cues,
+/// and blocks without timings (the `WEBVTT` header, `NOTE`, `STYLE` and `REGION`) are not
+/// counted. markup is removed by [`strip_markup`](fn.strip_markup.html)
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::subtitle::count_subtitles;
+/// use bicycle_book_wordcount::CountConfig;
+/// let srt = "1\n00:00:01,000 --> 00:00:02,000\n<i>Hello</i> there\n\n\
+///            2\n00:00:03,000 --> 00:00:04,000\nhello again\n";
+/// let config = CountConfig::default().ignore_case(true);
+/// let freqs = count_subtitles(Cursor::new(srt), &config).unwrap();
+/// assert_eq!(freqs["hello"], 2);
+/// assert_eq!(freqs.get("00"), None);
+/// assert_eq!(freqs.len(), 3);
+/// ```
+pub fn count_subtitles(
+    input: impl BufRead,
+    config: &CountConfig,
+) -> Result<Frequencies, CountError> {
+    let mut tally = Tally::new(config);
+    // a timing line was read in the current block
+    let mut timed = false;
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        if line.trim().is_empty() {
+            timed = false;
+        } else if timed {
+            tally.line(&strip_markup(line))?;
+        } else if line.contains("-->") {
+            timed = true;
+        }
+        Ok::<_, CountError>(())
+    })?;
+    Ok(tally.finish())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CountOption;
+    use std::io::Cursor;
+
+    #[test]
+    fn strip_markup_works() {
+        assert_eq!(
+            strip_markup("<v Roger Bingham><c.yellow>We</c> are <00:00:01.500>here"),
+            "We are here"
+        );
+        assert_eq!(strip_markup("a < b &unknown; c &"), "a < b &unknown; c &");
+        assert_eq!(strip_markup("&lt;tag&gt;"), "<tag>");
+    }
+
+    #[test]
+    fn vtt_blocks_are_skipped() {
+        let vtt = "\u{feff}WEBVTT - title\n\n\
+                   NOTE a comment\nover lines\n\n\
+                   STYLE\n::cue { color: red }\n\n\
+                   intro\n00:01.000 --> 00:04.000 align:start\n- Never\n- drink &amp; drive\n\n\
+                   00:05.000 --> 00:06.000\nnever\n";
+        let config = CountConfig::new(CountOption::Line);
+        let freqs = count_subtitles(Cursor::new(vtt), &config).unwrap();
+        let mut lines: Vec<_> = freqs.keys().map(String::as_str).collect();
+        lines.sort_unstable();
+        assert_eq!(lines, vec!["- Never", "- drink & drive", "never"]);
+    }
+}
@@ -0,0 +1,540 @@
+//! accumulator of a counting pass
+
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::time::Instant;
+
+use crate::case::Spellings;
+use crate::config::line_key;
+use crate::decode::strip_bom;
+use crate::error::{CountError, Limit};
+use crate::phrase::Phrases;
+use crate::scope::Scope;
+use crate::segment::needs_segmentation;
+use crate::{tokens, word_regex, CountConfig, CountOption, Frequencies};
+
+/// finds keys of tokens as configured
+pub(crate) struct Scanner {
+    config: CountConfig,
+    /// regex of words, of the word chars of `config` if configured
+    re: Regex,
+    scope: Scope,
+    phrases: Phrases,
+    /// normalized stop words
+    stop_words: HashSet<String>,
+    /// normalized allowed words, all are allowed if `None`
+    allowed_words: Option<HashSet<String>>,
+    /// a line has been scanned
+    started: bool,
+    /// lines scanned of the input
+    lines: usize,
+    /// scanning the last line was stopped by the time limit
+    timed_out: bool,
+}
+
+impl Scanner {
+    pub(crate) fn new(config: &CountConfig) -> Self {
+        Scanner {
+            config: config.clone(),
+            re: config.word_chars.clone().unwrap_or_else(word_regex),
+            scope: Scope::new(config),
+            phrases: Phrases::new(config),
+            stop_words: config
+                .stop_words
+                .iter()
+                .map(|word| config.normalize(word).into_owned())
+                .collect(),
+            allowed_words: config.allowed_words.as_ref().map(|words| {
+                words
+                    .iter()
+                    .map(|word| config.normalize(word).into_owned())
+                    .collect()
+            }),
+            started: false,
+            lines: 0,
+            timed_out: false,
+        }
+    }
+
+    /// start scanning another input
+    pub(crate) fn reset(&mut self) {
+        self.scope = Scope::new(&self.config);
+        self.started = false;
+        self.lines = 0;
+    }
+
+    /// an error if scanning the last line was stopped by the time limit of the configuration
+    pub(crate) fn check_time(&self) -> Result<(), CountError> {
+        match self.config.time_limit {
+            Some(limit) if self.timed_out => Err(CountError::TimeLimit {
+                line: self.lines,
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// call `f` with the key of a token made by another tokenizer, unless it is filtered out.
+    /// the token is normalized and filtered as a token of a line, but neither split nor scoped
+    pub(crate) fn token<'a>(&mut self, token: &'a str, mut f: impl FnMut(&str, &'a str)) {
+        self.lines += 1;
+        self.timed_out = false;
+        let key = self.config.normalize(token);
+        let allowed_words = self.allowed_words.as_ref();
+        if let Some(key) = accept(&self.config, &self.stop_words, allowed_words, &key) {
+            f(&key, token)
+        }
+    }
+
+    /// call `f` with the key and the original token of each token in a line.
+    /// a byte order mark at the start of the first line is removed unless `keep_bom`.
+    /// matches of patterns past the time limit are not scanned, see
+    /// [`check_time`](#method.check_time)
+    pub(crate) fn line<'a>(&mut self, line: &'a str, mut f: impl FnMut(&str, &'a str)) {
+        let Scanner {
+            config,
+            re,
+            scope,
+            phrases,
+            stop_words,
+            allowed_words,
+            started,
+            lines,
+            timed_out,
+        } = self;
+        *lines += 1;
+        *timed_out = false;
+        let deadline = config.time_limit.map(|limit| Instant::now() + limit);
+        let late = || deadline.is_some_and(|deadline| Instant::now() > deadline);
+        let line = if *started || config.keep_bom {
+            line
+        } else {
+            strip_bom(line)
+        };
+        *started = true;
+        let mut f = |key: &str, token: &'a str| {
+            if let Some(key) = accept(config, stop_words, allowed_words.as_ref(), key) {
+                f(&key, token)
+            }
+        };
+        scope.segments(line, |segment, _| match (&config.pattern, &config.option) {
+            _ if *timed_out => {}
+            (Some((pattern, group)), _) => {
+                for caps in pattern.captures_iter(segment) {
+                    if late() {
+                        *timed_out = true;
+                        break;
+                    }
+                    if let Some(m) = caps.get(*group) {
+                        let token = m.as_str();
+                        f(&config.normalize(token), token);
+                    }
+                }
+            }
+            (None, CountOption::Custom(pattern)) => {
+                for m in pattern.find_iter(segment) {
+                    if late() {
+                        *timed_out = true;
+                        break;
+                    }
+                    let token = m.as_str();
+                    f(&config.normalize(token), token);
+                }
+            }
+            (None, CountOption::Word) if !phrases.is_empty() => {
+                let mut words = Vec::new();
+                split_tokens(config, re, segment, |word, i| words.push((word, i)));
+                let keys: Vec<_> = words
+                    .iter()
+                    .map(|(word, _)| config.normalize(word))
+                    .collect();
+                phrases.group(segment, &words, &keys, &mut f);
+            }
+            (None, _) => split_tokens(config, re, segment, |token, _| {
+                f(&config.normalize(token), token)
+            }),
+        });
+    }
+}
+
+/// the key to count of a normalized `key`, `None` if it is not counted
+fn accept<'k>(
+    config: &CountConfig,
+    stop_words: &HashSet<String>,
+    allowed_words: Option<&HashSet<String>>,
+    key: &'k str,
+) -> Option<Cow<'k, str>> {
+    let key = match &config.transform {
+        Some(transform) => transform.apply(key)?,
+        None => Cow::Borrowed(key),
+    };
+    let key = match config.line_key_bytes {
+        Some(limit) if config.option == CountOption::Line => line_key(key, limit),
+        _ => key,
+    };
+    let key = match config.max_token {
+        Some((limit, policy)) => policy.apply(key, limit)?,
+        None => key,
+    };
+    let allowed = allowed_words.map_or(true, |words| words.contains(&*key));
+    let accepted = config
+        .filter
+        .as_ref()
+        .map_or(true, |filter| filter.accepts(&key));
+    let excluded = config.exclude_patterns.iter().any(|re| re.is_match(&key));
+    if allowed && accepted && !excluded && !stop_words.contains(&*key) {
+        Some(key)
+    } else {
+        None
+    }
+}
+
+/// same as `tokens`, but words are of the word chars of `config` (`re`), and split by its
+/// dictionary
+fn split_tokens<'a>(
+    config: &CountConfig,
+    re: &Regex,
+    segment: &'a str,
+    mut f: impl FnMut(&'a str, usize),
+) {
+    let mut f = |token: &'a str, i| match &config.dictionary {
+        Some(dict) if config.option == CountOption::Word && needs_segmentation(token) => {
+            for word in dict.segment(token) {
+                f(word, i + (word.as_ptr() as usize - token.as_ptr() as usize));
+            }
+        }
+        _ => f(token, i),
+    };
+    match config.word_chars {
+        // not the fast path of ASCII lines, which is of `\w`
+        Some(_) if config.option == CountOption::Word => {
+            for m in re.find_iter(segment) {
+                f(m.as_str(), m.start());
+            }
+        }
+        _ => tokens(re, segment, &config.option, f),
+    }
+}
+
+/// counts tokens of lines as configured
+pub(crate) struct Tally {
+    scanner: Scanner,
+    freqs: Frequencies,
+    spellings: Option<Spellings>,
+    /// report keys by their preferred spellings
+    rekey: bool,
+    /// bytes of buffers of keys of `freqs`, for the limit of memory
+    key_bytes: usize,
+}
+
+impl Tally {
+    pub(crate) fn new(config: &CountConfig) -> Self {
+        Tally::with_capacity(config, 0)
+    }
+
+    /// a tally with room for `capacity` distinct keys
+    pub(crate) fn with_capacity(config: &CountConfig, capacity: usize) -> Self {
+        let track = config.ignore_case && config.original_case;
+        Tally {
+            scanner: Scanner::new(config),
+            freqs: Frequencies::with_capacity(capacity),
+            spellings: if track {
+                Some(Spellings::default())
+            } else {
+                None
+            },
+            rekey: track,
+            key_bytes: 0,
+        }
+    }
+
+    /// a tally which also records original spellings of keys, for `finish_with_spellings`
+    pub(crate) fn with_spellings(config: &CountConfig) -> Self {
+        let mut tally = Tally::new(config);
+        tally.spellings.get_or_insert_with(Spellings::default);
+        tally
+    }
+
+    /// count tokens of a line
+    pub(crate) fn line(&mut self, line: &str) -> Result<(), CountError> {
+        self.line_with(line, |_| {})
+    }
+
+    /// count tokens of a line, and call `f` with each key
+    pub(crate) fn line_with(
+        &mut self,
+        line: &str,
+        mut f: impl FnMut(&str),
+    ) -> Result<(), CountError> {
+        self.line_with_tokens(line, |key, _| f(key))
+    }
+
+    /// count tokens of a line, and call `f` with each key and the original token,
+    /// a slice of `line`
+    pub(crate) fn line_with_tokens<'a>(
+        &mut self,
+        line: &'a str,
+        f: impl FnMut(&str, &'a str),
+    ) -> Result<(), CountError> {
+        self.line_weighted(line, 1, f)
+    }
+
+    /// count each token of a line `weight` times, and call `f` with each key and the original
+    /// token. nothing is counted with weight 0, but the line is scanned, so that it opens or
+    /// closes scopes such as code blocks and is numbered
+    pub(crate) fn line_weighted<'a>(
+        &mut self,
+        line: &'a str,
+        weight: usize,
+        f: impl FnMut(&str, &'a str),
+    ) -> Result<(), CountError> {
+        match self.scanner.config.limits.line {
+            Some(n) if line.len() > n => {
+                return Err(CountError::Limit {
+                    limit: Limit::Line(n),
+                    // the line is counted by the scanner
+                    line: self.scanner.lines + 1,
+                });
+            }
+            _ => {}
+        }
+        if weight == 0 {
+            self.scanner.line(line, |_, _| {});
+            return self.scanner.check_time();
+        }
+        self.add(weight, |scanner, g| scanner.line(line, g), f)
+    }
+
+    /// count a token made by another tokenizer, see `Scanner::token`. errors of limits tell the
+    /// number of the token as the line
+    pub(crate) fn token(&mut self, token: &str) -> Result<(), CountError> {
+        self.add(1, |scanner, g| scanner.token(token, g), |_, _| {})
+    }
+
+    /// number a line without counting anything of it, such as a line without a token to
+    /// [`token`](#method.token), so that errors of limits tell the line of the input
+    pub(crate) fn skip_line(&mut self) {
+        self.scanner.lines += 1;
+    }
+
+    /// count each key found by `scan` `weight` times, and call `f` with each key and the
+    /// original token
+    fn add<'a>(
+        &mut self,
+        weight: usize,
+        scan: impl FnOnce(&mut Scanner, &mut dyn FnMut(&str, &'a str)),
+        mut f: impl FnMut(&str, &'a str),
+    ) -> Result<(), CountError> {
+        let Tally {
+            scanner,
+            freqs,
+            spellings,
+            key_bytes,
+            ..
+        } = self;
+        let policy = scanner.config.overflow;
+        let max_keys = scanner.config.max_keys;
+        let limits = scanner.config.limits;
+        let mut overflowed = None;
+        let mut over_limit = None;
+        scan(scanner, &mut |key, token| {
+            // the rest of a line after an error is not counted
+            if overflowed.is_some() || over_limit.is_some() {
+                return;
+            }
+            match freqs.get_mut(key) {
+                Some(count) => match policy.add(*count, weight) {
+                    Some(n) => *count = n,
+                    None => {
+                        overflowed = Some(key.to_string());
+                        return;
+                    }
+                },
+                None => {
+                    freqs.insert(key.to_string(), weight);
+                    *key_bytes += key.len();
+                    if let Some(max) = max_keys {
+                        if freqs.len() > max {
+                            evict(freqs, spellings, max - max / 4);
+                            *key_bytes = freqs.keys().map(String::len).sum();
+                        }
+                    }
+                    over_limit = match (limits.keys, limits.memory) {
+                        (Some(n), _) if freqs.len() > n => Some(Limit::Keys(n)),
+                        (_, Some(n)) if *key_bytes + freqs.table_bytes() > n => {
+                            Some(Limit::Memory(n))
+                        }
+                        _ => None,
+                    };
+                    if over_limit.is_some() {
+                        // nor is the key over the limit, so that counts are within it
+                        freqs.remove(key);
+                        *key_bytes -= key.len();
+                        return;
+                    }
+                }
+            }
+            if let Some(spellings) = spellings {
+                spellings.add(key, token, weight);
+            }
+            f(key, token);
+        });
+        match (overflowed, over_limit) {
+            (Some(token), _) => Err(CountError::Overflow(token)),
+            (None, Some(limit)) => Err(CountError::Limit {
+                limit,
+                line: scanner.lines,
+            }),
+            (None, None) => scanner.check_time(),
+        }
+    }
+
+    /// counts so far, keyed before `original_case` is applied
+    pub(crate) fn frequencies(&self) -> &Frequencies {
+        &self.freqs
+    }
+
+    /// finish counting of an input, and start counting another one with the same scanner
+    pub(crate) fn restart(&mut self) -> Frequencies {
+        self.scanner.reset();
+        self.key_bytes = 0;
+        let freqs = mem::take(&mut self.freqs);
+        match &mut self.spellings {
+            Some(spellings) if self.rekey => {
+                let spellings = mem::take(spellings);
+                freqs
+                    .into_map()
+                    .into_iter()
+                    .map(|(key, count)| (preferred(&spellings, key), count))
+                    .collect()
+            }
+            _ => freqs,
+        }
+    }
+
+    pub(crate) fn finish(self) -> Frequencies {
+        self.finish_with(HashMap::<String, ()>::new()).0
+    }
+
+    /// finish counting, along with a map from keys to some data gathered by `line_with`
+    pub(crate) fn finish_with<V>(
+        self,
+        side: HashMap<String, V>,
+    ) -> (Frequencies, HashMap<String, V>) {
+        match self.spellings {
+            Some(spellings) if self.rekey => {
+                let rekey = |key| preferred(&spellings, key);
+                let freqs = self
+                    .freqs
+                    .into_map()
+                    .into_iter()
+                    .map(|(key, count)| (rekey(key), count))
+                    .collect::<HashMap<_, _>>();
+                let side = side
+                    .into_iter()
+                    .map(|(key, value)| (rekey(key), value))
+                    .collect();
+                (freqs.into(), side)
+            }
+            _ => (self.freqs, side),
+        }
+    }
+
+    /// finish counting, along with counts of original spellings of each key. keys are the
+    /// same as of the result. empty unless made by `with_spellings`
+    pub(crate) fn finish_with_spellings(self) -> (Frequencies, HashMap<String, Frequencies>) {
+        let variants = match &self.spellings {
+            Some(spellings) => spellings.to_frequencies(),
+            None => HashMap::new(),
+        };
+        self.finish_with(variants)
+    }
+}
+
+/// the preferred spelling of `key`, or itself
+fn preferred(spellings: &Spellings, key: String) -> String {
+    match spellings.preferred(&key) {
+        Some(spelling) => spelling.to_string(),
+        None => key,
+    }
+}
+
+/// keep the `keep` most frequent keys (ties broken by the smallest key)
+fn evict(freqs: &mut Frequencies, spellings: &mut Option<Spellings>, keep: usize) {
+    let evicted: Vec<String> = freqs.sorted()[keep..]
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .collect();
+    for key in &evicted {
+        freqs.remove(key);
+        if let Some(spellings) = spellings {
+            spellings.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Limits;
+
+    #[test]
+    fn lines_of_weight_0_are_scanned() {
+        let config = CountConfig::default().skip_code_blocks(true);
+        let mut tally = Tally::new(&config);
+        for (line, weight) in [("```", 0), ("code", 1), ("```", 1), ("text", 1)] {
+            tally.line_weighted(line, weight, |_, _| {}).unwrap();
+        }
+        assert_eq!(tally.scanner.lines, 4);
+        let freqs = tally.finish();
+        assert_eq!(freqs.get("code"), None);
+        assert_eq!(freqs["text"], 1);
+    }
+
+    #[test]
+    fn max_keys_evicts_least_frequent() {
+        let config = CountConfig::default()
+            .max_keys(4)
+            .ignore_case(true)
+            .original_case(true);
+        let mut tally = Tally::new(&config);
+        tally.line("A A A b b c d").unwrap();
+        assert_eq!(tally.frequencies().len(), 4);
+        tally.line("e").unwrap();
+        // 5 keys, the least frequent 2 of them are evicted
+        let mut keys: Vec<_> = tally.frequencies().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["a", "b", "c"]);
+        tally.line("d").unwrap();
+
+        let freqs = tally.finish();
+        assert_eq!(freqs["A"], 3);
+        assert_eq!(freqs["d"], 1);
+        assert_eq!(freqs.len(), 4);
+    }
+
+    #[test]
+    fn counting_stops_at_a_limit() {
+        let config = CountConfig::default().limits(Limits::new().keys(2));
+        let mut tally = Tally::new(&config);
+        tally.line("a b a").unwrap();
+        match tally.line("b c a b") {
+            Err(CountError::Limit { limit, line }) => {
+                assert_eq!((limit, line), (Limit::Keys(2), 2))
+            }
+            result => panic!("{:?}", result),
+        }
+        let mut exp = HashMap::new();
+        exp.insert("a".to_string(), 2);
+        exp.insert("b".to_string(), 2);
+        assert_eq!(**tally.frequencies(), exp);
+
+        let config = CountConfig::default().limits(Limits::new().memory(1));
+        let mut tally = Tally::new(&config);
+        assert!(tally.line("a a").is_err());
+        assert!(tally.frequencies().is_empty());
+        assert_eq!(tally.key_bytes, 0);
+    }
+}
@@ -0,0 +1,444 @@
+//! counts per period of timestamped lines such as logs, see [`count_by_time`](fn.count_by_time.html)
+
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+
+use crate::decode::{read_lines, Transcoder};
+use crate::tally::Tally;
+use crate::{CountConfig, CountError, Frequencies};
+
+/// date and time of a line, without time zone. fields missing in the format are 0 for the
+/// year and the time, and 1 for the month and the day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Timestamp {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl Default for Timestamp {
+    fn default() -> Self {
+        Timestamp {
+            year: 0,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+        }
+    }
+}
+
+/// `2024-01-02 03:04:05`
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// length of time of a bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Period {
+    Minute,
+    Hour,
+    Day,
+}
+
+impl Period {
+    /// start of the period containing `time`
+    pub fn start(self, time: Timestamp) -> Timestamp {
+        let mut start = Timestamp { second: 0, ..time };
+        if self != Period::Minute {
+            start.minute = 0;
+        }
+        if self == Period::Day {
+            start.hour = 0;
+        }
+        start
+    }
+
+    /// the period starting at `start`, e.g. `2024-01-02 03:00` for an hour or `2024-01-02`
+    /// for a day
+    pub fn label(self, start: Timestamp) -> String {
+        let text = start.to_string();
+        match self {
+            Period::Minute | Period::Hour => text[..16].to_string(),
+            Period::Day => text[..10].to_string(),
+        }
+    }
+}
+
+/// `minute`, `hour` or `day`
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "minute" => Ok(Period::Minute),
+            "hour" => Ok(Period::Hour),
+            "day" => Ok(Period::Day),
+            _ => Err(format!("unknown period {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Item {
+    Literal(char),
+    /// a space matches any number of spaces, so that `%e` matches days padded by spaces
+    Space,
+    Year,
+    ShortYear,
+    Month,
+    MonthName,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    /// digits of a fraction of a second
+    Fraction,
+    /// `Z`, or an offset like `+0900` or `+09:00`
+    Zone,
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// format of timestamps at the start of lines, in a subset of the directives of `strftime`
+///
+/// `%Y` (4 digits), `%y` (2 digits, of 2000s), `%m`, `%b` (`Jan` to `Dec`), `%d`, `%e`, `%H`,
+/// `%M`, `%S`, `%f` (fraction of a second), `%z` (`Z` or an offset, ignored), `%F`
+/// (`%Y-%m-%d`), `%T` (`%H:%M:%S`) and `%%`. other chars match themselves
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::timeline::TimeFormat;
+/// let format = TimeFormat::new("[%d/%b/%Y:%T %z]").unwrap();
+/// let (time, rest) = format.parse_prefix("[10/Oct/2024:13:55:36 +0900] GET /").unwrap();
+/// assert_eq!(time.to_string(), "2024-10-10 13:55:36");
+/// assert_eq!(rest, " GET /");
+/// assert!(TimeFormat::new("%Q").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeFormat {
+    items: Vec<Item>,
+}
+
+impl TimeFormat {
+    /// parse `pattern`, error for an unknown directive
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let mut items = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                items.push(if c == ' ' {
+                    Item::Space
+                } else {
+                    Item::Literal(c)
+                });
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => items.push(Item::Year),
+                Some('y') => items.push(Item::ShortYear),
+                Some('m') => items.push(Item::Month),
+                Some('b') => items.push(Item::MonthName),
+                Some('d') | Some('e') => items.push(Item::Day),
+                Some('H') => items.push(Item::Hour),
+                Some('M') => items.push(Item::Minute),
+                Some('S') => items.push(Item::Second),
+                Some('f') => items.push(Item::Fraction),
+                Some('z') => items.push(Item::Zone),
+                Some('F') => items.extend_from_slice(&[
+                    Item::Year,
+                    Item::Literal('-'),
+                    Item::Month,
+                    Item::Literal('-'),
+                    Item::Day,
+                ]),
+                Some('T') => items.extend_from_slice(&[
+                    Item::Hour,
+                    Item::Literal(':'),
+                    Item::Minute,
+                    Item::Literal(':'),
+                    Item::Second,
+                ]),
+                Some('%') => items.push(Item::Literal('%')),
+                Some(c) => return Err(format!("unknown directive %{}", c)),
+                None => return Err("% at the end".to_string()),
+            }
+        }
+        Ok(TimeFormat { items })
+    }
+
+    /// timestamp at the start of `line` and the rest of the line, `None` if the line does not
+    /// start with a valid timestamp
+    pub fn parse_prefix<'a>(&self, line: &'a str) -> Option<(Timestamp, &'a str)> {
+        let mut time = Timestamp::default();
+        let mut rest = line;
+        for item in &self.items {
+            match *item {
+                Item::Literal(c) => rest = rest.strip_prefix(c)?,
+                Item::Space => {
+                    rest = rest.strip_prefix(' ')?;
+                    rest = rest.trim_start_matches(' ');
+                }
+                Item::Year => time.year = digits(&mut rest, 4, 4)? as u16,
+                Item::ShortYear => time.year = 2000 + digits(&mut rest, 2, 2)? as u16,
+                Item::Month => time.month = digits(&mut rest, 1, 2)? as u8,
+                Item::MonthName => {
+                    let i = MONTHS.iter().position(|name| rest.starts_with(name))?;
+                    time.month = i as u8 + 1;
+                    rest = &rest[3..];
+                }
+                Item::Day => {
+                    rest = rest.trim_start_matches(' ');
+                    time.day = digits(&mut rest, 1, 2)? as u8;
+                }
+                Item::Hour => time.hour = digits(&mut rest, 1, 2)? as u8,
+                Item::Minute => time.minute = digits(&mut rest, 2, 2)? as u8,
+                Item::Second => time.second = digits(&mut rest, 2, 2)? as u8,
+                Item::Fraction => {
+                    digits(&mut rest, 1, usize::MAX)?;
+                }
+                Item::Zone => {
+                    if let Some(after) = rest.strip_prefix('Z') {
+                        rest = after;
+                    } else {
+                        rest = rest.strip_prefix(&['+', '-'][..])?;
+                        digits(&mut rest, 2, 2)?;
+                        rest = rest.strip_prefix(':').unwrap_or(rest);
+                        digits(&mut rest, 2, 2)?;
+                    }
+                }
+            }
+        }
+        let valid = (1..=12).contains(&time.month)
+            && (1..=31).contains(&time.day)
+            && time.hour < 24
+            && time.minute < 60
+            && time.second <= 60;
+        if valid {
+            Some((time, rest))
+        } else {
+            None
+        }
+    }
+}
+
+/// value of `min` to `max` ASCII digits at the start of `text`, which are removed
+fn digits(text: &mut &str, min: usize, max: usize) -> Option<u32> {
+    let len = text
+        .bytes()
+        .take(max)
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    if len < min {
+        return None;
+    }
+    let value = text[..len].bytes().fold(0u32, |n, b| {
+        n.saturating_mul(10).saturating_add((b - b'0') as u32)
+    });
+    *text = &text[len..];
+    Some(value)
+}
+
+/// counts of each period, see [`count_by_time`](fn.count_by_time.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    pub period: Period,
+    /// counts by the start of each period, in order of time. periods without lines are not
+    /// included
+    pub counts: BTreeMap<Timestamp, Frequencies>,
+    /// count of lines before the first timestamp, which are not counted
+    pub undated: usize,
+}
+
+impl Timeline {
+    /// count of `token` in each period
+    pub fn series(&self, token: &str) -> Vec<(Timestamp, usize)> {
+        self.counts
+            .iter()
+            .map(|(&start, freqs)| (start, freqs.get(token).copied().unwrap_or(0)))
+            .collect()
+    }
+
+    /// counts of all periods
+    pub fn total(&self) -> Frequencies {
+        let mut total = Frequencies::new();
+        for freqs in self.counts.values() {
+            total.merge(freqs);
+        }
+        total
+    }
+}
+
+/// count tokens of lines per `period` of the timestamps at the start of lines, in a pass.
+/// the timestamp itself is not counted. lines without a timestamp, e.g. of stack traces, are
+/// counted in the period of the previous line
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::timeline::{count_by_time, Period, TimeFormat};
+/// use bicycle_book_wordcount::CountConfig;
+/// let log = "2024-10-10 13:55:36 error disk\n\
+///            2024-10-10 13:58:01 error net\n\
+///            2024-10-10 14:02:00 info ok\n";
+/// let format = TimeFormat::new("%F %T").unwrap();
+/// let config = CountConfig::default();
+/// let timeline = count_by_time(Cursor::new(log), &config, &format, Period::Hour).unwrap();
+/// let errors: Vec<_> = timeline
+///     .series("error")
+///     .into_iter()
+///     .map(|(start, n)| (Period::Hour.label(start), n))
+///     .collect();
+/// assert_eq!(
+///     errors,
+///     vec![("2024-10-10 13:00".to_string(), 2), ("2024-10-10 14:00".to_string(), 0)]
+/// );
+/// ```
+pub fn count_by_time(
+    input: impl BufRead,
+    config: &CountConfig,
+    format: &TimeFormat,
+    period: Period,
+) -> Result<Timeline, CountError> {
+    let mut tally = Tally::new(config);
+    let mut counts = BTreeMap::new();
+    let mut current = None;
+    let mut undated = 0;
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        let text = match format.parse_prefix(line) {
+            Some((time, rest)) => {
+                let start = period.start(time);
+                match current {
+                    Some(previous) if previous != start => {
+                        add_period(&mut counts, previous, tally.restart())
+                    }
+                    _ => {}
+                }
+                current = Some(start);
+                rest
+            }
+            None if current.is_none() => {
+                undated += 1;
+                return Ok(());
+            }
+            None => line,
+        };
+        tally.line(text)
+    })?;
+    if let Some(last) = current {
+        add_period(&mut counts, last, tally.finish());
+    }
+    Ok(Timeline {
+        period,
+        counts,
+        undated,
+    })
+}
+
+/// add `freqs` to the period of `start`, which may be counted already if lines are not in
+/// order of time
+fn add_period(counts: &mut BTreeMap<Timestamp, Frequencies>, start: Timestamp, freqs: Frequencies) {
+    match counts.entry(start) {
+        Entry::Vacant(entry) => {
+            entry.insert(freqs);
+        }
+        Entry::Occupied(mut entry) => entry.get_mut().merge(&freqs),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn parse_prefix_works() {
+        let syslog = TimeFormat::new("%b %e %T").unwrap();
+        let (time, rest) = syslog.parse_prefix("Oct  9 07:05:01 host cron").unwrap();
+        assert_eq!(time.to_string(), "0000-10-09 07:05:01");
+        assert_eq!(rest, " host cron");
+        let iso = TimeFormat::new("%FT%T.%f%z").unwrap();
+        let (time, rest) = iso.parse_prefix("2024-10-10T01:02:03.456+09:00 x").unwrap();
+        assert_eq!(time.to_string(), "2024-10-10 01:02:03");
+        assert_eq!(rest, " x");
+        assert!(iso.parse_prefix("2024-13-10T01:02:03.4Z").is_none());
+        assert!(iso.parse_prefix("at 2024-10-10T01:02:03.4Z").is_none());
+        assert_eq!(
+            TimeFormat::new("%y%m%d 100%%")
+                .unwrap()
+                .parse_prefix("241001 100%"),
+            Some((
+                Timestamp {
+                    year: 2024,
+                    month: 10,
+                    ..Timestamp::default()
+                },
+                ""
+            ))
+        );
+        assert!(TimeFormat::new("%").is_err());
+    }
+
+    #[test]
+    fn periods_work() {
+        let time = Timestamp {
+            year: 2024,
+            month: 10,
+            day: 10,
+            hour: 13,
+            minute: 55,
+            second: 36,
+        };
+        assert_eq!(
+            Period::Minute.label(Period::Minute.start(time)),
+            "2024-10-10 13:55"
+        );
+        assert_eq!(
+            Period::Hour.label(Period::Hour.start(time)),
+            "2024-10-10 13:00"
+        );
+        assert_eq!(Period::Day.label(Period::Day.start(time)), "2024-10-10");
+        assert_eq!("day".parse(), Ok(Period::Day));
+        assert!("week".parse::<Period>().is_err());
+    }
+
+    #[test]
+    fn count_by_time_works() {
+        let log = "header\n\
+                   2024-10-10 error a\n\
+                   \tat trace\n\
+                   2024-10-11 error b\n\
+                   2024-10-10 error c\n";
+        let format = TimeFormat::new("%F").unwrap();
+        let config = CountConfig::default();
+        let timeline = count_by_time(Cursor::new(log), &config, &format, Period::Day).unwrap();
+        assert_eq!(timeline.undated, 1);
+        assert_eq!(timeline.counts.len(), 2);
+        let days: Vec<_> = timeline
+            .series("error")
+            .into_iter()
+            .map(|(_, n)| n)
+            .collect();
+        assert_eq!(days, vec![2, 1]);
+        assert_eq!(timeline.series("trace")[0].1, 1);
+        assert_eq!(timeline.total().get("header"), None);
+        assert_eq!(timeline.total()["error"], 3);
+    }
+}
@@ -0,0 +1,168 @@
+//! spans of counting, for embedders observing it inside a larger instrumented service, see
+//! [`set_subscriber`](fn.set_subscriber.html)
+//!
+//! a span is closed for each input counted by [`try_count_with`](../fn.try_count_with.html)
+//! (and so [`count`](../fn.count.html) and the like) while a subscriber is set. without one,
+//! counting is not slowed down by any timing.
+
+use std::io::{self, BufRead, Read};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::decode::{read_lines, Transcoder};
+use crate::tally::Tally;
+use crate::{CountConfig, CountError, Frequencies};
+
+/// a receiver of spans, such as a bridge to `tracing` or metrics of the service
+pub trait Subscriber: Send + Sync {
+    /// called with each span when it is closed, on the thread which counted
+    fn on_close(&self, span: &Span);
+}
+
+/// what counting one input took
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    /// name of the operation, such as `"count"`
+    pub name: &'static str,
+    /// bytes read from the input, before decoding
+    pub bytes: usize,
+    /// lines counted, not including lines skipped by the decode policy
+    pub lines: usize,
+    /// lines skipped by [`DecodePolicy::SkipLine`](../decode/enum.DecodePolicy.html#variant.SkipLine)
+    pub skipped: usize,
+    /// distinct keys counted
+    pub unique: usize,
+    /// time of the phase of reading and tallying lines
+    pub read: Duration,
+    /// time of the phase of finishing the counts (such as merging spellings)
+    pub finish: Duration,
+}
+
+static SUBSCRIBER: RwLock<Option<Arc<dyn Subscriber>>> = RwLock::new(None);
+
+/// send spans of counting in any thread to `subscriber`, instead of the one set before
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use std::sync::{Arc, Mutex};
+/// use bicycle_book_wordcount::trace::{self, Span, Subscriber};
+/// use bicycle_book_wordcount::{count, CountOption};
+/// struct Spans(Mutex<Vec<Span>>);
+/// impl Subscriber for Spans {
+///     fn on_close(&self, span: &Span) {
+///         self.0.lock().unwrap().push(span.clone());
+///     }
+/// }
+/// let spans = Arc::new(Spans(Mutex::new(Vec::new())));
+/// trace::set_subscriber(spans.clone());
+/// count(Cursor::new("aa bb aa\ncc\n"), CountOption::Word);
+/// trace::clear_subscriber();
+/// let spans = spans.0.lock().unwrap();
+/// assert_eq!((spans[0].bytes, spans[0].lines, spans[0].unique), (12, 2, 3));
+/// ```
+pub fn set_subscriber(subscriber: Arc<dyn Subscriber>) {
+    *SUBSCRIBER.write().unwrap_or_else(|e| e.into_inner()) = Some(subscriber);
+}
+
+/// stop sending spans
+pub fn clear_subscriber() {
+    *SUBSCRIBER.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// the subscriber set, if any
+pub(crate) fn subscriber() -> Option<Arc<dyn Subscriber>> {
+    SUBSCRIBER.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// `try_count_with`, closing a span to `subscriber` when done (also when it fails)
+pub(crate) fn count_with(
+    subscriber: &dyn Subscriber,
+    input: impl BufRead,
+    config: &CountConfig,
+) -> Result<Frequencies, CountError> {
+    let start = Instant::now();
+    let mut input = Bytes { inner: input, n: 0 };
+    let mut tally = Tally::new(config);
+    let mut lines = 0;
+    let skipped = read_lines(Transcoder::new(&mut input), config.reading(), |line| {
+        lines += 1;
+        tally.line(line)
+    });
+    let read = start.elapsed();
+    let mut span = Span {
+        name: "count",
+        bytes: input.n,
+        lines,
+        skipped: 0,
+        unique: 0,
+        read,
+        finish: Duration::default(),
+    };
+    let result = skipped.map(|skipped| {
+        span.skipped = skipped;
+        let freqs = tally.finish();
+        span.unique = freqs.len();
+        span.finish = start.elapsed() - read;
+        freqs
+    });
+    subscriber.on_close(&span);
+    result
+}
+
+/// a reader counting the bytes read or consumed of `inner`
+struct Bytes<R> {
+    inner: R,
+    n: usize,
+}
+
+impl<R: Read> Read for Bytes<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.n += n;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Bytes<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.n += amt;
+        self.inner.consume(amt);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::decode::DecodePolicy;
+    use crate::{try_count_with, CountOption};
+    use std::sync::Mutex;
+
+    struct Spans(Mutex<Vec<Span>>);
+
+    impl Subscriber for Spans {
+        fn on_close(&self, span: &Span) {
+            self.0.lock().unwrap().push(span.clone());
+        }
+    }
+
+    #[test]
+    fn skipped_lines_are_reported() {
+        let spans = Arc::new(Spans(Mutex::new(Vec::new())));
+        set_subscriber(spans.clone());
+        let input = b"tracing b\xffd\nline\nline\n".to_vec();
+        let config = CountConfig::new(CountOption::Line).invalid_input(DecodePolicy::SkipLine);
+        let freqs = try_count_with(&input[..], &config).unwrap();
+        clear_subscriber();
+        assert_eq!(freqs["line"], 2);
+        // other tests may count while the subscriber is set
+        let spans = spans.0.lock().unwrap();
+        let span = spans.iter().find(|span| span.bytes == input.len()).unwrap();
+        assert_eq!((span.lines, span.skipped, span.unique), (2, 1, 1));
+    }
+}
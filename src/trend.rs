@@ -0,0 +1,120 @@
+//! frequency trend over the position in input, see [`count_trend`](fn.count_trend.html)
+
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use crate::decode::{read_lines, Transcoder};
+use crate::tally::Tally;
+use crate::{CountConfig, CountError, Frequencies};
+
+/// counts of top tokens in each of equal-sized buckets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trend {
+    /// counts of the whole input
+    pub freqs: Frequencies,
+    /// top tokens in the order of [`Frequencies::top`](../frequencies/struct.Frequencies.html#method.top),
+    /// with the count in each bucket
+    pub tokens: Vec<(String, Vec<usize>)>,
+}
+
+impl Trend {
+    /// counts per bucket of a token, if it is one of the top tokens
+    pub fn buckets(&self, token: &str) -> Option<&[usize]> {
+        self.tokens
+            .iter()
+            .find(|(t, _)| t == token)
+            .map(|(_, counts)| counts.as_slice())
+    }
+}
+
+/// split all tokens of input into `buckets` parts of (nearly) equal count of tokens, and count
+/// the `top` most frequent tokens in each part
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::trend::count_trend;
+/// use bicycle_book_wordcount::CountConfig;
+/// let input = Cursor::new("start a a\nb b b\nb a end");
+/// let trend = count_trend(input, &CountConfig::default(), 3, 2).unwrap();
+/// assert_eq!(trend.buckets("b"), Some(&[0, 3, 1][..]));
+/// assert_eq!(trend.buckets("a"), Some(&[2, 0, 1][..]));
+/// assert_eq!(trend.buckets("end"), None);
+/// ```
+///
+/// # Panics
+///
+/// `buckets` is 0
+pub fn count_trend(
+    input: impl BufRead,
+    config: &CountConfig,
+    buckets: usize,
+    top: usize,
+) -> Result<Trend, CountError> {
+    assert!(buckets > 0, "buckets must not be 0");
+    let mut tally = Tally::new(config);
+    // keys are interned, since all tokens are kept until the total is known
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    let mut sequence = Vec::new();
+
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        tally.line_with(line, |key| {
+            let next = ids.len();
+            let id = match ids.get(key) {
+                Some(&id) => id,
+                None => *ids.entry(key.to_string()).or_insert(next),
+            };
+            sequence.push(id);
+        })
+    })?;
+
+    let mut counts = vec![vec![0; buckets]; ids.len()];
+    let total = sequence.len();
+    for (i, &id) in sequence.iter().enumerate() {
+        counts[id][i * buckets / total] += 1;
+    }
+    let side: HashMap<_, _> = ids
+        .into_iter()
+        .map(|(key, id)| (key, std::mem::take(&mut counts[id])))
+        .collect();
+    let (freqs, mut side) = tally.finish_with(side);
+    let tokens = freqs
+        .top(top)
+        .into_iter()
+        .map(|(token, _)| (token.clone(), side.remove(token).unwrap_or_default()))
+        .collect();
+    Ok(Trend { freqs, tokens })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CountOption;
+    use std::io::Cursor;
+
+    #[test]
+    fn buckets_are_even() {
+        let input = Cursor::new("a b c d e f g");
+        let trend = count_trend(input, &CountConfig::new(CountOption::Char), 3, 10).unwrap();
+        let sums: Vec<usize> = (0..3)
+            .map(|b| trend.tokens.iter().map(|(_, c)| c[b]).sum())
+            .collect();
+        assert_eq!(sums, vec![5, 4, 4]);
+        assert_eq!(trend.tokens[0], (" ".to_string(), vec![2, 2, 2]));
+    }
+
+    #[test]
+    fn trend_of_empty_input() {
+        let trend = count_trend(Cursor::new(""), &CountConfig::default(), 4, 3).unwrap();
+        assert!(trend.tokens.is_empty());
+        assert!(trend.freqs.is_empty());
+    }
+
+    #[test]
+    fn trend_follows_original_case() {
+        let config = CountConfig::default().ignore_case(true).original_case(true);
+        let trend = count_trend(Cursor::new("Rust rust Rust go"), &config, 2, 1).unwrap();
+        assert_eq!(trend.tokens, vec![("Rust".to_string(), vec![2, 1])]);
+    }
+}
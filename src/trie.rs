@@ -0,0 +1,188 @@
+//! prefix trees of counted tokens, for autocomplete, see [`Trie`](struct.Trie.html)
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::{json, Frequencies};
+
+/// a node of a prefix tree of tokens by chars, with the count of the token ending at it and
+/// the total count of tokens under it (cumulative count)
+///
+/// children are in the order of chars, so the tree and its JSON are the same for the same
+/// counts.
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::trie::Trie;
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let freqs = count_str("car cart cart cat dog", CountOption::Word);
+/// let trie = Trie::of(&freqs);
+/// assert_eq!(trie.total(), 5);
+/// let ca = trie.get("ca").unwrap();
+/// assert_eq!((ca.count(), ca.total()), (0, 4));
+/// assert_eq!(
+///     trie.completions("ca", 2),
+///     vec![("cart".to_string(), 2), ("car".to_string(), 1)]
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trie {
+    count: usize,
+    total: usize,
+    children: BTreeMap<char, Trie>,
+}
+
+impl Trie {
+    /// an empty tree
+    pub fn new() -> Self {
+        Trie::default()
+    }
+
+    /// tree of the tokens of `freqs`
+    pub fn of(freqs: &Frequencies) -> Self {
+        let mut trie = Trie::new();
+        for (token, &count) in freqs.iter() {
+            trie.insert(token, count);
+        }
+        trie
+    }
+
+    /// add `count` occurrences of `token`. counts saturate at `usize::MAX`
+    pub fn insert(&mut self, token: &str, count: usize) {
+        let mut node = self;
+        node.total = node.total.saturating_add(count);
+        for c in token.chars() {
+            node = node.children.entry(c).or_default();
+            node.total = node.total.saturating_add(count);
+        }
+        node.count = node.count.saturating_add(count);
+    }
+
+    /// count of the token ending at this node
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// count of the tokens starting with the prefix of this node, including itself
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// the node of `prefix`, if a token starts with it
+    pub fn get(&self, prefix: &str) -> Option<&Trie> {
+        prefix
+            .chars()
+            .try_fold(self, |node, c| node.children.get(&c))
+    }
+
+    /// child nodes by their chars, in order
+    pub fn children(&self) -> impl Iterator<Item = (char, &Trie)> + '_ {
+        self.children.iter().map(|(&c, node)| (c, node))
+    }
+
+    /// the `n` most frequent tokens starting with `prefix` (relative to this node), with their
+    /// counts, in descending order of counts and then in order of tokens
+    pub fn completions(&self, prefix: &str, n: usize) -> Vec<(String, usize)> {
+        let mut found = Vec::new();
+        let mut stack: Vec<_> = self
+            .get(prefix)
+            .map(|node| (prefix.to_string(), node))
+            .into_iter()
+            .collect();
+        while let Some((prefix, node)) = stack.pop() {
+            if node.count > 0 {
+                found.push((prefix.clone(), node.count));
+            }
+            for (&c, child) in node.children.iter().rev() {
+                let mut prefix = prefix.clone();
+                prefix.push(c);
+                stack.push((prefix, child));
+            }
+        }
+        found.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        found.truncate(n);
+        found
+    }
+
+    /// write the tree as nested JSON objects, like
+    /// `{"count":0,"total":2,"children":{"a":{"count":2,"total":2}}}`. `children` is omitted
+    /// for leaves
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bicycle_book_wordcount::trie::Trie;
+    /// let mut trie = Trie::new();
+    /// trie.insert("ab", 1);
+    /// trie.insert("a", 2);
+    /// let mut out = Vec::new();
+    /// trie.write_json(&mut out).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(out).unwrap(),
+    ///     "{\"count\":0,\"total\":3,\"children\":{\"a\":{\"count\":2,\"total\":3,\
+    ///      \"children\":{\"b\":{\"count\":1,\"total\":1}}}}}\n"
+    /// );
+    /// ```
+    pub fn write_json(&self, mut out: impl Write) -> io::Result<()> {
+        self.write_node(&mut out)?;
+        out.write_all(b"\n")
+    }
+
+    fn write_node(&self, out: &mut impl Write) -> io::Result<()> {
+        write!(out, "{{\"count\":{},\"total\":{}", self.count, self.total)?;
+        if !self.children.is_empty() {
+            out.write_all(b",\"children\":{")?;
+            for (i, (&c, child)) in self.children.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b",")?;
+                }
+                write!(out, "{}:", json::string(c.encode_utf8(&mut [0; 4])))?;
+                child.write_node(out)?;
+            }
+            out.write_all(b"}")?;
+        }
+        out.write_all(b"}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{count_str, CountOption};
+
+    #[test]
+    fn totals_are_cumulative() {
+        let freqs = count_str("日本 日本語 日本語 日曜 a", CountOption::Word);
+        let trie = Trie::of(&freqs);
+        assert_eq!(trie.total(), freqs.total());
+        assert_eq!(trie.get("日").unwrap().total(), 4);
+        assert_eq!(trie.get("日本").unwrap().count(), 1);
+        assert_eq!(trie.get("日本").unwrap().total(), 3);
+        assert_eq!(trie.get("日本語x"), None);
+        let chars: Vec<_> = trie.children().map(|(c, _)| c).collect();
+        assert_eq!(chars, vec!['a', '日']);
+        assert_eq!(
+            trie.completions("日", 10),
+            vec![
+                ("日本語".to_string(), 2),
+                ("日曜".to_string(), 1),
+                ("日本".to_string(), 1)
+            ]
+        );
+        assert_eq!(trie.completions("x", 10), vec![]);
+    }
+
+    #[test]
+    fn json_is_escaped() {
+        let mut trie = Trie::new();
+        trie.insert("\"", 1);
+        let mut out = Vec::new();
+        trie.write_json(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"count\":0,\"total\":1,\"children\":{\"\\\"\":{\"count\":1,\"total\":1}}}\n"
+        );
+        assert_eq!(Trie::of(&Frequencies::new()), Trie::new());
+    }
+}
@@ -0,0 +1,276 @@
+//! folding of rare tokens into much more frequent ones within a small edit distance, to clean
+//! typos of OCR'd and user-generated text, see [`TypoFolding`](struct.TypoFolding.html)
+
+use std::collections::{HashMap, HashSet};
+
+use crate::Frequencies;
+
+/// Levenshtein distance between `a` and `b` in chars: the fewest insertions, deletions and
+/// substitutions of chars to turn one into the other
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::typo::levenshtein;
+/// assert_eq!(levenshtein("kitten", "sitting"), 3);
+/// assert_eq!(levenshtein("日本語", "日本"), 1);
+/// assert_eq!(levenshtein("", "abc"), 3);
+/// ```
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + (ca != cb) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// a token folded into another by [`TypoFolding::apply`](struct.TypoFolding.html#method.apply)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merge {
+    /// the rare token
+    pub from: String,
+    /// the frequent token it was added to
+    pub into: String,
+    /// count of `from`
+    pub count: usize,
+    pub distance: usize,
+}
+
+/// settings of folding typos
+///
+/// a token is folded into the nearest token within [`max_distance`](#method.max_distance)
+/// which is at least [`min_ratio`](#method.min_ratio) times as frequent, ties broken by the
+/// more frequent, then the smallest token. tokens folded into others do not receive any
+///
+/// # Examples
+///
+/// ```
+/// use bicycle_book_wordcount::typo::TypoFolding;
+/// use bicycle_book_wordcount::{count_str, CountOption};
+/// let text = "receive ".repeat(20) + "recieve reveive ship shop";
+/// let freqs = count_str(&text, CountOption::Word);
+/// let (folded, merges) = TypoFolding::new().apply(&freqs);
+/// assert_eq!(folded["receive"], 21);
+/// assert_eq!(folded.get("recieve"), Some(&1));
+/// assert_eq!(folded.get("reveive"), None);
+/// assert_eq!(merges[0].from, "reveive");
+/// assert_eq!(merges.len(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypoFolding {
+    max_distance: usize,
+    min_ratio: f64,
+    min_chars: usize,
+}
+
+impl Default for TypoFolding {
+    fn default() -> Self {
+        TypoFolding::new()
+    }
+}
+
+impl TypoFolding {
+    /// fold within distance 1 into tokens 10 times as frequent, tokens of 4 chars or more
+    pub fn new() -> Self {
+        TypoFolding {
+            max_distance: 1,
+            min_ratio: 10.0,
+            min_chars: 4,
+        }
+    }
+
+    /// the largest [`levenshtein`](fn.levenshtein.html) distance of a typo
+    pub fn max_distance(mut self, n: usize) -> Self {
+        self.max_distance = n;
+        self
+    }
+
+    /// how many times a token must be as frequent as a typo to receive it
+    pub fn min_ratio(mut self, ratio: f64) -> Self {
+        self.min_ratio = ratio;
+        self
+    }
+
+    /// tokens of fewer chars are not folded, since short words are often a char apart
+    pub fn min_chars(mut self, n: usize) -> Self {
+        self.min_chars = n;
+        self
+    }
+
+    /// `freqs` with typos folded, and the merges in the order of the counts of typos
+    /// (descending, then by token)
+    pub fn apply(&self, freqs: &Frequencies) -> (Frequencies, Vec<Merge>) {
+        let sorted = freqs.sorted();
+        let lens: Vec<usize> = sorted
+            .iter()
+            .map(|(token, _)| token.chars().count())
+            .collect();
+        // tokens kept so far, as indexes of `sorted`, which are more frequent than the next
+        let mut kept = Deletions::new(self.max_distance);
+        let mut merges = Vec::new();
+        let mut folded = Frequencies::with_capacity(sorted.len());
+        for (i, &(token, count)) in sorted.iter().enumerate() {
+            let mut best: Option<(usize, usize)> = None;
+            // the first kept token is the most frequent
+            let enough = |k: usize| sorted[k].1 as f64 >= count as f64 * self.min_ratio;
+            if lens[i] >= self.min_chars && kept.first().is_some_and(enough) {
+                kept.near(&sorted, token, |k, distance| {
+                    // the earlier is the more frequent, then the smaller token
+                    let better = best.map_or(true, |(b, d)| (distance, k) < (d, b));
+                    if better && enough(k) {
+                        best = Some((k, distance));
+                    }
+                });
+            }
+            match best {
+                Some((k, distance)) => {
+                    let into = sorted[k].0;
+                    folded.extend(Some((into.clone(), count)));
+                    merges.push(Merge {
+                        from: token.clone(),
+                        into: into.clone(),
+                        count,
+                        distance,
+                    });
+                }
+                None => {
+                    kept.insert(token, i);
+                    folded.extend(Some((token.clone(), count)));
+                }
+            }
+        }
+        (folded, merges)
+    }
+}
+
+/// kept tokens by the strings made of them by deleting up to `max` chars, to find the tokens
+/// near one by the strings made of it the same way (symmetric deletion), without trying each
+#[derive(Debug)]
+struct Deletions {
+    max: usize,
+    first: Option<usize>,
+    index: HashMap<String, Vec<usize>>,
+}
+
+impl Deletions {
+    fn new(max: usize) -> Self {
+        Deletions {
+            max,
+            first: None,
+            index: HashMap::new(),
+        }
+    }
+
+    /// the token inserted first
+    fn first(&self) -> Option<usize> {
+        self.first
+    }
+
+    /// insert the token of index `i`
+    fn insert(&mut self, token: &str, i: usize) {
+        self.first.get_or_insert(i);
+        for deleted in deletions(token, self.max) {
+            self.index.entry(deleted).or_default().push(i);
+        }
+    }
+
+    /// call `f` with each index of a token within `max` of `token`, and its distance
+    fn near(&self, tokens: &[(&String, usize)], token: &str, mut f: impl FnMut(usize, usize)) {
+        let mut near: Vec<usize> = deletions(token, self.max)
+            .iter()
+            .filter_map(|deleted| self.index.get(deleted))
+            .flatten()
+            .copied()
+            .collect();
+        near.sort_unstable();
+        near.dedup();
+        for k in near {
+            let distance = levenshtein(token, tokens[k].0);
+            if distance <= self.max {
+                f(k, distance);
+            }
+        }
+    }
+}
+
+/// `token` and the strings made of it by deleting up to `max` chars
+fn deletions(token: &str, max: usize) -> HashSet<String> {
+    let mut all = HashSet::new();
+    all.insert(token.to_string());
+    let mut last = vec![token.to_string()];
+    for _ in 0..max {
+        let mut next = Vec::new();
+        for s in &last {
+            for (at, c) in s.char_indices() {
+                let deleted = format!("{}{}", &s[..at], &s[at + c.len_utf8()..]);
+                if all.insert(deleted.clone()) {
+                    next.push(deleted);
+                }
+            }
+        }
+        last = next;
+    }
+    all
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{count_str, CountOption};
+
+    #[test]
+    fn levenshtein_works() {
+        assert_eq!(levenshtein("flaw", "lawn"), 2);
+        assert_eq!(levenshtein("same", "same"), 0);
+        assert_eq!(levenshtein("abc", ""), 3);
+        assert_eq!(levenshtein("teh", "the"), 2);
+    }
+
+    #[test]
+    fn nearest_and_most_frequent_wins() {
+        let text = "colour ".repeat(30) + &"color ".repeat(40) + "colr";
+        let freqs = count_str(&text, CountOption::Word);
+        let (folded, merges) = TypoFolding::new().min_ratio(20.0).apply(&freqs);
+        // "colour" is not folded into "color", which is not 20 times as frequent
+        assert_eq!(folded["colour"], 30);
+        assert_eq!(folded["color"], 41);
+        assert_eq!(merges.len(), 1);
+        assert_eq!(merges[0].into, "color");
+        let (folded, merges) = TypoFolding::new()
+            .min_ratio(1.0)
+            .max_distance(2)
+            .apply(&freqs);
+        assert_eq!(folded.len(), 1);
+        assert_eq!(folded["color"], 71);
+        assert_eq!(merges.len(), 2);
+    }
+
+    #[test]
+    fn short_tokens_are_not_folded() {
+        let text = "the ".repeat(50) + "thy";
+        let freqs = count_str(&text, CountOption::Word);
+        assert_eq!(TypoFolding::new().apply(&freqs).0, freqs);
+        let (folded, _) = TypoFolding::new().min_chars(3).apply(&freqs);
+        assert_eq!(folded["the"], 51);
+    }
+
+    #[test]
+    fn ties_go_to_the_more_frequent_then_the_smaller() {
+        let text = "abce ".repeat(40) + &"abcd ".repeat(30) + &"abcg ".repeat(30) + "abcf";
+        let freqs = count_str(&text, CountOption::Word);
+        let (_, merges) = TypoFolding::new().apply(&freqs);
+        assert_eq!(merges[0].into, "abce");
+        let text = "abcd ".repeat(30) + &"abcg ".repeat(30) + "abcf";
+        let freqs = count_str(&text, CountOption::Word);
+        let (folded, merges) = TypoFolding::new().apply(&freqs);
+        assert_eq!(merges[0].into, "abcd");
+        assert_eq!(folded["abcg"], 30);
+    }
+}
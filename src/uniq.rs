@@ -0,0 +1,96 @@
+//! counting of runs of equal lines of sorted input, like `sort | uniq -c`,
+//! see [`Runs`](struct.Runs.html)
+
+use std::io::{self, BufRead, Lines};
+
+use crate::decode::{strip_bom, Transcoder};
+
+/// iterator over lines of input and counts of their consecutive repetitions
+///
+/// only the current line is held, so memory does not grow with input. equal lines are counted
+/// together only if they are adjacent, as in sorted input; otherwise each run is a pair of its
+/// own. input is UTF-8 (or UTF-16 with a byte order mark), and a byte order mark is removed
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::uniq::Runs;
+/// let runs: Vec<_> = Runs::new(Cursor::new("a\na\nb\na\n"))
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(
+///     runs,
+///     vec![("a".to_string(), 2), ("b".to_string(), 1), ("a".to_string(), 1)]
+/// );
+/// ```
+pub struct Runs<R> {
+    lines: Lines<Transcoder<R>>,
+    /// the line of the current run and its count
+    current: Option<(String, usize)>,
+    started: bool,
+}
+
+impl<R: BufRead> Runs<R> {
+    pub fn new(input: R) -> Self {
+        Runs {
+            lines: Transcoder::new(input).lines(),
+            current: None,
+            started: false,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Runs<R> {
+    type Item = io::Result<(String, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => return Some(Err(e)),
+                None => return self.current.take().map(Ok),
+            };
+            if !self.started {
+                self.started = true;
+                line = strip_bom(&line).to_string();
+            }
+            match &mut self.current {
+                Some((current, count)) if *current == line => *count += 1,
+                _ => {
+                    if let Some(run) = self.current.replace((line, 1)) {
+                        return Some(Ok(run));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    fn runs(input: &[u8]) -> io::Result<Vec<(String, usize)>> {
+        Runs::new(Cursor::new(input)).collect()
+    }
+
+    #[test]
+    fn runs_are_counted() {
+        let expected = vec![("a".to_string(), 2), ("b".to_string(), 3)];
+        assert_eq!(runs(b"a\na\nb\nb\nb\n").unwrap(), expected);
+        assert_eq!(runs(b"\xef\xbb\xbfa\r\na\nb\nb\nb").unwrap(), expected);
+        assert_eq!(runs(b"").unwrap(), vec![]);
+        assert_eq!(runs(b"\n\n").unwrap(), vec![(String::new(), 2)]);
+    }
+
+    #[test]
+    fn broken_input_fails() {
+        let mut runs = Runs::new(Cursor::new(&b"a\n\xff\n"[..]));
+        assert_eq!(
+            runs.next().unwrap().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
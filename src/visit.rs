@@ -0,0 +1,196 @@
+//! counting with a handler of each line which may fail, see
+//! [`count_visiting`](fn.count_visiting.html)
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::decode::{read_lines, strip_bom, Transcoder};
+use crate::tally::Tally;
+use crate::{CountConfig, CountError, Frequencies};
+
+/// what to do when the handler of a line fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum LineErrorPolicy {
+    /// stop counting with [`VisitError::Line`](enum.VisitError.html#variant.Line)
+    #[default]
+    Abort,
+    /// record the failure, leave the line uncounted and go on
+    Record,
+}
+
+/// a line whose handler failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineFailure<E> {
+    /// line number, starting from 1
+    pub line: usize,
+    pub error: E,
+}
+
+impl<E: fmt::Display> fmt::Display for LineFailure<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+/// result of [`count_visiting`](fn.count_visiting.html)
+#[derive(Debug, Clone)]
+pub struct VisitReport<E> {
+    /// counts of the lines handled successfully
+    pub freqs: Frequencies,
+    /// count of lines read
+    pub lines: usize,
+    /// lines whose handler failed, in order, with [`LineErrorPolicy::Record`](enum.LineErrorPolicy.html#variant.Record)
+    pub failures: Vec<LineFailure<E>>,
+}
+
+/// error of [`count_visiting`](fn.count_visiting.html)
+#[derive(Debug)]
+pub enum VisitError<E> {
+    /// failed to read or count input
+    Count(CountError),
+    /// the handler of a line failed, with [`LineErrorPolicy::Abort`](enum.LineErrorPolicy.html#variant.Abort)
+    Line(LineFailure<E>),
+}
+
+impl<E: fmt::Display> fmt::Display for VisitError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VisitError::Count(e) => e.fmt(f),
+            VisitError::Line(failure) => write!(f, "failed to handle {}", failure),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for VisitError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            VisitError::Count(e) => Some(e),
+            VisitError::Line(failure) => Some(&failure.error),
+        }
+    }
+}
+
+impl<E> From<CountError> for VisitError<E> {
+    fn from(e: CountError) -> Self {
+        VisitError::Count(e)
+    }
+}
+
+impl<E> From<io::Error> for VisitError<E> {
+    fn from(e: io::Error) -> Self {
+        VisitError::Count(CountError::Io(e))
+    }
+}
+
+/// count lines of input as configured, calling `handler` with the number (starting from 1)
+/// and the text of each line before it is counted. a line is counted only if its handler
+/// succeeds; when it fails, `policy` decides whether to stop or to record it and go on
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::visit::{count_visiting, LineErrorPolicy};
+/// use bicycle_book_wordcount::CountConfig;
+/// let input = "GET /a\nbroken\nGET /b";
+/// let check = |_, line: &str| {
+///     if line.starts_with("GET ") {
+///         Ok(())
+///     } else {
+///         Err("not a request")
+///     }
+/// };
+/// let config = CountConfig::default();
+/// let report = count_visiting(Cursor::new(input), &config, LineErrorPolicy::Record, check).unwrap();
+/// assert_eq!(report.freqs["GET"], 2);
+/// assert_eq!(report.failures[0].line, 2);
+///
+/// let err = count_visiting(Cursor::new(input), &config, LineErrorPolicy::Abort, check).unwrap_err();
+/// assert_eq!(err.to_string(), "failed to handle line 2: not a request");
+/// ```
+pub fn count_visiting<E>(
+    input: impl BufRead,
+    config: &CountConfig,
+    policy: LineErrorPolicy,
+    mut handler: impl FnMut(usize, &str) -> Result<(), E>,
+) -> Result<VisitReport<E>, VisitError<E>> {
+    let mut tally = Tally::new(config);
+    let mut lines = 0;
+    let mut failures = Vec::new();
+    read_lines(Transcoder::new(input), config.reading(), |line| {
+        lines += 1;
+        let text = if lines == 1 && !config.keep_bom {
+            strip_bom(line)
+        } else {
+            line
+        };
+        match (handler(lines, text), policy) {
+            (Ok(()), _) => Ok(tally.line(line)?),
+            (Err(error), LineErrorPolicy::Abort) => {
+                Err(VisitError::Line(LineFailure { line: lines, error }))
+            }
+            (Err(error), LineErrorPolicy::Record) => {
+                failures.push(LineFailure { line: lines, error });
+                Ok(())
+            }
+        }
+    })?;
+    Ok(VisitReport {
+        freqs: tally.finish(),
+        lines,
+        failures,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CountOption;
+
+    #[test]
+    fn failures_are_recorded() {
+        let config = CountConfig::new(CountOption::Line);
+        let input = "\u{feff}1\nx\n2\ny\n\u{feff}3";
+        let mut seen = Vec::new();
+        let report = count_visiting(
+            input.as_bytes(),
+            &config,
+            LineErrorPolicy::Record,
+            |n, line| {
+                seen.push(line.to_string());
+                line.trim_start_matches('\u{feff}')
+                    .parse::<u32>()
+                    .map(|_| ())
+                    .map_err(|e| (n, e))
+            },
+        )
+        .unwrap();
+        assert_eq!(seen[0], "1");
+        assert_eq!(report.lines, 5);
+        assert_eq!(report.freqs.len(), 3);
+        assert_eq!(report.freqs["\u{feff}3"], 1);
+        let lines: Vec<_> = report.failures.iter().map(|f| f.line).collect();
+        assert_eq!(lines, vec![2, 4]);
+        assert_eq!(report.failures[1].error.0, 4);
+    }
+
+    #[test]
+    fn read_errors_abort() {
+        let config = CountConfig::default();
+        let err = count_visiting(
+            &b"ok\n\xff"[..],
+            &config,
+            LineErrorPolicy::Record,
+            |_, _| Ok::<_, io::Error>(()),
+        )
+        .unwrap_err();
+        match &err {
+            VisitError::Count(CountError::Io(e)) => {
+                assert_eq!(e.kind(), io::ErrorKind::InvalidData)
+            }
+            _ => panic!("{}", err),
+        }
+        assert!(err.source().is_some());
+    }
+}
@@ -0,0 +1,493 @@
+//! directory traversal for counting many files
+//!
+//! on Windows, files and directories are opened by their extended-length paths (see
+//! [`long_path`](fn.long_path.html)), so deep trees beyond `MAX_PATH` can be counted, too.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::decode::Encoding;
+
+/// bytes inspected by [`is_binary`](fn.is_binary.html)
+const SNIFF_LEN: usize = 8000;
+
+/// all files under `root` (recursively), sorted by path, following symbolic links but ones
+/// making a cycle
+///
+/// if `root` is a file, it is the only one.
+pub fn files(root: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    Ok(files_with(root, Symlinks::Follow)?.files)
+}
+
+/// how symbolic links under the root of a traversal are treated. the root itself is followed
+/// if it is a link
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Symlinks {
+    /// follow links, but a link to a directory being traversed (which would never end) is
+    /// left out as [`Reason::Cycle`](enum.Reason.html#variant.Cycle)
+    #[default]
+    Follow,
+    /// leave out links as [`Reason::Symlink`](enum.Reason.html#variant.Symlink)
+    Skip,
+    /// follow links, and fail on a link to a directory being traversed
+    Error,
+}
+
+impl FromStr for Symlinks {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "follow" => Ok(Symlinks::Follow),
+            "skip" => Ok(Symlinks::Skip),
+            "error" => Ok(Symlinks::Error),
+            _ => Err(format!("unknown symlink policy {:?}", s)),
+        }
+    }
+}
+
+/// why a file is left out of a traversal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    /// the file looks binary, see [`is_binary`](fn.is_binary.html)
+    Binary,
+    /// a symbolic link, with [`Symlinks::Skip`](enum.Symlinks.html#variant.Skip)
+    Symlink,
+    /// a symbolic link to a directory containing it
+    Cycle,
+    /// the directory (or its entry), or the file to sniff, can not be read
+    Unreadable(io::ErrorKind),
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reason::Binary => write!(f, "binary file"),
+            Reason::Symlink => write!(f, "symbolic link"),
+            Reason::Cycle => write!(f, "symbolic link to a parent directory"),
+            Reason::Unreadable(kind) => write!(f, "unreadable ({})", kind),
+        }
+    }
+}
+
+/// files found by [`files_with`](fn.files_with.html) or [`text_files`](fn.text_files.html)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Traversal {
+    /// files to count, sorted by path
+    pub files: Vec<PathBuf>,
+    /// files left out, sorted by path
+    pub skipped: Vec<(PathBuf, Reason)>,
+}
+
+/// all files under `root` (recursively) as [`files`](fn.files.html), with symbolic links
+/// treated by `symlinks`
+///
+/// # Examples
+///
+/// ```no_run
+/// use bicycle_book_wordcount::walk::{files_with, Symlinks};
+/// let traversal = files_with("docs", Symlinks::Skip).unwrap();
+/// for (link, reason) in &traversal.skipped {
+///     eprintln!("skipped {}: {}", link.display(), reason);
+/// }
+/// ```
+pub fn files_with(root: impl AsRef<Path>, symlinks: Symlinks) -> io::Result<Traversal> {
+    let mut traversal = Traversal::default();
+    visit(
+        root.as_ref(),
+        true,
+        symlinks,
+        &mut Vec::new(),
+        &mut traversal,
+    )?;
+    traversal.files.sort();
+    traversal.skipped.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(traversal)
+}
+
+/// same as [`files`](fn.files.html), but binary files are skipped
+pub fn text_files(root: impl AsRef<Path>) -> io::Result<Traversal> {
+    text_files_with(root, Symlinks::Follow)
+}
+
+/// same as [`files_with`](fn.files_with.html), but binary files are skipped
+pub fn text_files_with(root: impl AsRef<Path>, symlinks: Symlinks) -> io::Result<Traversal> {
+    Ok(files_with(root, symlinks)?.skip_binary())
+}
+
+impl Traversal {
+    /// leave out binary files, see [`is_binary`](fn.is_binary.html), and files which can not
+    /// be read to tell (such as broken links) as
+    /// [`Reason::Unreadable`](enum.Reason.html#variant.Unreadable)
+    pub fn skip_binary(mut self) -> Self {
+        for file in mem::take(&mut self.files) {
+            match is_binary(&file) {
+                Ok(false) => self.files.push(file),
+                Ok(true) => self.skipped.push((file, Reason::Binary)),
+                Err(e) => self.skipped.push((file, Reason::Unreadable(e.kind()))),
+            }
+        }
+        self.skipped.sort_by(|a, b| a.0.cmp(&b.0));
+        self
+    }
+}
+
+/// whether the file looks binary: the first 8000 bytes contain a NUL byte (as git does).
+/// UTF-16 text with a byte order mark is not binary
+pub fn is_binary(path: impl AsRef<Path>) -> io::Result<bool> {
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    open(path)?.take(SNIFF_LEN as u64).read_to_end(&mut head)?;
+    Ok(looks_binary(&head))
+}
+
+fn looks_binary(head: &[u8]) -> bool {
+    Encoding::detect(head) == Encoding::Utf8 && head.contains(&0)
+}
+
+/// open a file for reading by [`long_path`](fn.long_path.html)
+pub fn open(path: impl AsRef<Path>) -> io::Result<File> {
+    File::open(long_path(path.as_ref()))
+}
+
+/// whether `path` is a directory (or a link to one), by [`long_path`](fn.long_path.html)
+pub fn is_dir(path: impl AsRef<Path>) -> bool {
+    fs::metadata(long_path(path.as_ref())).is_ok_and(|metadata| metadata.is_dir())
+}
+
+/// `path` which can be opened even if it is longer than `MAX_PATH` (260 chars) on Windows:
+/// made absolute and normalized, with the prefix `\\?\` (`\\?\UNC\` for UNC paths like
+/// `\\server\share`). paths with the prefix already, device paths and paths on other
+/// platforms are returned as they are
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    {
+        let absolute = match std::env::current_dir() {
+            Ok(dir) if !path.is_absolute() => dir.join(path),
+            _ => path.to_path_buf(),
+        };
+        if let Some(extended) = absolute.to_str().and_then(extend) {
+            return Cow::Owned(PathBuf::from(extended));
+        }
+    }
+    Cow::Borrowed(path)
+}
+
+/// `path` for messages, without the prefix added by [`long_path`](fn.long_path.html), and with
+/// U+FFFD in place of parts which are not Unicode (such as names in Latin-1 on Linux)
+pub fn display(path: &Path) -> Cow<'_, str> {
+    let lossy = path.to_string_lossy();
+    match strip_extended(&lossy) {
+        Some(stripped) if cfg!(windows) => Cow::Owned(stripped),
+        _ => lossy,
+    }
+}
+
+/// extended-length path of an absolute Windows path, resolving `.` and `..` (which the prefix
+/// turns off) without going above the drive or the share
+#[cfg_attr(not(windows), allow(dead_code))]
+fn extend(path: &str) -> Option<String> {
+    if path.starts_with(r"\\?\") || path.starts_with(r"\\.\") {
+        return None;
+    }
+    let path = path.replace('/', r"\");
+    let (prefix, root, rest) = match path.strip_prefix(r"\\") {
+        Some(unc) => (r"\\?\UNC\", 2, unc),
+        None => match path.as_bytes() {
+            [drive, b':', b'\\', ..] if drive.is_ascii_alphabetic() => (r"\\?\", 1, &path[..]),
+            _ => return None,
+        },
+    };
+    let mut parts = Vec::new();
+    for part in rest.split('\\') {
+        match part {
+            "" | "." => {}
+            ".." if parts.len() > root => {
+                parts.pop();
+            }
+            ".." => {}
+            part => parts.push(part),
+        }
+    }
+    if parts.len() < root {
+        return None;
+    }
+    let separator = if parts.len() == 1 { r"\" } else { "" };
+    Some(format!("{}{}{}", prefix, parts.join(r"\"), separator))
+}
+
+/// `path` without the prefix of an extended-length path
+fn strip_extended(path: &str) -> Option<String> {
+    match path.strip_prefix(r"\\?\UNC\") {
+        Some(unc) => Some(format!(r"\\{}", unc)),
+        None => path.strip_prefix(r"\\?\").map(str::to_string),
+    }
+}
+
+/// add files under `path` to `traversal`, with the real paths of the directories containing
+/// it in `ancestors`. a directory under the root which can not be read is left out as
+/// [`Reason::Unreadable`](enum.Reason.html#variant.Unreadable)
+fn visit(
+    path: &Path,
+    root: bool,
+    symlinks: Symlinks,
+    ancestors: &mut Vec<PathBuf>,
+    traversal: &mut Traversal,
+) -> io::Result<()> {
+    let long = long_path(path);
+    let link = fs::symlink_metadata(&long).is_ok_and(|metadata| metadata.file_type().is_symlink());
+    if link && !root && symlinks == Symlinks::Skip {
+        traversal
+            .skipped
+            .push((path.to_path_buf(), Reason::Symlink));
+        return Ok(());
+    }
+    if !is_dir(path) {
+        // including a broken link, failing when it is read
+        traversal.files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let unreadable = |traversal: &mut Traversal, e: io::Error| {
+        if root {
+            return Err(e);
+        }
+        let reason = Reason::Unreadable(e.kind());
+        traversal.skipped.push((path.to_path_buf(), reason));
+        Ok(())
+    };
+    let real = match fs::canonicalize(&long) {
+        Ok(real) => real,
+        Err(e) => return unreadable(traversal, e),
+    };
+    if ancestors.contains(&real) {
+        if symlinks == Symlinks::Error {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("{}: {}", display(path), Reason::Cycle),
+            ));
+        }
+        traversal.skipped.push((path.to_path_buf(), Reason::Cycle));
+        return Ok(());
+    }
+    let entries = match fs::read_dir(&long) {
+        Ok(entries) => entries,
+        Err(e) => return unreadable(traversal, e),
+    };
+    ancestors.push(real);
+    // joined to `path` as given, not to its long path, which is only for opening
+    for entry in entries {
+        match entry {
+            Ok(entry) => visit(
+                &path.join(entry.file_name()),
+                false,
+                symlinks,
+                ancestors,
+                traversal,
+            )?,
+            // the entry has no name to tell, so the directory is told
+            Err(e) => {
+                let reason = Reason::Unreadable(e.kind());
+                traversal.skipped.push((path.to_path_buf(), reason));
+            }
+        }
+    }
+    ancestors.pop();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn files_works() -> io::Result<()> {
+        let root = std::env::temp_dir().join("wordcount_walk_files_works");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub"))?;
+        fs::write(root.join("b.txt"), "b")?;
+        fs::write(root.join("sub").join("a.txt"), "a")?;
+
+        let found = files(&root)?;
+        fs::remove_dir_all(&root)?;
+        assert_eq!(
+            found,
+            vec![root.join("b.txt"), root.join("sub").join("a.txt")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn binary_files_are_skipped() -> io::Result<()> {
+        assert!(looks_binary(b"\x7fELF\x02\x01\x00\x00"));
+        assert!(!looks_binary(b"plain text"));
+        assert!(!looks_binary(b"\xff\xfea\x00b\x00"));
+
+        let root = std::env::temp_dir().join("wordcount_walk_binary_files_are_skipped");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root)?;
+        fs::write(root.join("a.txt"), "a")?;
+        fs::write(root.join("b.bin"), b"\x00\x01")?;
+
+        let traversal = text_files(&root)?;
+        fs::remove_dir_all(&root)?;
+        assert_eq!(traversal.files, vec![root.join("a.txt")]);
+        assert_eq!(
+            traversal.skipped,
+            vec![(root.join("b.bin"), Reason::Binary)]
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_cycles_end() -> io::Result<()> {
+        use std::os::unix::fs::symlink;
+
+        let root = std::env::temp_dir().join("wordcount_walk_symlink_cycles_end");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub"))?;
+        fs::write(root.join("sub").join("a.txt"), "a")?;
+        symlink(&root, root.join("sub").join("up"))?;
+        symlink(root.join("sub").join("a.txt"), root.join("b.txt"))?;
+
+        let followed = files_with(&root, Symlinks::Follow);
+        let skipped = files_with(&root, Symlinks::Skip);
+        let failed = files_with(&root, Symlinks::Error);
+        fs::remove_dir_all(&root)?;
+        let followed = followed?;
+        assert_eq!(
+            followed.files,
+            vec![root.join("b.txt"), root.join("sub").join("a.txt")]
+        );
+        assert_eq!(
+            followed.skipped,
+            vec![(root.join("sub").join("up"), Reason::Cycle)]
+        );
+        let skipped = skipped?;
+        assert_eq!(skipped.files, vec![root.join("sub").join("a.txt")]);
+        assert_eq!(
+            skipped.skipped,
+            vec![
+                (root.join("b.txt"), Reason::Symlink),
+                (root.join("sub").join("up"), Reason::Symlink)
+            ]
+        );
+        assert!(failed
+            .unwrap_err()
+            .to_string()
+            .ends_with("up: symbolic link to a parent directory"));
+        assert_eq!("skip".parse(), Ok(Symlinks::Skip));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn broken_links_are_not_sniffed() -> io::Result<()> {
+        let root = std::env::temp_dir().join("wordcount_walk_broken_links_are_not_sniffed");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root)?;
+        fs::write(root.join("a.txt"), "a")?;
+        std::os::unix::fs::symlink(root.join("none"), root.join("broken"))?;
+
+        let found = files(&root)?;
+        let traversal = text_files(&root);
+        fs::remove_dir_all(&root)?;
+        assert_eq!(found, vec![root.join("a.txt"), root.join("broken")]);
+        let traversal = traversal?;
+        assert_eq!(traversal.files, vec![root.join("a.txt")]);
+        assert_eq!(
+            traversal.skipped,
+            vec![(
+                root.join("broken"),
+                Reason::Unreadable(io::ErrorKind::NotFound)
+            )]
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_directories_are_skipped() -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join("wordcount_walk_unreadable_directories");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("locked"))?;
+        fs::write(root.join("a.txt"), "a")?;
+        fs::set_permissions(root.join("locked"), fs::Permissions::from_mode(0o000))?;
+        let readable = fs::read_dir(root.join("locked")).is_ok();
+        let found = files_with(&root, Symlinks::Follow);
+        fs::set_permissions(root.join("locked"), fs::Permissions::from_mode(0o755))?;
+        fs::remove_dir_all(&root)?;
+        if readable {
+            // permissions are not checked, e.g. for root
+            return Ok(());
+        }
+        let found = found?;
+        assert_eq!(found.files, vec![root.join("a.txt")]);
+        assert_eq!(
+            found.skipped,
+            vec![(
+                root.join("locked"),
+                Reason::Unreadable(io::ErrorKind::PermissionDenied)
+            )]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn long_paths_are_extended() {
+        assert_eq!(
+            extend(r"C:\src\.\old\..\a.txt").as_deref(),
+            Some(r"\\?\C:\src\a.txt")
+        );
+        assert_eq!(extend("d:/a//b/").as_deref(), Some(r"\\?\d:\a\b"));
+        assert_eq!(extend(r"C:\..\..").as_deref(), Some(r"\\?\C:\"));
+        assert_eq!(
+            extend(r"\\server\share\..\x").as_deref(),
+            Some(r"\\?\UNC\server\share\x")
+        );
+        assert_eq!(extend(r"\\server"), None);
+        assert_eq!(extend(r"\\?\C:\a"), None);
+        assert_eq!(extend(r"\\.\pipe\x"), None);
+        assert_eq!(extend("relative/a.txt"), None);
+
+        assert_eq!(strip_extended(r"\\?\C:\a").as_deref(), Some(r"C:\a"));
+        assert_eq!(
+            strip_extended(r"\\?\UNC\server\share").as_deref(),
+            Some(r"\\server\share")
+        );
+        assert_eq!(strip_extended(r"C:\a"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn names_which_are_not_unicode_are_found() -> io::Result<()> {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let root = std::env::temp_dir().join("wordcount_walk_names_which_are_not_unicode");
+        let _ = fs::remove_dir_all(&root);
+        let name = OsStr::from_bytes(b"caf\xe9.txt");
+        if fs::create_dir_all(&root)
+            .and_then(|_| fs::write(root.join(name), "a"))
+            .is_err()
+        {
+            // some file systems accept only UTF-8 names
+            let _ = fs::remove_dir_all(&root);
+            return Ok(());
+        }
+
+        let found = files(&root)?;
+        let binary = is_binary(&found[0]);
+        fs::remove_dir_all(&root)?;
+        assert_eq!(found, vec![root.join(name)]);
+        assert!(!binary?);
+        assert!(display(&found[0]).ends_with("caf\u{fffd}.txt"));
+        Ok(())
+    }
+}
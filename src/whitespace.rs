@@ -0,0 +1,150 @@
+//! whitespace composition and indentation of lines
+
+use std::io::BufRead;
+
+use crate::decode::{strip_bom, Transcoder};
+use crate::stats::Distribution;
+
+/// whitespace composition of an input
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct WhitespaceStats {
+    /// count of lines
+    pub lines: usize,
+    /// count of tab chars
+    pub tabs: usize,
+    /// count of space chars (U+0020)
+    pub spaces: usize,
+    /// lines ending with whitespace
+    pub trailing: usize,
+    /// lines indented by tabs only
+    pub tab_indented: usize,
+    /// lines indented by spaces only
+    pub space_indented: usize,
+    /// lines indented by both tabs and spaces
+    pub mixed_indented: usize,
+}
+
+impl WhitespaceStats {
+    /// add a line
+    pub(crate) fn add_line(&mut self, line: &str) {
+        self.lines += 1;
+        self.tabs += line.matches('\t').count();
+        self.spaces += line.matches(' ').count();
+        if line.ends_with(char::is_whitespace) {
+            self.trailing += 1;
+        }
+
+        let indent = indent(line);
+        match (indent.contains('\t'), indent.contains(' ')) {
+            (true, false) => self.tab_indented += 1,
+            (false, true) => self.space_indented += 1,
+            (true, true) => self.mixed_indented += 1,
+            (false, false) => {}
+        }
+    }
+}
+
+/// whitespace composition, read from input
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::whitespace::whitespace_stats;
+/// let stats = whitespace_stats(Cursor::new("\tfoo\n  bar \n \tbaz"));
+/// assert_eq!(stats.tabs, 2);
+/// assert_eq!(stats.spaces, 4);
+/// assert_eq!(stats.trailing, 1);
+/// assert_eq!(stats.mixed_indented, 1);
+/// ```
+///
+/// # Panics
+///
+/// input is neither UTF-8 nor UTF-16 with a byte order mark
+pub fn whitespace_stats(input: impl BufRead) -> WhitespaceStats {
+    let mut stats = WhitespaceStats::default();
+    for (i, line) in Transcoder::new(input).lines().enumerate() {
+        let line = line.unwrap();
+        let line = if i == 0 { strip_bom(&line) } else { &line };
+        stats.add_line(line);
+    }
+    stats
+}
+
+/// distribution of indentation widths of lines, read from input
+///
+/// width is counted in spaces, and a tab advances to the next multiple of `tab_width`.
+/// blank lines (whitespace only) are not counted.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use bicycle_book_wordcount::whitespace::indent_widths;
+/// let widths = indent_widths(Cursor::new("a\n    b\n\tc\n  \n  d"), 4);
+/// assert_eq!(widths.histogram()[&4], 2);
+/// assert_eq!(widths.histogram()[&2], 1);
+/// assert_eq!(widths.len(), 4);
+/// ```
+///
+/// # Panics
+///
+/// input is neither UTF-8 nor UTF-16 with a byte order mark, or `tab_width` is 0
+pub fn indent_widths(input: impl BufRead, tab_width: usize) -> Distribution {
+    assert!(tab_width > 0, "tab_width must be positive");
+    let mut widths = Distribution::new();
+    for (i, line) in Transcoder::new(input).lines().enumerate() {
+        let line = line.unwrap();
+        let line = if i == 0 { strip_bom(&line) } else { &line };
+        if !line.trim().is_empty() {
+            widths.add(indent_width(line, tab_width));
+        }
+    }
+    widths
+}
+
+/// width of the indentation, tabs are expanded
+pub(crate) fn indent_width(line: &str, tab_width: usize) -> usize {
+    indent(line).chars().fold(0, |width, c| match c {
+        '\t' => (width / tab_width + 1) * tab_width,
+        _ => width + 1,
+    })
+}
+
+/// leading tabs and spaces of a line
+fn indent(line: &str) -> &str {
+    let rest = line.trim_start_matches(&[' ', '\t'][..]);
+    &line[..line.len() - rest.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn indent_width_works() {
+        assert_eq!(indent_width("x", 4), 0);
+        assert_eq!(indent_width("\tx", 4), 4);
+        assert_eq!(indent_width("  \tx", 4), 4);
+        assert_eq!(indent_width("     \t x", 4), 9);
+        assert_eq!(indent_width("\t\tx", 8), 16);
+    }
+
+    #[test]
+    fn whitespace_stats_works() {
+        let stats = whitespace_stats(Cursor::new("a b\n\tx\n    y\t\n\t  z\n\n"));
+        assert_eq!(
+            stats,
+            WhitespaceStats {
+                lines: 5,
+                tabs: 3,
+                spaces: 7,
+                trailing: 1,
+                tab_indented: 1,
+                space_indented: 1,
+                mixed_indented: 1,
+            }
+        );
+    }
+}
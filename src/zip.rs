@@ -0,0 +1,409 @@
+//! reading of zip archives, of entries stored or compressed by deflate (RFC 1951)
+
+use std::io;
+
+/// an archive read into memory
+#[derive(Debug, Clone)]
+pub(crate) struct Archive {
+    bytes: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    name: String,
+    method: u16,
+    /// offset of the local header
+    offset: usize,
+    compressed: usize,
+    size: usize,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("zip: {}", message))
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> io::Result<u16> {
+    match bytes.get(at..at + 2) {
+        Some(b) => Ok(u16::from_le_bytes([b[0], b[1]])),
+        None => Err(invalid("truncated")),
+    }
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> io::Result<usize> {
+    match bytes.get(at..at + 4) {
+        Some(b) => Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize),
+        None => Err(invalid("truncated")),
+    }
+}
+
+impl Archive {
+    /// read the central directory of `bytes`. zip64 archives are not supported
+    pub(crate) fn new(bytes: Vec<u8>) -> io::Result<Self> {
+        // the end of central directory record is 22 bytes and a comment of up to 65535 bytes
+        let earliest = bytes.len().saturating_sub(22 + 0xffff);
+        let end = (earliest..bytes.len().saturating_sub(21))
+            .rev()
+            .find(|&i| bytes[i..i + 4] == [0x50, 0x4b, 0x05, 0x06])
+            .ok_or_else(|| invalid("no end of central directory"))?;
+        let count = u16_at(&bytes, end + 10)? as usize;
+        let mut at = u32_at(&bytes, end + 16)?;
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            if u32_at(&bytes, at)? != 0x0201_4b50 {
+                return Err(invalid("broken central directory"));
+            }
+            let name_len = u16_at(&bytes, at + 28)? as usize;
+            let extra_len = u16_at(&bytes, at + 30)? as usize;
+            let comment_len = u16_at(&bytes, at + 32)? as usize;
+            let name = bytes
+                .get(at + 46..at + 46 + name_len)
+                .ok_or_else(|| invalid("truncated"))?;
+            entries.push(Entry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                method: u16_at(&bytes, at + 10)?,
+                compressed: u32_at(&bytes, at + 20)?,
+                size: u32_at(&bytes, at + 24)?,
+                offset: u32_at(&bytes, at + 42)?,
+            });
+            at += 46 + name_len + extra_len + comment_len;
+        }
+        Ok(Archive { bytes, entries })
+    }
+
+    /// content of the entry `name`, `NotFound` if there is none, `InvalidData` if it is
+    /// larger than `max` bytes
+    pub(crate) fn read(&self, name: &str, max: usize) -> io::Result<Vec<u8>> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, format!("zip: no entry {}", name))
+            })?;
+        let at = entry.offset;
+        if u32_at(&self.bytes, at)? != 0x0403_4b50 {
+            return Err(invalid("broken local header"));
+        }
+        let start = at
+            + 30
+            + u16_at(&self.bytes, at + 26)? as usize
+            + u16_at(&self.bytes, at + 28)? as usize;
+        let data = self
+            .bytes
+            .get(start..start + entry.compressed)
+            .ok_or_else(|| invalid("truncated"))?;
+        if entry.size > max || (entry.method == 0 && data.len() > max) {
+            return Err(invalid(&format!("{} is too large", name)));
+        }
+        match entry.method {
+            0 => Ok(data.to_vec()),
+            8 => inflate(data, entry.size),
+            _ => Err(invalid("unsupported compression method")),
+        }
+    }
+}
+
+/// bits of a deflate stream, least significant first
+struct Bits<'a> {
+    data: &'a [u8],
+    at: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl Bits<'_> {
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        while self.count < n {
+            let byte = *self
+                .data
+                .get(self.at)
+                .ok_or_else(|| invalid("truncated deflate"))?;
+            self.buffer |= (byte as u32) << self.count;
+            self.at += 1;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1 << n) - 1);
+        self.buffer = self.buffer.checked_shr(n).unwrap_or(0);
+        self.count -= n;
+        Ok(value)
+    }
+}
+
+/// a canonical Huffman code, by count of codes of each length and symbols in order of codes
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= bits.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("bad huffman code"))
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// order of lengths of the code length code
+const ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// decompress raw deflate `data` of `size` bytes, failing as soon as it is larger
+pub(crate) fn inflate(data: &[u8], size: usize) -> io::Result<Vec<u8>> {
+    // the size is of the header, and deflate expands at most 1032 times
+    let mut out = Vec::with_capacity(size.min(data.len().saturating_mul(1032)));
+    let mut bits = Bits {
+        data,
+        at: 0,
+        buffer: 0,
+        count: 0,
+    };
+    loop {
+        let last = bits.bits(1)? == 1;
+        match bits.bits(2)? {
+            0 => {
+                // stored, from the next byte
+                bits.buffer = 0;
+                bits.count = 0;
+                let len = u16_at(data, bits.at)? as usize;
+                let stored = data
+                    .get(bits.at + 4..bits.at + 4 + len)
+                    .ok_or_else(|| invalid("truncated deflate"))?;
+                if out.len() + stored.len() > size {
+                    return Err(invalid("larger than the size"));
+                }
+                out.extend_from_slice(stored);
+                bits.at += 4 + len;
+            }
+            1 => {
+                let mut lengths = [0; 288];
+                lengths[..144].iter_mut().for_each(|len| *len = 8);
+                lengths[144..256].iter_mut().for_each(|len| *len = 9);
+                lengths[256..280].iter_mut().for_each(|len| *len = 7);
+                lengths[280..].iter_mut().for_each(|len| *len = 8);
+                let literals = Huffman::new(&lengths);
+                let distances = Huffman::new(&[5; 30]);
+                codes(&mut bits, &mut out, size, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic(&mut bits)?;
+                codes(&mut bits, &mut out, size, &literals, &distances)?;
+            }
+            _ => return Err(invalid("bad deflate block")),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+/// codes of a dynamic block
+fn dynamic(bits: &mut Bits) -> io::Result<(Huffman, Huffman)> {
+    let literal_count = bits.bits(5)? as usize + 257;
+    let distance_count = bits.bits(5)? as usize + 1;
+    let code_count = bits.bits(4)? as usize + 4;
+    let mut lengths = [0u8; 19];
+    for &i in &ORDER[..code_count] {
+        lengths[i] = bits.bits(3)? as u8;
+    }
+    let code = Huffman::new(&lengths);
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let symbol = code.decode(bits)?;
+        let (len, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths.last().ok_or_else(|| invalid("bad repeat"))?;
+                (previous, 3 + bits.bits(2)?)
+            }
+            17 => (0, 3 + bits.bits(3)?),
+            _ => (0, 11 + bits.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat(len).take(repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err(invalid("too many lengths"));
+    }
+    let distances = Huffman::new(&lengths[literal_count..]);
+    lengths.truncate(literal_count);
+    Ok((Huffman::new(&lengths), distances))
+}
+
+/// literals and back references of a block until its end, up to `size` bytes of `out`
+fn codes(
+    bits: &mut Bits,
+    out: &mut Vec<u8>,
+    size: usize,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> io::Result<()> {
+    loop {
+        if out.len() > size {
+            return Err(invalid("larger than the size"));
+        }
+        let symbol = literals.decode(bits)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let i = symbol - 257;
+                if i >= LENGTH_BASE.len() {
+                    return Err(invalid("bad length"));
+                }
+                let len = LENGTH_BASE[i] as usize + bits.bits(LENGTH_EXTRA[i] as u32)? as usize;
+                let d = distances.decode(bits)? as usize;
+                if d >= DISTANCE_BASE.len() {
+                    return Err(invalid("bad distance"));
+                }
+                let distance =
+                    DISTANCE_BASE[d] as usize + bits.bits(DISTANCE_EXTRA[d] as u32)? as usize;
+                if distance > out.len() {
+                    return Err(invalid("distance too far"));
+                }
+                let start = out.len() - distance;
+                // copies may overlap what they write
+                for k in 0..len {
+                    out.push(out[start + k]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    /// an archive of stored entries
+    pub(crate) fn archive(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut directory = Vec::new();
+        for (name, content) in files {
+            let offset = bytes.len() as u32;
+            let header = |sig: u32, central: bool| {
+                let mut h = sig.to_le_bytes().to_vec();
+                if central {
+                    h.extend_from_slice(&[20, 0]);
+                }
+                h.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+                let len = (content.len() as u32).to_le_bytes();
+                h.extend_from_slice(&len);
+                h.extend_from_slice(&len);
+                h.extend_from_slice(&(name.len() as u16).to_le_bytes());
+                h.extend_from_slice(&[0, 0]);
+                if central {
+                    h.extend_from_slice(&[0; 10]);
+                    h.extend_from_slice(&offset.to_le_bytes());
+                }
+                h.extend_from_slice(name.as_bytes());
+                h
+            };
+            bytes.extend(header(0x0403_4b50, false));
+            bytes.extend_from_slice(content.as_bytes());
+            directory.extend(header(0x0201_4b50, true));
+        }
+        let start = bytes.len() as u32;
+        let count = (files.len() as u16).to_le_bytes();
+        bytes.extend_from_slice(&directory);
+        bytes.extend_from_slice(&[0x50, 0x4b, 0x05, 0x06, 0, 0, 0, 0]);
+        bytes.extend_from_slice(&count);
+        bytes.extend_from_slice(&count);
+        bytes.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&start.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn stored_entries_are_read() {
+        let zip = Archive::new(archive(&[("a.txt", "alpha"), ("dir/b.txt", "")])).unwrap();
+        assert_eq!(zip.read("a.txt", 5).unwrap(), b"alpha");
+        assert_eq!(zip.read("dir/b.txt", 0).unwrap(), b"");
+        assert!(zip.read("a.txt", 4).is_err());
+        let missing = zip.read("c.txt", 5).unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+        assert!(Archive::new(b"not a zip".to_vec()).is_err());
+    }
+
+    #[test]
+    fn inflate_works() {
+        // zlib.compress(b"hello hello hello", wbits=-15), a fixed block
+        let fixed = [0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x90, 0x00];
+        assert_eq!(inflate(&fixed, 17).unwrap(), b"hello hello hello");
+        // a stored block
+        let stored = [0x01, 0x03, 0x00, 0xfc, 0xff, b'a', b'b', b'c'];
+        assert_eq!(inflate(&stored, 3).unwrap(), b"abc");
+        assert!(inflate(&fixed[..4], 17).is_err());
+        // sizes are not trusted
+        assert!(inflate(&fixed, 16).is_err());
+        assert!(inflate(&stored, 2).is_err());
+        assert!(inflate(&fixed, usize::MAX).is_ok());
+    }
+
+    #[test]
+    fn inflate_dynamic_block() {
+        let words: Vec<_> = "the quick brown fox jumps over lazy dog while seven wizards box \
+                             zebras quietly and jovial kings fight"
+            .split_whitespace()
+            .collect();
+        let text: Vec<_> = (0..120).map(|i| words[i * 7 % words.len()]).collect();
+        let text = text.join(" ");
+        assert_eq!(inflate(DYNAMIC, text.len()).unwrap(), text.as_bytes());
+    }
+
+    /// zlib.compress of the text above, level 9 and wbits -15
+    const DYNAMIC: &[u8] = &[
+        0xed, 0xcd, 0xc1, 0x15, 0x84, 0x20, 0x0c, 0x45, 0xd1, 0x56, 0x7e, 0x6b, 0x41, 0x22, 0x44,
+        0x91, 0x28, 0x41, 0x50, 0xaa, 0xd7, 0x69, 0x61, 0xd6, 0xee, 0xdf, 0x79, 0xb7, 0x46, 0x86,
+        0xd7, 0x00, 0xca, 0x1e, 0xb3, 0x5e, 0xe8, 0x32, 0xa8, 0x78, 0xc3, 0x2c, 0x21, 0x56, 0x24,
+        0x1a, 0x37, 0x8e, 0x53, 0xb8, 0xa6, 0x1b, 0xae, 0x68, 0xcf, 0x30, 0x6e, 0x9c, 0xb1, 0x4a,
+        0x0e, 0x06, 0x6d, 0x5c, 0x30, 0xd8, 0x15, 0xb2, 0x5f, 0x35, 0xad, 0xe8, 0x51, 0x12, 0x63,
+        0xd1, 0x26, 0x94, 0xb0, 0x9c, 0xdb, 0x6e, 0x70, 0xef, 0xb5, 0x7e, 0xca, 0xa7, 0xfc, 0xaf,
+        0x3c,
+    ];
+}